@@ -1,3 +1,5 @@
+use nu_ansi_term::Style;
+
 /// A span of source code, with positions in bytes
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Span {
@@ -24,6 +26,54 @@ impl Span {
 
         Span { start, end }
     }
+
+    /// Span of the shell-like argument that the cursor at `pos` completes.
+    /// Whitespace inside single or double quotes and whitespace escaped with a
+    /// backslash do not end the argument, e.g. for `cd "My Doc` the span covers
+    /// `"My Doc`. The span ends at `pos`
+    pub fn from_quoted_token(line: &str, pos: usize) -> Span {
+        let mut start = 0;
+        let mut quote = None;
+        let mut escaped = false;
+
+        for (index, c) in line[..pos].char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && quote != Some('\'') {
+                escaped = true;
+            } else if let Some(q) = quote {
+                if c == q {
+                    quote = None;
+                }
+            } else if c == '"' || c == '\'' {
+                quote = Some(c);
+            } else if c.is_whitespace() {
+                start = index + c.len_utf8();
+            }
+        }
+
+        Span::new(start, pos)
+    }
+}
+
+/// Removes the quotes and backslash escapes from an argument, e.g. the text
+/// covered by [`Span::from_quoted_token`], to get the value it stands for
+pub fn unquote(token: &str) -> String {
+    let mut value = String::with_capacity(token.len());
+    let mut quote = None;
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some('\''), c) => value.push(c),
+            (_, '\\') => value.push(chars.next().unwrap_or('\\')),
+            (_, c) => value.push(c),
+        }
+    }
+
+    value
 }
 
 /// A trait that defines how to convert a line and position to a list of potential completions in that position.
@@ -31,6 +81,13 @@ pub trait Completer: Send {
     /// the action that will take the line and position and convert it to a vector of completions, which include the
     /// span to replace and the contents of that replacement
     fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion>;
+
+    /// Message shown by the menu in place of the suggestions when [`Completer::complete`]
+    /// found none for the same line and position, telling why, e.g. `no files match`.
+    /// With `None` the menu shows its default message
+    fn empty_message(&self, _line: &str, _pos: usize) -> Option<String> {
+        None
+    }
 }
 
 /// Suggestion returned by the Completer
@@ -40,6 +97,90 @@ pub struct Suggestion {
     pub value: String,
     /// Optional description for the replacement
     pub description: Option<String>,
+    /// Optional style of the value in the menu, e.g. to tell keywords and paths apart.
+    /// Menus use their text style without it
+    pub style: Option<Style>,
     /// Replacement span in the buffer
     pub span: Span,
+    /// Whether the completion continues after accepting the suggestion, like after
+    /// a directory (ending with `/`) when completing a path
+    pub continue_completion: bool,
+    /// Whether the start of the value matching the typed text without regard to case
+    /// keeps the casing that was typed, e.g. `READ` completed with `readline` becomes
+    /// `READline`. By default the value replaces the span verbatim
+    pub match_typed_case: bool,
+}
+
+impl Suggestion {
+    /// Suggestion replacing `span` in the buffer with `value`, without a description or
+    /// style. Unlike a struct literal, building suggestions with it and the builders
+    /// keeps compiling when fields are added
+    pub fn new(value: impl Into<String>, span: Span) -> Self {
+        Self {
+            value: value.into(),
+            span,
+            ..Self::default()
+        }
+    }
+
+    /// A builder for the description shown next to the value
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// A builder for the style of the value in the menu
+    #[must_use]
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// A builder to continue the completion after accepting the suggestion
+    #[must_use]
+    pub fn with_continue_completion(mut self, continue_completion: bool) -> Self {
+        self.continue_completion = continue_completion;
+        self
+    }
+
+    /// A builder to keep the typed case of the matching start of the value
+    #[must_use]
+    pub fn with_match_typed_case(mut self, match_typed_case: bool) -> Self {
+        self.match_typed_case = match_typed_case;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("cd Doc", "Doc", "Doc")]
+    #[case("cd \"My Doc", "\"My Doc", "My Doc")]
+    #[case("cd 'My Doc", "'My Doc", "My Doc")]
+    #[case("cd My\\ Doc", "My\\ Doc", "My Doc")]
+    #[case("cd \"My \\\"Doc", "\"My \\\"Doc", "My \"Doc")]
+    #[case("cd 'My\\ Doc", "'My\\ Doc", "My\\ Doc")]
+    #[case("cd \"My Doc\"/sub", "\"My Doc\"/sub", "My Doc/sub")]
+    #[case("cd ", "", "")]
+    #[case("", "", "")]
+    fn quoted_token_under_the_cursor(#[case] line: &str, #[case] token: &str, #[case] value: &str) {
+        let span = Span::from_quoted_token(line, line.len());
+
+        assert_eq!(&line[span.start..span.end], token);
+        assert_eq!(unquote(token), value);
+    }
+
+    #[test]
+    fn quoted_token_ends_at_the_cursor() {
+        let line = "cd \"My Documents\" --all";
+        let span = Span::from_quoted_token(line, 9);
+
+        assert_eq!(span, Span::new(3, 9));
+        assert_eq!(unquote(&line[span.start..span.end]), "My Do");
+    }
 }
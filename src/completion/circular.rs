@@ -1,4 +1,4 @@
-use crate::{core_editor::LineBuffer, Completer};
+use crate::{core_editor::LineBuffer, menu_functions::replace_in_buffer, Completer};
 
 /// A simple handler that will do a cycle-based rotation through the options given by the Completer
 pub struct CircularCompletionHandler {
@@ -52,14 +52,12 @@ impl CircularCompletionHandler {
             match self.index {
                 index if index < completions.len() => {
                     self.index += 1;
-                    let span = completions[index].span;
-
-                    let mut offset = present_buffer.insertion_point();
-                    offset += completions[index].value.len() - (span.end - span.start);
-
                     // TODO improve the support for multiline replace
-                    present_buffer.replace(span.start..span.end, &completions[index].value);
-                    present_buffer.set_insertion_point(offset);
+                    replace_in_buffer(
+                        present_buffer,
+                        completions[index].span,
+                        &completions[index].value,
+                    );
                 }
                 _ => {
                     self.reset_index();
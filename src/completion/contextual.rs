@@ -0,0 +1,242 @@
+use crate::{unquote, Completer, Span, Suggestion};
+use std::collections::BTreeMap;
+
+/// Subcommands and flags a command can be completed with, see [`ContextualCompleter`]
+#[derive(Debug, Default, Clone)]
+pub struct CommandCompletions {
+    subcommands: BTreeMap<String, CommandCompletions>,
+    flags: BTreeMap<String, Option<String>>,
+}
+
+impl CommandCompletions {
+    /// Creates the completions of a command without subcommands or flags
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder adding a subcommand with its own completions
+    #[must_use]
+    pub fn with_subcommand(mut self, name: &str, completions: CommandCompletions) -> Self {
+        self.subcommands.insert(name.to_string(), completions);
+        self
+    }
+
+    /// Builder adding a flag, e.g. `--message`, shown with its description in the menu
+    #[must_use]
+    pub fn with_flag(mut self, flag: &str, description: Option<&str>) -> Self {
+        self.flags
+            .insert(flag.to_string(), description.map(str::to_string));
+        self
+    }
+}
+
+/// A completer that looks at the arguments before the cursor to decide what to complete.
+///
+/// The first argument is completed with the registered commands. After a command its
+/// subcommands are suggested and an argument starting with `-` is completed with the flags
+/// of the innermost subcommand. Quotes and backslash escapes are honored, and an argument
+/// `|`, `;` or `&&` starts a new command.
+///
+/// # Example
+///
+/// ```rust
+/// use reedline::{CommandCompletions, Completer, ContextualCompleter};
+///
+/// let commit = CommandCompletions::new()
+///     .with_flag("--amend", Some("Replace the tip of the branch"))
+///     .with_flag("--message", Some("Use the given message"));
+/// let git = CommandCompletions::new()
+///     .with_subcommand("commit", commit)
+///     .with_subcommand("checkout", CommandCompletions::new());
+///
+/// let completer = ContextualCompleter::default().with_command("git", git);
+///
+/// let values = |line: &str| -> Vec<String> {
+///     completer
+///         .complete(line, line.len())
+///         .into_iter()
+///         .map(|suggestion| suggestion.value)
+///         .collect()
+/// };
+/// assert_eq!(values("git c"), vec!["checkout", "commit"]);
+/// assert_eq!(values("git commit --m"), vec!["--message"]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ContextualCompleter {
+    commands: BTreeMap<String, CommandCompletions>,
+}
+
+impl ContextualCompleter {
+    /// Builder adding a command with its completions
+    #[must_use]
+    pub fn with_command(mut self, name: &str, completions: CommandCompletions) -> Self {
+        self.commands.insert(name.to_string(), completions);
+        self
+    }
+
+    /// Candidates and their descriptions for the argument following `arguments`
+    fn candidates(&self, arguments: &[String], word: &str) -> Vec<(&String, Option<&String>)> {
+        let (command, arguments) = match arguments.split_first() {
+            Some(split) => split,
+            None => return self.commands.keys().map(|name| (name, None)).collect(),
+        };
+
+        let mut completions = match self.commands.get(command) {
+            Some(completions) => completions,
+            None => return vec![],
+        };
+        let mut positional = false;
+        for argument in arguments {
+            if argument.starts_with('-') {
+                continue;
+            }
+            match completions.subcommands.get(argument) {
+                Some(subcommand) if !positional => completions = subcommand,
+                _ => positional = true,
+            }
+        }
+
+        if word.starts_with('-') {
+            completions
+                .flags
+                .iter()
+                .map(|(flag, description)| (flag, description.as_ref()))
+                .collect()
+        } else if positional {
+            // Subcommands don't follow other arguments
+            vec![]
+        } else {
+            completions
+                .subcommands
+                .keys()
+                .map(|name| (name, None))
+                .collect()
+        }
+    }
+}
+
+impl Completer for ContextualCompleter {
+    fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let span = Span::from_quoted_token(line, pos);
+        let word = unquote(&line[span.start..span.end]);
+
+        let arguments = arguments(&line[..span.start]);
+        let command_start = arguments
+            .iter()
+            .rposition(|argument| matches!(argument.as_str(), "|" | ";" | "&&"))
+            .map_or(0, |separator| separator + 1);
+
+        self.candidates(&arguments[command_start..], &word)
+            .into_iter()
+            .filter(|(value, _)| value.starts_with(&word))
+            .map(|(value, description)| Suggestion {
+                description: description.cloned(),
+                ..Suggestion::new(value.clone(), span)
+            })
+            .collect()
+    }
+}
+
+/// Splits the line into its unquoted arguments
+fn arguments(line: &str) -> Vec<String> {
+    let mut arguments = vec![];
+
+    // Walks backwards over the line, one argument at a time
+    let mut end = line.trim_end().len();
+    while end > 0 {
+        let span = Span::from_quoted_token(line, end);
+        arguments.push(unquote(&line[span.start..end]));
+        end = line[..span.start].trim_end().len();
+    }
+
+    arguments.reverse();
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn git_completer() -> ContextualCompleter {
+        let commit = CommandCompletions::new()
+            .with_flag("--amend", Some("Replace the tip of the branch"))
+            .with_flag("--message", Some("Use the given message"))
+            .with_flag("-m", None);
+        let checkout = CommandCompletions::new().with_flag("-b", Some("Create a new branch"));
+        let remote = CommandCompletions::new()
+            .with_subcommand("add", CommandCompletions::new())
+            .with_subcommand("remove", CommandCompletions::new());
+        let git = CommandCompletions::new()
+            .with_flag("--version", None)
+            .with_subcommand("commit", commit)
+            .with_subcommand("checkout", checkout)
+            .with_subcommand("clone", CommandCompletions::new())
+            .with_subcommand("remote", remote);
+
+        ContextualCompleter::default()
+            .with_command("git", git)
+            .with_command("grep", CommandCompletions::new())
+    }
+
+    fn values(line: &str) -> Vec<String> {
+        git_completer()
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[rstest]
+    #[case("g", &["git", "grep"])]
+    #[case("git c", &["checkout", "clone", "commit"])]
+    #[case("git ", &["checkout", "clone", "commit", "remote"])]
+    #[case("git -", &["--version"])]
+    #[case("git commit -", &["--amend", "--message", "-m"])]
+    #[case("git commit --a", &["--amend"])]
+    #[case("git checkout -", &["-b"])]
+    #[case("git remote a", &["add"])]
+    #[case("git --version c", &["checkout", "clone", "commit"])]
+    #[case("git commit -m \"some message\" --a", &["--amend"])]
+    #[case("ls | git cl", &["clone"])]
+    #[case("make && gi", &["git"])]
+    #[case("git clone c", &[])]
+    #[case("ls -", &[])]
+    fn completes_from_the_preceding_arguments(#[case] line: &str, #[case] expected: &[&str]) {
+        assert_eq!(values(line), expected);
+    }
+
+    #[test]
+    fn flags_carry_their_description() {
+        let suggestions = git_completer().complete("git commit --am", 15);
+
+        assert_eq!(
+            suggestions,
+            vec![Suggestion::new("--amend", Span::new(11, 15))
+                .with_description("Replace the tip of the branch")]
+        );
+    }
+
+    #[test]
+    fn only_the_text_before_the_cursor_counts() {
+        let suggestions = git_completer().complete("git co --amend", 6);
+
+        assert_eq!(
+            suggestions
+                .iter()
+                .map(|suggestion| (suggestion.value.as_str(), suggestion.span))
+                .collect::<Vec<_>>(),
+            vec![("commit", Span::new(4, 6))]
+        );
+    }
+
+    #[rstest]
+    #[case("git commit", &["git", "commit"])]
+    #[case("  git   commit  ", &["git", "commit"])]
+    #[case("cd \"My Documents\" 'a b' c\\ d", &["cd", "My Documents", "a b", "c d"])]
+    #[case("", &[])]
+    fn splits_the_line_into_arguments(#[case] line: &str, #[case] expected: &[&str]) {
+        assert_eq!(arguments(line), expected);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Completer, Span, Suggestion};
+use crate::{CaseSensitivity, Completer, Span, Suggestion};
 use std::{
     collections::{BTreeMap, BTreeSet},
     str::Chars,
@@ -26,6 +26,10 @@ use std::{
 pub struct DefaultCompleter {
     root: CompletionNode,
     min_word_len: usize,
+    inserted_words: usize,
+    case_sensitivity: CaseSensitivity,
+    sort: bool,
+    max_results: Option<usize>,
 }
 
 impl Default for DefaultCompleter {
@@ -34,6 +38,10 @@ impl Default for DefaultCompleter {
         Self {
             root: CompletionNode::new(inclusions),
             min_word_len: 2,
+            inserted_words: 0,
+            case_sensitivity: CaseSensitivity::Sensitive,
+            sort: true,
+            max_results: None,
         }
     }
 }
@@ -55,17 +63,17 @@ impl Completer for DefaultCompleter {
     /// assert_eq!(
     ///     completions.complete("bat",3),
     ///     vec![
-    ///         Suggestion {value: "batcave".into(), description: None, span: Span { start: 0, end: 3 }},
-    ///         Suggestion {value: "batman".into(), description: None, span: Span { start: 0, end: 3 }},
-    ///         Suggestion {value: "batmobile".into(), description: None, span: Span { start: 0, end: 3 }},
+    ///         Suggestion::new("batcave", Span { start: 0, end: 3 }),
+    ///         Suggestion::new("batman", Span { start: 0, end: 3 }),
+    ///         Suggestion::new("batmobile", Span { start: 0, end: 3 }),
     ///     ]);
     ///
     /// assert_eq!(
     ///     completions.complete("to the bat",10),
     ///     vec![
-    ///         Suggestion {value: "batcave".into(), description: None, span: Span { start: 7, end: 10 }},
-    ///         Suggestion {value: "batman".into(), description: None, span: Span { start: 7, end: 10 }},
-    ///         Suggestion {value: "batmobile".into(), description: None, span: Span { start: 7, end: 10 }},
+    ///         Suggestion::new("batcave", Span { start: 7, end: 10 }),
+    ///         Suggestion::new("batman", Span { start: 7, end: 10 }),
+    ///         Suggestion::new("batmobile", Span { start: 7, end: 10 }),
     ///     ]);
     /// ```
     fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
@@ -85,31 +93,40 @@ impl Completer for DefaultCompleter {
                     } else {
                         span_line = format!("{} {}", s, span_line);
                     }
-                    if let Some(mut extensions) = self.root.complete(span_line.chars()) {
-                        extensions.sort();
-                        completions.extend(
-                            extensions
-                                .iter()
-                                .map(|ext| {
-                                    let span = Span::new(
-                                        pos - span_line.len() - span_line_whitespaces,
-                                        pos,
-                                    );
-
-                                    Suggestion {
-                                        value: format!("{}{}", span_line, ext),
-                                        description: None,
-                                        span,
-                                    }
-                                })
-                                .filter(|t| t.value.len() > (t.span.end - t.span.start))
-                                .collect::<Vec<Suggestion>>(),
-                        );
+                    let case_sensitive = self.case_sensitivity.is_case_sensitive(&span_line);
+                    let mut words =
+                        self.root
+                            .complete(span_line.chars(), case_sensitive, String::new());
+                    if self.sort {
+                        if case_sensitive {
+                            words.sort_by(|(a, _), (b, _)| a.cmp(b));
+                        } else {
+                            words.sort_by_cached_key(|(word, _)| {
+                                (word.to_lowercase(), word.clone())
+                            });
+                        }
+                    } else {
+                        words.sort_by_key(|(_, insertion)| *insertion);
                     }
+                    completions.extend(
+                        words
+                            .into_iter()
+                            .map(|(word, _)| {
+                                let span =
+                                    Span::new(pos - span_line.len() - span_line_whitespaces, pos);
+
+                                Suggestion::new(word, span)
+                            })
+                            .filter(|t| t.value.len() > (t.span.end - t.span.start))
+                            .collect::<Vec<Suggestion>>(),
+                    );
                 }
             }
         }
         completions.dedup();
+        if let Some(max_results) = self.max_results {
+            completions.truncate(max_results);
+        }
         completions
     }
 }
@@ -130,6 +147,8 @@ impl DefaultCompleter {
 
     /// Insert `external_commands` list in the object root
     ///
+    /// The words are added to the existing ones, so new commands can be registered at any time
+    ///
     /// # Arguments
     ///
     /// * `line`    A vector of `String` containing the external commands
@@ -150,7 +169,8 @@ impl DefaultCompleter {
     pub fn insert(&mut self, words: Vec<String>) {
         for word in words {
             if word.len() >= self.min_word_len {
-                self.root.insert(word.chars());
+                self.root.insert(word.chars(), self.inserted_words);
+                self.inserted_words += 1;
             }
         }
     }
@@ -171,15 +191,15 @@ impl DefaultCompleter {
     /// completions.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completions.complete("te",2),
-    ///     vec![Suggestion {value: "test".into(), description: None, span: Span { start: 0, end: 2 }}]);
+    ///     vec![Suggestion::new("test", Span { start: 0, end: 2 })]);
     ///
     /// let mut completions = DefaultCompleter::with_inclusions(&['-', '_']);
     /// completions.insert(vec!["test-hyphen","test_underscore"].iter().map(|s| s.to_string()).collect());
     /// assert_eq!(
     ///     completions.complete("te",2),
     ///     vec![
-    ///         Suggestion {value: "test-hyphen".into(), description: None, span: Span { start: 0, end: 2 }},
-    ///         Suggestion {value: "test_underscore".into(), description: None, span: Span { start: 0, end: 2 }},
+    ///         Suggestion::new("test-hyphen", Span { start: 0, end: 2 }),
+    ///         Suggestion::new("test_underscore", Span { start: 0, end: 2 }),
     ///     ]);
     /// ```
     pub fn with_inclusions(incl: &[char]) -> Self {
@@ -192,6 +212,39 @@ impl DefaultCompleter {
         }
     }
 
+    /// Removes a single word, keeping all other words that start with it.
+    /// Returns whether the word was known
+    ///
+    /// # Example
+    /// ```
+    /// use reedline::{DefaultCompleter,Completer};
+    ///
+    /// let mut completions = DefaultCompleter::default();
+    /// completions.insert(vec!["bat","batman"].iter().map(|s| s.to_string()).collect());
+    /// assert!(completions.remove("bat"));
+    /// assert!(!completions.contains("bat"));
+    /// assert!(completions.contains("batman"));
+    /// assert!(!completions.remove("robin"));
+    /// ```
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.root.remove(word.chars())
+    }
+
+    /// Whether `word` is known to the completer
+    pub fn contains(&self, word: &str) -> bool {
+        self.root.contains(word.chars())
+    }
+
+    /// Returns the number of known words
+    pub fn len(&self) -> usize {
+        self.root.word_count() as usize
+    }
+
+    /// Whether no words are known
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty()
+    }
+
     /// Clears all the data from the tree
     /// # Example
     /// ```
@@ -207,6 +260,7 @@ impl DefaultCompleter {
     /// ```
     pub fn clear(&mut self) {
         self.root.clear();
+        self.inserted_words = 0;
     }
 
     /// Returns a count of how many words that exist in the tree
@@ -265,12 +319,50 @@ impl DefaultCompleter {
         self.min_word_len = len;
         self
     }
+
+    /// A builder that sets how the case of the typed word is compared to the known words.
+    /// The suggestions keep the case of the known words.
+    ///
+    /// Defaults to [`CaseSensitivity::Sensitive`]
+    ///
+    /// # Example
+    /// ```
+    /// use reedline::{CaseSensitivity,DefaultCompleter,Completer};
+    ///
+    /// let mut completions = DefaultCompleter::default().with_case_sensitivity(CaseSensitivity::Insensitive);
+    /// completions.insert(vec!["Batman","batmobile"].iter().map(|s| s.to_string()).collect());
+    /// let values: Vec<String> = completions.complete("bat", 3).into_iter().map(|s| s.value).collect();
+    /// assert_eq!(values, vec!["Batman", "batmobile"]);
+    /// ```
+    #[must_use]
+    pub fn with_case_sensitivity(mut self, case_sensitivity: CaseSensitivity) -> Self {
+        self.case_sensitivity = case_sensitivity;
+        self
+    }
+
+    /// A builder that sets whether the suggestions for a word are sorted alphabetically,
+    /// otherwise they are kept in the order the words were inserted.
+    ///
+    /// Defaults to `true`. Sorting ignores the case unless the word is matched case sensitive
+    #[must_use]
+    pub fn with_sorting(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// A builder that limits the number of suggestions returned by `complete`
+    #[must_use]
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
 struct CompletionNode {
     subnodes: BTreeMap<char, CompletionNode>,
-    leaf: bool,
+    // Position in the order of insertion of the word ending at this node
+    leaf: Option<usize>,
     inclusions: Arc<BTreeSet<char>>,
 }
 
@@ -278,7 +370,7 @@ impl CompletionNode {
     fn new(incl: Arc<BTreeSet<char>>) -> Self {
         Self {
             subnodes: BTreeMap::new(),
-            leaf: false,
+            leaf: None,
             inclusions: incl,
         }
     }
@@ -289,7 +381,7 @@ impl CompletionNode {
 
     fn word_count(&self) -> u32 {
         let mut count = self.subnodes.values().map(CompletionNode::word_count).sum();
-        if self.leaf {
+        if self.leaf.is_some() {
             count += 1;
         }
         count
@@ -303,39 +395,88 @@ impl CompletionNode {
             + 1
     }
 
-    fn insert(&mut self, mut iter: Chars) {
+    fn is_empty(&self) -> bool {
+        self.leaf.is_none() && self.subnodes.is_empty()
+    }
+
+    /// Words are cut off at the first character which is not part of the tree
+    fn includes(&self, c: char) -> bool {
+        self.inclusions.contains(&c) || c.is_alphanumeric() || c.is_whitespace()
+    }
+
+    fn insert(&mut self, mut iter: Chars, insertion: usize) {
         if let Some(c) = iter.next() {
-            if self.inclusions.contains(&c) || c.is_alphanumeric() || c.is_whitespace() {
+            if self.includes(c) {
                 let inclusions = self.inclusions.clone();
                 let subnode = self
                     .subnodes
                     .entry(c)
                     .or_insert_with(|| CompletionNode::new(inclusions));
-                subnode.insert(iter);
+                subnode.insert(iter, insertion);
             } else {
-                self.leaf = true;
+                self.leaf.get_or_insert(insertion);
             }
         } else {
-            self.leaf = true;
+            self.leaf.get_or_insert(insertion);
         }
     }
 
-    fn complete(&self, mut iter: Chars) -> Option<Vec<String>> {
-        if let Some(c) = iter.next() {
-            if let Some(subnode) = self.subnodes.get(&c) {
-                subnode.complete(iter)
-            } else {
-                None
+    /// Removes the word of `iter` and prunes the nodes that are no longer part of any word
+    fn remove(&mut self, mut iter: Chars) -> bool {
+        match iter.next() {
+            Some(c) if self.includes(c) => {
+                let removed = match self.subnodes.get_mut(&c) {
+                    Some(subnode) => subnode.remove(iter),
+                    None => false,
+                };
+                if removed && self.subnodes[&c].is_empty() {
+                    self.subnodes.remove(&c);
+                }
+                removed
             }
+            _ => self.leaf.take().is_some(),
+        }
+    }
+
+    fn contains(&self, mut iter: Chars) -> bool {
+        match iter.next() {
+            Some(c) if self.includes(c) => self
+                .subnodes
+                .get(&c)
+                .is_some_and(|subnode| subnode.contains(iter)),
+            _ => self.leaf.is_some(),
+        }
+    }
+
+    /// Words starting with the characters of `iter`, together with their insertion position.
+    /// `matched` holds the characters of the words matched so far
+    fn complete(
+        &self,
+        mut iter: Chars,
+        case_sensitive: bool,
+        matched: String,
+    ) -> Vec<(String, usize)> {
+        if let Some(c) = iter.next() {
+            self.subnodes
+                .iter()
+                .filter(|(key, _)| {
+                    **key == c || !case_sensitive && key.to_lowercase().eq(c.to_lowercase())
+                })
+                .flat_map(|(key, subnode)| {
+                    let mut matched = matched.clone();
+                    matched.push(*key);
+                    subnode.complete(iter.clone(), case_sensitive, matched)
+                })
+                .collect()
         } else {
-            Some(self.collect(""))
+            self.collect(&matched)
         }
     }
 
-    fn collect(&self, partial: &str) -> Vec<String> {
+    fn collect(&self, partial: &str) -> Vec<(String, usize)> {
         let mut completions = vec![];
-        if self.leaf {
-            completions.push(partial.to_string());
+        if let Some(insertion) = self.leaf {
+            completions.push((partial.to_string(), insertion));
         }
 
         if !self.subnodes.is_empty() {
@@ -351,6 +492,111 @@ impl CompletionNode {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn mixed_case_completer() -> DefaultCompleter {
+        let mut completer = DefaultCompleter::default();
+        completer.insert(
+            ["gitk", "Github", "GitLab", "gist", "grep"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        completer
+    }
+
+    fn values(completer: &DefaultCompleter, line: &str) -> Vec<String> {
+        completer
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect()
+    }
+
+    #[rstest]
+    #[case(CaseSensitivity::Sensitive, "gi", vec!["gist", "gitk"])]
+    #[case(CaseSensitivity::Sensitive, "Gi", vec!["GitLab", "Github"])]
+    #[case(CaseSensitivity::Insensitive, "gi", vec!["gist", "Github", "gitk", "GitLab"])]
+    #[case(CaseSensitivity::Insensitive, "GIT", vec!["Github", "gitk", "GitLab"])]
+    #[case(CaseSensitivity::SmartCase, "gi", vec!["gist", "Github", "gitk", "GitLab"])]
+    #[case(CaseSensitivity::SmartCase, "Gi", vec!["GitLab", "Github"])]
+    fn suggestions_follow_the_case_sensitivity(
+        #[case] case_sensitivity: CaseSensitivity,
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        let completer = mixed_case_completer().with_case_sensitivity(case_sensitivity);
+
+        assert_eq!(values(&completer, line), expected);
+    }
+
+    #[test]
+    fn unsorted_suggestions_keep_the_insertion_order() {
+        let completer = mixed_case_completer()
+            .with_case_sensitivity(CaseSensitivity::Insensitive)
+            .with_sorting(false);
+
+        assert_eq!(
+            values(&completer, "gi"),
+            vec!["gitk", "Github", "GitLab", "gist"]
+        );
+    }
+
+    #[rstest]
+    #[case(true, vec!["gist", "Github"])]
+    #[case(false, vec!["gitk", "Github"])]
+    fn max_results_limits_the_suggestions(#[case] sort: bool, #[case] expected: Vec<&str>) {
+        let completer = mixed_case_completer()
+            .with_case_sensitivity(CaseSensitivity::Insensitive)
+            .with_sorting(sort)
+            .with_max_results(2);
+
+        assert_eq!(values(&completer, "gi"), expected);
+    }
+
+    #[test]
+    fn lookups_follow_inserts_and_removes() {
+        let mut completer = DefaultCompleter::default();
+        assert!(completer.is_empty());
+
+        completer.insert(vec!["batman".into(), "batcave".into()]);
+        let size = completer.size();
+        completer.insert(vec!["bat".into(), "batmobile".into()]);
+        assert_eq!(completer.len(), 4);
+
+        assert!(completer.remove("batmobile"));
+        assert!(completer.remove("bat"));
+        assert!(!completer.remove("bat"));
+        assert!(!completer.remove("batma"));
+        assert_eq!(completer.size(), size);
+
+        completer.insert(vec!["robin".into()]);
+        assert_eq!(completer.len(), 3);
+        assert!(completer.contains("robin"));
+        assert!(!completer.contains("bat"));
+        assert_eq!(values(&completer, "bat"), vec!["batcave", "batman"]);
+        assert_eq!(values(&completer, "ro"), vec!["robin"]);
+
+        for word in ["batman", "batcave", "robin"] {
+            assert!(completer.remove(word));
+        }
+        assert!(completer.is_empty());
+        assert_eq!(completer.size(), 1);
+        assert!(values(&completer, "b").is_empty());
+    }
+
+    #[test]
+    fn span_covers_the_matched_word() {
+        let completer = mixed_case_completer().with_case_sensitivity(CaseSensitivity::Insensitive);
+
+        assert_eq!(
+            completer.complete("echo GITL", 9),
+            vec![Suggestion::new("GitLab", Span { start: 5, end: 9 })]
+        );
+    }
+
     #[test]
     fn default_completer_with_non_ansi() {
         use super::*;
@@ -366,21 +612,9 @@ mod tests {
         assert_eq!(
             completions.complete("ｎ", 3),
             vec![
-                Suggestion {
-                    value: "ｎｕｌｌ".into(),
-                    description: None,
-                    span: Span { start: 0, end: 3 },
-                },
-                Suggestion {
-                    value: "ｎｕｍｂｅｒ".into(),
-                    description: None,
-                    span: Span { start: 0, end: 3 },
-                },
-                Suggestion {
-                    value: "ｎｕｓｈｅｌｌ".into(),
-                    description: None,
-                    span: Span { start: 0, end: 3 },
-                },
+                Suggestion::new("ｎｕｌｌ", Span { start: 0, end: 3 }),
+                Suggestion::new("ｎｕｍｂｅｒ", Span { start: 0, end: 3 }),
+                Suggestion::new("ｎｕｓｈｅｌｌ", Span { start: 0, end: 3 }),
             ]
         );
     }
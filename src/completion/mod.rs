@@ -1,7 +1,9 @@
 mod base;
 mod circular;
+mod contextual;
 mod default;
 
-pub use base::{Completer, Span, Suggestion};
+pub use base::{unquote, Completer, Span, Suggestion};
 pub use circular::CircularCompletionHandler;
+pub use contextual::{CommandCompletions, ContextualCompleter};
 pub use default::DefaultCompleter;
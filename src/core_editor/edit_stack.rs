@@ -61,6 +61,11 @@ where
     pub(super) fn current(&mut self) -> &T {
         &self.internal_list[self.index]
     }
+
+    /// Return a mutable reference to the entry currently being pointed to
+    pub(super) fn current_mut(&mut self) -> &mut T {
+        &mut self.internal_list[self.index]
+    }
 }
 
 #[cfg(test)]
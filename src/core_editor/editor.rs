@@ -1,5 +1,5 @@
 use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
-use crate::{core_editor::get_default_clipboard, EditCommand, UndoBehavior};
+use crate::{core_editor::get_default_clipboard, EditCommand, IndentUnit, UndoBehavior};
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -10,6 +10,14 @@ pub struct Editor {
     cut_buffer: Box<dyn Clipboard>,
 
     edit_stack: EditStack<LineBuffer>,
+    last_undo_behavior: UndoBehavior,
+    // The selection was started by the `Select*` commands (e.g. shift + arrow keys)
+    shift_selection: bool,
+    // Copy and cut the current line if there is no selection
+    copy_line_fallback: bool,
+    // Graphemes overwritten in overtype mode, `None` for characters appended at a line end
+    overtyped: Vec<Option<String>>,
+    indent_unit: IndentUnit,
 }
 
 impl Default for Editor {
@@ -18,6 +26,11 @@ impl Default for Editor {
             line_buffer: LineBuffer::new(),
             cut_buffer: Box::new(get_default_clipboard()),
             edit_stack: EditStack::new(),
+            last_undo_behavior: UndoBehavior::Full,
+            shift_selection: false,
+            copy_line_fallback: true,
+            overtyped: Vec::new(),
+            indent_unit: IndentUnit::default(),
         }
     }
 }
@@ -32,39 +45,91 @@ impl Editor {
     }
 
     pub fn run_edit_command(&mut self, command: &EditCommand) {
+        if self.shift_selection {
+            self.end_shift_selection(command);
+        }
+
         match command {
             EditCommand::MoveToStart => self.line_buffer.move_to_start(),
             EditCommand::MoveToLineStart => self.line_buffer.move_to_line_start(),
             EditCommand::MoveToEnd => self.line_buffer.move_to_end(),
             EditCommand::MoveToLineEnd => self.line_buffer.move_to_line_end(),
+            EditCommand::MoveToLine(line) => self.line_buffer.move_to_line(*line),
             EditCommand::MoveToPosition(pos) => self.line_buffer.set_insertion_point(*pos),
             EditCommand::MoveLeft => self.line_buffer.move_left(),
             EditCommand::MoveRight => self.line_buffer.move_right(),
             EditCommand::MoveWordLeft => self.line_buffer.move_word_left(),
             EditCommand::MoveWordRight => self.line_buffer.move_word_right(),
+            EditCommand::MoveSmallWordLeft => self.line_buffer.move_small_word_left(),
+            EditCommand::MoveSmallWordRight => self.line_buffer.move_small_word_right(),
+            EditCommand::MoveBigWordLeft => self.line_buffer.move_big_word_left(),
+            EditCommand::MoveBigWordRight => self.line_buffer.move_big_word_right(),
+            EditCommand::MoveToWordEnd => self.line_buffer.move_to_word_end(),
+            EditCommand::MoveToBigWordEnd => self.line_buffer.move_to_big_word_end(),
+            EditCommand::MoveToPreviousWordEnd => self.line_buffer.move_to_previous_word_end(),
+            EditCommand::MoveToPreviousBigWordEnd => {
+                self.line_buffer.move_to_previous_big_word_end();
+            }
             EditCommand::InsertChar(c) => self.insert_char(*c),
             EditCommand::InsertString(str) => self.line_buffer.insert_str(str),
+            EditCommand::InsertNewline => self.insert_newline(),
+            EditCommand::InsertNewlineWithIndent => self.insert_newline_with_indent(),
             EditCommand::ReplaceChars(n_chars, str) => self.replace_chars(*n_chars, str),
+            EditCommand::ReplaceChar(c) => {
+                let _ = self.line_buffer.replace_char_at_cursor(*c);
+            }
+            EditCommand::OvertypeChar(c) => self.overtype_char(*c),
+            EditCommand::OvertypeBackspace => self.overtype_backspace(),
             EditCommand::Backspace => self.line_buffer.delete_left_grapheme(),
             EditCommand::Delete => self.line_buffer.delete_right_grapheme(),
             EditCommand::BackspaceWord => self.line_buffer.delete_word_left(),
             EditCommand::DeleteWord => self.line_buffer.delete_word_right(),
+            EditCommand::DeleteToMatchingBracket => self.line_buffer.delete_to_matching_bracket(),
             EditCommand::Clear => self.line_buffer.clear(),
             EditCommand::ClearToLineEnd => self.line_buffer.clear_to_line_end(),
+            EditCommand::ClearToBufferEnd => self.clear_to_end(),
+            EditCommand::ClearToBufferStart => self.clear_to_insertion_point(),
             EditCommand::CutCurrentLine => self.cut_current_line(),
+            EditCommand::KillWholeLine => self.kill_whole_line(),
+            EditCommand::JoinLines => self.line_buffer.join_lines(),
+            EditCommand::Indent => self.line_buffer.indent_lines(&self.indent_unit.text()),
+            EditCommand::Dedent => self.line_buffer.dedent_lines(&self.indent_unit.text()),
+            EditCommand::OpenLineBelow => self.line_buffer.open_line_below(),
+            EditCommand::OpenLineAbove => self.line_buffer.open_line_above(),
             EditCommand::CutFromStart => self.cut_from_start(),
             EditCommand::CutFromLineStart => self.cut_from_line_start(),
             EditCommand::CutToEnd => self.cut_from_end(),
             EditCommand::CutToLineEnd => self.cut_to_line_end(),
             EditCommand::CutWordLeft => self.cut_word_left(),
             EditCommand::CutWordRight => self.cut_word_right(),
+            EditCommand::CutToWordEnd => self.cut_to_word_end(false),
+            EditCommand::CutToBigWordEnd => self.cut_to_word_end(true),
             EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
             EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
             EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
             EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
             EditCommand::CapitalizeChar => self.line_buffer.capitalize_char(),
+            EditCommand::UppercaseToLineEnd => self.line_buffer.uppercase_to_line_end(),
+            EditCommand::LowercaseToLineEnd => self.line_buffer.lowercase_to_line_end(),
+            EditCommand::ToggleCaseChar => self.line_buffer.toggle_case_char(),
             EditCommand::SwapWords => self.line_buffer.swap_words(),
+            EditCommand::TransposeWords => self.line_buffer.transpose_words(),
             EditCommand::SwapGraphemes => self.line_buffer.swap_graphemes(),
+            EditCommand::SetSelectionAnchor => {
+                self.shift_selection = false;
+                self.line_buffer.set_anchor();
+            }
+            EditCommand::SelectLeft => self.extend_selection(LineBuffer::move_left),
+            EditCommand::SelectRight => self.extend_selection(LineBuffer::move_right),
+            EditCommand::SelectToLineStart => self.extend_selection(LineBuffer::move_to_line_start),
+            EditCommand::SelectToLineEnd => self.extend_selection(LineBuffer::move_to_line_end),
+            EditCommand::ClearSelection => self.line_buffer.clear_anchor(),
+            EditCommand::DeleteSelection => self.line_buffer.delete_selection(),
+            EditCommand::YankSelection => self.yank_selection(),
+            EditCommand::UppercaseSelection => self.line_buffer.uppercase_selection(),
+            EditCommand::CopySelection => self.copy_selection(false),
+            EditCommand::CutSelection => self.copy_selection(true),
+            EditCommand::CopyCurrentLine => self.copy_current_line(),
             EditCommand::Undo => self.undo(),
             EditCommand::Redo => self.redo(),
             EditCommand::CutRightUntil(c) => self.cut_right_until_char(*c, false, true),
@@ -76,15 +141,30 @@ impl Editor {
             EditCommand::MoveLeftUntil(c) => self.move_left_until_char(*c, false, true),
             EditCommand::MoveLeftBefore(c) => self.move_left_until_char(*c, true, true),
         }
-        match command.undo_behavior() {
+        if !matches!(
+            command,
+            EditCommand::OvertypeChar(_) | EditCommand::OvertypeBackspace
+        ) {
+            self.end_overtype();
+        }
+
+        let undo_behavior = command.undo_behavior();
+        // Changes to the buffer end a selection, as the anchor may no longer be valid
+        if matches!(undo_behavior, UndoBehavior::Full | UndoBehavior::Coalesce) {
+            self.line_buffer.clear_anchor();
+        }
+        match undo_behavior {
             UndoBehavior::Ignore => {}
             UndoBehavior::Full => {
                 self.remember_undo_state(true);
             }
             UndoBehavior::Coalesce => {
-                self.remember_undo_state(false);
+                // Typing after moving the cursor starts a new undo entry
+                self.remember_undo_state(self.last_undo_behavior == UndoBehavior::MoveCursor);
             }
+            UndoBehavior::MoveCursor => self.remember_cursor_position(),
         }
+        self.last_undo_behavior = undo_behavior;
     }
 
     pub fn move_line_up(&mut self) {
@@ -99,6 +179,48 @@ impl Editor {
         self.line_buffer.insert_char(c);
     }
 
+    /// Overwrites the grapheme under the cursor and moves behind it, appending at a line end
+    fn overtype_char(&mut self, c: char) {
+        let replaced = self.line_buffer.overtype_char(c);
+        self.overtyped.push(replaced);
+    }
+
+    /// Moves left, restoring the grapheme overwritten there during the current overtype
+    fn overtype_backspace(&mut self) {
+        match self.overtyped.pop() {
+            Some(Some(original)) => {
+                self.line_buffer.move_left();
+                let insertion_point = self.line_buffer.insertion_point();
+                let grapheme_end = self.line_buffer.grapheme_right_index();
+                self.line_buffer
+                    .replace_range(insertion_point..grapheme_end, &original);
+            }
+            Some(None) => self.line_buffer.delete_left_grapheme(),
+            None => self.line_buffer.move_left(),
+        }
+    }
+
+    /// Forgets the overwritten graphemes, backspace no longer restores them
+    pub fn end_overtype(&mut self) {
+        self.overtyped.clear();
+    }
+
+    /// Starts a new line that reuses the indentation of the current line
+    pub fn insert_newline_with_indent(&mut self) {
+        let indent = self.line_buffer.current_line_indent().to_string();
+        self.insert_newline();
+        self.line_buffer.insert_str(&indent);
+    }
+
+    /// Inserts the line break of the platform
+    fn insert_newline(&mut self) {
+        #[cfg(windows)]
+        {
+            self.line_buffer.insert_char('\r');
+        }
+        self.line_buffer.insert_char('\n');
+    }
+
     /// Directly change the cursor position measured in bytes in the buffer
     ///
     /// ## Unicode safety:
@@ -130,6 +252,28 @@ impl Editor {
         self.line_buffer.clear_range(range);
     }
 
+    /// Replace the content of the cut buffer, pasted like cut text
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        self.cut_buffer.set(text, ClipboardMode::Normal);
+    }
+
+    /// Content of the cut buffer, `None` if it is empty
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        let (content, _) = self.cut_buffer.get();
+        (!content.is_empty()).then_some(content)
+    }
+
+    /// Set whether [`EditCommand::CopySelection`] and [`EditCommand::CutSelection`]
+    /// use the current line when nothing is selected
+    pub fn set_copy_line_fallback(&mut self, copy_line_fallback: bool) {
+        self.copy_line_fallback = copy_line_fallback;
+    }
+
+    /// Set the whitespace used by [`EditCommand::Indent`] and [`EditCommand::Dedent`]
+    pub fn set_indent_unit(&mut self, indent_unit: IndentUnit) {
+        self.indent_unit = indent_unit;
+    }
+
     pub fn insertion_point(&self) -> usize {
         self.line_buffer.insertion_point()
     }
@@ -174,11 +318,13 @@ impl Editor {
     fn undo(&mut self) {
         let val = self.edit_stack.undo();
         self.line_buffer = val.clone();
+        self.line_buffer.clear_anchor();
     }
 
     fn redo(&mut self) {
         let val = self.edit_stack.redo();
         self.line_buffer = val.clone();
+        self.line_buffer.clear_anchor();
     }
 
     pub fn remember_undo_state(&mut self, is_after_action: bool) -> Option<()> {
@@ -192,6 +338,95 @@ impl Editor {
         Some(())
     }
 
+    /// Stores the cursor position with the current undo entry
+    ///
+    /// If the buffer was changed without being recorded, e.g. by replacing it
+    /// with a history entry, a new entry is added instead
+    fn remember_cursor_position(&mut self) {
+        let current = self.edit_stack.current_mut();
+        if current.get_buffer() == self.line_buffer.get_buffer() {
+            current.set_insertion_point(self.line_buffer.insertion_point());
+        } else {
+            self.edit_stack.insert(self.line_buffer.clone());
+        }
+    }
+
+    /// Anchors a selection if there is none and moves the cursor to extend it
+    fn extend_selection(&mut self, movement: fn(&mut LineBuffer)) {
+        if self.line_buffer.selection_range().is_none() {
+            self.line_buffer.set_anchor();
+        }
+        self.shift_selection = true;
+        movement(&mut self.line_buffer);
+    }
+
+    /// Handles a selection started by the `Select*` commands before running `command`
+    ///
+    /// Plain cursor moves end the selection, inserted text replaces it
+    fn end_shift_selection(&mut self, command: &EditCommand) {
+        match command {
+            EditCommand::SelectLeft
+            | EditCommand::SelectRight
+            | EditCommand::SelectToLineStart
+            | EditCommand::SelectToLineEnd
+            | EditCommand::DeleteSelection
+            | EditCommand::UppercaseSelection
+            | EditCommand::CutSelection => return,
+            EditCommand::InsertChar(_) | EditCommand::InsertString(_) => {
+                self.line_buffer.delete_selection();
+            }
+            _ => {}
+        }
+        if command.undo_behavior() != UndoBehavior::Ignore {
+            self.line_buffer.clear_anchor();
+            self.shift_selection = false;
+        }
+    }
+
+    /// Copies the selected text into the cut buffer and ends the selection
+    fn yank_selection(&mut self) {
+        if let Some(range) = self.line_buffer.selection_range() {
+            let selection = &self.line_buffer.get_buffer()[range];
+            if !selection.is_empty() {
+                self.cut_buffer.set(selection, ClipboardMode::Normal);
+            }
+        }
+        self.line_buffer.clear_anchor();
+    }
+
+    /// Copies the selected text to the clipboard and removes it from the buffer if `cut` is set
+    ///
+    /// Without a selection the current line is used, unless the fallback is turned off
+    fn copy_selection(&mut self, cut: bool) {
+        match self.line_buffer.selection_range() {
+            Some(range) if !range.is_empty() => {
+                let selection = &self.line_buffer.get_buffer()[range.clone()];
+                self.cut_buffer.set(selection, ClipboardMode::Normal);
+                if cut {
+                    self.set_insertion_point(range.start);
+                    self.clear_range(range);
+                }
+            }
+            _ if self.copy_line_fallback => {
+                if cut {
+                    self.cut_current_line();
+                } else {
+                    self.copy_current_line();
+                }
+            }
+            _ => {}
+        }
+        self.line_buffer.clear_anchor();
+    }
+
+    fn copy_current_line(&mut self) {
+        let line_range = self.line_buffer.current_line_range();
+        let line = &self.line_buffer.get_buffer()[line_range];
+        if !line.is_empty() {
+            self.cut_buffer.set(line, ClipboardMode::Lines);
+        }
+    }
+
     fn cut_current_line(&mut self) {
         let deletion_range = self.line_buffer.current_line_range();
 
@@ -203,6 +438,13 @@ impl Editor {
         }
     }
 
+    fn kill_whole_line(&mut self) {
+        let killed = self.line_buffer.kill_whole_line();
+        if !killed.is_empty() {
+            self.cut_buffer.set(&killed, ClipboardMode::Normal);
+        }
+    }
+
     fn cut_from_start(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         if insertion_offset > 0 {
@@ -269,6 +511,28 @@ impl Editor {
         }
     }
 
+    /// Cuts through the last character of the next word, including it
+    fn cut_to_word_end(&mut self, big_word: bool) {
+        let insertion_offset = self.line_buffer.insertion_point();
+        let word_end = if big_word {
+            self.line_buffer.big_word_end_right_index()
+        } else {
+            self.line_buffer.word_end_right_index()
+        };
+        let cut_end = self.line_buffer.get_buffer()[word_end..]
+            .chars()
+            .next()
+            .map_or(word_end, |c| word_end + c.len_utf8());
+        if cut_end > insertion_offset {
+            let cut_range = insertion_offset..cut_end;
+            self.cut_buffer.set(
+                &self.line_buffer.get_buffer()[cut_range.clone()],
+                ClipboardMode::Normal,
+            );
+            self.clear_range(cut_range);
+        }
+    }
+
     fn insert_cut_buffer_before(&mut self) {
         match self.cut_buffer.get() {
             (content, ClipboardMode::Normal) => {
@@ -374,6 +638,8 @@ impl Editor {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::core_editor::clip_buffer::LocalClipboard;
+    use pretty_assertions::assert_eq;
 
     fn editor_with(buffer: &str) -> Editor {
         let mut editor = Editor::default();
@@ -385,6 +651,38 @@ mod test {
         s.chars().map(|c| EditCommand::InsertChar(c)).collect()
     }
 
+    #[test]
+    fn clear_to_buffer_end_crosses_newlines() {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.line_buffer.set_insertion_point(5);
+
+        editor.run_edit_command(&EditCommand::ClearToBufferEnd);
+
+        assert_eq!(editor.get_buffer(), "one\nt");
+        assert_eq!(editor.insertion_point(), 5);
+    }
+
+    #[test]
+    fn clear_to_buffer_start_crosses_newlines() {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.line_buffer.set_insertion_point(5);
+
+        editor.run_edit_command(&EditCommand::ClearToBufferStart);
+
+        assert_eq!(editor.get_buffer(), "wo\nthree");
+        assert_eq!(editor.insertion_point(), 0);
+    }
+
+    #[test]
+    fn clear_to_line_end_stops_at_the_newline() {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.line_buffer.set_insertion_point(5);
+
+        editor.run_edit_command(&EditCommand::ClearToLineEnd);
+
+        assert_eq!(editor.get_buffer(), "one\nt\nthree");
+    }
+
     #[test]
     fn test_undo_works_on_work_boundries() {
         let mut editor = editor_with("This is a");
@@ -408,4 +706,242 @@ mod test {
         editor.run_edit_command(&EditCommand::Redo);
         assert_eq!(editor.get_buffer(), "This is a test");
     }
+
+    #[test]
+    fn test_undo_restores_cursor_position() {
+        let mut editor = Editor::default();
+        editor.run_edit_command(&EditCommand::InsertString("hello world".into()));
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::MoveWordRight);
+        assert_eq!(editor.insertion_point(), 5);
+
+        editor.run_edit_command(&EditCommand::DeleteWord);
+        assert_eq!(editor.get_buffer(), "hello");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "hello world");
+        assert_eq!(editor.insertion_point(), 5);
+
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "hello");
+        assert_eq!(editor.insertion_point(), 5);
+    }
+
+    #[test]
+    fn test_undo_of_typing_after_cursor_move_restores_cursor() {
+        let mut editor = Editor::default();
+        for cmd in str_to_edit_commands("abc") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        for cmd in str_to_edit_commands("xy") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "axybc");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "abc");
+        assert_eq!(editor.insertion_point(), 1);
+    }
+
+    #[test]
+    fn test_cursor_moves_are_not_separate_undo_steps() {
+        let mut editor = Editor::default();
+        editor.run_edit_command(&EditCommand::InsertString("one".into()));
+        editor.run_edit_command(&EditCommand::InsertString(" two".into()));
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        editor.run_edit_command(&EditCommand::MoveToStart);
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "one");
+        assert_eq!(editor.insertion_point(), 3);
+    }
+
+    #[test]
+    fn test_yank_selection_fills_cut_buffer() {
+        let mut editor = editor_with("This is a test");
+        editor.run_edit_command(&EditCommand::MoveWordLeft);
+        editor.run_edit_command(&EditCommand::SetSelectionAnchor);
+        editor.run_edit_command(&EditCommand::MoveToEnd);
+        editor.run_edit_command(&EditCommand::YankSelection);
+        assert_eq!(editor.line_buffer().selection_range(), None);
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "testThis is a test");
+    }
+
+    #[test]
+    fn test_edits_end_the_selection() {
+        let mut editor = editor_with("This is a test");
+        editor.run_edit_command(&EditCommand::SetSelectionAnchor);
+        editor.run_edit_command(&EditCommand::MoveLeft);
+        assert_eq!(editor.line_buffer().selection_range(), Some(13..14));
+
+        editor.run_edit_command(&EditCommand::InsertChar('x'));
+        assert_eq!(editor.line_buffer().selection_range(), None);
+        assert_eq!(editor.get_buffer(), "This is a tesxt");
+    }
+
+    #[test]
+    fn test_typing_replaces_shift_selection() {
+        let mut editor = editor_with("hello world");
+        for _ in 0..5 {
+            editor.run_edit_command(&EditCommand::SelectLeft);
+        }
+        assert_eq!(editor.line_buffer().selection_range(), Some(6..11));
+
+        editor.run_edit_command(&EditCommand::InsertChar('X'));
+        assert_eq!(editor.get_buffer(), "hello X");
+        assert_eq!(editor.line_buffer().selection_range(), None);
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "hello world");
+    }
+
+    #[test]
+    fn test_plain_move_ends_shift_selection() {
+        let mut editor = editor_with("hello world");
+        editor.run_edit_command(&EditCommand::SelectToLineStart);
+        assert_eq!(editor.line_buffer().selection_range(), Some(0..11));
+
+        editor.run_edit_command(&EditCommand::MoveRight);
+        assert_eq!(editor.line_buffer().selection_range(), None);
+
+        editor.run_edit_command(&EditCommand::InsertChar('X'));
+        assert_eq!(editor.get_buffer(), "hXello world");
+    }
+
+    fn editor_with_clipboard(buffer: &str) -> Editor {
+        let mut editor = editor_with(buffer);
+        editor.cut_buffer = Box::new(LocalClipboard::new());
+        editor
+    }
+
+    #[test]
+    fn test_copy_selection_keeps_buffer() {
+        let mut editor = editor_with_clipboard("hello world");
+        editor.run_edit_command(&EditCommand::SelectToLineStart);
+        editor.run_edit_command(&EditCommand::CopySelection);
+
+        assert_eq!(editor.get_buffer(), "hello world");
+        assert_eq!(editor.cut_buffer.get().0, "hello world");
+        assert_eq!(editor.line_buffer().selection_range(), None);
+    }
+
+    #[test]
+    fn test_cut_selection_removes_range() {
+        let mut editor = editor_with_clipboard("hello world");
+        for _ in 0..6 {
+            editor.run_edit_command(&EditCommand::SelectLeft);
+        }
+        editor.run_edit_command(&EditCommand::CutSelection);
+
+        assert_eq!(editor.get_buffer(), "hello");
+        assert_eq!(editor.insertion_point(), 5);
+        assert_eq!(editor.cut_buffer.get().0, " world");
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), " worldhello");
+    }
+
+    #[test]
+    fn test_copy_and_cut_fall_back_to_current_line() {
+        let mut editor = editor_with_clipboard("first\nsecond");
+        editor.run_edit_command(&EditCommand::MoveToStart);
+
+        editor.run_edit_command(&EditCommand::CopySelection);
+        assert_eq!(editor.get_buffer(), "first\nsecond");
+        assert_eq!(editor.cut_buffer.get().0, "first\n");
+
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "second");
+        assert_eq!(editor.cut_buffer.get().0, "first\n");
+    }
+
+    #[test]
+    fn test_copy_current_line_ignores_the_selection() {
+        let mut editor = editor_with_clipboard("first\nsecond");
+        editor.run_edit_command(&EditCommand::SelectLeft);
+        let selection = editor.line_buffer().selection_range();
+        editor.run_edit_command(&EditCommand::CopyCurrentLine);
+
+        assert_eq!(editor.get_buffer(), "first\nsecond");
+        assert!(selection.is_some());
+        assert_eq!(editor.line_buffer().selection_range(), selection);
+        assert_eq!(editor.cut_buffer.get().0, "second");
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "second\nfirst\nsecond");
+    }
+
+    #[test]
+    fn test_kill_whole_line_keeps_the_line_break() {
+        let mut editor = editor_with_clipboard("first\nsecond\nthird");
+        editor.line_buffer.set_insertion_point(9);
+
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        assert_eq!(editor.get_buffer(), "first\n\nthird");
+        assert_eq!(editor.insertion_point(), 6);
+        assert_eq!(editor.cut_buffer.get().0, "second");
+
+        // An empty line leaves the kill ring alone
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        assert_eq!(editor.cut_buffer.get().0, "second");
+
+        editor.run_edit_command(&EditCommand::MoveToEnd);
+        editor.run_edit_command(&EditCommand::KillWholeLine);
+        assert_eq!(editor.get_buffer(), "first\n\n");
+        assert_eq!(editor.cut_buffer.get().0, "third");
+
+        editor.run_edit_command(&EditCommand::MoveToStart);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "thirdfirst\n\n");
+    }
+
+    #[test]
+    fn test_copy_without_selection_does_nothing_without_fallback() {
+        let mut editor = editor_with_clipboard("hello world");
+        editor.set_copy_line_fallback(false);
+
+        editor.run_edit_command(&EditCommand::CutSelection);
+        assert_eq!(editor.get_buffer(), "hello world");
+        assert_eq!(editor.cut_buffer.get().0, "");
+    }
+
+    #[test]
+    fn test_indent_uses_the_configured_unit() {
+        let mut editor = editor_with("first\nsecond");
+        editor.run_edit_command(&EditCommand::Indent);
+        assert_eq!(editor.get_buffer(), "first\n    second");
+
+        editor.set_indent_unit(IndentUnit::Tab);
+        editor.run_edit_command(&EditCommand::Indent);
+        assert_eq!(editor.get_buffer(), "first\n\t    second");
+
+        editor.run_edit_command(&EditCommand::Dedent);
+        editor.run_edit_command(&EditCommand::Dedent);
+        assert_eq!(editor.get_buffer(), "first\n   second");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_newline_with_indent_copies_current_indentation() {
+        let mut editor = editor_with("if true {\n\t  echo");
+        editor.run_edit_command(&EditCommand::InsertNewlineWithIndent);
+        assert_eq!(editor.get_buffer(), "if true {\n\t  echo\n\t  ");
+
+        editor.run_edit_command(&EditCommand::InsertNewlineWithIndent);
+        assert_eq!(editor.get_buffer(), "if true {\n\t  echo\n\t  \n\t  ");
+        assert_eq!(
+            editor.line_buffer().insertion_point(),
+            editor.get_buffer().len()
+        );
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "if true {\n\t  echo\n\t  ");
+    }
 }
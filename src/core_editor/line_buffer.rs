@@ -8,6 +8,9 @@ use {
 pub struct LineBuffer {
     lines: String,
     insertion_point: usize,
+    selection_anchor: Option<usize>,
+    // Further cursors in ascending order, where the basic edits apply as well
+    secondary_cursors: Vec<usize>,
 }
 
 impl From<&str> for LineBuffer {
@@ -26,6 +29,10 @@ impl LineBuffer {
 
     /// Replaces the content between [`start`..`end`] with `text`
     pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        for cursor in &mut self.secondary_cursors {
+            *cursor = position_after_replace(*cursor, &range, text);
+        }
+        self.secondary_cursors.dedup();
         self.lines.replace_range(range, text);
     }
 
@@ -83,6 +90,126 @@ impl LineBuffer {
     pub fn set_buffer(&mut self, buffer: String) {
         self.lines = buffer;
         self.insertion_point = self.lines.len();
+        self.selection_anchor = None;
+        self.secondary_cursors.clear();
+    }
+
+    /// Adds a secondary cursor at `offset`.
+    ///
+    /// [`LineBuffer::insert_char()`], [`LineBuffer::delete_left_grapheme()`],
+    /// [`LineBuffer::delete_right_grapheme()`] and [`LineBuffer::clear_to_line_end()`]
+    /// edit at the secondary cursors as well, other edits only at the insertion point.
+    /// Those move the secondary cursors along with the text they are on
+    pub fn add_cursor(&mut self, offset: usize) {
+        if offset == self.insertion_point || !self.lines.is_char_boundary(offset) {
+            return;
+        }
+        if let Err(index) = self.secondary_cursors.binary_search(&offset) {
+            self.secondary_cursors.insert(index, offset);
+        }
+    }
+
+    /// Offsets of the secondary cursors, in ascending order
+    pub fn secondary_cursors(&self) -> &[usize] {
+        &self.secondary_cursors
+    }
+
+    /// Removes all secondary cursors, leaving the insertion point
+    pub fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
+    }
+
+    /// Runs `edit` at the insertion point and at each secondary cursor.
+    ///
+    /// Starts with the last cursor, so an edit only moves the cursors behind it, which are
+    /// shifted by the change in length. Cursors ending up at the same offset are merged
+    fn at_each_cursor(&mut self, mut edit: impl FnMut(&mut Self)) {
+        if self.secondary_cursors.is_empty() {
+            edit(self);
+            return;
+        }
+
+        // A cursor the insertion point was moved onto would get the edit twice
+        let insertion_point = self.insertion_point;
+        let mut cursors: Vec<(usize, bool)> = std::mem::take(&mut self.secondary_cursors)
+            .into_iter()
+            .filter(|&cursor| cursor != insertion_point && self.lines.is_char_boundary(cursor))
+            .map(|cursor| (cursor, false))
+            .chain(std::iter::once((self.insertion_point, true)))
+            .collect();
+        cursors.sort_unstable();
+
+        for index in (0..cursors.len()).rev() {
+            let len = self.lines.len();
+            self.insertion_point = cursors[index].0;
+            edit(self);
+            cursors[index].0 = self.insertion_point;
+
+            let new_len = self.lines.len();
+            for (cursor, _) in &mut cursors[index + 1..] {
+                *cursor = (*cursor + new_len).saturating_sub(len);
+            }
+        }
+
+        for (cursor, primary) in cursors {
+            if primary {
+                self.insertion_point = cursor;
+            } else {
+                self.secondary_cursors.push(cursor);
+            }
+        }
+        let insertion_point = self.insertion_point;
+        self.secondary_cursors.dedup();
+        self.secondary_cursors
+            .retain(|&cursor| cursor != insertion_point);
+    }
+
+    /// Anchors a selection at the current insertion point
+    ///
+    /// Moving the insertion point afterwards extends the selection
+    pub fn set_anchor(&mut self) {
+        self.selection_anchor = Some(self.insertion_point);
+    }
+
+    /// Removes the selection anchor
+    pub fn clear_anchor(&mut self) {
+        self.selection_anchor = None;
+    }
+
+    /// Range between the selection anchor and the insertion point, if an anchor is set
+    ///
+    /// The range is normalized so that `start <= end`, independent of the
+    /// direction in which the selection was extended
+    pub fn selection_range(&self) -> Option<Range<usize>> {
+        let anchor = self.selection_anchor?;
+        if anchor > self.lines.len() || !self.lines.is_char_boundary(anchor) {
+            return None;
+        }
+
+        Some(anchor.min(self.insertion_point)..anchor.max(self.insertion_point))
+    }
+
+    /// Deletes the selected text and removes the selection anchor
+    ///
+    /// The insertion point is placed where the selection started
+    pub fn delete_selection(&mut self) {
+        if let Some(range) = self.selection_range() {
+            self.clear_range(range.clone());
+            self.insertion_point = range.start;
+        }
+        self.selection_anchor = None;
+    }
+
+    /// Uppercases the selected text and removes the selection anchor
+    ///
+    /// The insertion point is placed where the selection started
+    pub fn uppercase_selection(&mut self) {
+        if let Some(range) = self.selection_range() {
+            let uppercased = self.lines[range.clone()].to_uppercase();
+            self.replace_range(range.clone(), &uppercased);
+            self.insertion_point = range.start;
+        }
+        self.selection_anchor = None;
     }
 
     /// Calculates the current the user is on
@@ -115,6 +242,17 @@ impl LineBuffer {
         // str is guaranteed to be utf8, thus \n is safe to assume 1 byte long
     }
 
+    /// Move the cursor before the first character of the given line, counted from 1
+    /// like the line numbers of an editor. Lines past the last one go to the last line
+    pub fn move_to_line(&mut self, line: usize) {
+        self.insertion_point = self
+            .lines
+            .match_indices('\n')
+            .take(line.saturating_sub(1))
+            .last()
+            .map_or(0, |(offset, _)| offset + 1);
+    }
+
     /// Move cursor position to the end of the line
     ///
     /// Insertion will append to the line.
@@ -189,6 +327,112 @@ impl LineBuffer {
             .unwrap_or(0)
     }
 
+    /// Cursor position at the start of the next small word to the right, like `w` in vi
+    ///
+    /// A small word is a run of alphanumeric characters and underscores or a run of
+    /// other non-blank characters
+    pub fn small_word_right_index(&self) -> usize {
+        self.vi_word_right_index(false)
+    }
+
+    /// Cursor position at the start of the next big word to the right, like `W` in vi
+    ///
+    /// A big word is a run of non-whitespace characters
+    pub fn big_word_right_index(&self) -> usize {
+        self.vi_word_right_index(true)
+    }
+
+    /// Cursor position at the start of the small word to the left, like `b` in vi
+    pub fn small_word_left_index(&self) -> usize {
+        self.vi_word_left_index(false)
+    }
+
+    /// Cursor position at the start of the big word to the left, like `B` in vi
+    pub fn big_word_left_index(&self) -> usize {
+        self.vi_word_left_index(true)
+    }
+
+    /// Cursor position on the last character of the next small word to the right,
+    /// like `e` in vi
+    ///
+    /// Stays in place if there is no word end to the right
+    pub fn word_end_right_index(&self) -> usize {
+        self.vi_word_end_right_index(false)
+    }
+
+    /// Cursor position on the last character of the next big word to the right,
+    /// like `E` in vi
+    pub fn big_word_end_right_index(&self) -> usize {
+        self.vi_word_end_right_index(true)
+    }
+
+    /// Cursor position on the last character of the previous small word, like `ge` in vi
+    pub fn word_end_left_index(&self) -> usize {
+        self.vi_word_end_left_index(false)
+    }
+
+    /// Cursor position on the last character of the previous big word, like `gE` in vi
+    pub fn big_word_end_left_index(&self) -> usize {
+        self.vi_word_end_left_index(true)
+    }
+
+    fn vi_word_right_index(&self, big_word: bool) -> usize {
+        let mut previous_class = None;
+        for (i, c) in self.lines[self.insertion_point..].char_indices() {
+            let class = CharClass::of(c, big_word);
+            if class != CharClass::Whitespace && previous_class.is_some_and(|prev| prev != class) {
+                return self.insertion_point + i;
+            }
+            previous_class = Some(class);
+        }
+
+        self.lines.len()
+    }
+
+    fn vi_word_end_right_index(&self, big_word: bool) -> usize {
+        let mut word_end: Option<(usize, CharClass)> = None;
+        for (i, c) in self.lines[self.insertion_point..].char_indices().skip(1) {
+            let class = CharClass::of(c, big_word);
+            match word_end {
+                None if class == CharClass::Whitespace => {}
+                Some((_, word_class)) if word_class != class => break,
+                _ => word_end = Some((i, class)),
+            }
+        }
+
+        word_end.map_or(self.insertion_point, |(i, _)| self.insertion_point + i)
+    }
+
+    fn vi_word_end_left_index(&self, big_word: bool) -> usize {
+        let mut next_class = self.lines[self.insertion_point..]
+            .chars()
+            .next()
+            .map(|c| CharClass::of(c, big_word));
+        for (i, c) in self.lines[..self.insertion_point].char_indices().rev() {
+            let class = CharClass::of(c, big_word);
+            if class != CharClass::Whitespace && next_class != Some(class) {
+                return i;
+            }
+            next_class = Some(class);
+        }
+
+        0
+    }
+
+    fn vi_word_left_index(&self, big_word: bool) -> usize {
+        let mut word_class = None;
+        for (i, c) in self.lines[..self.insertion_point].char_indices().rev() {
+            let class = CharClass::of(c, big_word);
+            match word_class {
+                None if class != CharClass::Whitespace => word_class = Some(class),
+                Some(word_class) if word_class != class => return i + c.len_utf8(),
+                _ => {}
+            }
+        }
+
+        0
+    }
+
     /// Move cursor position *behind* the next unicode grapheme to the right
     pub fn move_right(&mut self) {
         self.insertion_point = self.grapheme_right_index();
@@ -209,10 +453,85 @@ impl LineBuffer {
         self.insertion_point = self.word_right_index();
     }
 
+    /// Move cursor position to the start of the next small word to the right
+    pub fn move_small_word_right(&mut self) {
+        self.insertion_point = self.small_word_right_index();
+    }
+
+    /// Move cursor position to the start of the small word to the left
+    pub fn move_small_word_left(&mut self) {
+        self.insertion_point = self.small_word_left_index();
+    }
+
+    /// Move cursor position onto the last character of the next small word
+    pub fn move_to_word_end(&mut self) {
+        self.insertion_point = self.word_end_right_index();
+    }
+
+    /// Move cursor position onto the last character of the next big word
+    pub fn move_to_big_word_end(&mut self) {
+        self.insertion_point = self.big_word_end_right_index();
+    }
+
+    /// Move cursor position onto the last character of the previous small word
+    pub fn move_to_previous_word_end(&mut self) {
+        self.insertion_point = self.word_end_left_index();
+    }
+
+    /// Move cursor position onto the last character of the previous big word
+    pub fn move_to_previous_big_word_end(&mut self) {
+        self.insertion_point = self.big_word_end_left_index();
+    }
+
+    /// Move cursor position to the start of the next big word to the right
+    pub fn move_big_word_right(&mut self) {
+        self.insertion_point = self.big_word_right_index();
+    }
+
+    /// Move cursor position to the start of the big word to the left
+    pub fn move_big_word_left(&mut self) {
+        self.insertion_point = self.big_word_left_index();
+    }
+
     ///Insert a single character at the insertion point and move right
     pub fn insert_char(&mut self, c: char) {
-        self.lines.insert(self.insertion_point, c);
-        self.move_right();
+        self.at_each_cursor(|buffer| {
+            buffer.lines.insert(buffer.insertion_point, c);
+            buffer.move_right();
+        });
+    }
+
+    /// Replace the grapheme under the cursor with `c`, the cursor stays in front of it
+    ///
+    /// Returns the replaced grapheme or `None` at the end of a line, where nothing is replaced
+    pub fn replace_char_at_cursor(&mut self, c: char) -> Option<String> {
+        if self.insertion_point >= self.find_current_line_end() {
+            return None;
+        }
+
+        let grapheme_end = self.grapheme_right_index();
+        let replaced = self.lines[self.insertion_point..grapheme_end].to_string();
+        self.replace_range(
+            self.insertion_point..grapheme_end,
+            c.encode_utf8(&mut [0; 4]),
+        );
+
+        Some(replaced)
+    }
+
+    /// Overwrite the grapheme under the cursor with `c` and move behind it.
+    /// At the end of a line `c` is inserted instead
+    ///
+    /// Returns the overwritten grapheme, `None` if `c` was inserted
+    pub fn overtype_char(&mut self, c: char) -> Option<String> {
+        let replaced = self.replace_char_at_cursor(c);
+        if replaced.is_some() {
+            self.move_right();
+        } else {
+            self.insert_char(c);
+        }
+
+        replaced
     }
 
     /// Insert `&str` at the cursor position in the current line.
@@ -222,26 +541,183 @@ impl LineBuffer {
     /// ## Unicode safety:
     /// Does not validate the incoming string or the current cursor position
     pub fn insert_str(&mut self, string: &str) {
-        self.lines.insert_str(self.insertion_point(), string);
+        self.replace(self.insertion_point..self.insertion_point, string);
         self.insertion_point = self.insertion_point() + string.len();
     }
 
+    /// Inserts `string` at the cursor and places the cursor `cursor_offset` bytes into it,
+    /// e.g. between the parentheses of `()`
+    ///
+    /// The offset is clamped to the end of the inserted text, an offset inside a grapheme
+    /// places the cursor in front of it
+    pub fn insert_str_with_cursor(&mut self, string: &str, cursor_offset: usize) {
+        let offset = string
+            .grapheme_indices(true)
+            .map(|(index, _)| index)
+            .chain(std::iter::once(string.len()))
+            .take_while(|&index| index <= cursor_offset)
+            .last()
+            .unwrap_or(0);
+
+        self.replace(self.insertion_point..self.insertion_point, string);
+        self.insertion_point += offset;
+    }
+
     /// Empty buffer and reset cursor
     pub fn clear(&mut self) {
         self.lines = String::new();
         self.insertion_point = 0;
+        self.selection_anchor = None;
+        self.secondary_cursors.clear();
     }
 
     /// Clear everything beginning at the cursor to the right/end.
     /// Keeps the cursor at the end.
     pub fn clear_to_end(&mut self) {
-        self.lines.truncate(self.insertion_point);
+        self.replace(self.insertion_point..self.lines.len(), "");
     }
 
     /// Clear beginning at the cursor up to the end of the line.
     /// Newline character at the end remains.
     pub fn clear_to_line_end(&mut self) {
-        self.clear_range(self.insertion_point..self.find_current_line_end());
+        self.at_each_cursor(|buffer| {
+            buffer.clear_range(buffer.insertion_point..buffer.find_current_line_end());
+        });
+    }
+
+    /// Clear the content of the current line, keeping its line break, and return it.
+    /// Leaves the cursor at the start of the now empty line
+    pub fn kill_whole_line(&mut self) -> String {
+        self.move_to_line_start();
+        let range = self.insertion_point..self.find_current_line_end();
+        let killed = self.lines[range.clone()].to_string();
+        self.clear_range(range);
+        killed
+    }
+
+    /// The line ending used in the buffer, `\r\n` if there is one and `\n` otherwise
+    fn line_ending(&self) -> &'static str {
+        if self.lines.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Start a new line below the current one, like `o` in vi.
+    ///
+    /// The new line gets the indentation of the current line and the cursor is
+    /// placed behind it
+    pub fn open_line_below(&mut self) {
+        let indent = self.current_line_indent().to_string();
+        self.insertion_point = self.find_current_line_end();
+        self.insert_str(&format!("{}{}", self.line_ending(), indent));
+    }
+
+    /// Start a new line above the current one, like `O` in vi.
+    ///
+    /// The new line gets the indentation of the current line and the cursor is
+    /// placed behind it
+    pub fn open_line_above(&mut self) {
+        let indent = self.current_line_indent().to_string();
+        let line_ending = self.line_ending();
+        self.insertion_point = self.current_line_range().start;
+        self.insert_str(&format!("{}{}", indent, line_ending));
+        self.insertion_point -= line_ending.len();
+    }
+
+    /// Join the current line with the next one, like `J` in vi.
+    ///
+    /// The line break and the whitespace around it are replaced by a single space,
+    /// or by nothing if either line is blank. The cursor is left where the lines were
+    /// joined. Nothing changes on the last line
+    pub fn join_lines(&mut self) {
+        let line_end = self.find_current_line_end();
+        let next_line_start = match self.lines[line_end..].find('\n') {
+            Some(offset) => line_end + offset + 1,
+            None => return,
+        };
+
+        let is_blank = |c: char| matches!(c, ' ' | '\t');
+        let line_start = self.current_line_range().start;
+        let join_start = line_start
+            + self.lines[line_start..line_end]
+                .trim_end_matches(is_blank)
+                .len();
+        let next_line = self.lines[next_line_start..].trim_start_matches(is_blank);
+        let join_end = self.lines.len() - next_line.len();
+
+        let separator = if join_start == line_start
+            || next_line.is_empty()
+            || next_line.starts_with(['\r', '\n'])
+        {
+            ""
+        } else {
+            " "
+        };
+
+        self.replace_range(join_start..join_end, separator);
+        self.insertion_point = join_start;
+    }
+
+    /// Add `indent` at the start of the current line, or of every line touched by the
+    /// selection
+    pub fn indent_lines(&mut self, indent: &str) {
+        for line_start in self.affected_line_starts().into_iter().rev() {
+            self.replace_keeping_positions(line_start..line_start, indent);
+        }
+    }
+
+    /// Remove up to one `indent` from the start of the current line, or of every line
+    /// touched by the selection
+    ///
+    /// Removes at most as many blanks as `indent` has characters, stopping after a tab.
+    /// Lines without leading whitespace are left alone
+    pub fn dedent_lines(&mut self, indent: &str) {
+        let max_blanks = indent.chars().count().max(1);
+        for line_start in self.affected_line_starts().into_iter().rev() {
+            let mut end = line_start;
+            for c in self.lines[line_start..].chars().take(max_blanks) {
+                match c {
+                    ' ' => end += 1,
+                    '\t' => {
+                        end += 1;
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+            self.replace_keeping_positions(line_start..end, "");
+        }
+    }
+
+    /// Start offsets of the lines touched by the selection, or of the current line
+    fn affected_line_starts(&self) -> Vec<usize> {
+        let range = self
+            .selection_range()
+            .unwrap_or(self.insertion_point..self.insertion_point);
+        let first_line_start = self.lines[..range.start]
+            .rfind('\n')
+            .map_or(0, |offset| offset + 1);
+
+        std::iter::once(first_line_start)
+            .chain(
+                self.lines[range.start..range.end]
+                    .match_indices('\n')
+                    .map(|(offset, _)| range.start + offset + 1)
+                    .filter(|&line_start| line_start < range.end),
+            )
+            .collect()
+    }
+
+    /// Replace `range` and move the insertion point and the selection anchor along
+    /// with the text they are on
+    fn replace_keeping_positions(&mut self, range: Range<usize>, text: &str) {
+        self.insertion_point = position_after_replace(self.insertion_point, &range, text);
+        self.selection_anchor = self
+            .selection_anchor
+            .map(|anchor| position_after_replace(anchor, &range, text));
+        self.replace_range(range, text);
     }
 
     /// Clear from the start of the buffer to the cursor.
@@ -268,7 +744,19 @@ impl LineBuffer {
     where
         R: std::ops::RangeBounds<usize>,
     {
-        self.lines.replace_range(range, replace_with);
+        use std::ops::Bound;
+
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.lines.len(),
+        };
+        self.replace(start..end, replace_with);
     }
 
     /// Checks to see if the current edit position is pointing to whitespace
@@ -293,6 +781,29 @@ impl LineBuffer {
         left_index..right_index
     }
 
+    /// The word the cursor is in or directly behind, as moved over by the word motions
+    ///
+    /// Empty if the cursor is on whitespace or punctuation away from the end of a word
+    pub fn current_word(&self) -> &str {
+        &self.lines[self.word_at_cursor()]
+    }
+
+    /// The part of [`LineBuffer::current_word()`] before the cursor, e.g. the prefix to
+    /// complete. Empty at the start of a word
+    pub fn word_before_cursor(&self) -> &str {
+        &self.lines[self.word_at_cursor().start..self.insertion_point]
+    }
+
+    fn word_at_cursor(&self) -> Range<usize> {
+        self.lines
+            .split_word_bound_indices()
+            .filter(|(_, word)| !is_word_boundary(word))
+            .map(|(start, word)| start..start + word.len())
+            .rev()
+            .find(|range| range.start <= self.insertion_point && self.insertion_point <= range.end)
+            .unwrap_or(self.insertion_point..self.insertion_point)
+    }
+
     /// Range over the current line
     ///
     /// Starts on the first non-newline character and is an exclusive range
@@ -309,6 +820,16 @@ impl LineBuffer {
         left_index..right_index
     }
 
+    /// Leading whitespace (spaces and tabs) of the line the cursor is on
+    pub fn current_line_indent(&self) -> &str {
+        let line = &self.lines[self.current_line_range()];
+        let indent_len = line
+            .find(|c: char| !matches!(c, ' ' | '\t'))
+            .unwrap_or(line.len());
+
+        &line[..indent_len]
+    }
+
     /// Uppercases the current word
     pub fn uppercase_word(&mut self) {
         let change_range = self.current_word_range();
@@ -325,6 +846,45 @@ impl LineBuffer {
         self.move_word_right();
     }
 
+    /// Uppercases the text from the insertion point to the end of the current line
+    ///
+    /// The insertion point is not moved
+    pub fn uppercase_to_line_end(&mut self) {
+        let change_range = self.insertion_point..self.find_current_line_end();
+        let uppercased = self.lines[change_range.clone()].to_uppercase();
+        self.replace_range(change_range, &uppercased);
+    }
+
+    /// Lowercases the text from the insertion point to the end of the current line
+    ///
+    /// The insertion point is not moved
+    pub fn lowercase_to_line_end(&mut self) {
+        let change_range = self.insertion_point..self.find_current_line_end();
+        let lowercased = self.lines[change_range.clone()].to_lowercase();
+        self.replace_range(change_range, &lowercased);
+    }
+
+    /// Flips the case of the grapheme at the insertion point and moves the
+    /// insertion point right one grapheme (like `~` in vi).
+    ///
+    /// Graphemes without case, e.g. digits, are left as they are
+    pub fn toggle_case_char(&mut self) {
+        let change_range = self.insertion_point..self.grapheme_right_index();
+        let toggled: String = self.lines[change_range.clone()]
+            .chars()
+            .map(|c| {
+                if c.is_uppercase() {
+                    c.to_lowercase().to_string()
+                } else {
+                    c.to_uppercase().to_string()
+                }
+            })
+            .collect();
+        let end = change_range.start + toggled.len();
+        self.replace_range(change_range, &toggled);
+        self.insertion_point = end;
+    }
+
     /// Counts the number of words in the buffer
     pub fn word_count(&self) -> usize {
         self.lines.trim().split_whitespace().count()
@@ -351,21 +911,25 @@ impl LineBuffer {
 
     /// Deletes on grapheme to the left
     pub fn delete_left_grapheme(&mut self) {
-        let left_index = self.grapheme_left_index();
-        let insertion_offset = self.insertion_point();
-        if left_index < insertion_offset {
-            self.clear_range(left_index..insertion_offset);
-            self.insertion_point = left_index;
-        }
+        self.at_each_cursor(|buffer| {
+            let left_index = buffer.grapheme_left_index();
+            let insertion_offset = buffer.insertion_point();
+            if left_index < insertion_offset {
+                buffer.clear_range(left_index..insertion_offset);
+                buffer.insertion_point = left_index;
+            }
+        });
     }
 
     /// Deletes one grapheme to the right
     pub fn delete_right_grapheme(&mut self) {
-        let right_index = self.grapheme_right_index();
-        let insertion_offset = self.insertion_point();
-        if right_index > insertion_offset {
-            self.clear_range(insertion_offset..right_index);
-        }
+        self.at_each_cursor(|buffer| {
+            let right_index = buffer.grapheme_right_index();
+            let insertion_offset = buffer.insertion_point();
+            if right_index > insertion_offset {
+                buffer.clear_range(insertion_offset..right_index);
+            }
+        });
     }
 
     /// Deletes one word to the left
@@ -397,6 +961,36 @@ impl LineBuffer {
         }
     }
 
+    /// Transposes the word before the cursor with the word after it.
+    ///
+    /// Follows the emacs/readline semantics: if the cursor is inside a word
+    /// that word is dragged past the previous one and at the end of the line
+    /// the last two words are swapped. The cursor is placed behind the second
+    /// word. Does nothing if there aren't two words to transpose.
+    pub fn transpose_words(&mut self) {
+        let initial_offset = self.insertion_point;
+
+        self.move_word_right();
+        let word_2_end = self.insertion_point;
+        self.move_word_left();
+        let word_2_start = self.insertion_point;
+        self.move_word_left();
+        let word_1_start = self.insertion_point;
+        self.move_word_right();
+        let word_1_end = self.insertion_point;
+
+        if word_1_start == word_2_start || word_2_start < word_1_end {
+            self.insertion_point = initial_offset;
+            return;
+        }
+
+        let word_1 = self.lines[word_1_start..word_1_end].to_string();
+        let word_2 = self.lines[word_2_start..word_2_end].to_string();
+        self.replace_range(word_2_start..word_2_end, &word_1);
+        self.replace_range(word_1_start..word_1_end, &word_2);
+        self.insertion_point = word_2_end;
+    }
+
     /// Swaps current grapheme with grapheme on right
     pub fn swap_graphemes(&mut self) {
         let initial_offset = self.insertion_point();
@@ -574,13 +1168,96 @@ impl LineBuffer {
             self.insertion_point = index + c.len_utf8();
         }
     }
+
+    /// Finds the index of the bracket matching the one under the cursor
+    ///
+    /// Supports `()`, `[]` and `{}` and respects nesting of the same bracket kind.
+    /// Returns `None` if the cursor is not on a bracket or the pair is unbalanced
+    pub fn matching_bracket_index(&self) -> Option<usize> {
+        let current = self.lines[self.insertion_point..].chars().next()?;
+
+        if let Some(&(_, close)) = BRACKET_PAIRS.iter().find(|(open, _)| *open == current) {
+            let mut depth = 0;
+            for (i, c) in self.lines[self.insertion_point..].char_indices() {
+                if c == current {
+                    depth += 1;
+                } else if c == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(self.insertion_point + i);
+                    }
+                }
+            }
+        } else if let Some(&(open, _)) = BRACKET_PAIRS.iter().find(|(_, close)| *close == current) {
+            let mut depth = 0;
+            for (i, c) in self.lines[..=self.insertion_point].char_indices().rev() {
+                if c == current {
+                    depth += 1;
+                } else if c == open {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Deletes from the bracket under the cursor up to and including its matching bracket
+    ///
+    /// Leaves the buffer untouched if no matching bracket is found
+    pub fn delete_to_matching_bracket(&mut self) {
+        if let Some(index) = self.matching_bracket_index() {
+            let start = index.min(self.insertion_point);
+            let end = index.max(self.insertion_point) + 1;
+            self.clear_range(start..end);
+            self.insertion_point = start;
+        }
+    }
 }
 
+/// Bracket pairs recognized when looking for a matching bracket
+const BRACKET_PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
 /// Match any sequence of characters that are considered a word boundary
+/// Offset of `position` after `range` is replaced by `text`, following the text it is on.
+/// Positions inside of the range end up behind `text`
+fn position_after_replace(position: usize, range: &Range<usize>, text: &str) -> usize {
+    if position >= range.end {
+        position - range.len() + text.len()
+    } else if position > range.start {
+        range.start + text.len()
+    } else {
+        position
+    }
+}
+
 fn is_word_boundary(s: &str) -> bool {
     !s.chars().any(char::is_alphanumeric)
 }
 
+/// Kinds of characters separating the words of the vi motions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(c: char, big_word: bool) -> Self {
+        if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if big_word || c.is_alphanumeric() || c == '_' {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -594,6 +1271,60 @@ mod test {
         line_buffer
     }
 
+    // Arabic word embedded in an english command, each letter takes two bytes
+    const MIXED_DIRECTION: &str = "echo مرحبا world";
+
+    #[rstest]
+    #[case(4, 5)]
+    #[case(5, 7)]
+    #[case(13, 15)]
+    #[case(15, 16)]
+    fn moving_right_through_rtl_text_follows_the_logical_order(
+        #[case] start: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(MIXED_DIRECTION);
+        line_buffer.set_insertion_point(start);
+        line_buffer.move_right();
+
+        assert_eq!(line_buffer.insertion_point(), expected);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn moving_through_rtl_words_follows_the_logical_order() {
+        let mut line_buffer = buffer_with(MIXED_DIRECTION);
+        line_buffer.move_word_left();
+        assert_eq!(line_buffer.insertion_point(), 16);
+        line_buffer.move_word_left();
+        assert_eq!(line_buffer.insertion_point(), 5);
+        line_buffer.move_left();
+        line_buffer.move_left();
+        assert_eq!(line_buffer.insertion_point(), 3);
+    }
+
+    #[test]
+    fn inserting_into_rtl_text_uses_logical_offsets() {
+        let mut line_buffer = buffer_with(MIXED_DIRECTION);
+        line_buffer.set_insertion_point(9);
+        line_buffer.insert_str("ـ");
+
+        assert_eq!(line_buffer.get_buffer(), "echo مرـحبا world");
+        assert_eq!(line_buffer.insertion_point(), 11);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn arabic_diacritics_move_with_their_letter() {
+        // Fatha on the first letter
+        let mut line_buffer = buffer_with("مَرحبا");
+        line_buffer.move_to_start();
+        line_buffer.move_right();
+
+        assert_eq!(line_buffer.insertion_point(), 4);
+        line_buffer.assert_valid();
+    }
+
     #[test]
     fn test_new_buffer_is_empty() {
         let line_buffer = LineBuffer::new();
@@ -611,15 +1342,57 @@ mod test {
         line_buffer.assert_valid();
     }
 
-    #[test]
-    fn insert_str_updates_insertion_point_point_correctly() {
-        let mut line_buffer = LineBuffer::new();
-        line_buffer.insert_str("this is a command");
+    #[rstest]
+    #[case("hello", 0, 'j', "jello", Some("h"))]
+    #[case("ab😀c", 2, 'x', "abxc", Some("😀"))]
+    #[case("ab\ncd", 2, 'x', "ab\ncd", None)]
+    #[case("ab", 2, 'x', "ab", None)]
+    fn replace_char_at_cursor_keeps_the_cursor_in_place(
+        #[case] input: &str,
+        #[case] insertion_point: usize,
+        #[case] c: char,
+        #[case] expected: &str,
+        #[case] replaced: Option<&str>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(insertion_point);
 
-        let expected_updated_insertion_point = 17;
+        assert_eq!(line_buffer.replace_char_at_cursor(c).as_deref(), replaced);
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), insertion_point);
+        line_buffer.assert_valid();
+    }
 
-        assert_eq!(
-            expected_updated_insertion_point,
+    #[rstest]
+    #[case("hello", 1, "hEllo", 2)]
+    #[case("hello", 5, "helloE", 6)]
+    #[case("ab\ncd", 2, "abE\ncd", 3)]
+    #[case("ab\ncd", 3, "ab\nEd", 4)]
+    fn overtype_char_overwrites_in_the_middle_and_appends_at_the_line_end(
+        #[case] input: &str,
+        #[case] insertion_point: usize,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(insertion_point);
+
+        line_buffer.overtype_char('E');
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_insertion_point);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn insert_str_updates_insertion_point_point_correctly() {
+        let mut line_buffer = LineBuffer::new();
+        line_buffer.insert_str("this is a command");
+
+        let expected_updated_insertion_point = 17;
+
+        assert_eq!(
+            expected_updated_insertion_point,
             line_buffer.insertion_point()
         );
         line_buffer.assert_valid();
@@ -740,6 +1513,245 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("foo.bar baz", 0, 3, 8)]
+    #[case("foo.bar baz", 3, 4, 8)]
+    #[case("foo.bar baz", 4, 8, 8)]
+    #[case("foo.bar baz", 8, 11, 11)]
+    #[case("foo.bar  \n  baz", 4, 12, 12)]
+    #[case("foo_bar baz", 0, 8, 8)]
+    #[case("", 0, 0, 0)]
+    fn test_vi_word_right_index(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] small_word: usize,
+        #[case] big_word: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.small_word_right_index(), small_word);
+        assert_eq!(line_buffer.big_word_right_index(), big_word);
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 0, 2, 2)]
+    #[case("foo bar baz", 2, 6, 6)]
+    #[case("foo bar baz", 4, 6, 6)]
+    #[case("foo bar baz", 10, 10, 10)]
+    #[case("foo.bar baz", 0, 2, 6)]
+    #[case("foo.bar baz", 2, 3, 6)]
+    #[case("foo  \n  bar", 2, 10, 10)]
+    #[case("", 0, 0, 0)]
+    fn test_word_end_right_index(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] small_word: usize,
+        #[case] big_word: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.word_end_right_index(), small_word);
+        assert_eq!(line_buffer.big_word_end_right_index(), big_word);
+    }
+
+    #[rstest]
+    #[case("foo bar baz", 8, 6, 6)]
+    #[case("foo bar baz", 5, 2, 2)]
+    #[case("foo bar baz", 4, 2, 2)]
+    #[case("foo bar baz", 1, 0, 0)]
+    #[case("foo.bar baz", 5, 3, 0)]
+    #[case("foo.bar baz", 3, 2, 0)]
+    #[case("foo  \n  bar", 8, 2, 2)]
+    #[case("", 0, 0, 0)]
+    fn test_word_end_left_index(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] small_word: usize,
+        #[case] big_word: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.word_end_left_index(), small_word);
+        assert_eq!(line_buffer.big_word_end_left_index(), big_word);
+    }
+
+    #[rstest]
+    #[case("foo.bar baz", 11, 8, 8)]
+    #[case("foo.bar baz", 8, 4, 0)]
+    #[case("foo.bar baz", 4, 3, 0)]
+    #[case("foo.bar baz", 3, 0, 0)]
+    #[case("foo.bar baz", 6, 4, 0)]
+    #[case("foo  \n  baz", 8, 0, 0)]
+    #[case("", 0, 0, 0)]
+    fn test_vi_word_left_index(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] small_word: usize,
+        #[case] big_word: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.small_word_left_index(), small_word);
+        assert_eq!(line_buffer.big_word_left_index(), big_word);
+    }
+
+    #[rstest]
+    #[case("This is a test", 5, LineBuffer::move_word_right, Some(5..7))]
+    #[case("This is a test", 5, LineBuffer::move_word_left, Some(0..5))]
+    #[case("This is a test", 5, |b: &mut LineBuffer| { b.move_to_end(); b.move_left() }, Some(5..13))]
+    #[case("This is a test", 5, |b: &mut LineBuffer| { b.move_right(); b.move_left() }, Some(5..5))]
+    #[case("😇 test", 0, LineBuffer::move_right, Some(0..4))]
+    fn anchor_then_move_selects_range(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] move_cursor: fn(&mut LineBuffer),
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(anchor);
+        line_buffer.set_anchor();
+
+        move_cursor(&mut line_buffer);
+
+        assert_eq!(line_buffer.selection_range(), expected);
+    }
+
+    #[test]
+    fn selection_is_empty_without_anchor() {
+        let mut line_buffer = buffer_with("This is a test");
+        assert_eq!(line_buffer.selection_range(), None);
+
+        line_buffer.set_anchor();
+        line_buffer.move_word_left();
+        assert_eq!(line_buffer.selection_range(), Some(10..14));
+
+        line_buffer.clear_anchor();
+        assert_eq!(line_buffer.selection_range(), None);
+    }
+
+    #[rstest]
+    #[case("This is a test", 5, 9, "This  test", 5)]
+    #[case("This is a test", 9, 5, "This  test", 5)]
+    #[case("This is a test", 5, 5, "This is a test", 5)]
+    fn delete_selection_works(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(anchor);
+        line_buffer.set_anchor();
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.delete_selection();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("This is a test", 5, 9, "This IS A test", 5)]
+    #[case("This is a test", 14, 10, "This is a TEST", 10)]
+    fn uppercase_selection_works(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(anchor);
+        line_buffer.set_anchor();
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.uppercase_selection();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("This is a test", 5, "This IS A TEST", 5)]
+    #[case("This is a test", 0, "THIS IS A TEST", 0)]
+    #[case("This is a test", 14, "This is a test", 14)]
+    #[case("first line\nsecond line", 6, "first LINE\nsecond line", 6)]
+    #[case("first line\r\nsecond", 6, "first LINE\r\nsecond", 6)]
+    #[case("", 0, "", 0)]
+    fn uppercase_to_line_end_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.uppercase_to_line_end();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("THIS IS A TEST", 5, "THIS is a test", 5)]
+    #[case("FIRST LINE\nSECOND LINE", 0, "first line\nSECOND LINE", 0)]
+    #[case("", 0, "", 0)]
+    fn lowercase_to_line_end_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.lowercase_to_line_end();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("This is a test", 0, "this is a test", 1)]
+    #[case("This is a test", 1, "THis is a test", 2)]
+    #[case("This is a test", 14, "This is a test", 14)]
+    #[case("1 test", 0, "1 test", 1)]
+    #[case("😇 test", 0, "😇 test", 4)]
+    #[case("straße", 4, "straSSe", 6)]
+    #[case("e\u{301}t", 0, "E\u{301}t", 3)]
+    fn toggle_case_char_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.toggle_case_char();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("This is a test", 13, "This is a TEST", 14)]
     #[case("This is a test", 10, "This is a TEST", 14)]
@@ -831,6 +1843,31 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("This is a test", 8, "This a is test", 9)]
+    #[case("This is a test", 0, "This is a test", 0)]
+    #[case("This is a test", 14, "This is test a", 14)]
+    #[case("This is a test", 6, "is This a test", 7)]
+    #[case("This is   a test", 8, "This a   is test", 11)]
+    #[case("This", 2, "This", 2)]
+    fn transpose_words_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.transpose_words();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("line 1\nline 2", 7, 0)]
     #[case("line 1\nline 2", 8, 1)]
@@ -1092,6 +2129,69 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("    line", 6, "    ")]
+    #[case("\t\tline", 3, "\t\t")]
+    #[case(" \t line", 0, " \t ")]
+    #[case("line", 2, "")]
+    #[case("    first\n\tsecond", 14, "\t")]
+    #[case("    first\n", 10, "")]
+    #[case("    first\r\n", 11, "")]
+    #[case("  ", 2, "  ")]
+    fn test_current_line_indent(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.current_line_indent(), expected);
+    }
+
+    #[rstest]
+    #[case("( a ( b ) c )", 0, Some(12))]
+    #[case("( a ( b ) c )", 12, Some(0))]
+    #[case("( a ( b ) c )", 4, Some(8))]
+    #[case("( a ( b ) c )", 8, Some(4))]
+    #[case("[ ( ] )", 0, Some(4))]
+    #[case("{ 😇 }", 0, Some(7))]
+    #[case("( a ( b ) c )", 2, None)]
+    #[case("( a ( b c )", 0, None)]
+    #[case("( a )", 5, None)]
+    fn test_matching_bracket_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.matching_bracket_index(), expected);
+    }
+
+    #[rstest]
+    #[case("x( a ( b ) c )y", 1, "xy", 1)]
+    #[case("x( a ( b ) c )y", 13, "xy", 1)]
+    #[case("x( a ( b ) c )y", 5, "x( a  c )y", 5)]
+    #[case("x( a ( b ) c )y", 3, "x( a ( b ) c )y", 3)]
+    #[case("x( a ( b c )y", 1, "x( a ( b c )y", 1)]
+    fn test_delete_to_matching_bracket(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        line_buffer.delete_to_matching_bracket();
+
+        assert_eq!(line_buffer.lines, expected);
+        assert_eq!(line_buffer.insertion_point(), expected_position);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("line", 0, 4)]
     #[case("line\nline", 1, 4)]
@@ -1197,6 +2297,222 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case(1, 0)]
+    #[case(2, 4)]
+    #[case(3, 12)]
+    #[case(4, 18)]
+    #[case(0, 0)]
+    #[case(5, 18)]
+    #[case(usize::MAX, 18)]
+    fn test_move_to_line(#[case] line: usize, #[case] expected: usize) {
+        let mut line_buffer = buffer_with("one\ntwo ö\r\nthree\nfour");
+        line_buffer.set_insertion_point(6);
+
+        line_buffer.move_to_line(line);
+
+        assert_eq!(line_buffer.insertion_point(), expected);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("()", 1, "f()", 2)]
+    #[case("()", 0, "f()", 1)]
+    #[case("()", 2, "f()", 3)]
+    #[case("()", 10, "f()", 3)]
+    #[case("", 3, "f", 1)]
+    #[case("\"ö\"", 2, "f\"ö\"", 2)]
+    #[case("\"ö\"", 3, "f\"ö\"", 4)]
+    fn insert_str_places_the_cursor_within(
+        #[case] inserted: &str,
+        #[case] cursor_offset: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with("f");
+
+        line_buffer.insert_str_with_cursor(inserted, cursor_offset);
+
+        assert_eq!(line_buffer.get_buffer(), output);
+        assert_eq!(line_buffer.insertion_point(), out_location);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn insert_str_with_cursor_in_the_middle_of_the_buffer() {
+        let mut line_buffer = buffer_with("echo  done");
+        line_buffer.set_insertion_point(5);
+
+        line_buffer.insert_str_with_cursor("$()", 2);
+
+        assert_eq!(line_buffer.get_buffer(), "echo $() done");
+        assert_eq!(line_buffer.insertion_point(), 7);
+    }
+
+    fn buffer_with_cursors(content: &str, insertion_point: usize, cursors: &[usize]) -> LineBuffer {
+        let mut line_buffer = buffer_with(content);
+        line_buffer.set_insertion_point(insertion_point);
+        for cursor in cursors {
+            line_buffer.add_cursor(*cursor);
+        }
+        line_buffer
+    }
+
+    #[test]
+    fn insert_char_at_two_cursors() {
+        let mut line_buffer = buffer_with_cursors("ab\ncd", 2, &[5]);
+
+        line_buffer.insert_char('x');
+        line_buffer.insert_char('ö');
+
+        assert_eq!(line_buffer.get_buffer(), "abxö\ncdxö");
+        assert_eq!(line_buffer.insertion_point(), 5);
+        assert_eq!(line_buffer.secondary_cursors(), &[11]);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn insert_char_shifts_the_insertion_point_behind_a_cursor() {
+        let mut line_buffer = buffer_with_cursors("one two", 7, &[0, 4]);
+
+        line_buffer.insert_char('_');
+
+        assert_eq!(line_buffer.get_buffer(), "_one _two_");
+        assert_eq!(line_buffer.insertion_point(), 10);
+        assert_eq!(line_buffer.secondary_cursors(), &[1, 6]);
+    }
+
+    #[rstest]
+    #[case("hello hello", 5, &[11], "hell hell", 4, &[9])]
+    #[case("ab", 2, &[1], "", 0, &[])]
+    #[case("aé aé", 3, &[0, 7], "a a", 1, &[0, 3])]
+    fn delete_left_grapheme_at_each_cursor(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] cursors: &[usize],
+        #[case] output: &str,
+        #[case] out_location: usize,
+        #[case] out_cursors: &[usize],
+    ) {
+        let mut line_buffer = buffer_with_cursors(input, in_location, cursors);
+
+        line_buffer.delete_left_grapheme();
+
+        assert_eq!(line_buffer.get_buffer(), output);
+        assert_eq!(line_buffer.insertion_point(), out_location);
+        assert_eq!(line_buffer.secondary_cursors(), out_cursors);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn delete_right_grapheme_at_each_cursor() {
+        let mut line_buffer = buffer_with_cursors("xab xcd", 4, &[0]);
+
+        line_buffer.delete_right_grapheme();
+
+        assert_eq!(line_buffer.get_buffer(), "ab cd");
+        assert_eq!(line_buffer.insertion_point(), 3);
+        assert_eq!(line_buffer.secondary_cursors(), &[0]);
+    }
+
+    #[rstest]
+    #[case("one two\nthree four", 3, &[13], "one\nthree", 3, &[9])]
+    #[case("one two three", 7, &[3], "one", 3, &[])]
+    fn clear_to_line_end_at_each_cursor(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] cursors: &[usize],
+        #[case] output: &str,
+        #[case] out_location: usize,
+        #[case] out_cursors: &[usize],
+    ) {
+        let mut line_buffer = buffer_with_cursors(input, in_location, cursors);
+
+        line_buffer.clear_to_line_end();
+
+        assert_eq!(line_buffer.get_buffer(), output);
+        assert_eq!(line_buffer.insertion_point(), out_location);
+        assert_eq!(line_buffer.secondary_cursors(), out_cursors);
+    }
+
+    #[test]
+    fn insertion_point_moved_onto_a_cursor_is_edited_once() {
+        let mut line_buffer = buffer_with_cursors("ab", 0, &[2]);
+        line_buffer.set_insertion_point(2);
+
+        line_buffer.insert_char('x');
+
+        assert_eq!(line_buffer.get_buffer(), "abx");
+        assert_eq!(line_buffer.insertion_point(), 3);
+        assert_eq!(line_buffer.secondary_cursors(), &[] as &[usize]);
+    }
+
+    #[rstest]
+    #[case("ab cd", 2, &[0, 4], |b: &mut LineBuffer| b.insert_str("ü"), "abü cd", &[0, 6])]
+    #[case(
+        "ab cd", 2, &[4],
+        |b: &mut LineBuffer| b.insert_str_with_cursor("()", 1),
+        "ab() cd", &[6]
+    )]
+    #[case("aé bc", 3, &[5], LineBuffer::clear_to_insertion_point, " bc", &[2])]
+    #[case("aé bcé", 3, &[1, 6], LineBuffer::clear_to_end, "aé", &[1, 3])]
+    #[case("aé bc", 4, &[1, 5], |b: &mut LineBuffer| b.clear_range(1..4), "abc", &[1, 2])]
+    #[case("aé bc", 0, &[6], |b: &mut LineBuffer| b.replace(0..1, "xyz"), "xyzé bc", &[8])]
+    fn other_edits_move_the_cursors_along(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] cursors: &[usize],
+        #[case] edit: fn(&mut LineBuffer),
+        #[case] output: &str,
+        #[case] out_cursors: &[usize],
+    ) {
+        let mut line_buffer = buffer_with_cursors(input, in_location, cursors);
+
+        edit(&mut line_buffer);
+
+        assert_eq!(line_buffer.get_buffer(), output);
+        assert_eq!(line_buffer.secondary_cursors(), out_cursors);
+        for &cursor in line_buffer.secondary_cursors() {
+            assert!(line_buffer.get_buffer().is_char_boundary(cursor));
+        }
+    }
+
+    #[test]
+    fn secondary_cursors_are_validated_and_reset() {
+        let mut line_buffer = buffer_with_cursors("grüße", 0, &[0, 3, 4, 4, 10]);
+        assert_eq!(line_buffer.secondary_cursors(), &[4]);
+
+        line_buffer.set_buffer("new".into());
+        assert_eq!(line_buffer.secondary_cursors(), &[] as &[usize]);
+    }
+
+    #[rstest]
+    #[case("hello world", 2, "hello", "he")]
+    #[case("hello world", 0, "hello", "")]
+    #[case("hello world", 5, "hello", "hello")]
+    #[case("hello world", 6, "world", "")]
+    #[case("hello world", 11, "world", "world")]
+    #[case("hello  world", 6, "", "")]
+    #[case("git commit --amend", 12, "", "")]
+    #[case("git commit --amend", 15, "amend", "am")]
+    #[case("foo-bar", 3, "foo", "foo")]
+    #[case("foo-bar", 4, "bar", "")]
+    #[case("first\nsecond", 8, "second", "se")]
+    #[case("cd grüße", 7, "grüße", "grü")]
+    #[case("", 0, "", "")]
+    fn test_current_word(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] word: &str,
+        #[case] before_cursor: &str,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.current_word(), word);
+        assert_eq!(line_buffer.word_before_cursor(), before_cursor);
+    }
+
     #[rstest]
     #[case("", 0, 0..0)]
     #[case("line", 0, 0..4)]
@@ -1218,6 +2534,193 @@ mod test {
         assert_eq!(line_buffer.current_line_range(), expected);
     }
 
+    #[rstest]
+    #[case("first\nsecond", 2, "first\n\nsecond", 6)]
+    #[case("first\nsecond", 8, "first\nsecond\n", 13)]
+    #[case("first\r\nsecond", 0, "first\r\n\r\nsecond", 7)]
+    #[case("first\r\nsecond", 9, "first\r\nsecond\r\n", 15)]
+    #[case("  first\nsecond", 4, "  first\n  \nsecond", 10)]
+    #[case("", 0, "\n", 1)]
+    fn test_open_line_below(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.open_line_below();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("first\nsecond", 2, "\nfirst\nsecond", 0)]
+    #[case("first\nsecond", 8, "first\n\nsecond", 6)]
+    #[case("first\r\nsecond", 9, "first\r\n\r\nsecond", 7)]
+    #[case("first\n\tsecond", 9, "first\n\t\n\tsecond", 7)]
+    #[case("", 0, "\n", 0)]
+    fn test_open_line_above(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.open_line_above();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("first\nsecond", 2, "first second", 5)]
+    #[case("first\nsecond\nthird", 8, "first\nsecond third", 12)]
+    #[case("first\r\nsecond", 0, "first second", 5)]
+    #[case("first  \n\t  second", 0, "first second", 5)]
+    #[case("first\n  ", 0, "first", 5)]
+    #[case("first\n\nthird", 0, "first\nthird", 5)]
+    #[case("\nsecond", 0, "second", 0)]
+    #[case("  \nsecond", 1, "second", 0)]
+    #[case("first\nsecond", 8, "first\nsecond", 8)]
+    #[case("", 0, "", 0)]
+    fn test_join_lines(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.join_lines();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("first\nsecond\nthird", 8, "    ", "first\n    second\nthird", 12)]
+    #[case("first\nsecond", 6, "    ", "first\n    second", 10)]
+    #[case("first\nsecond", 5, "\t", "\tfirst\nsecond", 6)]
+    #[case("", 0, "  ", "  ", 2)]
+    fn test_indent_lines(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] indent: &str,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.indent_lines(indent);
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("first\n  second\nthird", 10, "    ", "first\nsecond\nthird", 8)]
+    #[case("first\n      second", 14, "    ", "first\n  second", 10)]
+    #[case("first\n\t  second", 7, "    ", "first\n  second", 6)]
+    #[case("first\n   second", 7, "    ", "first\nsecond", 6)]
+    #[case("first\nsecond", 8, "    ", "first\nsecond", 8)]
+    #[case("\t\tfirst", 3, "\t", "\tfirst", 2)]
+    fn test_dedent_lines(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] indent: &str,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.dedent_lines(indent);
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[test]
+    fn indent_and_dedent_every_selected_line() {
+        let mut line_buffer = buffer_with("first\nsecond\nthird\nfourth");
+        line_buffer.set_insertion_point(2);
+        line_buffer.set_anchor();
+        line_buffer.set_insertion_point(15);
+
+        line_buffer.indent_lines("  ");
+
+        assert_eq!(
+            line_buffer.get_buffer(),
+            "  first\n  second\n  third\nfourth"
+        );
+        assert_eq!(line_buffer.selection_range(), Some(4..21));
+
+        line_buffer.dedent_lines("  ");
+
+        assert_eq!(line_buffer.get_buffer(), "first\nsecond\nthird\nfourth");
+        assert_eq!(line_buffer.selection_range(), Some(2..15));
+    }
+
+    #[test]
+    fn selection_ending_at_a_line_start_does_not_indent_that_line() {
+        let mut line_buffer = buffer_with("first\nsecond");
+        line_buffer.set_insertion_point(0);
+        line_buffer.set_anchor();
+        line_buffer.set_insertion_point(6);
+
+        line_buffer.indent_lines("\t");
+
+        assert_eq!(line_buffer.get_buffer(), "\tfirst\nsecond");
+    }
+
+    #[rstest]
+    #[case("first\nsecond\nthird", 9, "first\n\nthird", 6, "second")]
+    #[case("first\r\nsecond\r\nthird", 10, "first\r\n\r\nthird", 7, "second")]
+    #[case("first\nsecond", 8, "first\n", 6, "second")]
+    #[case("first\nsecond", 2, "\nsecond", 0, "first")]
+    #[case("first\n\nthird", 6, "first\n\nthird", 6, "")]
+    #[case("", 0, "", 0, "")]
+    fn test_kill_whole_line(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+        #[case] killed: &str,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.kill_whole_line(), killed);
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("This is a test", 7, "This is", 7)]
     #[case("This is a test\nunrelated", 7, "This is\nunrelated", 7)]
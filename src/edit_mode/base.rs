@@ -1,5 +1,7 @@
 use crate::{enums::ReedlineEvent, PromptEditMode};
-use crossterm::event::Event;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 
 /// Define the style of parsing for the edit events
 /// Available default options:
@@ -12,3 +14,77 @@ pub trait EditMode: Send {
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
 }
+
+/// Translates the mouse events that are relevant for the line editor
+///
+/// Left clicks keep their terminal position while scrolling pages through an active menu
+pub(crate) fn parse_mouse_event(event: MouseEvent) -> ReedlineEvent {
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            ReedlineEvent::MouseClick(event.column, event.row)
+        }
+        MouseEventKind::ScrollDown => ReedlineEvent::MenuPageNext,
+        MouseEventKind::ScrollUp => ReedlineEvent::MenuPagePrevious,
+        _ => ReedlineEvent::Mouse,
+    }
+}
+
+/// The text a key stands for when it is inserted literally instead of being
+/// interpreted as a command, e.g. after [`ReedlineEvent::QuotedInsert`]
+///
+/// Control combinations of letters give their control character and special keys
+/// the sequence a terminal sends for them
+pub(crate) fn parse_literal_key(key: KeyEvent) -> Option<String> {
+    let literal = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_uppercase() {
+                c @ '@'..='_' => char::from(c as u8 - b'@').to_string(),
+                '?' => "\x7f".to_string(),
+                _ => return None,
+            }
+        }
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            c.to_ascii_uppercase().to_string()
+        }
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "\n".to_string(),
+        KeyCode::Tab => "\t".to_string(),
+        KeyCode::Esc => "\x1b".to_string(),
+        KeyCode::Backspace => "\x7f".to_string(),
+        KeyCode::Up => "\x1b[A".to_string(),
+        KeyCode::Down => "\x1b[B".to_string(),
+        KeyCode::Right => "\x1b[C".to_string(),
+        KeyCode::Left => "\x1b[D".to_string(),
+        KeyCode::Home => "\x1b[H".to_string(),
+        KeyCode::End => "\x1b[F".to_string(),
+        KeyCode::Delete => "\x1b[3~".to_string(),
+        _ => return None,
+    };
+
+    Some(literal)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(KeyCode::Tab, KeyModifiers::NONE, Some("\t"))]
+    #[case(KeyCode::Enter, KeyModifiers::NONE, Some("\n"))]
+    #[case(KeyCode::Esc, KeyModifiers::NONE, Some("\x1b"))]
+    #[case(KeyCode::Char('a'), KeyModifiers::CONTROL, Some("\x01"))]
+    #[case(KeyCode::Char('['), KeyModifiers::CONTROL, Some("\x1b"))]
+    #[case(KeyCode::Char('x'), KeyModifiers::SHIFT, Some("X"))]
+    #[case(KeyCode::Left, KeyModifiers::NONE, Some("\x1b[D"))]
+    #[case(KeyCode::F(1), KeyModifiers::NONE, None)]
+    fn literal_keys(
+        #[case] code: KeyCode,
+        #[case] modifiers: KeyModifiers,
+        #[case] expected: Option<&str>,
+    ) {
+        let literal = parse_literal_key(KeyEvent { code, modifiers });
+        assert_eq!(literal.as_deref(), expected);
+    }
+}
@@ -1,4 +1,5 @@
 use super::{
+    base::parse_mouse_event,
     keybindings::{add_common_keybindings, edit_bind, Keybindings},
     EditMode,
 };
@@ -9,6 +10,9 @@ use crate::{
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 
 /// Returns the current default emacs keybindings
+///
+/// `C-u` and `M-<digit>` start a numeric argument for the next command, so cutting
+/// from the start of the line is bound to `M-k`
 pub fn default_emacs_keybindings() -> Keybindings {
     use EditCommand as EC;
     use KeyCode as KC;
@@ -30,7 +34,7 @@ pub fn default_emacs_keybindings() -> Keybindings {
         ]),
     );
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::CutToEnd));
-    kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
+    kb.add_binding(KM::CONTROL, KC::Char('u'), ReedlineEvent::UniversalArgument);
     kb.add_binding(
         KM::CONTROL,
         KC::Char('y'),
@@ -39,6 +43,8 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('h'), edit_bind(EC::Backspace));
     kb.add_binding(KM::CONTROL, KC::Char('w'), edit_bind(EC::CutWordLeft));
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
+    kb.add_binding(KM::CONTROL, KC::Char('v'), ReedlineEvent::QuotedInsert);
+    kb.add_binding(KM::CONTROL, KC::Char('o'), ReedlineEvent::AcceptAndGetNext);
 
     // ALT
     kb.add_binding(KM::ALT, KC::Left, edit_bind(EC::MoveWordLeft));
@@ -62,79 +68,86 @@ pub fn default_emacs_keybindings() -> Keybindings {
         ]),
     );
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('k'), edit_bind(EC::CutFromStart));
     kb.add_binding(KM::ALT, KC::Char('u'), edit_bind(EC::UppercaseWord));
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
     kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
+    kb.add_binding(KM::ALT, KC::Char('t'), edit_bind(EC::TransposeWords));
     kb.add_binding(
         KM::ALT,
         KC::Char('m'),
         ReedlineEvent::Edit(vec![EditCommand::BackspaceWord]),
     );
 
+    // SHIFT
+    kb.add_binding(KM::SHIFT, KC::Left, edit_bind(EC::SelectLeft));
+    kb.add_binding(KM::SHIFT, KC::Right, edit_bind(EC::SelectRight));
+    kb.add_binding(KM::SHIFT, KC::Home, edit_bind(EC::SelectToLineStart));
+    kb.add_binding(KM::SHIFT, KC::End, edit_bind(EC::SelectToLineEnd));
+
+    kb.add_binding(KM::NONE, KC::Insert, ReedlineEvent::ToggleOvertype);
+    // Whether `Enter` inserts a line break is up to the validator, see `ReedlineEvent::Enter`
+    kb.add_binding(KM::NONE, KC::Enter, ReedlineEvent::Enter);
+
     add_common_keybindings(&mut kb);
 
     kb
 }
 
+/// Largest numeric argument, so a long chain of `C-u` can't stall the editor
+const MAX_ARGUMENT: usize = 1_000_000;
+
+/// Numeric argument entered with `C-u` or `M-<digit>`, applied to the next command
+struct Argument {
+    count: usize,
+    // Digits replace the count started by `C-u` and are appended to it afterwards
+    typed_digits: bool,
+}
+
 /// This parses the incoming Events like a emacs style-editor
 pub struct Emacs {
     keybindings: Keybindings,
+    argument: Option<Argument>,
 }
 
 impl Default for Emacs {
     fn default() -> Self {
         Emacs {
             keybindings: default_emacs_keybindings(),
+            argument: None,
         }
     }
 }
 
 impl EditMode for Emacs {
     fn parse_event(&mut self, event: Event) -> ReedlineEvent {
-        match event {
-            Event::Key(KeyEvent { code, modifiers }) => match (modifiers, code) {
-                (modifier, KeyCode::Char(c)) => {
-                    // Note. The modifier can also be a combination of modifiers, for
-                    // example:
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-                    //
-                    // Mixed modifiers are used by non american keyboards that have extra
-                    // keys like 'alt gr'. Keep this in mind if in the future there are
-                    // cases where an event is not being captured
-                    let c = match modifier {
-                        KeyModifiers::NONE => c,
-                        _ => c.to_ascii_lowercase(),
-                    };
-
-                    if modifier == KeyModifiers::NONE
-                        || modifier == KeyModifiers::SHIFT
-                        || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
-                        || modifier
-                            == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-                    {
-                        ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                            if modifier == KeyModifiers::SHIFT {
-                                c.to_ascii_uppercase()
-                            } else {
-                                c
-                            },
-                        )])
-                    } else {
-                        self.keybindings
-                            .find_binding(modifier, KeyCode::Char(c))
-                            .unwrap_or(ReedlineEvent::None)
-                    }
+        let event = match event {
+            Event::Key(KeyEvent { code, modifiers }) => {
+                if self.parse_argument(modifiers, code) {
+                    return ReedlineEvent::None;
                 }
-                (KeyModifiers::NONE, KeyCode::Enter) => ReedlineEvent::Enter,
-                _ => self
-                    .keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
-            },
-
-            Event::Mouse(_) => ReedlineEvent::Mouse,
-            Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
+                self.parse_key(modifiers, code)
+            }
+
+            Event::Mouse(mouse) => return parse_mouse_event(mouse),
+            Event::Resize(width, height) => return ReedlineEvent::Resize(width, height),
+        };
+
+        // The argument ends with the first key that isn't part of it
+        match (event, self.argument.take()) {
+            (ReedlineEvent::UniversalArgument, argument) => {
+                let count = argument.map_or(4, |argument| argument.count.saturating_mul(4));
+                self.argument = Some(Argument {
+                    count: count.min(MAX_ARGUMENT),
+                    typed_digits: false,
+                });
+                ReedlineEvent::None
+            }
+            (ReedlineEvent::None, _) => ReedlineEvent::None,
+            (event, None) => event,
+            (event, Some(argument)) => {
+                ReedlineEvent::Multiple(vec![ReedlineEvent::NumericArgument(argument.count), event])
+            }
         }
     }
 
@@ -146,7 +159,85 @@ impl EditMode for Emacs {
 impl Emacs {
     /// Emacs style input parsing constructor if you want to use custom keybindings
     pub fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+        Emacs {
+            keybindings,
+            argument: None,
+        }
+    }
+
+    /// Adds `M-<digit>`, or a digit following `C-u` or `M-<digit>`, to the numeric argument.
+    /// `M-<digit>` keys with a keybinding of their own are not part of it.
+    ///
+    /// Returns false if the key isn't part of the argument
+    fn parse_argument(&mut self, modifiers: KeyModifiers, code: KeyCode) -> bool {
+        let digit = match (modifiers, code) {
+            (KeyModifiers::ALT, KeyCode::Char(c))
+                if self.keybindings.find_binding(modifiers, code).is_none() =>
+            {
+                c.to_digit(10)
+            }
+            (KeyModifiers::NONE, KeyCode::Char(c)) if self.argument.is_some() => c.to_digit(10),
+            _ => None,
+        };
+        let digit = match digit {
+            Some(digit) => digit,
+            None => return false,
+        };
+
+        let count = match &self.argument {
+            Some(argument) if argument.typed_digits => argument
+                .count
+                .saturating_mul(10)
+                .saturating_add(digit as usize),
+            _ => digit as usize,
+        };
+        self.argument = Some(Argument {
+            count: count.min(MAX_ARGUMENT),
+            typed_digits: true,
+        });
+
+        true
+    }
+
+    fn parse_key(&self, modifiers: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        match (modifiers, code) {
+            (modifier, KeyCode::Char(c)) => {
+                // Note. The modifier can also be a combination of modifiers, for
+                // example:
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                //
+                // Mixed modifiers are used by non american keyboards that have extra
+                // keys like 'alt gr'. Keep this in mind if in the future there are
+                // cases where an event is not being captured
+                let c = match modifier {
+                    KeyModifiers::NONE => c,
+                    _ => c.to_ascii_lowercase(),
+                };
+
+                if modifier == KeyModifiers::NONE
+                    || modifier == KeyModifiers::SHIFT
+                    || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                    || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                {
+                    ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                        if modifier == KeyModifiers::SHIFT {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c
+                        },
+                    )])
+                } else {
+                    self.keybindings
+                        .find_binding(modifier, KeyCode::Char(c))
+                        .unwrap_or(ReedlineEvent::None)
+                }
+            }
+            _ => self
+                .keybindings
+                .find_binding(modifiers, code)
+                .unwrap_or(ReedlineEvent::None),
+        }
     }
 }
 
@@ -154,6 +245,7 @@ impl Emacs {
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
     fn ctrl_l_leads_to_clear_screen_event() {
@@ -217,6 +309,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn enter_can_be_rebound() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Enter,
+            ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+        );
+        let mut emacs = Emacs::new(keybindings);
+        let enter = Event::Key(KeyEvent {
+            modifiers: KeyModifiers::NONE,
+            code: KeyCode::Enter,
+        });
+
+        assert_eq!(
+            emacs.parse_event(enter),
+            ReedlineEvent::Edit(vec![EditCommand::InsertNewline])
+        );
+        assert_eq!(Emacs::default().parse_event(enter), ReedlineEvent::Enter);
+    }
+
     #[test]
     fn return_none_reedline_event_when_keybinding_is_not_found() {
         let keybindings = Keybindings::default();
@@ -246,4 +359,148 @@ mod test {
             ReedlineEvent::Edit(vec![EditCommand::InsertChar('😀')])
         );
     }
+
+    fn key(modifiers: KeyModifiers, c: char) -> Event {
+        Event::Key(KeyEvent {
+            modifiers,
+            code: KeyCode::Char(c),
+        })
+    }
+
+    #[rstest]
+    #[case(&[key(KeyModifiers::CONTROL, 'u')], 4)]
+    #[case(&[key(KeyModifiers::CONTROL, 'u'), key(KeyModifiers::CONTROL, 'u')], 16)]
+    #[case(&[key(KeyModifiers::ALT, '2'), key(KeyModifiers::ALT, '0')], 20)]
+    #[case(&[key(KeyModifiers::ALT, '1'), key(KeyModifiers::NONE, '2')], 12)]
+    #[case(&[key(KeyModifiers::CONTROL, 'u'), key(KeyModifiers::NONE, '5')], 5)]
+    #[case(&[key(KeyModifiers::CONTROL, 'u'), key(KeyModifiers::NONE, '0')], 0)]
+    #[case(&[key(KeyModifiers::ALT, '3'), key(KeyModifiers::CONTROL, 'u')], 12)]
+    fn numeric_argument_is_applied_to_the_next_key(
+        #[case] argument: &[Event],
+        #[case] count: usize,
+    ) {
+        let mut emacs = Emacs::default();
+        for event in argument {
+            assert_eq!(emacs.parse_event(*event), ReedlineEvent::None);
+        }
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'd')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::NumericArgument(count),
+                ReedlineEvent::CtrlD
+            ])
+        );
+        // Only the next key gets the argument
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, '1')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('1')])
+        );
+    }
+
+    #[test]
+    fn numeric_argument_is_dropped_by_unbound_keys() {
+        let mut emacs = Emacs::default();
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'u'));
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'q')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'x')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('x')])
+        );
+    }
+
+    #[test]
+    fn numeric_argument_is_limited() {
+        let mut emacs = Emacs::default();
+        for _ in 0..20 {
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'u'));
+        }
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'x')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::NumericArgument(MAX_ARGUMENT),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('x')])
+            ])
+        );
+    }
+
+    #[test]
+    fn bound_alt_digits_are_no_numeric_argument() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::ALT,
+            KeyCode::Char('1'),
+            ReedlineEvent::Menu("completion_menu".into()),
+        );
+        let mut emacs = Emacs::new(keybindings);
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::ALT, '1')),
+            ReedlineEvent::Menu("completion_menu".into())
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::ALT, '2')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::ALT, '1')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::NumericArgument(2),
+                ReedlineEvent::Menu("completion_menu".into())
+            ])
+        );
+    }
+
+    #[test]
+    fn alt_k_cuts_from_the_start_of_the_line() {
+        let mut emacs = Emacs::default();
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::ALT, 'k')),
+            ReedlineEvent::Edit(vec![EditCommand::CutFromStart])
+        );
+    }
+
+    #[test]
+    fn menu_navigation_can_be_bound_to_custom_keys() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('n'),
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuNext, ReedlineEvent::Down]),
+        );
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('p'),
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuPrevious, ReedlineEvent::Up]),
+        );
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('y'),
+            ReedlineEvent::MenuAccept,
+        );
+
+        let mut emacs = Emacs::new(keybindings);
+        let ctrl = |c| {
+            Event::Key(KeyEvent {
+                modifiers: KeyModifiers::CONTROL,
+                code: KeyCode::Char(c),
+            })
+        };
+
+        assert_eq!(
+            emacs.parse_event(ctrl('n')),
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuNext, ReedlineEvent::Down])
+        );
+        assert_eq!(
+            emacs.parse_event(ctrl('p')),
+            ReedlineEvent::UntilFound(vec![ReedlineEvent::MenuPrevious, ReedlineEvent::Up])
+        );
+        assert_eq!(emacs.parse_event(ctrl('y')), ReedlineEvent::MenuAccept);
+    }
 }
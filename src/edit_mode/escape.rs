@@ -0,0 +1,221 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+
+/// Tells a lone Escape apart from an escape sequence that reaches the editor in pieces
+///
+/// On slow connections the keys of a sequence like `Esc [ A` (arrow up) can arrive as
+/// separate key events. An Escape is held back until the timeout runs out. Keys that
+/// follow within the timeout are merged with it: `[`/`O` and a final character give the
+/// arrow, `Home` or `End` key, any other character the character with `Alt`.
+/// A timeout of zero passes every event on as is.
+pub(crate) struct EscapeSequence {
+    timeout: Duration,
+    // Characters received after the held back Escape and when to give up waiting
+    pending: Option<(Vec<char>, Instant)>,
+}
+
+impl EscapeSequence {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        EscapeSequence {
+            timeout,
+            pending: None,
+        }
+    }
+
+    /// Feeds the next terminal event, returns the events that are ready to be parsed
+    pub(crate) fn push(&mut self, event: Event, now: Instant) -> Vec<Event> {
+        if self.timeout.is_zero() {
+            return vec![event];
+        }
+
+        let mut events = self.take_expired(now);
+
+        let c = match (&self.pending, event) {
+            (
+                None,
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc,
+                    modifiers: KeyModifiers::NONE,
+                }),
+            ) => {
+                self.pending = Some((vec![], now + self.timeout));
+                return events;
+            }
+            (
+                Some(_),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                }),
+            ) => c,
+            (Some(_), event) => {
+                events.extend(self.take_pending());
+                events.push(event);
+                return events;
+            }
+            (None, event) => {
+                events.push(event);
+                return events;
+            }
+        };
+
+        let (sequence, _) = self
+            .pending
+            .as_mut()
+            .expect("already checked for a pending escape");
+
+        let merged = match (sequence.as_slice(), c) {
+            ([], '[' | 'O') => {
+                sequence.push(c);
+                return events;
+            }
+            ([], c) => Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT)),
+            (['[' | 'O'], 'A') => Some(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE)),
+            (['[' | 'O'], 'B') => Some(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE)),
+            (['[' | 'O'], 'C') => Some(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE)),
+            (['[' | 'O'], 'D') => Some(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE)),
+            (['[' | 'O'], 'H') => Some(KeyEvent::new(KeyCode::Home, KeyModifiers::NONE)),
+            (['[' | 'O'], 'F') => Some(KeyEvent::new(KeyCode::End, KeyModifiers::NONE)),
+            _ => None,
+        };
+
+        match merged {
+            Some(key) => {
+                self.pending = None;
+                events.push(Event::Key(key));
+            }
+            None => {
+                // Not a sequence we know, the keys were typed on their own
+                events.extend(self.take_pending());
+                events.push(Event::Key(KeyEvent::new(
+                    KeyCode::Char(c),
+                    KeyModifiers::NONE,
+                )));
+            }
+        }
+
+        events
+    }
+
+    /// Until when the held back Escape waits for the rest of a sequence
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|(_, deadline)| *deadline)
+    }
+
+    /// The held back keys as they were typed, once the timeout ran out
+    pub(crate) fn take_expired(&mut self, now: Instant) -> Vec<Event> {
+        match self.deadline() {
+            Some(deadline) if deadline <= now => self.take_pending(),
+            _ => vec![],
+        }
+    }
+
+    fn take_pending(&mut self) -> Vec<Event> {
+        match self.pending.take() {
+            Some((sequence, _)) => std::iter::once(KeyCode::Esc)
+                .chain(sequence.into_iter().map(KeyCode::Char))
+                .map(|code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn chars(text: &str) -> Vec<Event> {
+        text.chars().map(|c| key(KeyCode::Char(c))).collect()
+    }
+
+    /// Feeds the events 1ms apart, collecting everything that is ready
+    fn feed(escape: &mut EscapeSequence, start: Instant, events: Vec<Event>) -> Vec<Event> {
+        events
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, event)| escape.push(event, start + Duration::from_millis(i as u64)))
+            .collect()
+    }
+
+    #[rstest]
+    #[case("[A", key(KeyCode::Up))]
+    #[case("[B", key(KeyCode::Down))]
+    #[case("OC", key(KeyCode::Right))]
+    #[case("[D", key(KeyCode::Left))]
+    #[case("[H", key(KeyCode::Home))]
+    #[case("OF", key(KeyCode::End))]
+    #[case("b", Event::Key(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT)))]
+    fn keys_within_the_timeout_are_merged(#[case] sequence: &str, #[case] expected: Event) {
+        let mut escape = EscapeSequence::new(TIMEOUT);
+        let mut events = vec![key(KeyCode::Esc)];
+        events.extend(chars(sequence));
+
+        assert_eq!(feed(&mut escape, Instant::now(), events), vec![expected]);
+        assert_eq!(escape.deadline(), None);
+    }
+
+    #[test]
+    fn lone_escape_is_held_back_until_the_timeout() {
+        let mut escape = EscapeSequence::new(TIMEOUT);
+        let start = Instant::now();
+
+        assert_eq!(escape.push(key(KeyCode::Esc), start), vec![]);
+        assert_eq!(escape.deadline(), Some(start + TIMEOUT));
+        assert_eq!(escape.take_expired(start + TIMEOUT / 2), vec![]);
+        assert_eq!(
+            escape.take_expired(start + TIMEOUT),
+            vec![key(KeyCode::Esc)]
+        );
+        assert_eq!(escape.deadline(), None);
+    }
+
+    #[test]
+    fn keys_after_the_timeout_stay_apart() {
+        let mut escape = EscapeSequence::new(TIMEOUT);
+        let start = Instant::now();
+
+        assert_eq!(escape.push(key(KeyCode::Esc), start), vec![]);
+        assert_eq!(
+            escape.push(key(KeyCode::Char('j')), start + TIMEOUT * 2),
+            vec![key(KeyCode::Esc), key(KeyCode::Char('j'))]
+        );
+    }
+
+    #[test]
+    fn unknown_sequences_are_passed_on_as_typed() {
+        let mut escape = EscapeSequence::new(TIMEOUT);
+        let mut events = vec![key(KeyCode::Esc)];
+        events.extend(chars("[x"));
+
+        let mut expected = vec![key(KeyCode::Esc)];
+        expected.extend(chars("[x"));
+        assert_eq!(feed(&mut escape, Instant::now(), events), expected);
+    }
+
+    #[test]
+    fn other_events_end_the_sequence() {
+        let mut escape = EscapeSequence::new(TIMEOUT);
+        let events = vec![key(KeyCode::Esc), key(KeyCode::Enter)];
+
+        assert_eq!(feed(&mut escape, Instant::now(), events.clone()), events);
+    }
+
+    #[test]
+    fn zero_timeout_passes_events_on() {
+        let mut escape = EscapeSequence::new(Duration::ZERO);
+        let mut events = vec![key(KeyCode::Esc)];
+        events.extend(chars("[A"));
+
+        assert_eq!(feed(&mut escape, Instant::now(), events.clone()), events);
+        assert_eq!(escape.deadline(), None);
+    }
+}
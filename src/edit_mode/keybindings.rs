@@ -59,6 +59,19 @@ impl Keybindings {
         self.bindings.insert(key_combo, command);
     }
 
+    /// Removes the keybinding of a key, returning the event it was bound to.
+    ///
+    /// Without a binding the key does nothing, e.g. `Ctrl-C` no longer ends
+    /// [`crate::Reedline::read_line()`] with [`crate::Signal::CtrlC`]
+    pub fn remove_binding(
+        &mut self,
+        modifier: KeyModifiers,
+        key_code: KeyCode,
+    ) -> Option<ReedlineEvent> {
+        let key_combo = KeyCombination { modifier, key_code };
+        self.bindings.remove(&key_combo)
+    }
+
     /// Find a keybinding based on the modifier and keycode
     pub fn find_binding(&self, modifier: KeyModifiers, key_code: KeyCode) -> Option<ReedlineEvent> {
         let key_combo = KeyCombination { modifier, key_code };
@@ -93,9 +106,16 @@ pub fn add_common_keybindings(kb: &mut Keybindings) {
     );
     kb.add_binding(KM::NONE, KC::Home, edit_bind(EC::MoveToLineStart));
 
+    kb.add_binding(KM::ALT, KC::Enter, ReedlineEvent::SubmitForce);
+
     kb.add_binding(KM::CONTROL, KC::Char('c'), ReedlineEvent::CtrlC);
     kb.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
     kb.add_binding(KM::CONTROL, KC::Char('r'), ReedlineEvent::SearchHistory);
+    kb.add_binding(
+        KM::CONTROL,
+        KC::Char('s'),
+        ReedlineEvent::SearchHistoryForward,
+    );
 
     kb.add_binding(
         KM::CONTROL,
@@ -146,6 +166,13 @@ pub fn add_common_keybindings(kb: &mut Keybindings) {
             ReedlineEvent::Right,
         ]),
     );
+    kb.add_binding(KM::NONE, KC::PageUp, ReedlineEvent::HistoryPrefixSearchUp);
+    kb.add_binding(
+        KM::NONE,
+        KC::PageDown,
+        ReedlineEvent::HistoryPrefixSearchDown,
+    );
+
     kb.add_binding(
         KM::CONTROL,
         KC::Char('p'),
@@ -1,9 +1,12 @@
 mod base;
 mod emacs;
+mod escape;
 mod keybindings;
 mod vi;
 
+pub(crate) use base::parse_literal_key;
 pub use base::EditMode;
 pub use emacs::{default_emacs_keybindings, Emacs};
+pub(crate) use escape::EscapeSequence;
 pub use keybindings::Keybindings;
 pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
@@ -43,6 +43,40 @@ where
             let _ = input.next();
             Some(Command::MoveWordLeft)
         }
+        Some('W') => {
+            let _ = input.next();
+            Some(Command::MoveBigWordRight)
+        }
+        Some('e') => {
+            let _ = input.next();
+            Some(Command::MoveToWordEnd)
+        }
+        Some('E') => {
+            let _ = input.next();
+            Some(Command::MoveToBigWordEnd)
+        }
+        Some('g') => {
+            let _ = input.next();
+            match input.peek() {
+                Some('e') => {
+                    let _ = input.next();
+                    Some(Command::MoveToPreviousWordEnd)
+                }
+                Some('E') => {
+                    let _ = input.next();
+                    Some(Command::MoveToPreviousBigWordEnd)
+                }
+                Some(_) => {
+                    let _ = input.next();
+                    None
+                }
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('B') => {
+            let _ = input.next();
+            Some(Command::MoveBigWordLeft)
+        }
         Some('i') => {
             let _ = input.next();
             Some(Command::EnterViInsert)
@@ -87,6 +121,26 @@ where
             let _ = input.next();
             Some(Command::AppendToEnd)
         }
+        Some('~') => {
+            let _ = input.next();
+            Some(Command::ToggleCase)
+        }
+        Some('G') => {
+            let _ = input.next();
+            Some(Command::MoveToLine)
+        }
+        Some('J') => {
+            let _ = input.next();
+            Some(Command::JoinLines)
+        }
+        Some('o') => {
+            let _ = input.next();
+            Some(Command::OpenLineBelow)
+        }
+        Some('O') => {
+            let _ = input.next();
+            Some(Command::OpenLineAbove)
+        }
         Some('f') => {
             let _ = input.next();
             match input.peek() {
@@ -108,6 +162,21 @@ where
                 None => Some(Command::Incomplete),
             }
         }
+        Some('r') => {
+            let _ = input.next();
+            match input.peek() {
+                Some(c) => {
+                    let c = **c;
+                    let _ = input.next();
+                    Some(Command::ReplaceChar(c))
+                }
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('R') => {
+            let _ = input.next();
+            Some(Command::EnterViReplace)
+        }
         Some('T') => {
             let _ = input.next();
             match input.peek() {
@@ -132,8 +201,15 @@ pub enum Command {
     MoveDown,
     MoveWordRight,
     MoveWordLeft,
+    MoveBigWordRight,
+    MoveBigWordLeft,
+    MoveToWordEnd,
+    MoveToBigWordEnd,
+    MoveToPreviousWordEnd,
+    MoveToPreviousBigWordEnd,
     MoveToLineStart,
     MoveToLineEnd,
+    MoveToLine,
     EnterViAppend,
     EnterViInsert,
     Undo,
@@ -145,6 +221,12 @@ pub enum Command {
     MoveLeftUntil(char),
     MoveLeftBefore(char),
     HistorySearch,
+    ToggleCase,
+    JoinLines,
+    OpenLineBelow,
+    OpenLineAbove,
+    ReplaceChar(char),
+    EnterViReplace,
 }
 
 impl Command {
@@ -156,8 +238,20 @@ impl Command {
             Self::MoveRight => vec![ReedlineOption::Event(ReedlineEvent::Right)],
             Self::MoveToLineStart => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart)],
             Self::MoveToLineEnd => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd)],
-            Self::MoveWordLeft => vec![ReedlineOption::Edit(EditCommand::MoveWordLeft)],
-            Self::MoveWordRight => vec![ReedlineOption::Edit(EditCommand::MoveWordRight)],
+            // Without a count `G` goes to the last line
+            Self::MoveToLine => vec![ReedlineOption::Edit(EditCommand::MoveToLine(usize::MAX))],
+            Self::MoveWordLeft => vec![ReedlineOption::Edit(EditCommand::MoveSmallWordLeft)],
+            Self::MoveWordRight => vec![ReedlineOption::Edit(EditCommand::MoveSmallWordRight)],
+            Self::MoveBigWordLeft => vec![ReedlineOption::Edit(EditCommand::MoveBigWordLeft)],
+            Self::MoveBigWordRight => vec![ReedlineOption::Edit(EditCommand::MoveBigWordRight)],
+            Self::MoveToWordEnd => vec![ReedlineOption::Edit(EditCommand::MoveToWordEnd)],
+            Self::MoveToBigWordEnd => vec![ReedlineOption::Edit(EditCommand::MoveToBigWordEnd)],
+            Self::MoveToPreviousWordEnd => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToPreviousWordEnd)]
+            }
+            Self::MoveToPreviousBigWordEnd => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToPreviousBigWordEnd)]
+            }
             Self::EnterViInsert => vec![ReedlineOption::Event(ReedlineEvent::Repaint)],
             Self::EnterViAppend => vec![ReedlineOption::Edit(EditCommand::MoveRight)],
             Self::PasteAfter => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferAfter)],
@@ -173,6 +267,12 @@ impl Command {
             Self::MoveLeftBefore(c) => vec![ReedlineOption::Edit(EditCommand::MoveLeftBefore(*c))],
             Self::DeleteChar => vec![ReedlineOption::Edit(EditCommand::Delete)],
             Self::HistorySearch => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
+            Self::ToggleCase => vec![ReedlineOption::Edit(EditCommand::ToggleCaseChar)],
+            Self::JoinLines => vec![ReedlineOption::Edit(EditCommand::JoinLines)],
+            Self::OpenLineBelow => vec![ReedlineOption::Edit(EditCommand::OpenLineBelow)],
+            Self::OpenLineAbove => vec![ReedlineOption::Edit(EditCommand::OpenLineAbove)],
+            Self::ReplaceChar(c) => vec![ReedlineOption::Edit(EditCommand::ReplaceChar(*c))],
+            Self::EnterViReplace => vec![ReedlineOption::Event(ReedlineEvent::Repaint)],
             // Mark a command as incomplete whenever a motion is required to finish the command
             Self::Delete | Self::Change | Self::Incomplete => vec![ReedlineOption::Incomplete],
         }
@@ -188,6 +288,10 @@ impl Command {
                 Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CutToEnd)]),
                 Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CutCurrentLine)]),
                 Motion::Word => Some(vec![ReedlineOption::Edit(EditCommand::CutWordRight)]),
+                Motion::WordEnd => Some(vec![ReedlineOption::Edit(EditCommand::CutToWordEnd)]),
+                Motion::BigWordEnd => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CutToBigWordEnd)])
+                }
                 Motion::RightUntil(c) => {
                     Some(vec![ReedlineOption::Edit(EditCommand::CutRightUntil(*c))])
                 }
@@ -216,6 +320,14 @@ impl Command {
                     ReedlineOption::Edit(EditCommand::CutWordRight),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
                 ]),
+                Motion::WordEnd => Some(vec![
+                    ReedlineOption::Edit(EditCommand::CutToWordEnd),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
+                Motion::BigWordEnd => Some(vec![
+                    ReedlineOption::Edit(EditCommand::CutToBigWordEnd),
+                    ReedlineOption::Event(ReedlineEvent::Repaint),
+                ]),
                 Motion::RightUntil(c) => Some(vec![
                     ReedlineOption::Edit(EditCommand::CutRightUntil(*c)),
                     ReedlineOption::Event(ReedlineEvent::Repaint),
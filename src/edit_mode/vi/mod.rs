@@ -6,7 +6,7 @@ mod vi_keybindings;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 pub use vi_keybindings::{default_vi_insert_keybindings, default_vi_normal_keybindings};
 
-use super::EditMode;
+use super::{base::parse_mouse_event, EditMode};
 use crate::{
     edit_mode::{keybindings::Keybindings, vi::parser::parse},
     enums::{EditCommand, ReedlineEvent},
@@ -17,6 +17,7 @@ use crate::{
 enum ViMode {
     Normal,
     Insert,
+    Replace,
 }
 
 /// This parses incoming input `Event`s like a Vi-Style editor
@@ -59,8 +60,9 @@ impl EditMode for Vi {
             Event::Key(KeyEvent { code, modifiers }) => match (self.mode, modifiers, code) {
                 (ViMode::Normal, modifier, KeyCode::Char(c)) => {
                     // The repeat character is the only character that is not managed
-                    // by the parser since the last event is stored in the editor
-                    if c == '.' {
+                    // by the parser since the last event is stored in the editor.
+                    // Within a pending command (e.g. `r.`) it is a regular character
+                    if c == '.' && self.cache.is_empty() {
                         if let Some(event) = &self.previous {
                             return event.clone();
                         }
@@ -84,6 +86,8 @@ impl EditMode for Vi {
 
                         if res.enter_insert_mode() {
                             self.mode = ViMode::Insert;
+                        } else if res.enter_replace_mode() {
+                            self.mode = ViMode::Replace;
                         }
 
                         let event = res.to_reedline_event();
@@ -105,7 +109,7 @@ impl EditMode for Vi {
                         ReedlineEvent::None
                     }
                 }
-                (ViMode::Insert, modifier, KeyCode::Char(c)) => {
+                (ViMode::Insert | ViMode::Replace, modifier, KeyCode::Char(c)) => {
                     // Note. The modifier can also be a combination of modifiers, for
                     // example:
                     //     KeyModifiers::CONTROL | KeyModifiers::ALT
@@ -125,13 +129,16 @@ impl EditMode for Vi {
                         || modifier
                             == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
                     {
-                        ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                            if modifier == KeyModifiers::SHIFT {
-                                c.to_ascii_uppercase()
-                            } else {
-                                c
-                            },
-                        )])
+                        let c = if modifier == KeyModifiers::SHIFT {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c
+                        };
+                        ReedlineEvent::Edit(vec![if self.mode == ViMode::Replace {
+                            EditCommand::OvertypeChar(c)
+                        } else {
+                            EditCommand::InsertChar(c)
+                        }])
                     } else {
                         self.insert_keybindings
                             .find_binding(modifier, KeyCode::Char(c))
@@ -144,20 +151,33 @@ impl EditMode for Vi {
                     ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
                 }
                 (_, KeyModifiers::NONE, KeyCode::Enter) => {
-                    self.mode = ViMode::Insert;
-                    ReedlineEvent::Enter
+                    let keybindings = match self.mode {
+                        ViMode::Normal => &self.normal_keybindings,
+                        ViMode::Insert | ViMode::Replace => &self.insert_keybindings,
+                    };
+                    let event = keybindings
+                        .find_binding(modifiers, code)
+                        .unwrap_or(ReedlineEvent::None);
+                    // The next line is started in insert mode
+                    if event == ReedlineEvent::Enter {
+                        self.mode = ViMode::Insert;
+                    }
+                    event
+                }
+                (ViMode::Replace, KeyModifiers::NONE, KeyCode::Backspace) => {
+                    ReedlineEvent::Edit(vec![EditCommand::OvertypeBackspace])
                 }
                 (ViMode::Normal, _, _) => self
                     .normal_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
-                (ViMode::Insert, _, _) => self
+                (ViMode::Insert | ViMode::Replace, _, _) => self
                     .insert_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
             },
 
-            Event::Mouse(_) => ReedlineEvent::Mouse,
+            Event::Mouse(mouse) => parse_mouse_event(mouse),
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
         }
     }
@@ -166,6 +186,7 @@ impl EditMode for Vi {
         match self.mode {
             ViMode::Normal => PromptEditMode::Vi(PromptViMode::Normal),
             ViMode::Insert => PromptEditMode::Vi(PromptViMode::Insert),
+            ViMode::Replace => PromptEditMode::Vi(PromptViMode::Replace),
         }
     }
 }
@@ -191,6 +212,34 @@ mod test {
         assert!(matches!(vi.mode, ViMode::Normal));
     }
 
+    #[test]
+    fn enter_can_be_rebound() {
+        let mut insert_keybindings = default_vi_insert_keybindings();
+        insert_keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Enter,
+            ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+        );
+        let mut vi = Vi::new(insert_keybindings, default_vi_normal_keybindings());
+        let enter = Event::Key(KeyEvent {
+            modifiers: KeyModifiers::NONE,
+            code: KeyCode::Enter,
+        });
+
+        assert_eq!(
+            vi.parse_event(enter),
+            ReedlineEvent::Edit(vec![EditCommand::InsertNewline])
+        );
+
+        // The default binding submits from normal mode and returns to insert mode
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Vi::default()
+        };
+        assert_eq!(vi.parse_event(enter), ReedlineEvent::Enter);
+        assert_eq!(vi.mode, ViMode::Insert);
+    }
+
     #[test]
     fn keybinding_without_modifier_test() {
         let mut keybindings = default_vi_normal_keybindings();
@@ -256,7 +305,7 @@ mod test {
 
         let esc = Event::Key(KeyEvent {
             modifiers: KeyModifiers::NONE,
-            code: KeyCode::Char('e'),
+            code: KeyCode::Char('q'),
         });
         let result = vi.parse_event(esc);
 
@@ -9,6 +9,14 @@ where
             let _ = input.next();
             Some(Motion::Word)
         }
+        Some('e') => {
+            let _ = input.next();
+            Some(Motion::WordEnd)
+        }
+        Some('E') => {
+            let _ = input.next();
+            Some(Motion::BigWordEnd)
+        }
         Some('d') => {
             let _ = input.next();
             Some(Motion::Line)
@@ -44,6 +52,8 @@ where
 #[derive(Debug, PartialEq, Eq)]
 pub enum Motion {
     Word,
+    WordEnd,
+    BigWordEnd,
     Line,
     Start,
     End,
@@ -30,13 +30,28 @@ impl ParseResult {
             (Some(Command::EnterViInsert), None)
                 | (Some(Command::EnterViAppend), None)
                 | (Some(Command::AppendToEnd), None)
+                | (Some(Command::OpenLineBelow), None)
+                | (Some(Command::OpenLineAbove), None)
                 | (Some(Command::HistorySearch), None)
                 | (Some(Command::Change), Some(_))
         )
     }
 
+    pub fn enter_replace_mode(&self) -> bool {
+        matches!(
+            (&self.command, &self.motion),
+            (Some(Command::EnterViReplace), None)
+        )
+    }
+
     pub fn to_reedline_event(&self) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
+            // The count of `G` is the line to go to instead of a repetition
+            (Some(line), Some(Command::MoveToLine), None, None) => {
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLine(
+                    *line,
+                )])])
+            }
             // Movements with h,j,k,l are always single char or a number followed
             // by a single command (char)
             (multiplier, Some(command), None, None) => {
@@ -297,6 +312,29 @@ mod tests {
     #[case(&['d', 'd'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
     #[case(&['d', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRight])]))]
+    #[case(&['r', 'x'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::ReplaceChar('x')])]))]
+    #[case(&['r', '.'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::ReplaceChar('.')])]))]
+    #[case(&['R'], ReedlineEvent::Multiple(vec![ReedlineEvent::Repaint]))]
+    #[case(&['2', '~'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ToggleCaseChar]),
+        ReedlineEvent::Edit(vec![EditCommand::ToggleCaseChar])
+        ]))]
+    #[case(&['o'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::OpenLineBelow])]))]
+    #[case(&['O'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::OpenLineAbove])]))]
+    #[case(&['J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines])]))]
+    #[case(&['5', 'G'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLine(5)])]))]
+    #[case(&['G'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLine(usize::MAX)])]))]
+    #[case(&['w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveSmallWordRight])]))]
+    #[case(&['b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveSmallWordLeft])]))]
+    #[case(&['W'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveBigWordRight])]))]
+    #[case(&['B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveBigWordLeft])]))]
+    #[case(&['e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToWordEnd])]))]
+    #[case(&['E'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToBigWordEnd])]))]
+    #[case(&['g', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToPreviousWordEnd])]))]
+    #[case(&['g', 'E'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToPreviousBigWordEnd])]))]
+    #[case(&['g'], ReedlineEvent::None)]
+    #[case(&['d', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutToWordEnd])]))]
+    #[case(&['d', 'E'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutToBigWordEnd])]))]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let res = vi_parse(input);
         let output = res.to_reedline_event();
@@ -9,6 +9,7 @@ use crossterm::event::{KeyCode as KC, KeyModifiers as KM};
 pub fn default_vi_normal_keybindings() -> Keybindings {
     let mut kb = Keybindings::new();
 
+    kb.add_binding(KM::NONE, KC::Enter, ReedlineEvent::Enter);
     kb.add_binding(KM::CONTROL, KC::Char('c'), ReedlineEvent::CtrlC);
     kb.add_binding(KM::CONTROL, KC::Char('l'), ReedlineEvent::ClearScreen);
     kb.add_binding(
@@ -40,6 +41,8 @@ pub fn default_vi_insert_keybindings() -> Keybindings {
     let mut kb = Keybindings::new();
 
     add_common_keybindings(&mut kb);
+    kb.add_binding(KM::NONE, KC::Enter, ReedlineEvent::Enter);
+    kb.add_binding(KM::CONTROL, KC::Char('v'), ReedlineEvent::QuotedInsert);
 
     kb
 }
@@ -1,25 +1,41 @@
 use {
     crate::{
-        completion::{CircularCompletionHandler, Completer, DefaultCompleter},
-        core_editor::Editor,
-        edit_mode::{EditMode, Emacs},
-        enums::{EventStatus, ReedlineEvent},
+        completion::{CircularCompletionHandler, Completer, DefaultCompleter, Suggestion},
+        core_editor::{Editor, LineBuffer},
+        edit_mode::{parse_literal_key, EditMode, Emacs, EscapeSequence},
+        enums::{
+            AbbreviationPosition, BellStyle, EmptyLineBehavior, EventStatus, HintWrap, IndentUnit,
+            MenuPlacement, ReedlineEvent, Restriction, TabBehavior,
+        },
         highlighter::SimpleMatchHighlighter,
         hinter::{DefaultHinter, Hinter},
         history::{FileBackedHistory, History, HistoryNavigationQuery},
-        menu::{Menu, MenuEvent, ReedlineMenu},
-        painting::{Painter, PromptLines},
-        prompt::{PromptEditMode, PromptHistorySearchStatus},
+        menu::{menu_functions, MaxHeight, Menu, MenuEvent, MenuNotification, ReedlineMenu},
+        painting::{estimate_required_lines, line_width, offset_at_position, Painter, PromptLines},
+        prompt::{PromptCompletion, PromptEditMode, PromptHistorySearchStatus, PromptViMode},
         utils::text_manipulation,
-        DefaultValidator, EditCommand, ExampleHighlighter, Highlighter, Prompt,
-        PromptHistorySearch, Signal, ValidationResult, Validator,
+        CommandStatus, DefaultValidator, Diagnostic, EditCommand, ExampleHighlighter, Highlighter,
+        Prompt, PromptHistorySearch, Signal, ValidationResult, Validator,
     },
     crossterm::{
+        cursor::CursorShape,
         event,
-        event::{Event, KeyCode, KeyEvent, KeyModifiers},
-        terminal, Result,
+        event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
+        execute, terminal, Result,
     },
-    std::{borrow::Borrow, io, time::Duration},
+    nu_ansi_term::Style,
+    std::{
+        borrow::Cow,
+        collections::HashMap,
+        io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc::Receiver,
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+    unicode_segmentation::UnicodeSegmentation,
 };
 
 #[cfg(feature = "bashisms")]
@@ -36,6 +52,34 @@ const POLL_WAIT: u64 = 10;
 // a paste. 10 events in 10 milliseconds is conservative enough (unlikely somebody
 // will type more than 10 characters in 10 milliseconds)
 const EVENTS_THRESHOLD: usize = 10;
+// How often messages of an external printer are checked for while no key is pressed
+const EXTERNAL_PRINTER_WAIT: Duration = Duration::from_millis(100);
+// How often a prompt awaiting an update is checked while no key is pressed
+const PROMPT_UPDATE_WAIT: Duration = Duration::from_millis(50);
+
+/// Computes the text inserted for a [`ReedlineEvent::InsertSnippet`]
+pub type Snippet = Box<dyn Fn() -> String + Send>;
+
+/// Receives the changes of the menus, see [`Reedline::with_menu_observer`]
+pub type MenuObserver = Box<dyn FnMut(MenuNotification) + Send>;
+
+/// Receives the buffer and the cursor position whenever they change, see
+/// [`Reedline::with_buffer_change_listener`]
+pub type BufferChangeListener = Box<dyn FnMut(&str, usize) + Send>;
+
+/// Asks a running [`Reedline::read_line`] to redraw the prompt and the buffer from
+/// another thread, see [`Reedline::redraw_handle`]
+#[derive(Debug, Clone, Default)]
+pub struct RedrawHandle {
+    requested: Arc<AtomicBool>,
+}
+
+impl RedrawHandle {
+    /// Requests a [`Reedline::redraw`], done the next time the line editor is polled
+    pub fn request_redraw(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+}
 
 /// Determines if inputs should be used to extend the regular line buffer,
 /// traverse the history in the standard prompt or edit the search string in the
@@ -81,8 +125,11 @@ pub struct Reedline {
     history: Box<dyn History>,
     input_mode: InputMode,
 
-    // Validator
-    validator: Box<dyn Validator>,
+    // Buffer restored when a `history_prefix_search` finds nothing (more)
+    history_prefix_search_origin: LineBuffer,
+
+    // Validator, without one `Enter` always submits
+    validator: Option<Box<dyn Validator>>,
 
     // Stdout
     painter: Painter,
@@ -94,6 +141,10 @@ pub struct Reedline {
     completer: Box<dyn Completer>,
     quick_completions: bool,
     partial_completions: bool,
+    tab_behavior: TabBehavior,
+    tab_inserts_spaces: Option<usize>,
+    // Paint the buffer with the selected menu suggestion applied
+    menu_preview: bool,
 
     // Performs bash style circular rotation through the available completions
     circular_completion_handler: CircularCompletionHandler,
@@ -104,6 +155,12 @@ pub struct Reedline {
     // Showcase hints based on various strategies (history, language-completion, spellcheck, etc)
     hinter: Box<dyn Hinter>,
     hide_hints: bool,
+    // Buffer for which the hint was dismissed with `clear_hint`, hints return once it changes
+    hint_dismissed_for: Option<String>,
+
+    // Time without keystrokes before the hint is shown, the hint is held back until `hint_deadline`
+    hint_delay: Duration,
+    hint_deadline: Option<Instant>,
 
     // Is Some(n) read_line() should repaint prompt every `n` milliseconds
     animate: bool,
@@ -111,14 +168,114 @@ pub struct Reedline {
     // Use ansi coloring or not
     use_ansi_coloring: bool,
 
+    // Capture mouse events to place the cursor and select menu entries
+    mouse_capture: bool,
+
+    // Indent continuation lines like the line before them
+    auto_indent: bool,
+
+    // The next key is inserted literally (set by `ReedlineEvent::QuotedInsert`)
+    quoted_insert: bool,
+
+    // Typed characters overwrite the text under the cursor (toggled by `ReedlineEvent::ToggleOvertype`)
+    overtype: bool,
+
+    // Messages of background tasks printed above the prompt
+    external_printer: Option<Receiver<String>>,
+
+    // Redraws requested from other threads while `read_line` blocks
+    redraw_handle: RedrawHandle,
+
+    // Words replaced by their expansion when a space is typed or the buffer is submitted
+    abbreviations: HashMap<String, String>,
+    abbreviation_position: AbbreviationPosition,
+
+    // Opening and closing characters inserted together, e.g. brackets and quotes,
+    // and whether a backspace between an empty pair deletes both
+    auto_pairs: Vec<(char, char)>,
+    auto_pair_deletion: bool,
+
+    // Typed characters that open the completion menu when the completer has suggestions
+    completion_triggers: Vec<char>,
+
+    // Generators of the text inserted by `ReedlineEvent::InsertSnippet`
+    snippets: HashMap<String, Snippet>,
+
+    // Holds back an Escape to merge it with the keys of an escape sequence arriving late
+    escape_sequence: EscapeSequence,
+
+    // Whether submitted lines are added to the history
+    history_recording: bool,
+
+    // Reported by the embedder and passed to the prompt, see `Reedline::set_last_command_status`
+    last_command_status: Option<CommandStatus>,
+
+    // The history entry the next `read_line` starts with, see `ReedlineEvent::AcceptAndGetNext`
+    next_history_entry: Option<String>,
+
+    // Which edit commands and events are carried out
+    edit_command_restriction: Restriction<EditCommand>,
+    event_restriction: Restriction<ReedlineEvent>,
+
+    // A new reverse search resumes the last one, remembered as its query and match
+    resume_history_search: bool,
+    last_history_search: Option<(String, String)>,
+    // Number of matches listed below the reverse search
+    history_search_candidates: usize,
+    // All matches of the last query listed below the reverse search
+    history_search_matches: Option<(String, Vec<String>)>,
+
+    // Verdict of the validator on the last buffer submitted in the current read_line()
+    last_validation_result: Option<ValidationResult>,
+
+    // Problems the validator found in the buffer at the last paint
+    diagnostics: Vec<Diagnostic>,
+
+    // Cursor shapes for the vi normal and insert modes and the shape currently shown
+    cursor_shapes: Option<ViCursorShapes>,
+    current_cursor_shape: Option<CursorShape>,
+
+    // Feedback for rejected actions, rung after the events of a read cycle were handled
+    bell_style: BellStyle,
+    bell_pending: bool,
+
+    // Count the next edit or movement is applied with, and the last edit or movement for
+    // `ReedlineEvent::Repeat`
+    numeric_argument: Option<usize>,
+    last_repeatable_event: Option<ReedlineEvent>,
+
+    // What `Enter` does on an empty buffer
+    empty_line_behavior: EmptyLineBehavior,
+
     // Engine Menus
     menus: Vec<ReedlineMenu>,
+
+    // Notified about the menus, with the open menu and its selection it last saw
+    menu_observer: Option<MenuObserver>,
+    observed_menu: Option<(String, Option<Suggestion>)>,
+
+    // Notified about changes of the buffer, with the buffer and cursor it last saw
+    buffer_change_listener: Option<BufferChangeListener>,
+    observed_buffer: (String, usize),
+}
+
+/// Cursor shapes used in the modes of the [`Vi`](crate::Vi) edit mode
+#[derive(Debug, Clone, Copy)]
+struct ViCursorShapes {
+    normal: CursorShape,
+    insert: CursorShape,
 }
 
 impl Drop for Reedline {
     fn drop(&mut self) {
         // Ensures that the terminal is in a good state if we panic semigracefully
         // Calling `disable_raw_mode()` twice is fine with Linux
+        if self.current_cursor_shape.is_some() {
+            let _ignore = self.painter.set_cursor_shape(None);
+        }
+        if self.mouse_capture {
+            let _ignore = execute!(io::stderr(), DisableMouseCapture);
+        }
         let _ignore = terminal::disable_raw_mode();
     }
 }
@@ -128,30 +285,72 @@ impl Reedline {
     #[must_use]
     pub fn create() -> Self {
         let history = Box::new(FileBackedHistory::default());
-        let painter = Painter::new(std::io::BufWriter::new(std::io::stderr()));
+        let painter = Painter::new(std::io::BufWriter::new(Box::new(std::io::stderr())));
         let buffer_highlighter = Box::new(ExampleHighlighter::default());
         let completer = Box::new(DefaultCompleter::default());
         let hinter = Box::new(DefaultHinter::default());
-        let validator = Box::new(DefaultValidator);
+        let validator = Box::new(DefaultValidator::default());
         let edit_mode = Box::new(Emacs::default());
 
         Reedline {
             editor: Editor::default(),
             history,
             input_mode: InputMode::Regular,
+            history_prefix_search_origin: LineBuffer::new(),
             painter,
             edit_mode,
             completer,
             quick_completions: false,
             partial_completions: false,
+            tab_behavior: TabBehavior::default(),
+            tab_inserts_spaces: None,
+            menu_preview: false,
             circular_completion_handler: CircularCompletionHandler::default(),
             highlighter: buffer_highlighter,
             hinter,
             hide_hints: false,
-            validator,
+            hint_dismissed_for: None,
+            hint_delay: Duration::ZERO,
+            hint_deadline: None,
+            validator: Some(validator),
             animate: false,
             use_ansi_coloring: true,
+            mouse_capture: false,
+            auto_indent: false,
+            quoted_insert: false,
+            overtype: false,
+            external_printer: None,
+            redraw_handle: RedrawHandle::default(),
+            abbreviations: HashMap::new(),
+            abbreviation_position: AbbreviationPosition::default(),
+            auto_pairs: Vec::new(),
+            auto_pair_deletion: true,
+            completion_triggers: Vec::new(),
+            snippets: HashMap::new(),
+            escape_sequence: EscapeSequence::new(Duration::ZERO),
+            history_recording: true,
+            next_history_entry: None,
+            last_command_status: None,
+            edit_command_restriction: Restriction::default(),
+            event_restriction: Restriction::default(),
+            resume_history_search: false,
+            last_history_search: None,
+            history_search_candidates: 0,
+            history_search_matches: None,
+            last_validation_result: None,
+            diagnostics: Vec::new(),
+            cursor_shapes: None,
+            current_cursor_shape: None,
+            bell_style: BellStyle::default(),
+            empty_line_behavior: EmptyLineBehavior::default(),
+            bell_pending: false,
+            numeric_argument: None,
+            last_repeatable_event: None,
             menus: Vec::new(),
+            menu_observer: None,
+            buffer_change_listener: None,
+            observed_buffer: (String::new(), 0),
+            observed_menu: None,
         }
     }
 
@@ -177,6 +376,36 @@ impl Reedline {
         self
     }
 
+    /// A builder to only show the hint after no key has been pressed for `hint_delay`,
+    /// so the hint doesn't flicker while typing. The hint disappears with the next key.
+    ///
+    /// A delay of zero (the default) shows the hint right away
+    #[must_use]
+    pub fn with_hint_delay(mut self, hint_delay: Duration) -> Self {
+        self.hint_delay = hint_delay;
+        self
+    }
+
+    /// A builder to print the messages sent over `receiver` above the prompt while
+    /// [`Reedline::read_line()`] is active, e.g. the output of background tasks.
+    ///
+    /// The prompt and the input are painted again below the messages. Messages
+    /// arriving while the buffer is taller than the screen are painted over by it.
+    ///
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::sync::mpsc;
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut line_editor = Reedline::create().with_external_printer(receiver);
+    /// std::thread::spawn(move || sender.send("done".to_string()));
+    /// ```
+    #[must_use]
+    pub fn with_external_printer(mut self, receiver: Receiver<String>) -> Self {
+        self.external_printer = Some(receiver);
+        self
+    }
+
     /// A builder to configure the tab completion
     /// # Example
     /// ```rust,no_run
@@ -216,6 +445,148 @@ impl Reedline {
         self
     }
 
+    /// A builder to show the buffer with the suggestion selected in a menu already applied.
+    /// The preview follows the navigation through the menu, the buffer itself only changes
+    /// when the suggestion is accepted and stays as it was when the menu is aborted
+    #[must_use]
+    pub fn with_menu_preview(mut self, menu_preview: bool) -> Self {
+        self.menu_preview = menu_preview;
+        self
+    }
+
+    /// A builder to select what happens when a menu is activated, e.g. by pressing `Tab`.
+    ///
+    /// [`TabBehavior::AcceptSingle`] and [`TabBehavior::CompleteCommonPrefix`] take
+    /// effect in addition to the quick and partial completion options
+    #[must_use]
+    pub fn with_tab_behavior(mut self, tab_behavior: TabBehavior) -> Self {
+        self.tab_behavior = tab_behavior;
+        self
+    }
+
+    /// A builder to insert spaces instead of opening a menu that has nothing to offer.
+    ///
+    /// With `Some(count)`, activating a completion menu (usually bound to `Tab`) that
+    /// finds no suggestions inserts `count` spaces as a single edit. Available
+    /// suggestions still open the menu as usual, like menus that can't quick complete,
+    /// e.g. the history menu
+    #[must_use]
+    pub fn with_tab_inserts_spaces(mut self, tab_inserts_spaces: Option<usize>) -> Self {
+        self.tab_inserts_spaces = tab_inserts_spaces;
+        self
+    }
+
+    /// A builder to indent continuation lines of an incomplete input.
+    ///
+    /// When enabled, the newline inserted on `Enter` for input that fails validation
+    /// is followed by the leading whitespace of the line the cursor was on
+    #[must_use]
+    pub fn with_auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// A builder to change the shape of the cursor between the vi normal and insert modes,
+    /// e.g. a block in normal mode and a beam ([`CursorShape::Line`]) in insert mode.
+    ///
+    /// Not every terminal supports changing the cursor shape, so this is off by default.
+    /// The default shape of the terminal is restored once [`Reedline::read_line`] returns
+    #[must_use]
+    pub fn with_cursor_shapes(mut self, normal: CursorShape, insert: CursorShape) -> Self {
+        self.cursor_shapes = Some(ViCursorShapes { normal, insert });
+        self
+    }
+
+    /// A builder to select whether copying or cutting without a selection uses the current line
+    /// ([`EditCommand::CopySelection`] and [`EditCommand::CutSelection`]). Enabled by default
+    #[must_use]
+    pub fn with_copy_line_fallback(mut self, copy_line_fallback: bool) -> Self {
+        self.editor.set_copy_line_fallback(copy_line_fallback);
+        self
+    }
+
+    /// A builder to set the whitespace added by [`EditCommand::Indent`] and removed by
+    /// [`EditCommand::Dedent`]. Four spaces by default
+    #[must_use]
+    pub fn with_indent_unit(mut self, indent_unit: IndentUnit) -> Self {
+        self.editor.set_indent_unit(indent_unit);
+        self
+    }
+
+    /// A builder to limit the [`EditCommand`]s that are carried out, e.g. for a restricted
+    /// confirmation prompt. Blocked commands ring the bell set by [`Reedline::with_bell()`]
+    ///
+    /// Without [`EditCommand::InsertNewline`] and [`EditCommand::InsertNewlineWithIndent`]
+    /// `Enter` submits even input the [`Validator`] considers incomplete
+    ///
+    /// ```rust
+    /// use reedline::{EditCommand, Reedline, Restriction};
+    ///
+    /// let line_editor = Reedline::create().with_edit_command_restriction(Restriction::Deny(vec![
+    ///     EditCommand::InsertNewline,
+    ///     EditCommand::InsertNewlineWithIndent,
+    /// ]));
+    /// ```
+    #[must_use]
+    pub fn with_edit_command_restriction(mut self, restriction: Restriction<EditCommand>) -> Self {
+        self.edit_command_restriction = restriction;
+        self
+    }
+
+    /// A builder to limit the [`ReedlineEvent`]s that are carried out, e.g. to disable the
+    /// history with [`ReedlineEvent::SearchHistory`], [`ReedlineEvent::Up`] and
+    /// [`ReedlineEvent::Down`]. Blocked events ring the bell set by [`Reedline::with_bell()`]
+    ///
+    /// The restriction applies to the events inside of [`ReedlineEvent::UntilFound`] and
+    /// [`ReedlineEvent::Multiple`], not to the lists themselves. [`ReedlineEvent::Resize`]
+    /// and [`ReedlineEvent::Repaint`] are always carried out
+    #[must_use]
+    pub fn with_event_restriction(mut self, restriction: Restriction<ReedlineEvent>) -> Self {
+        self.event_restriction = restriction;
+        self
+    }
+
+    /// A builder to give feedback when an action has nothing to act on, like moving
+    /// past the end of the line, a completion without matches or a failing history search
+    #[must_use]
+    pub fn with_bell(mut self, bell_style: BellStyle) -> Self {
+        self.bell_style = bell_style;
+        self
+    }
+
+    /// A builder to choose what `Enter` does on an empty buffer. By default the empty
+    /// string is submitted, empty lines are never recorded in the history
+    #[must_use]
+    pub fn with_empty_line_behavior(mut self, behavior: EmptyLineBehavior) -> Self {
+        self.empty_line_behavior = behavior;
+        self
+    }
+
+    /// A builder to limit the rows taken up by the menus, so a long menu on a small
+    /// terminal doesn't push the prompt out of view. The menu scrolls within the limit
+    #[must_use]
+    pub fn with_menu_max_height(mut self, max_height: MaxHeight) -> Self {
+        self.painter.set_menu_max_height(Some(max_height));
+        self
+    }
+
+    /// A builder to choose where a menu is painted when the rows below the prompt
+    /// can't hold it. By default the prompt is scrolled up to paint it below, see
+    /// [`MenuPlacement::AboveWhenNearBottom`] for painting it over the rows above instead
+    #[must_use]
+    pub fn with_menu_placement(mut self, menu_placement: MenuPlacement) -> Self {
+        self.painter.set_menu_placement(menu_placement);
+        self
+    }
+
+    /// A builder to choose how a hint too long for the rest of the row is painted.
+    /// By default it wraps onto the following rows like the buffer
+    #[must_use]
+    pub fn with_hint_wrap(mut self, hint_wrap: HintWrap) -> Self {
+        self.painter.set_hint_wrap(hint_wrap);
+        self
+    }
+
     /// A builder which enables or disables the use of ansi coloring in the prompt
     /// and in the command line syntax highlighting.
     #[must_use]
@@ -232,6 +603,18 @@ impl Reedline {
         self
     }
 
+    /// A builder which enables or disables mouse support.
+    ///
+    /// With mouse capture, clicking places the cursor in the buffer or selects
+    /// an entry of the active menu and scrolling pages through the menu.
+    /// Note that the terminal's own scrollback won't react to the mouse wheel
+    /// while a line is read.
+    #[must_use]
+    pub fn with_mouse(mut self, mouse_capture: bool) -> Self {
+        self.mouse_capture = mouse_capture;
+        self
+    }
+
     /// A builder that configures the highlighter for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -274,6 +657,141 @@ impl Reedline {
         self
     }
 
+    /// A builder that sets abbreviations, which are expanded like in `fish`
+    ///
+    /// When a space is typed or Enter is pressed right after a word that is an
+    /// abbreviation, the word is replaced by its expansion first, e.g. `gco ` becomes
+    /// `git checkout `. The expansion is undone in one step. By default only command
+    /// names are expanded, see [`Reedline::with_abbreviation_position`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    /// use std::collections::HashMap;
+    ///
+    /// let abbreviations = HashMap::from([("gco".to_string(), "git checkout".to_string())]);
+    /// let mut line_editor = Reedline::create().with_abbreviations(abbreviations);
+    /// ```
+    #[must_use]
+    pub fn with_abbreviations(mut self, abbreviations: HashMap<String, String>) -> Self {
+        self.abbreviations = abbreviations;
+        self
+    }
+
+    /// A builder that sets which words are expanded when they match an abbreviation
+    #[must_use]
+    pub fn with_abbreviation_position(mut self, position: AbbreviationPosition) -> Self {
+        self.abbreviation_position = position;
+        self
+    }
+
+    /// A builder that sets the pairs of characters, like brackets and quotes, inserted
+    /// together when the opening one is typed, with the cursor placed between them
+    ///
+    /// Typing the closing character in front of the same one moves past it, and a
+    /// backspace between an empty pair deletes both, unless turned off with
+    /// [`Reedline::with_auto_pair_deletion`]. A pair is not inserted in front of a
+    /// word, inside a string delimited by one of the quotes, or for a quote right after
+    /// a word like in `it's`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let pairs = vec![('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+    /// let mut line_editor = Reedline::create().with_auto_pairs(pairs);
+    /// ```
+    #[must_use]
+    pub fn with_auto_pairs(mut self, pairs: Vec<(char, char)>) -> Self {
+        self.auto_pairs = pairs;
+        self
+    }
+
+    /// A builder that sets whether a backspace between an empty auto-pair, like `(|)`,
+    /// deletes the closing character as well. On by default
+    #[must_use]
+    pub fn with_auto_pair_deletion(mut self, auto_pair_deletion: bool) -> Self {
+        self.auto_pair_deletion = auto_pair_deletion;
+        self
+    }
+
+    /// A builder that opens the completion menu when one of `triggers` is typed, e.g. `/`
+    /// to list the entries of a directory. The menu only opens if the completer has
+    /// suggestions for the buffer, otherwise the character is just inserted
+    #[must_use]
+    pub fn with_completion_triggers(mut self, triggers: &[char]) -> Self {
+        self.completion_triggers = triggers.to_vec();
+        self
+    }
+
+    /// A builder that registers a snippet to be inserted by [`ReedlineEvent::InsertSnippet`]
+    ///
+    /// The text is computed every time the snippet is inserted and can be undone at once.
+    /// Registering a snippet under a used name replaces the previous one.
+    ///
+    /// # Example
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{default_emacs_keybindings, Emacs, Reedline, ReedlineEvent};
+    ///
+    /// let mut keybindings = default_emacs_keybindings();
+    /// keybindings.add_binding(
+    ///     KeyModifiers::ALT,
+    ///     KeyCode::Char('w'),
+    ///     ReedlineEvent::InsertSnippet("cwd".into()),
+    /// );
+    ///
+    /// let mut line_editor = Reedline::create()
+    ///     .with_edit_mode(Box::new(Emacs::new(keybindings)))
+    ///     .with_snippet(
+    ///         "cwd",
+    ///         Box::new(|| {
+    ///             std::env::current_dir()
+    ///                 .map(|dir| dir.display().to_string())
+    ///                 .unwrap_or_default()
+    ///         }),
+    ///     );
+    /// ```
+    #[must_use]
+    pub fn with_snippet(mut self, name: &str, snippet: Snippet) -> Self {
+        self.snippets.insert(name.to_string(), snippet);
+        self
+    }
+
+    /// A builder that sets how long an Escape waits for the rest of an escape sequence
+    ///
+    /// Over slow connections the keys of a sequence, e.g. for an arrow key, can arrive
+    /// apart and the leading Escape would e.g. switch [`Vi`](crate::Vi) into normal mode.
+    /// With a timeout the Escape is held back that long: keys following in time are
+    /// read as the key of the sequence or, for other characters, as the character
+    /// with `Alt`. The default of zero treats every Escape on its own right away.
+    #[must_use]
+    pub fn with_escape_timeout(mut self, timeout: Duration) -> Self {
+        self.escape_sequence = EscapeSequence::new(timeout);
+        self
+    }
+
+    /// A builder that lets the reverse history search resume the previous search
+    ///
+    /// Starting a search brings back the query and the match of the last search in the
+    /// session, e.g. after it was aborted to run another command. By default every
+    /// search starts from scratch.
+    #[must_use]
+    pub fn with_history_search_resume(mut self, resume: bool) -> Self {
+        self.resume_history_search = resume;
+        self
+    }
+
+    /// A builder to list up to `count` matches below the reverse history search
+    ///
+    /// The list pages through the matches as the search moves on, marking the current
+    /// one. With `0`, the default, only the current match is shown.
+    #[must_use]
+    pub fn with_history_search_candidates(mut self, count: usize) -> Self {
+        self.history_search_candidates = count;
+        self
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust,no_run
@@ -282,11 +800,36 @@ impl Reedline {
     /// use reedline::{DefaultValidator, Reedline};
     ///
     /// let mut line_editor =
-    /// Reedline::create().with_validator(Box::new(DefaultValidator));
+    /// Reedline::create().with_validator(Box::new(DefaultValidator::default()));
     /// ```
     #[must_use]
     pub fn with_validator(mut self, validator: Box<dyn Validator>) -> Self {
-        self.validator = validator;
+        self.validator = Some(validator);
+        self
+    }
+
+    /// A builder that removes the validator, so `Enter` always submits the buffer
+    ///
+    /// Line breaks are then only added by the keys bound to
+    /// [`EditCommand::InsertNewline`], e.g.
+    /// ```rust
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use reedline::{default_emacs_keybindings, EditCommand, Emacs, Reedline, ReedlineEvent};
+    ///
+    /// let mut keybindings = default_emacs_keybindings();
+    /// keybindings.add_binding(
+    ///     KeyModifiers::ALT,
+    ///     KeyCode::Enter,
+    ///     ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+    /// );
+    ///
+    /// let line_editor = Reedline::create()
+    ///     .with_edit_mode(Box::new(Emacs::new(keybindings)))
+    ///     .disable_validator();
+    /// ```
+    #[must_use]
+    pub fn disable_validator(mut self) -> Self {
+        self.validator = None;
         self
     }
 
@@ -297,6 +840,50 @@ impl Reedline {
         self
     }
 
+    /// A builder that sets an observer notified when a menu opens, highlights another
+    /// suggestion or closes
+    ///
+    /// The notifications are sent once the menu has been updated for painting.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::{MenuNotification, Reedline};
+    ///
+    /// let line_editor = Reedline::create().with_menu_observer(Box::new(|notification| {
+    ///     if let MenuNotification::SelectionChanged {
+    ///         selection: Some(suggestion),
+    ///         ..
+    ///     } = notification
+    ///     {
+    ///         // e.g. show the help of `suggestion.value` in a side pane
+    ///     }
+    /// }));
+    /// ```
+    #[must_use]
+    pub fn with_menu_observer(mut self, observer: MenuObserver) -> Self {
+        self.menu_observer = Some(observer);
+        self
+    }
+
+    /// A builder that sets a listener called with the buffer and the cursor position
+    /// whenever an event changed either of them, e.g. to mirror the editor in a GUI
+    ///
+    /// Events that leave both as they were don't call the listener.
+    ///
+    /// # Example
+    /// ```rust
+    /// use reedline::Reedline;
+    ///
+    /// let line_editor = Reedline::create().with_buffer_change_listener(Box::new(|buffer, cursor| {
+    ///     // e.g. render `buffer` with a caret at the byte offset `cursor`
+    /// }));
+    /// ```
+    #[must_use]
+    pub fn with_buffer_change_listener(mut self, listener: BufferChangeListener) -> Self {
+        self.buffer_change_listener = Some(listener);
+        self
+    }
+
     /// A builder that appends a menu to the engine
     #[must_use]
     pub fn with_menu(mut self, menu: Box<dyn Menu>, completer: Option<Box<dyn Completer>>) -> Self {
@@ -334,6 +921,115 @@ impl Reedline {
         &*self.history
     }
 
+    /// Swap the [`History`] at runtime, e.g. to keep a separate history per project
+    ///
+    /// The current history is synced to disk first and handed back, so it can be
+    /// activated again later. If syncing fails, the current history stays active.
+    /// The entry queued by [`ReedlineEvent::AcceptAndGetNext`] and the last history
+    /// search belong to the previous history and are dropped
+    pub fn set_history(&mut self, history: Box<dyn History>) -> std::io::Result<Box<dyn History>> {
+        self.history.sync()?;
+        self.next_history_entry = None;
+        self.last_history_search = None;
+        Ok(std::mem::replace(&mut self.history, history))
+    }
+
+    /// Turn the recording of submitted lines into the [`History`] on or off
+    ///
+    /// While turned off, e.g. for entering sensitive data, the existing history can still
+    /// be navigated. The setting persists across [`Reedline::read_line`] calls.
+    pub fn set_history_recording(&mut self, recording: bool) {
+        self.history_recording = recording;
+    }
+
+    /// Returns whether submitted lines are currently recorded into the [`History`]
+    pub fn history_recording(&self) -> bool {
+        self.history_recording
+    }
+
+    /// Returns what the [`Validator`] made of the buffer last submitted in the current
+    /// or latest [`Reedline::read_line`] call
+    ///
+    /// `None` if nothing was submitted yet, e.g. when the line editor was left with
+    /// [`Signal::CtrlC`]. Lets the caller tell whether a [`Signal::Success`] contains a
+    /// complete entry
+    pub fn last_validation_result(&self) -> Option<ValidationResult> {
+        self.last_validation_result
+    }
+
+    /// Returns the problems [`Validator::diagnostics`] found in the buffer as it was
+    /// last painted, e.g. to show their messages. Their spans are underlined in the buffer
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the current contents of the buffer together with the position of the cursor
+    ///
+    /// The position is a byte offset into the buffer which always lies on a grapheme
+    /// boundary, so it can be used to slice the buffer. It is the `pos` passed to the
+    /// [`Completer`] and [`Hinter`]
+    pub fn current_buffer(&self) -> (&str, usize) {
+        (self.editor.get_buffer(), self.editor.insertion_point())
+    }
+
+    /// Reports how the last command accepted by [`Reedline::read_line`] went, for the
+    /// prompt to show from the next `read_line` on, see [`Prompt::set_last_command_status`]
+    pub fn set_last_command_status(&mut self, exit_code: i32, duration: Duration) {
+        self.last_command_status = Some(CommandStatus {
+            exit_code,
+            duration,
+        });
+    }
+
+    /// Seeds the clipboard used by the cut and paste commands, e.g.
+    /// [`EditCommand::PasteCutBufferBefore`]
+    ///
+    /// This is the system clipboard with the `system_clipboard` feature and a buffer
+    /// local to the line editor otherwise
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        self.editor.set_clipboard_text(text);
+    }
+
+    /// Returns the content of the clipboard used by the cut and paste commands,
+    /// `None` if it is empty
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        self.editor.clipboard_text()
+    }
+
+    /// Screen position of the cursor as `(column, row)`, 0-based like the positions of
+    /// `crossterm`, e.g. to place a popup next to it
+    ///
+    /// Valid between keystrokes while [`Reedline::read_line()`] runs and considers
+    /// multiline and wrapped input. `None` before the first paint and after `read_line`
+    /// returned
+    pub fn cursor_screen_position(&self) -> Option<(u16, u16)> {
+        self.painter.cursor_position()
+    }
+
+    /// Returns the unformatted hint currently shown after the buffer,
+    /// empty if there is none
+    pub fn current_hint(&self) -> String {
+        if self.hints_active() {
+            self.hinter.complete_hint()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Closes the active menu, keeping the buffer as it is
+    ///
+    /// Takes effect with the next paint, e.g. before an application shows its own overlay
+    pub fn dismiss_menu(&mut self) {
+        self.menus
+            .iter_mut()
+            .for_each(|menu| menu.menu_event(MenuEvent::Deactivate));
+    }
+
+    /// Hides the current hint until the buffer is edited
+    pub fn clear_hint(&mut self) {
+        self.hint_dismissed_for = Some(self.editor.get_buffer().to_string());
+    }
+
     /// Update the underlying [`History`] to/from disk
     pub fn sync_history(&mut self) -> std::io::Result<()> {
         // TODO: check for interactions in the non-submitting events
@@ -347,34 +1043,186 @@ impl Reedline {
     /// handles user inputs.
     pub fn read_line(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
         terminal::enable_raw_mode()?;
+        if self.mouse_capture {
+            execute!(io::stderr(), EnableMouseCapture)?;
+        }
 
         let result = self.read_line_helper(prompt);
 
+        if self.current_cursor_shape.take().is_some() {
+            self.painter.set_cursor_shape(None)?;
+        }
+
+        if self.mouse_capture {
+            execute!(io::stderr(), DisableMouseCapture)?;
+        }
         terminal::disable_raw_mode()?;
 
         result
     }
 
-    /// Writes `msg` to the terminal with a following carriage return and newline
-    fn print_line(&mut self, msg: &str) -> Result<()> {
-        self.painter.paint_line(msg)
+    /// Wait for a single key press without line editing, like `read -n1` in a shell,
+    /// e.g. to answer a yes/no question.
+    ///
+    /// The terminal is in raw mode only while waiting, nothing is painted and the
+    /// buffer is left as it is. Other events on the way, like resizes, are consumed
+    pub fn read_key(&mut self) -> Result<KeyEvent> {
+        terminal::enable_raw_mode()?;
+        let result = self.next_key_event(event::read);
+        terminal::disable_raw_mode()?;
+
+        result
     }
 
-    /// Clear the screen by printing enough whitespace to start the prompt or
-    /// other output back at the first line of the terminal.
+    /// Reads events from `read` until a key is pressed
+    fn next_key_event(&mut self, mut read: impl FnMut() -> Result<Event>) -> Result<KeyEvent> {
+        loop {
+            match read()? {
+                Event::Key(key) => return Ok(key),
+                Event::Resize(width, height) => self.painter.handle_resize(width, height),
+                Event::Mouse(_) => {}
+            }
+        }
+    }
+
+    /// Wait for input like [`Reedline::read_line`], with the buffer prefilled with `initial`,
+    /// e.g. to edit a failed command again.
+    ///
+    /// The cursor is placed at the byte offset `cursor`, or at the end of `initial` if it is
+    /// `None`. Offsets inside a grapheme move to its start. Undo goes back to an empty buffer.
+    pub fn read_line_with_initial(
+        &mut self,
+        prompt: &dyn Prompt,
+        initial: &str,
+        cursor: Option<usize>,
+    ) -> Result<Signal> {
+        self.next_history_entry = None;
+        self.set_initial_buffer(initial, cursor);
+        self.read_line(prompt)
+    }
+
+    /// Starts with the entry following the one submitted by [`ReedlineEvent::AcceptAndGetNext`]
+    ///
+    /// The history traversal continues from the newest occurrence of the entry, so it can
+    /// be submitted the same way
+    fn load_next_history_entry(&mut self) {
+        let entry = match self.next_history_entry.take() {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        self.set_initial_buffer(&entry, None);
+        let steps_back = self
+            .history
+            .iter_chronologic()
+            .rev()
+            .position(|previous| previous == entry);
+        if let Some(steps_back) = steps_back {
+            self.history
+                .set_navigation(HistoryNavigationQuery::Normal(LineBuffer::new()));
+            for _ in 0..=steps_back {
+                self.history.back();
+            }
+            self.input_mode = InputMode::HistoryTraversal;
+        }
+    }
+
+    /// Replaces the buffer with `initial` as the only undoable step
+    fn set_initial_buffer(&mut self, initial: &str, cursor: Option<usize>) {
+        let cursor = match cursor {
+            Some(cursor) => initial
+                .grapheme_indices(true)
+                .map(|(index, _)| index)
+                .chain(std::iter::once(initial.len()))
+                .take_while(|index| *index <= cursor)
+                .last()
+                .unwrap_or(0),
+            None => initial.len(),
+        };
+
+        self.run_edit_commands(&[EditCommand::Clear]);
+        self.editor.reset_undo_stack();
+        self.run_edit_commands(&[
+            EditCommand::InsertString(initial.to_string()),
+            EditCommand::MoveToPosition(cursor),
+        ]);
+    }
+
+    /// Writes `msg` to the terminal with a following carriage return and newline
+    fn print_line(&mut self, msg: &str) -> Result<()> {
+        self.painter.paint_line(msg)
+    }
+
+    /// Clear the screen by printing enough whitespace to start the prompt or
+    /// other output back at the first line of the terminal.
     pub fn clear_screen(&mut self) -> Result<()> {
         self.painter.clear_screen()?;
 
         Ok(())
     }
 
+    /// Repaint the prompt and the buffer from scratch below the current position of the cursor
+    ///
+    /// Unlike [`Reedline::clear_screen()`] nothing is cleared above. The buffer and the
+    /// cursor are kept, while the highlighting, the hint and the layout of an active
+    /// menu are computed again. Call it after writing to the terminal out-of-band,
+    /// which leaves the prompt position recorded by the painter stale.
+    ///
+    /// While [`Reedline::read_line`] blocks, request it through a
+    /// [`Reedline::redraw_handle`] instead.
+    pub fn redraw(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.painter.initialize_prompt_position()?;
+        self.repaint_from_scratch(prompt)
+    }
+
+    /// Returns a handle to request a [`Reedline::redraw`] from another thread
+    /// while [`Reedline::read_line`] blocks
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use reedline::Reedline;
+    ///
+    /// let line_editor = Reedline::create();
+    /// let redraw_handle = line_editor.redraw_handle();
+    /// std::thread::spawn(move || {
+    ///     println!("written out-of-band");
+    ///     redraw_handle.request_redraw();
+    /// });
+    /// ```
+    pub fn redraw_handle(&self) -> RedrawHandle {
+        self.redraw_handle.clone()
+    }
+
+    /// Does a redraw requested through a [`RedrawHandle`]
+    fn redraw_if_requested(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if self.redraw_handle.requested.swap(false, Ordering::Relaxed) {
+            self.redraw(prompt)?;
+        }
+        Ok(())
+    }
+
+    /// Repaints at the current prompt position without any state left from previous paints
+    fn repaint_from_scratch(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.hint_deadline = None;
+        self.current_cursor_shape = None;
+        self.repaint(prompt)
+    }
+
     /// Helper implementing the logic for [`Reedline::read_line()`] to be wrapped
     /// in a `raw_mode` context.
     fn read_line_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
         self.painter.initialize_prompt_position()?;
         self.hide_hints = false;
+        self.hint_deadline = None;
+        self.hint_dismissed_for = None;
+        self.numeric_argument = None;
+        self.last_validation_result = None;
+        self.load_next_history_entry();
+        prompt.set_last_command_status(self.last_command_status);
 
         self.repaint(prompt)?;
+        // An initial buffer, see `read_line_with_initial`
+        self.notify_buffer_change_listener();
 
         let mut crossterm_events: Vec<Event> = vec![];
         let mut reedline_events: Vec<ReedlineEvent> = vec![];
@@ -382,7 +1230,32 @@ impl Reedline {
         loop {
             let mut paste_enter_state = false;
 
-            if event::poll(Duration::from_millis(1000))? {
+            self.print_external_messages(prompt)?;
+            self.redraw_if_requested(prompt)?;
+            self.repaint_updated_prompt(prompt)?;
+
+            if self
+                .painter
+                .visual_bell_deadline()
+                .is_some_and(|deadline| deadline <= Instant::now())
+            {
+                self.painter.end_visual_bell()?;
+            }
+
+            // An Escape without a sequence following in time
+            for event in self.escape_sequence.take_expired(Instant::now()) {
+                let event = self.parse_event(event);
+                reedline_events.push(event);
+            }
+
+            // Expired escapes are handled in this pass, without waiting for more input
+            let timeout = if reedline_events.is_empty() {
+                self.poll_timeout(prompt)
+            } else {
+                Duration::ZERO
+            };
+
+            if event::poll(timeout)? {
                 let mut latest_resize = None;
 
                 // There could be multiple events queued up!
@@ -397,7 +1270,8 @@ impl Reedline {
                             code: KeyCode::Enter,
                             modifiers: KeyModifiers::NONE,
                         }) => {
-                            crossterm_events.push(enter);
+                            crossterm_events
+                                .extend(self.escape_sequence.push(enter, Instant::now()));
                             // Break early to check if the input is complete and
                             // can be send to the hosting application. If
                             // multiple complete entries are submitted, events
@@ -407,11 +1281,18 @@ impl Reedline {
                             break;
                         }
                         x => {
-                            crossterm_events.push(x);
+                            crossterm_events.extend(self.escape_sequence.push(x, Instant::now()));
                         }
                     }
                 }
 
+                if crossterm_events
+                    .iter()
+                    .any(|event| matches!(event, Event::Key(_)))
+                {
+                    self.delay_hint();
+                }
+
                 if let Some((x, y)) = latest_resize {
                     reedline_events.push(ReedlineEvent::Resize(x, y));
                 }
@@ -421,7 +1302,7 @@ impl Reedline {
                 // (Text should only be `EditCommand::InsertChar`s)
                 let mut last_edit_commands = None;
                 for event in crossterm_events.drain(..) {
-                    match (&mut last_edit_commands, self.edit_mode.parse_event(event)) {
+                    match (&mut last_edit_commands, self.parse_event(event)) {
                         (None, ReedlineEvent::Edit(ec)) => {
                             last_edit_commands = Some(ec);
                         }
@@ -441,13 +1322,16 @@ impl Reedline {
                 if let Some(ec) = last_edit_commands {
                     reedline_events.push(ReedlineEvent::Edit(ec));
                 }
-            } else if self.animate && !self.painter.exceeds_screen_size() {
+            } else if self.hint_delay_elapsed()
+                || self.animate && !self.painter.exceeds_screen_size()
+            {
                 reedline_events.push(ReedlineEvent::Repaint);
             };
 
             for event in reedline_events.drain(..) {
                 match self.handle_event(prompt, event)? {
                     EventStatus::Exits(signal) => {
+                        self.painter.end_visual_bell()?;
                         // Move the cursor below the input area, for external commands or new read_line call
                         self.painter.move_cursor_to_end()?;
                         return Ok(signal);
@@ -462,14 +1346,279 @@ impl Reedline {
                     }
                 }
             }
+
+            if std::mem::take(&mut self.bell_pending) {
+                self.painter.ring_bell(self.bell_style)?;
+            }
+        }
+    }
+
+    /// Time to wait for the next terminal event, shortened to show a delayed hint in time
+    fn poll_timeout(&self, prompt: &dyn Prompt) -> Duration {
+        let timeout = self
+            .hint_deadline
+            .map_or(Duration::from_millis(1000), |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+
+        let timeout = match self.escape_sequence.deadline() {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        };
+
+        let timeout = match self.painter.visual_bell_deadline() {
+            Some(deadline) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+            None => timeout,
+        };
+
+        // Handed out redraw handles are checked as often as the external printer
+        let timeout = if self.external_printer.is_some()
+            || Arc::strong_count(&self.redraw_handle.requested) > 1
+        {
+            timeout.min(EXTERNAL_PRINTER_WAIT)
+        } else {
+            timeout
+        };
+
+        if prompt.awaits_update() {
+            timeout.min(PROMPT_UPDATE_WAIT)
+        } else {
+            timeout
+        }
+    }
+
+    /// Paints the prompt again if it changed in the background, see [`Prompt::poll_update`]
+    fn repaint_updated_prompt(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        if prompt.poll_update() {
+            self.repaint(prompt)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Prints the messages waiting at the external printer above the prompt and
+    /// repaints the prompt below them
+    fn print_external_messages(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        let messages: Vec<String> = match &self.external_printer {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return Ok(()),
+        };
+
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        self.painter.print_above_prompt(&messages)?;
+        self.repaint(prompt)
+    }
+
+    /// Holds back the hint until no key has been pressed for the hint delay
+    fn delay_hint(&mut self) {
+        if !self.hint_delay.is_zero() {
+            self.hint_deadline = Some(Instant::now() + self.hint_delay);
+        }
+    }
+
+    /// Whether a held back hint is due to be shown
+    fn hint_delay_elapsed(&mut self) -> bool {
+        match self.hint_deadline {
+            Some(deadline) if deadline <= Instant::now() => {
+                self.hint_deadline = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Translates a terminal event with the edit mode, unless the key was requested
+    /// to be inserted literally
+    fn parse_event(&mut self, event: Event) -> ReedlineEvent {
+        if let Event::Key(key) = event {
+            if std::mem::take(&mut self.quoted_insert) {
+                if let Some(literal) = parse_literal_key(key) {
+                    return ReedlineEvent::Edit(vec![EditCommand::InsertString(literal)]);
+                }
+            }
         }
+
+        self.edit_mode.parse_event(event)
     }
 
     fn handle_event(&mut self, prompt: &dyn Prompt, event: ReedlineEvent) -> Result<EventStatus> {
-        if self.input_mode == InputMode::HistorySearch {
-            self.handle_history_search_event(prompt, event)
+        let status = if self.input_mode == InputMode::HistorySearch {
+            let query = self.search_query();
+            let status = self.handle_history_search_event(prompt, event)?;
+            if self.bell_style != BellStyle::None && self.is_failed_search(query) {
+                self.bell_pending = true;
+            }
+            if self.resume_history_search {
+                self.remember_history_search();
+            }
+            status
         } else {
-            self.handle_editor_event(prompt, event)
+            self.handle_editor_event(prompt, event)?
+        };
+
+        self.notify_buffer_change_listener();
+        Ok(status)
+    }
+
+    /// Drops the commands the [`Restriction`] doesn't permit, ringing the bell if any was
+    fn permitted_commands(&mut self, commands: Vec<EditCommand>) -> Vec<EditCommand> {
+        let count = commands.len();
+        let commands: Vec<EditCommand> = commands
+            .into_iter()
+            .filter(|command| self.edit_command_restriction.permits(command))
+            .collect();
+        if commands.len() < count {
+            self.ring_bell_if_enabled();
+        }
+
+        commands
+    }
+
+    /// Whether the [`Restriction`] blocks `event`, ringing the bell if it does
+    ///
+    /// The events inside of `UntilFound` and `Multiple` are checked one by one when
+    /// they are handled. Resizes and repaints keep the painted prompt intact
+    fn is_restricted(&mut self, event: &ReedlineEvent) -> bool {
+        let restricted = match event {
+            ReedlineEvent::UntilFound(_)
+            | ReedlineEvent::Multiple(_)
+            | ReedlineEvent::Resize(..)
+            | ReedlineEvent::Repaint => false,
+            event => !self.event_restriction.permits(event),
+        };
+        if restricted {
+            self.ring_bell_if_enabled();
+        }
+
+        restricted
+    }
+
+    fn ring_bell_if_enabled(&mut self) {
+        if self.bell_style != BellStyle::None {
+            self.bell_pending = true;
+        }
+    }
+
+    /// Tells the buffer change listener about the buffer if it changed since it was last notified
+    fn notify_buffer_change_listener(&mut self) {
+        let listener = match self.buffer_change_listener.as_mut() {
+            Some(listener) => listener,
+            None => return,
+        };
+
+        let buffer = self.editor.get_buffer();
+        let cursor = self.editor.insertion_point();
+        if self.observed_buffer.0 != buffer || self.observed_buffer.1 != cursor {
+            listener(buffer, cursor);
+            self.observed_buffer = (buffer.to_string(), cursor);
+        }
+    }
+
+    fn search_query(&self) -> Option<String> {
+        match self.history.get_navigation() {
+            HistoryNavigationQuery::SubstringSearch(substring) => Some(substring),
+            _ => None,
+        }
+    }
+
+    /// A history search failed if the query was changed and nothing matches it
+    fn is_failed_search(&self, previous_query: Option<String>) -> bool {
+        let query = self.search_query();
+        query != previous_query
+            && query.is_some_and(|query| !query.is_empty())
+            && self.history.string_at_cursor().is_none()
+    }
+
+    /// Edits and cursor movements, which a numeric argument applies to
+    fn is_repeatable(event: &ReedlineEvent) -> bool {
+        matches!(
+            event,
+            ReedlineEvent::Edit(_)
+                | ReedlineEvent::Left
+                | ReedlineEvent::Right
+                | ReedlineEvent::Up
+                | ReedlineEvent::Down
+        )
+    }
+
+    /// Handles `event` `count` times, stopping early if it exits the line editor
+    fn repeat_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+        count: usize,
+    ) -> io::Result<EventStatus> {
+        let mut status = EventStatus::Inapplicable;
+        for _ in 0..count {
+            match self.handle_editor_event(prompt, event.clone())? {
+                EventStatus::Handled => status = EventStatus::Handled,
+                EventStatus::Inapplicable => {}
+                exits @ EventStatus::Exits(_) => return Ok(exits),
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Decides whether handling `event` did nothing the user asked for: a motion or
+    /// deletion at the boundary of the buffer or a completion without any matches
+    fn is_rejected(
+        &mut self,
+        event: &ReedlineEvent,
+        status: &EventStatus,
+        before: &LineBuffer,
+    ) -> bool {
+        match event {
+            // The values were updated when the menu was activated
+            ReedlineEvent::Menu(_) => {
+                matches!(status, EventStatus::Handled)
+                    && self
+                        .menus
+                        .iter()
+                        .find(|menu| menu.is_active())
+                        .is_some_and(|menu| {
+                            menu.can_quick_complete() && menu.get_values().is_empty()
+                        })
+            }
+            ReedlineEvent::Edit(commands)
+                if !commands.iter().all(|command| {
+                    matches!(
+                        command,
+                        EditCommand::MoveToStart
+                            | EditCommand::MoveToLineStart
+                            | EditCommand::MoveToEnd
+                            | EditCommand::MoveToLineEnd
+                            | EditCommand::MoveToLine(_)
+                            | EditCommand::MoveLeft
+                            | EditCommand::MoveRight
+                            | EditCommand::MoveWordLeft
+                            | EditCommand::MoveWordRight
+                            | EditCommand::MoveRightUntil(_)
+                            | EditCommand::MoveRightBefore(_)
+                            | EditCommand::MoveLeftUntil(_)
+                            | EditCommand::MoveLeftBefore(_)
+                            | EditCommand::Backspace
+                            | EditCommand::Delete
+                            | EditCommand::BackspaceWord
+                            | EditCommand::DeleteWord
+                    )
+                }) =>
+            {
+                false
+            }
+            ReedlineEvent::Edit(_)
+            | ReedlineEvent::Left
+            | ReedlineEvent::Right
+            | ReedlineEvent::Up
+            | ReedlineEvent::Down
+            | ReedlineEvent::PreviousHistory
+            | ReedlineEvent::NextHistory
+            | ReedlineEvent::HistoryPrefixSearchUp
+            | ReedlineEvent::HistoryPrefixSearchDown => self.editor.line_buffer() == before,
+            _ => false,
         }
     }
 
@@ -478,6 +1627,10 @@ impl Reedline {
         prompt: &dyn Prompt,
         event: ReedlineEvent,
     ) -> io::Result<EventStatus> {
+        if self.is_restricted(&event) {
+            return Ok(EventStatus::Inapplicable);
+        }
+
         match event {
             ReedlineEvent::UntilFound(events) => {
                 for event in events {
@@ -504,28 +1657,55 @@ impl Reedline {
                 }
             }
             ReedlineEvent::CtrlC => {
+                // Like Escape, only leaves the search. The next one interrupts
                 self.input_mode = InputMode::Regular;
-                Ok(EventStatus::Exits(Signal::CtrlC))
+                Ok(EventStatus::Handled)
             }
             ReedlineEvent::ClearScreen => Ok(EventStatus::Exits(Signal::CtrlL)),
             ReedlineEvent::Enter | ReedlineEvent::HistoryHintComplete => {
-                if let Some(string) = self.history.string_at_cursor() {
-                    self.editor.set_buffer(string);
-                    self.editor.remember_undo_state(true);
-                }
-
+                self.accept_history_search();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::Left | ReedlineEvent::Right => {
+                // Continue editing the match
+                self.accept_history_search();
+                self.handle_editor_event(prompt, event)
+            }
+            ReedlineEvent::Esc => {
+                // The buffer is only replaced once a match is accepted
                 self.input_mode = InputMode::Regular;
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::Multiple(events) => {
+                let mut latest_signal = EventStatus::Inapplicable;
+                for event in events {
+                    // Leaving the search hands the remaining events to the editor
+                    match self.handle_event(prompt, event)? {
+                        EventStatus::Handled => {
+                            latest_signal = EventStatus::Handled;
+                        }
+                        EventStatus::Inapplicable => {
+                            // NO OP
+                        }
+                        EventStatus::Exits(signal) => {
+                            return Ok(EventStatus::Exits(signal));
+                        }
+                    }
+                }
+
+                Ok(latest_signal)
+            }
             ReedlineEvent::ExecuteHostCommand(host_command) => {
                 // TODO: Decide if we need to do something special to have a nicer painter state on the next go
                 Ok(EventStatus::Exits(Signal::Success(host_command)))
             }
             ReedlineEvent::Edit(commands) => {
+                let commands = self.permitted_commands(commands);
                 self.run_history_commands(&commands);
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::Mouse => Ok(EventStatus::Handled),
+            ReedlineEvent::MouseClick(..) => Ok(EventStatus::Inapplicable),
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Handled)
@@ -534,11 +1714,17 @@ impl Reedline {
                 // A handled Event causes a repaint
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::PreviousHistory | ReedlineEvent::Up | ReedlineEvent::SearchHistory => {
+            ReedlineEvent::PreviousHistory
+            | ReedlineEvent::Up
+            | ReedlineEvent::HistoryPrefixSearchUp
+            | ReedlineEvent::SearchHistory => {
                 self.history.back();
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::NextHistory | ReedlineEvent::Down => {
+            ReedlineEvent::NextHistory
+            | ReedlineEvent::Down
+            | ReedlineEvent::HistoryPrefixSearchDown
+            | ReedlineEvent::SearchHistoryForward => {
                 self.history.forward();
                 // Hacky way to ensure that we don't fall of into failed search going forward
                 if self.history.string_at_cursor().is_none() {
@@ -547,12 +1733,17 @@ impl Reedline {
                 Ok(EventStatus::Handled)
             }
             // TODO: Check if events should be handled
-            ReedlineEvent::Right
-            | ReedlineEvent::Left
-            | ReedlineEvent::ActionHandler
-            | ReedlineEvent::Multiple(_)
+            ReedlineEvent::ActionHandler
+            | ReedlineEvent::SubmitForce
+            | ReedlineEvent::AcceptAndGetNext
+            | ReedlineEvent::QuotedInsert
+            | ReedlineEvent::InsertSnippet(_)
+            | ReedlineEvent::NumericArgument(_)
+            | ReedlineEvent::Repeat(_)
+            | ReedlineEvent::UniversalArgument
+            | ReedlineEvent::ToggleOvertype
+            | ReedlineEvent::CompleteCommonPrefix
             | ReedlineEvent::None
-            | ReedlineEvent::Esc
             | ReedlineEvent::HistoryHintWordComplete
             | ReedlineEvent::Menu(_)
             | ReedlineEvent::MenuNext
@@ -562,7 +1753,9 @@ impl Reedline {
             | ReedlineEvent::MenuLeft
             | ReedlineEvent::MenuRight
             | ReedlineEvent::MenuPageNext
-            | ReedlineEvent::MenuPagePrevious => Ok(EventStatus::Inapplicable),
+            | ReedlineEvent::MenuPagePrevious
+            | ReedlineEvent::MenuAccept
+            | ReedlineEvent::MenuAbort => Ok(EventStatus::Inapplicable),
         }
     }
 
@@ -570,14 +1763,88 @@ impl Reedline {
         &mut self,
         prompt: &dyn Prompt,
         event: ReedlineEvent,
+    ) -> io::Result<EventStatus> {
+        if self.is_restricted(&event) {
+            return Ok(EventStatus::Inapplicable);
+        }
+
+        // With a numeric argument `Ctrl-D` only deletes, the buffer emptying doesn't exit
+        let event = match event {
+            ReedlineEvent::CtrlD if self.numeric_argument.is_some() && !self.editor.is_empty() => {
+                ReedlineEvent::Edit(vec![EditCommand::Delete])
+            }
+            event => event,
+        };
+
+        match &event {
+            event if Self::is_repeatable(event) => {
+                self.last_repeatable_event = Some(event.clone());
+                if let Some(count) = self.numeric_argument.take() {
+                    return self.repeat_event(prompt, event.clone(), count);
+                }
+            }
+            // Events that wait for or are made of the event the argument applies to
+            ReedlineEvent::NumericArgument(_)
+            | ReedlineEvent::Repeat(_)
+            | ReedlineEvent::Multiple(_)
+            | ReedlineEvent::UntilFound(_)
+            | ReedlineEvent::None
+            | ReedlineEvent::Repaint
+            | ReedlineEvent::Resize(..)
+            | ReedlineEvent::Mouse => {}
+            _ => self.numeric_argument = None,
+        }
+
+        if self.bell_style == BellStyle::None {
+            return self.apply_editor_event(prompt, event);
+        }
+
+        // Composed events are judged by the events they are made of
+        let before = self.editor.line_buffer().clone();
+        let status = self.apply_editor_event(prompt, event.clone())?;
+        if self.is_rejected(&event, &status, &before) {
+            self.bell_pending = true;
+        }
+
+        Ok(status)
+    }
+
+    fn apply_editor_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
     ) -> io::Result<EventStatus> {
         match event {
             ReedlineEvent::Menu(name) => {
                 if self.active_menu().is_none() {
+                    let quick_completions =
+                        self.quick_completions || self.tab_behavior != TabBehavior::AlwaysMenu;
+                    let partial_completions = self.partial_completions
+                        || self.tab_behavior == TabBehavior::CompleteCommonPrefix;
+
                     if let Some(menu) = self.menus.iter_mut().find(|menu| menu.name() == name) {
-                        menu.menu_event(MenuEvent::Activate(self.quick_completions));
+                        menu.menu_event(MenuEvent::Activate(quick_completions));
+
+                        if let Some(spaces) = self
+                            .tab_inserts_spaces
+                            .filter(|_| menu.can_quick_complete())
+                        {
+                            menu.update_values(
+                                self.editor.line_buffer(),
+                                self.history.as_ref(),
+                                self.completer.as_ref(),
+                            );
+
+                            if menu.get_values().is_empty() {
+                                menu.menu_event(MenuEvent::Deactivate);
+                                self.run_edit_commands(&[EditCommand::InsertString(
+                                    " ".repeat(spaces),
+                                )]);
+                                return Ok(EventStatus::Handled);
+                            }
+                        }
 
-                        if self.quick_completions && menu.can_quick_complete() {
+                        if quick_completions && menu.can_quick_complete() {
                             menu.update_values(
                                 self.editor.line_buffer(),
                                 self.history.as_ref(),
@@ -587,11 +1854,20 @@ impl Reedline {
                             if menu.get_values().len() == 1 {
                                 return self.handle_editor_event(prompt, ReedlineEvent::Enter);
                             }
+                        } else if self.bell_style != BellStyle::None && menu.can_quick_complete() {
+                            // The bell needs the values to tell a failed completion,
+                            // the repaint reuses them
+                            menu.update_values(
+                                self.editor.line_buffer(),
+                                self.history.as_ref(),
+                                self.completer.as_ref(),
+                            );
+                            menu.menu_event(MenuEvent::Activate(true));
                         }
 
-                        if self.partial_completions
+                        if partial_completions
                             && menu.can_partially_complete(
-                                self.quick_completions,
+                                quick_completions,
                                 self.editor.line_buffer(),
                                 self.history.as_ref(),
                                 self.completer.as_ref(),
@@ -661,6 +1937,20 @@ impl Reedline {
                         Ok(EventStatus::Handled)
                     })
             }
+            ReedlineEvent::MenuAccept => {
+                if self.accept_menu_selection() {
+                    Ok(EventStatus::Handled)
+                } else {
+                    Ok(EventStatus::Inapplicable)
+                }
+            }
+            ReedlineEvent::MenuAbort => {
+                self.active_menu()
+                    .map_or(Ok(EventStatus::Inapplicable), |menu| {
+                        menu.menu_event(MenuEvent::Deactivate);
+                        Ok(EventStatus::Handled)
+                    })
+            }
             ReedlineEvent::HistoryHintComplete => {
                 let current_hint = self.hinter.complete_hint();
                 if self.hints_active()
@@ -687,6 +1977,13 @@ impl Reedline {
                     Ok(EventStatus::Inapplicable)
                 }
             }
+            ReedlineEvent::CompleteCommonPrefix => {
+                if self.complete_common_prefix() {
+                    Ok(EventStatus::Handled)
+                } else {
+                    Ok(EventStatus::Inapplicable)
+                }
+            }
             ReedlineEvent::ActionHandler => {
                 let line_buffer = self.editor.line_buffer();
                 self.circular_completion_handler
@@ -694,6 +1991,7 @@ impl Reedline {
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::Esc => {
+                self.editor.end_overtype();
                 self.menus
                     .iter_mut()
                     .for_each(|menu| menu.menu_event(MenuEvent::Deactivate));
@@ -709,9 +2007,11 @@ impl Reedline {
                 }
             }
             ReedlineEvent::CtrlC => {
-                self.menus
-                    .iter_mut()
-                    .for_each(|menu| menu.menu_event(MenuEvent::Deactivate));
+                // An open menu is closed first, keeping the buffer
+                if let Some(menu) = self.active_menu() {
+                    menu.menu_event(MenuEvent::Deactivate);
+                    return Ok(EventStatus::Handled);
+                }
                 self.run_edit_commands(&[EditCommand::Clear]);
                 self.editor.reset_undo_stack();
                 Ok(EventStatus::Exits(Signal::CtrlC))
@@ -723,12 +2023,18 @@ impl Reedline {
                 Ok(EventStatus::Exits(Signal::CtrlL))
             }
             ReedlineEvent::Enter => {
-                for menu in self.menus.iter_mut() {
-                    if menu.is_active() {
-                        menu.replace_in_buffer(self.editor.line_buffer());
-                        menu.menu_event(MenuEvent::Deactivate);
+                if self.accept_menu_selection() {
+                    return Ok(EventStatus::Handled);
+                }
 
-                        return Ok(EventStatus::Handled);
+                if self.editor.is_empty() {
+                    match self.empty_line_behavior {
+                        EmptyLineBehavior::Submit => {}
+                        EmptyLineBehavior::Ignore => return Ok(EventStatus::Handled),
+                        EmptyLineBehavior::RepaintPrompt => {
+                            self.painter.start_prompt_below()?;
+                            return Ok(EventStatus::Handled);
+                        }
                     }
                 }
 
@@ -737,34 +2043,112 @@ impl Reedline {
                     return self.handle_editor_event(prompt, event);
                 }
 
-                let buffer = self.editor.get_buffer().to_string();
-                if matches!(self.validator.validate(&buffer), ValidationResult::Complete) {
-                    self.hide_hints = true;
-                    // Additional repaint to show the content without hints etc.
-                    self.repaint(prompt)?;
-                    self.history.append(self.editor.get_buffer());
-                    self.run_edit_commands(&[EditCommand::Clear]);
-                    self.editor.reset_undo_stack();
+                self.expand_abbreviation();
 
-                    Ok(EventStatus::Exits(Signal::Success(buffer)))
+                let validation = self.validate(self.editor.get_buffer());
+                self.last_validation_result = Some(validation);
+                let newline = if self.auto_indent {
+                    EditCommand::InsertNewlineWithIndent
                 } else {
-                    #[cfg(windows)]
-                    {
-                        self.run_edit_commands(&[EditCommand::InsertChar('\r')]);
-                    }
-                    self.run_edit_commands(&[EditCommand::InsertChar('\n')]);
+                    EditCommand::InsertNewline
+                };
+                // A restricted prompt without line breaks submits the input as it is
+                if validation == ValidationResult::Complete
+                    || !self.edit_command_restriction.permits(&newline)
+                {
+                    self.submit_buffer(prompt)
+                } else {
+                    self.run_edit_commands(&[newline]);
 
                     Ok(EventStatus::Handled)
                 }
             }
-            ReedlineEvent::ExecuteHostCommand(host_command) => {
-                // TODO: Decide if we need to do something special to have a nicer painter state on the next go
-                Ok(EventStatus::Exits(Signal::Success(host_command)))
+            ReedlineEvent::QuotedInsert => {
+                self.quoted_insert = true;
+                Ok(EventStatus::Handled)
             }
-            ReedlineEvent::Edit(commands) => {
-                self.run_edit_commands(&commands);
-                if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
-                    if self.quick_completions && menu.can_quick_complete() {
+            ReedlineEvent::ToggleOvertype => {
+                self.overtype = !self.overtype;
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::SubmitForce => {
+                // The verdict is still recorded, so the caller can tell a forced submission
+                let validation = self.validate(self.editor.get_buffer());
+                self.last_validation_result = Some(validation);
+                self.submit_buffer(prompt)
+            }
+            ReedlineEvent::AcceptAndGetNext => {
+                // The buffer already holds the recalled entry. The history is left where
+                // it is in case Enter doesn't submit the buffer
+                let next_entry = if self.input_mode == InputMode::HistoryTraversal
+                    && self.history.string_at_cursor().is_some()
+                {
+                    self.history.forward();
+                    let next_entry = self.history.string_at_cursor();
+                    self.history.back();
+                    next_entry
+                } else {
+                    None
+                };
+
+                let status = self.handle_editor_event(prompt, ReedlineEvent::Enter)?;
+                if matches!(status, EventStatus::Exits(_)) {
+                    self.input_mode = InputMode::Regular;
+                    self.next_history_entry = next_entry;
+                }
+                Ok(status)
+            }
+            ReedlineEvent::ExecuteHostCommand(host_command) => {
+                // TODO: Decide if we need to do something special to have a nicer painter state on the next go
+                Ok(EventStatus::Exits(Signal::Success(host_command)))
+            }
+            ReedlineEvent::NumericArgument(count) => {
+                self.numeric_argument = Some(count);
+                Ok(EventStatus::Handled)
+            }
+            // Only meaningful to the edit mode, which turns it into a `NumericArgument`
+            ReedlineEvent::UniversalArgument => Ok(EventStatus::Inapplicable),
+            ReedlineEvent::Repeat(count) => match self.last_repeatable_event.clone() {
+                Some(event) => {
+                    let count = count * self.numeric_argument.take().unwrap_or(1);
+                    self.repeat_event(prompt, event, count)
+                }
+                None => Ok(EventStatus::Inapplicable),
+            },
+            ReedlineEvent::InsertSnippet(name) => match self.snippets.get(&name) {
+                Some(snippet) => {
+                    let text = snippet();
+                    self.run_edit_commands(&[EditCommand::InsertString(text)]);
+                    Ok(EventStatus::Handled)
+                }
+                None => Ok(EventStatus::Inapplicable),
+            },
+            ReedlineEvent::Edit(commands) => {
+                let mut commands = self.permitted_commands(commands);
+                if commands.is_empty() {
+                    return Ok(EventStatus::Inapplicable);
+                }
+                let triggers_completion = matches!(
+                    commands.as_slice(),
+                    [EditCommand::InsertChar(c)] if self.completion_triggers.contains(c)
+                );
+                // Only a typed space, pasted text is kept as is
+                if commands == [EditCommand::InsertChar(' ')] {
+                    self.expand_abbreviation();
+                }
+                if let Some(paired) = self.auto_pair_commands(&commands) {
+                    commands = paired;
+                }
+                if self.overtype {
+                    for command in commands.iter_mut() {
+                        if let EditCommand::InsertChar(c) = command {
+                            *command = EditCommand::OvertypeChar(*c);
+                        }
+                    }
+                }
+                self.run_edit_commands(&commands);
+                if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
+                    if self.quick_completions && menu.can_quick_complete() {
                         menu.menu_event(MenuEvent::Edit(self.quick_completions));
                         menu.update_values(
                             self.editor.line_buffer(),
@@ -782,11 +2166,16 @@ impl Reedline {
                     } else {
                         menu.menu_event(MenuEvent::Edit(self.quick_completions));
                     }
+                } else if triggers_completion {
+                    self.open_triggered_completion();
                 }
 
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::Mouse => Ok(EventStatus::Inapplicable),
+            ReedlineEvent::MouseClick(column, row) => {
+                Ok(self.handle_mouse_click(prompt, column, row))
+            }
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Handled)
@@ -803,6 +2192,14 @@ impl Reedline {
                 self.next_history();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::HistoryPrefixSearchUp => {
+                self.history_prefix_search(false);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::HistoryPrefixSearchDown => {
+                self.history_prefix_search(true);
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Up => {
                 self.up_command();
                 Ok(EventStatus::Handled)
@@ -819,7 +2216,7 @@ impl Reedline {
                 self.run_edit_commands(&[EditCommand::MoveRight]);
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::SearchHistory => {
+            ReedlineEvent::SearchHistory | ReedlineEvent::SearchHistoryForward => {
                 // Make sure we are able to undo the result of a reverse history search
                 self.editor.remember_undo_state(true);
 
@@ -865,10 +2262,165 @@ impl Reedline {
         }
     }
 
+    /// Opens the first completion menu if the completer has suggestions for the buffer
+    fn open_triggered_completion(&mut self) {
+        if let Some(menu) = self.menus.iter_mut().find(|menu| menu.can_quick_complete()) {
+            menu.update_values(
+                self.editor.line_buffer(),
+                self.history.as_ref(),
+                self.completer.as_ref(),
+            );
+
+            if !menu.get_values().is_empty() {
+                menu.menu_event(MenuEvent::Activate(true));
+            }
+        }
+    }
+
     fn active_menu(&mut self) -> Option<&mut ReedlineMenu> {
         self.menus.iter_mut().find(|menu| menu.is_active())
     }
 
+    /// Leaves the line editor with the buffer, recording it into the history
+    fn submit_buffer(&mut self, prompt: &dyn Prompt) -> io::Result<EventStatus> {
+        let buffer = self.editor.get_buffer().to_string();
+        self.hide_hints = true;
+        // Additional repaint to show the content without hints etc.
+        self.repaint(prompt)?;
+        if self.history_recording && !buffer.is_empty() {
+            self.history.append(&buffer);
+        }
+        self.run_edit_commands(&[EditCommand::Clear]);
+        self.editor.reset_undo_stack();
+
+        Ok(EventStatus::Exits(Signal::Success(buffer)))
+    }
+
+    /// Replaces the buffer with the selection of the active menu and closes it.
+    ///
+    /// Returns false if there was no active menu
+    fn accept_menu_selection(&mut self) -> bool {
+        match self.menus.iter_mut().find(|menu| menu.is_active()) {
+            Some(menu) => {
+                let continue_completion = menu
+                    .get_value()
+                    .is_some_and(|suggestion| suggestion.continue_completion);
+                if let Some(observer) = self.menu_observer.as_mut() {
+                    self.observed_menu = None;
+                    observer(MenuNotification::Accepted {
+                        menu: menu.name().to_string(),
+                        selection: menu.get_value(),
+                    });
+                }
+                let previous_buffer = self.editor.get_buffer().to_string();
+
+                menu.replace_in_buffer(self.editor.line_buffer());
+                menu.menu_event(MenuEvent::Deactivate);
+
+                // Completing again without any progress would offer the same values forever
+                if continue_completion && previous_buffer != self.editor.get_buffer() {
+                    menu.update_values(
+                        self.editor.line_buffer(),
+                        self.history.as_ref(),
+                        self.completer.as_ref(),
+                    );
+                    if !menu.get_values().is_empty() {
+                        menu.menu_event(MenuEvent::Activate(true));
+                    }
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inserts the longest prefix shared by all the suggestions of the completer.
+    ///
+    /// Returns false if nothing could be added to the typed text
+    fn complete_common_prefix(&mut self) -> bool {
+        let suggestions = self
+            .completer
+            .complete(self.editor.get_buffer(), self.editor.insertion_point());
+        let span = match suggestions.first() {
+            Some(suggestion) => suggestion.span,
+            None => return false,
+        };
+
+        let buffer = self.editor.get_buffer();
+        if span.start > span.end
+            || !buffer.is_char_boundary(span.start)
+            || !buffer.is_char_boundary(span.end)
+        {
+            return false;
+        }
+
+        let prefix = match menu_functions::find_common_string(&suggestions) {
+            (Some(first), index) => &first.value[..index.unwrap_or(first.value.len())],
+            (None, _) => return false,
+        };
+        if prefix.len() <= span.end - span.start || &buffer[span.start..span.end] == prefix {
+            return false;
+        }
+
+        menu_functions::replace_in_buffer(self.editor.line_buffer(), span, prefix);
+        self.editor.remember_undo_state(true);
+
+        true
+    }
+
+    /// Selects the clicked menu entry or moves the cursor to the clicked
+    /// position of the buffer, based on the layout of the last repaint
+    fn handle_mouse_click(&mut self, prompt: &dyn Prompt, column: u16, row: u16) -> EventStatus {
+        // The rows scrolled out of view are not tracked for large buffers
+        if self.painter.exceeds_screen_size() {
+            return EventStatus::Inapplicable;
+        }
+
+        if let Some(menu_row) = self.painter.menu_row(row) {
+            return self
+                .active_menu()
+                .map_or(EventStatus::Inapplicable, |menu| {
+                    menu.menu_event(MenuEvent::Click {
+                        row: menu_row,
+                        column,
+                    });
+                    EventStatus::Handled
+                });
+        }
+
+        let prompt_indicator = match self.menus.iter().find(|menu| menu.is_active()) {
+            Some(menu) => menu.indicator().to_string(),
+            None => prompt
+                .render_prompt_indicator(self.prompt_edit_mode())
+                .to_string(),
+        };
+        let complete_prompt = prompt.render_prompt_styled().raw_string() + &prompt_indicator;
+        let screen_width = self.painter.screen_width().max(1);
+
+        let prompt_rows = estimate_required_lines(&complete_prompt, screen_width).saturating_sub(1);
+        let input_start_row = self.painter.prompt_start_row() + prompt_rows as u16;
+        let input_start_column =
+            complete_prompt.lines().last().map_or(0, line_width) % screen_width as usize;
+        let continuation_prompt = self.continuation_prompt(prompt);
+
+        match row.checked_sub(input_start_row) {
+            Some(input_row) => {
+                let offset = offset_at_position(
+                    self.editor.get_buffer(),
+                    input_start_column,
+                    |line_number| line_width(&continuation_prompt(line_number)),
+                    input_row,
+                    column,
+                    screen_width,
+                );
+                self.run_edit_commands(&[EditCommand::MoveToPosition(offset)]);
+                EventStatus::Handled
+            }
+            None => EventStatus::Inapplicable,
+        }
+    }
+
     fn previous_history(&mut self) {
         if self.input_mode != InputMode::HistoryTraversal {
             self.input_mode = InputMode::HistoryTraversal;
@@ -892,6 +2444,45 @@ impl Reedline {
         self.editor.move_to_end();
     }
 
+    /// Readline style `history-search-backward`/`history-search-forward`
+    ///
+    /// The text before the cursor when starting the traversal is the prefix the
+    /// recalled entries have to start with. The cursor stays behind the prefix.
+    /// Without a match the buffer is restored as it was before the traversal
+    fn history_prefix_search(&mut self, forward: bool) {
+        if self.input_mode != InputMode::HistoryTraversal
+            || !matches!(
+                self.history.get_navigation(),
+                HistoryNavigationQuery::PrefixSearch(_)
+            )
+        {
+            self.input_mode = InputMode::HistoryTraversal;
+            self.history_prefix_search_origin = self.editor.line_buffer().clone();
+            let prefix = self.editor.get_buffer()[..self.editor.insertion_point()].to_string();
+            self.history
+                .set_navigation(HistoryNavigationQuery::PrefixSearch(prefix));
+        }
+
+        if forward {
+            self.history.forward();
+        } else {
+            self.history.back();
+        }
+
+        match self.history.string_at_cursor() {
+            Some(entry) => {
+                self.editor.set_buffer(entry);
+                if let HistoryNavigationQuery::PrefixSearch(prefix) = self.history.get_navigation()
+                {
+                    self.editor.set_insertion_point(prefix.len());
+                }
+            }
+            None => self
+                .editor
+                .set_line_buffer(self.history_prefix_search_origin.clone()),
+        }
+    }
+
     /// Enable the search and navigation through the history from the line buffer prompt
     ///
     /// Enables either prefix search with output in the line buffer or simple traversal
@@ -919,8 +2510,63 @@ impl Reedline {
     /// This mode uses a separate prompt and handles keybindings slightly differently!
     fn enter_history_search(&mut self) {
         self.input_mode = InputMode::HistorySearch;
+        self.history_search_matches = None;
+
+        match self
+            .last_history_search
+            .clone()
+            .filter(|_| self.resume_history_search)
+        {
+            Some((query, search_match)) => self.restore_history_search(query, &search_match),
+            None => self
+                .history
+                .set_navigation(HistoryNavigationQuery::SubstringSearch("".to_string())),
+        }
+    }
+
+    /// Remembers the query and the match of the running reverse search
+    fn remember_history_search(&mut self) {
+        if let (Some(query), Some(search_match)) =
+            (self.search_query(), self.history.string_at_cursor())
+        {
+            if !query.is_empty() {
+                self.last_history_search = Some((query, search_match));
+            }
+        }
+    }
+
+    /// Searches for `query` again and steps back to `search_match`.
+    /// Stays at the newest match if `search_match` is not found anymore
+    fn restore_history_search(&mut self, query: String, search_match: &str) {
         self.history
-            .set_navigation(HistoryNavigationQuery::SubstringSearch("".to_string()));
+            .set_navigation(HistoryNavigationQuery::SubstringSearch(query.clone()));
+        self.history.back();
+
+        loop {
+            let current = self.history.string_at_cursor();
+            if current.is_none() || current.as_deref() == Some(search_match) {
+                return;
+            }
+
+            self.history.back();
+            if self.history.string_at_cursor() == current {
+                // Reached the oldest match
+                self.history
+                    .set_navigation(HistoryNavigationQuery::SubstringSearch(query));
+                self.history.back();
+                return;
+            }
+        }
+    }
+
+    /// Leave the history search with the current match as the line buffer
+    fn accept_history_search(&mut self) {
+        if let Some(string) = self.history.string_at_cursor() {
+            self.editor.set_buffer(string);
+            self.editor.remember_undo_state(true);
+        }
+
+        self.input_mode = InputMode::Regular;
     }
 
     /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
@@ -991,6 +2637,109 @@ impl Reedline {
         }
     }
 
+    /// Replaces the word ending at the cursor with its expansion if it is an abbreviation
+    fn expand_abbreviation(&mut self) {
+        if self.abbreviations.is_empty() {
+            return;
+        }
+
+        let buffer = self.editor.get_buffer();
+        let insertion_point = self.editor.insertion_point();
+        if buffer[insertion_point..]
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace())
+        {
+            return;
+        }
+
+        let before_word = buffer[..insertion_point].trim_end_matches(|c: char| !c.is_whitespace());
+        let word = &buffer[before_word.len()..insertion_point];
+        let at_command = match before_word.trim_end().chars().last() {
+            Some(c) => matches!(c, '|' | ';' | '&' | '('),
+            None => true,
+        };
+        if self.abbreviation_position == AbbreviationPosition::Command && !at_command {
+            return;
+        }
+
+        if let Some(expansion) = self.abbreviations.get(word) {
+            let commands = [
+                EditCommand::MoveToPosition(before_word.len()),
+                EditCommand::ReplaceChars(word.graphemes(true).count(), expansion.clone()),
+            ];
+            self.run_edit_commands(&commands);
+        }
+    }
+
+    /// Replaces a typed character or backspace with the commands completing, skipping or
+    /// deleting an auto-pair, if the cursor is at one
+    fn auto_pair_commands(&self, commands: &[EditCommand]) -> Option<Vec<EditCommand>> {
+        if self.auto_pairs.is_empty() || self.overtype {
+            return None;
+        }
+
+        let buffer = self.editor.get_buffer();
+        let insertion_point = self.editor.insertion_point();
+        let previous = buffer[..insertion_point].chars().next_back();
+        let next = buffer[insertion_point..].chars().next();
+        let is_closing = |c: char| self.auto_pairs.iter().any(|(_, close)| *close == c);
+
+        match commands {
+            [EditCommand::InsertChar(c)] => {
+                // An escaped quote is inserted
+                if next == Some(*c) && is_closing(*c) && previous != Some('\\') {
+                    return Some(vec![EditCommand::MoveRight]);
+                }
+
+                let (open, close) = self.auto_pairs.iter().find(|(open, _)| open == c)?;
+                let before_word =
+                    next.is_some_and(|next| !next.is_whitespace() && !is_closing(next));
+                let after_word = open == close && previous.is_some_and(char::is_alphanumeric);
+                if before_word || after_word || self.in_string(&buffer[..insertion_point]) {
+                    return None;
+                }
+
+                Some(vec![
+                    EditCommand::InsertChar(*open),
+                    EditCommand::InsertChar(*close),
+                    EditCommand::MoveLeft,
+                ])
+            }
+            [EditCommand::Backspace] if self.auto_pair_deletion => {
+                let previous = previous?;
+                self.auto_pairs
+                    .iter()
+                    .any(|(open, close)| *open == previous && next == Some(*close))
+                    .then(|| vec![EditCommand::Backspace, EditCommand::Delete])
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if `text` ends inside a string delimited by one of the auto-paired quotes
+    fn in_string(&self, text: &str) -> bool {
+        let mut quote = None;
+        let mut escaped = false;
+        for c in text.chars() {
+            match quote {
+                Some(_) if escaped => escaped = false,
+                Some(_) if c == '\\' => escaped = true,
+                Some(q) if c == q => quote = None,
+                None if self
+                    .auto_pairs
+                    .iter()
+                    .any(|(open, close)| open == close && *open == c) =>
+                {
+                    quote = Some(c);
+                }
+                _ => {}
+            }
+        }
+
+        quote.is_some()
+    }
+
     /// Executes [`EditCommand`] actions by modifying the internal state appropriately. Does not output itself.
     fn run_edit_commands(&mut self, commands: &[EditCommand]) {
         if self.input_mode == InputMode::HistoryTraversal {
@@ -1033,11 +2782,20 @@ impl Reedline {
 
     /// Checks if hints should be displayed and are able to be completed
     fn hints_active(&self) -> bool {
-        !self.hide_hints && self.input_mode == InputMode::Regular
+        !self.hide_hints
+            && self.hint_deadline.is_none()
+            && self.input_mode == InputMode::Regular
+            && self.hint_dismissed_for.as_deref() != Some(self.editor.get_buffer())
     }
 
     /// Repaint of either the buffer or the parts for reverse history search
     fn repaint(&mut self, prompt: &dyn Prompt) -> io::Result<()> {
+        let cursor_shape = self.cursor_shape();
+        if cursor_shape != self.current_cursor_shape {
+            self.painter.set_cursor_shape(cursor_shape)?;
+            self.current_cursor_shape = cursor_shape;
+        }
+
         // Repainting
         if self.input_mode == InputMode::HistorySearch {
             self.history_search_paint(prompt)
@@ -1111,6 +2869,7 @@ impl Reedline {
             let prompt_history_search = PromptHistorySearch::new(status, substring.clone());
 
             let res_string = self.history.string_at_cursor().unwrap_or_default();
+            let candidates = self.history_search_candidates(&substring, &res_string);
 
             // Highlight matches
             let res_string = if self.use_ansi_coloring {
@@ -1121,14 +2880,16 @@ impl Reedline {
                 res_string
             };
 
-            let lines = PromptLines::new(
+            let mut lines = PromptLines::new(
                 prompt,
                 self.prompt_edit_mode(),
                 Some(prompt_history_search),
                 &res_string,
                 "",
                 "",
+                self.use_ansi_coloring,
             );
+            lines.set_below_input(&candidates);
 
             self.painter
                 .repaint_buffer(prompt, &lines, None, self.use_ansi_coloring)?;
@@ -1137,29 +2898,213 @@ impl Reedline {
         Ok(())
     }
 
+    /// The page of matches for `query` containing `current`, one per line below the search.
+    /// The matches are in the order the search visits them, the newest first
+    fn history_search_candidates(&mut self, query: &str, current: &str) -> String {
+        let count = self.history_search_candidates;
+        if count == 0 || query.is_empty() || current.is_empty() {
+            return String::new();
+        }
+
+        // The history doesn't change during the search, only the query does
+        let matches = match self.history_search_matches.take() {
+            Some((cached_query, matches)) if cached_query == query => matches,
+            _ => self.history_search_matches(query),
+        };
+
+        let position = matches
+            .iter()
+            .position(|entry| entry == current)
+            .unwrap_or_default();
+        let page_start = position - position % count;
+
+        let mut candidates = String::new();
+        for (index, entry) in matches.iter().enumerate().skip(page_start).take(count) {
+            let line = entry.lines().next().unwrap_or_default();
+            candidates.push('\n');
+            if index != position {
+                candidates.push_str("  ");
+                candidates.push_str(line);
+            } else if self.use_ansi_coloring {
+                candidates.push_str(
+                    &Style::new()
+                        .reverse()
+                        .paint(format!("> {}", line))
+                        .to_string(),
+                );
+            } else {
+                candidates.push_str("> ");
+                candidates.push_str(line);
+            }
+        }
+
+        self.history_search_matches = Some((query.to_string(), matches));
+        candidates
+    }
+
+    /// The entries matching `query`, in the order the search visits them
+    fn history_search_matches(&self, query: &str) -> Vec<String> {
+        let case_sensitive = self.history.case_sensitivity().is_case_sensitive(query);
+        let fold_case = |text: &str| {
+            if case_sensitive {
+                text.to_string()
+            } else {
+                text.to_lowercase()
+            }
+        };
+        let query = fold_case(query);
+
+        let mut matches: Vec<String> = Vec::new();
+        for entry in self.history.iter_chronologic().rev() {
+            // The search skips a match that repeats the one before it
+            if fold_case(&entry).contains(&query) && matches.last() != Some(&entry) {
+                matches.push(entry);
+            }
+        }
+
+        matches
+    }
+
+    /// Cursor shape configured for the current edit mode, `None` for the terminal default
+    fn cursor_shape(&self) -> Option<CursorShape> {
+        let shapes = self.cursor_shapes?;
+        match self.prompt_edit_mode() {
+            PromptEditMode::Vi(PromptViMode::Normal) => Some(shapes.normal),
+            PromptEditMode::Vi(PromptViMode::Insert | PromptViMode::Replace) => Some(shapes.insert),
+            _ => None,
+        }
+    }
+
+    /// Updates the working details of the active menu, running the pending menu events
+    fn update_menu_working_details(&mut self) {
+        for menu in self.menus.iter_mut() {
+            if menu.is_active() {
+                menu.update_working_details(
+                    self.editor.line_buffer(),
+                    self.history.as_ref(),
+                    self.completer.as_ref(),
+                    &self.painter,
+                );
+            }
+        }
+    }
+
+    /// Tells the menu observer how the active menu changed since it was last notified
+    fn notify_menu_observer(&mut self) {
+        let observer = match self.menu_observer.as_mut() {
+            Some(observer) => observer,
+            None => return,
+        };
+
+        let current = self
+            .menus
+            .iter()
+            .find(|menu| menu.is_active())
+            .map(|menu| (menu.name().to_string(), menu.get_value()));
+
+        match (self.observed_menu.take(), current.clone()) {
+            (None, Some((menu, selection))) => {
+                observer(MenuNotification::Opened { menu, selection });
+            }
+            (Some((previous, _)), None) => {
+                observer(MenuNotification::Aborted { menu: previous });
+            }
+            (Some((previous, _)), Some((menu, selection))) if previous != menu => {
+                observer(MenuNotification::Aborted { menu: previous });
+                observer(MenuNotification::Opened { menu, selection });
+            }
+            (Some((_, previous)), Some((menu, selection))) if previous != selection => {
+                observer(MenuNotification::SelectionChanged { menu, selection });
+            }
+            _ => {}
+        }
+
+        self.observed_menu = current;
+    }
+
+    /// Copy of the buffer with the selected suggestion of the active menu applied,
+    /// only used for painting
+    fn menu_preview(&self) -> Option<LineBuffer> {
+        if !self.menu_preview {
+            return None;
+        }
+
+        let menu = self.menus.iter().find(|menu| menu.is_active())?;
+        let mut preview = LineBuffer::new();
+        preview.set_buffer(self.editor.get_buffer().to_string());
+        preview.set_insertion_point(self.editor.insertion_point());
+        menu.replace_in_buffer(&mut preview);
+        Some(preview)
+    }
+
+    /// Verdict of the validator on `buffer`, always complete without a validator
+    fn validate(&self, buffer: &str) -> ValidationResult {
+        self.validator
+            .as_ref()
+            .map_or(ValidationResult::Complete, |validator| {
+                validator.validate(buffer)
+            })
+    }
+
+    /// Continuation prompts for the lines of the buffer, reflecting the validation state of
+    /// the current buffer
+    fn continuation_prompt<'prompt>(
+        &self,
+        prompt: &'prompt dyn Prompt,
+    ) -> impl Fn(usize) -> Cow<'prompt, str> {
+        let validation = self.validate(self.editor.get_buffer());
+        move |line_number| prompt.render_prompt_continuation(line_number, validation)
+    }
+
     /// Triggers a full repaint including the prompt parts
     ///
     /// Includes the highlighting and hinting calls.
     fn buffer_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
-        let cursor_position_in_buffer = self.editor.insertion_point();
-        let buffer_to_paint = self.editor.get_buffer();
+        // Once edited, the buffer gets hints again even if the edit is undone
+        if self.hint_dismissed_for.as_deref() != Some(self.editor.get_buffer()) {
+            self.hint_dismissed_for = None;
+        }
+        self.update_menu_working_details();
+        self.notify_menu_observer();
+
+        let preview = self.menu_preview();
+        let (buffer_to_paint, cursor_position_in_buffer) = match &preview {
+            Some(preview) => (preview.get_buffer(), preview.insertion_point()),
+            None => (self.editor.get_buffer(), self.editor.insertion_point()),
+        };
 
-        let (before_cursor, after_cursor) = self
+        self.diagnostics = self
+            .validator
+            .as_ref()
+            .map(|validator| validator.diagnostics(buffer_to_paint))
+            .unwrap_or_default();
+        let mut styled_buffer = self
             .highlighter
-            .highlight(buffer_to_paint, cursor_position_in_buffer)
-            .render_around_insertion_point(
-                cursor_position_in_buffer,
-                prompt.render_prompt_multiline_indicator().borrow(),
-                self.use_ansi_coloring,
-            );
+            .highlight(buffer_to_paint, cursor_position_in_buffer);
+        for diagnostic in &self.diagnostics {
+            styled_buffer.restyle_range(diagnostic.span.start..diagnostic.span.end, |style| {
+                style.underline()
+            });
+        }
+
+        let (before_cursor, after_cursor) = styled_buffer.render_around_insertion_point_with(
+            cursor_position_in_buffer,
+            self.continuation_prompt(prompt),
+            self.use_ansi_coloring,
+        );
 
         let hint: String = if self.hints_active() {
-            self.hinter.handle(
+            let hint = self.hinter.handle(
                 buffer_to_paint,
                 cursor_position_in_buffer,
                 self.history.as_ref(),
-                self.use_ansi_coloring,
-            )
+                false,
+            );
+            if self.use_ansi_coloring && !hint.is_empty() {
+                self.hinter.style_hint(&hint).render_simple()
+            } else {
+                hint
+            }
         } else {
             String::new()
         };
@@ -1167,28 +3112,32 @@ impl Reedline {
         // Needs to add return carriage to newlines because when not in raw mode
         // some OS don't fully return the carriage
 
-        let lines = PromptLines::new(
+        let mut lines = PromptLines::new(
             prompt,
             self.prompt_edit_mode(),
             None,
             &before_cursor,
             &after_cursor,
             &hint,
+            self.use_ansi_coloring,
         );
 
-        // Updating the working details of the active menu
-        for menu in self.menus.iter_mut() {
-            if menu.is_active() {
-                menu.update_working_details(
-                    self.editor.line_buffer(),
-                    self.history.as_ref(),
-                    self.completer.as_ref(),
-                    &self.painter,
-                );
-            }
-        }
-
         let menu = self.menus.iter().find(|menu| menu.is_active());
+        if let Some(menu) = menu {
+            let values = menu.get_values();
+            lines.menu_indicator = prompt.render_prompt_completion_indicator(
+                self.prompt_edit_mode(),
+                PromptCompletion {
+                    candidates: values.len(),
+                    common_prefix: match menu_functions::find_common_string(values) {
+                        (Some(first), index) => {
+                            first.value[..index.unwrap_or(first.value.len())].to_string()
+                        }
+                        (None, _) => String::new(),
+                    },
+                },
+            );
+        }
 
         self.painter
             .repaint_buffer(prompt, &lines, menu, self.use_ansi_coloring)
@@ -1200,3 +3149,2372 @@ fn thread_safe() {
     fn f<S: Send>(_: S) {}
     f(Reedline::create());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        default_emacs_keybindings, AsyncPrompt, CompletionMenu, DefaultPrompt, Emacs, Span,
+        StyledText, Suggestion, Vi,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn engine_with_menu() -> Reedline {
+        let completer = DefaultCompleter::new(vec!["hello".into(), "help".into()]);
+        Reedline::create().with_menu(
+            Box::new(CompletionMenu::default()),
+            Some(Box::new(completer)),
+        )
+    }
+
+    fn handled(engine: &mut Reedline, event: ReedlineEvent) -> bool {
+        let prompt = DefaultPrompt::default();
+        matches!(
+            engine.handle_editor_event(&prompt, event).unwrap(),
+            EventStatus::Handled
+        )
+    }
+
+    fn engine_with_tab_behavior(words: &[&str], tab_behavior: TabBehavior) -> Reedline {
+        let completer = DefaultCompleter::new(words.iter().map(|w| w.to_string()).collect());
+        Reedline::create()
+            .with_menu(
+                Box::new(CompletionMenu::default()),
+                Some(Box::new(completer)),
+            )
+            .with_tab_behavior(tab_behavior)
+    }
+
+    fn menu_is_active(engine: &Reedline) -> bool {
+        engine.menus.iter().any(|menu| menu.is_active())
+    }
+
+    #[test]
+    fn escape_closes_the_menu_and_keeps_the_buffer() {
+        let mut engine = engine_with_menu();
+        engine.run_edit_commands(&[EditCommand::InsertString("hel".into())]);
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        assert!(menu_is_active(&engine));
+
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!menu_is_active(&engine));
+        assert_eq!(engine.editor.get_buffer(), "hel");
+    }
+
+    #[test]
+    fn ctrl_c_closes_the_menu_before_interrupting() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = engine_with_menu();
+        engine.run_edit_commands(&[EditCommand::InsertString("hel".into())]);
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+
+        assert!(handled(&mut engine, ReedlineEvent::CtrlC));
+        assert!(!menu_is_active(&engine));
+        assert_eq!(engine.editor.get_buffer(), "hel");
+
+        assert!(matches!(
+            engine.handle_event(&prompt, ReedlineEvent::CtrlC).unwrap(),
+            EventStatus::Exits(Signal::CtrlC)
+        ));
+        assert_eq!(engine.editor.get_buffer(), "");
+    }
+
+    #[test]
+    fn ctrl_c_on_an_empty_prompt_interrupts() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create();
+
+        assert!(matches!(
+            engine.handle_event(&prompt, ReedlineEvent::CtrlC).unwrap(),
+            EventStatus::Exits(Signal::CtrlC)
+        ));
+    }
+
+    #[test]
+    fn dismissed_menu_is_gone_with_the_next_paint() {
+        let mut engine = engine_with_menu();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        let prompt = DefaultPrompt::default();
+        engine.run_edit_commands(&[EditCommand::InsertString("hel".into())]);
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.repaint(&prompt).unwrap();
+        assert!(output.take().contains("help"));
+
+        engine.dismiss_menu();
+        engine.repaint(&prompt).unwrap();
+        assert!(!menu_is_active(&engine));
+        assert_eq!(engine.painter.menu_start_row(), None);
+        assert!(!output.take().contains("help"));
+        assert_eq!(engine.editor.get_buffer(), "hel");
+    }
+
+    #[test]
+    fn menu_observer_follows_the_selection() {
+        let notifications = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let observed = notifications.clone();
+        let mut engine = engine_with_menu().with_menu_observer(Box::new(move |notification| {
+            observed.lock().unwrap().push(notification);
+        }));
+        let (painter, _output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        engine.run_edit_commands(&[EditCommand::InsertString("hel".into())]);
+
+        let prompt = DefaultPrompt::default();
+        let mut step = |event| {
+            assert!(handled(&mut engine, event));
+            engine.repaint(&prompt).unwrap();
+            notifications
+                .lock()
+                .unwrap()
+                .drain(..)
+                .map(|notification| match notification {
+                    MenuNotification::Opened { selection, .. } => ("opened", selection),
+                    MenuNotification::SelectionChanged { selection, .. } => ("changed", selection),
+                    MenuNotification::Accepted { selection, .. } => ("accepted", selection),
+                    MenuNotification::Aborted { .. } => ("aborted", None),
+                })
+                .map(|(kind, selection)| (kind, selection.map(|suggestion| suggestion.value)))
+                .collect::<Vec<_>>()
+        };
+        let value = |value: &str| Some(value.to_string());
+
+        let open = || ReedlineEvent::Menu("completion_menu".into());
+        assert_eq!(step(open()), vec![("opened", value("hello"))]);
+        assert_eq!(
+            step(ReedlineEvent::MenuNext),
+            vec![("changed", value("help"))]
+        );
+        assert_eq!(
+            step(ReedlineEvent::MenuNext),
+            vec![("changed", value("hello"))]
+        );
+        assert_eq!(
+            step(ReedlineEvent::MenuPrevious),
+            vec![("changed", value("help"))]
+        );
+        assert_eq!(step(ReedlineEvent::Repaint), vec![]);
+        assert_eq!(
+            step(ReedlineEvent::MenuAccept),
+            vec![("accepted", value("help"))]
+        );
+
+        let retype = ReedlineEvent::Edit(vec![
+            EditCommand::Clear,
+            EditCommand::InsertString("hel".into()),
+        ]);
+        assert_eq!(step(retype), vec![]);
+        assert_eq!(step(open()), vec![("opened", value("hello"))]);
+        assert_eq!(step(ReedlineEvent::MenuAbort), vec![("aborted", None)]);
+    }
+
+    fn press_tab(engine: &mut Reedline, typed: &str) {
+        engine.run_edit_commands(&[EditCommand::InsertString(typed.into())]);
+        assert!(handled(
+            engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+    }
+
+    fn engine_with_tab_inserting_spaces(words: &[&str]) -> Reedline {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Tab,
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::Menu("completion_menu".to_string()),
+                ReedlineEvent::MenuNext,
+            ]),
+        );
+
+        engine_with_tab_behavior(words, TabBehavior::AlwaysMenu)
+            .with_edit_mode(Box::new(Emacs::new(keybindings)))
+            .with_tab_inserts_spaces(Some(4))
+    }
+
+    #[test]
+    fn tab_inserts_spaces_without_suggestions() {
+        let mut engine = engine_with_tab_inserting_spaces(&["hello", "help"]);
+        type_text(&mut engine, "if x:");
+
+        assert!(press(&mut engine, KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(engine.editor.get_buffer(), "if x:    ");
+        assert!(engine.active_menu().is_none());
+
+        press(&mut engine, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(engine.editor.get_buffer(), "if x:");
+    }
+
+    #[test]
+    fn history_menu_opens_instead_of_inserting_spaces() {
+        let mut engine = Reedline::create()
+            .with_menu(Box::new(crate::HistoryMenu::default()), None)
+            .with_tab_inserts_spaces(Some(4));
+        type_text(&mut engine, "xyz");
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("history_menu".to_string())
+        ));
+        assert_eq!(engine.editor.get_buffer(), "xyz");
+        assert!(engine.active_menu().is_some());
+    }
+
+    #[test]
+    fn tab_completes_instead_of_inserting_spaces() {
+        let mut engine = engine_with_tab_inserting_spaces(&["hello", "help"]);
+        type_text(&mut engine, "he");
+
+        assert!(press(&mut engine, KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(engine.editor.get_buffer(), "he");
+        assert!(engine.active_menu().is_some());
+
+        assert!(press(&mut engine, KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(engine.editor.get_buffer(), "he");
+        assert!(engine.active_menu().is_some());
+    }
+
+    #[rstest]
+    #[case(TabBehavior::AlwaysMenu, "he", true)]
+    #[case(TabBehavior::AcceptSingle, "hello", false)]
+    #[case(TabBehavior::CompleteCommonPrefix, "hello", false)]
+    fn tab_with_single_suggestion(
+        #[case] tab_behavior: TabBehavior,
+        #[case] expected_buffer: &str,
+        #[case] expected_menu: bool,
+    ) {
+        let mut engine = engine_with_tab_behavior(&["hello", "world"], tab_behavior);
+        press_tab(&mut engine, "he");
+
+        assert_eq!(engine.editor.get_buffer(), expected_buffer);
+        assert_eq!(engine.active_menu().is_some(), expected_menu);
+    }
+
+    #[rstest]
+    #[case(TabBehavior::AlwaysMenu, "he")]
+    #[case(TabBehavior::AcceptSingle, "he")]
+    #[case(TabBehavior::CompleteCommonPrefix, "hel")]
+    fn tab_with_shared_prefix(#[case] tab_behavior: TabBehavior, #[case] expected_buffer: &str) {
+        let mut engine = engine_with_tab_behavior(&["hello", "help"], tab_behavior);
+        press_tab(&mut engine, "he");
+
+        assert_eq!(engine.editor.get_buffer(), expected_buffer);
+        assert!(engine.active_menu().is_some());
+    }
+
+    #[rstest]
+    #[case(TabBehavior::AlwaysMenu)]
+    #[case(TabBehavior::AcceptSingle)]
+    #[case(TabBehavior::CompleteCommonPrefix)]
+    fn tab_without_shared_prefix(#[case] tab_behavior: TabBehavior) {
+        let mut engine = engine_with_tab_behavior(&["hello", "help"], tab_behavior);
+        press_tab(&mut engine, "hel");
+
+        assert_eq!(engine.editor.get_buffer(), "hel");
+        assert!(engine.active_menu().is_some());
+    }
+
+    #[rstest]
+    #[case(&["hello", "help"], "he", "hel", true)]
+    #[case(&["hello", "help"], "hel", "hel", false)]
+    #[case(&["hello", "world"], "he", "hello", true)]
+    #[case(&["hello", "world"], "x", "x", false)]
+    #[case(&["ｎｕｓｈｅｌｌ", "ｎｕｌｌ"], "ｎ", "ｎｕ", true)]
+    fn complete_common_prefix(
+        #[case] words: &[&str],
+        #[case] typed: &str,
+        #[case] expected_buffer: &str,
+        #[case] expected_handled: bool,
+    ) {
+        let completer =
+            DefaultCompleter::new_with_wordlen(words.iter().map(|w| w.to_string()).collect(), 1);
+        let mut engine = Reedline::create().with_completer(Box::new(completer));
+        engine.run_edit_commands(&[EditCommand::InsertString(typed.into())]);
+
+        assert_eq!(
+            handled(&mut engine, ReedlineEvent::CompleteCommonPrefix),
+            expected_handled
+        );
+        assert_eq!(engine.editor.get_buffer(), expected_buffer);
+        assert_eq!(engine.editor.insertion_point(), expected_buffer.len());
+        assert!(engine.active_menu().is_none());
+    }
+
+    #[test]
+    fn menu_events_are_inapplicable_without_active_menu() {
+        let mut engine = engine_with_menu();
+
+        assert!(!handled(&mut engine, ReedlineEvent::MenuNext));
+        assert!(!handled(&mut engine, ReedlineEvent::MenuAccept));
+        assert!(!handled(&mut engine, ReedlineEvent::MenuAbort));
+    }
+
+    #[test]
+    fn menu_abort_closes_menu_and_keeps_buffer() {
+        let mut engine = engine_with_menu();
+        engine.run_edit_commands(&[EditCommand::InsertString("he".into())]);
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        assert!(engine.active_menu().is_some());
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuAbort));
+        assert!(engine.active_menu().is_none());
+        assert_eq!(engine.editor.get_buffer(), "he");
+    }
+
+    #[test]
+    fn menu_accept_replaces_buffer_with_selection() {
+        let mut engine = engine_with_menu();
+        engine.run_edit_commands(&[EditCommand::InsertString("hel".into())]);
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        let menu = engine.menus.iter_mut().find(|m| m.is_active()).unwrap();
+        menu.update_values(
+            engine.editor.line_buffer(),
+            engine.history.as_ref(),
+            engine.completer.as_ref(),
+        );
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuAccept));
+        assert!(engine.active_menu().is_none());
+        assert_eq!(engine.editor.get_buffer(), "hello");
+    }
+
+    #[test]
+    fn cursor_screen_position_follows_wrapped_lines() {
+        let mut engine = Reedline::create();
+        let (painter, _output) = Painter::headless(10, 24);
+        engine.painter = painter;
+        let prompt = MatchCountPrompt;
+        assert_eq!(engine.cursor_screen_position(), None);
+
+        // `> echo a` on the first row, `::: bcdefg` and `hijk` on the next two
+        engine.run_edit_commands(&[EditCommand::InsertString("echo a\nbcdefghijk".into())]);
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(engine.cursor_screen_position(), Some((4, 2)));
+
+        engine.run_edit_commands(&[EditCommand::MoveToPosition(3)]);
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(engine.cursor_screen_position(), Some((5, 0)));
+
+        engine.painter.move_cursor_to_end().unwrap();
+        assert_eq!(engine.cursor_screen_position(), None);
+    }
+
+    #[test]
+    fn current_buffer_reports_the_byte_offset_of_the_cursor() {
+        let mut engine = Reedline::create();
+        assert_eq!(engine.current_buffer(), ("", 0));
+
+        type_text(&mut engine, "día 😀!");
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+
+        let (buffer, offset) = engine.current_buffer();
+        assert_eq!(buffer, "día 😀!");
+        assert_eq!(offset, 5);
+        assert_eq!(&buffer[offset..], "😀!");
+
+        press(&mut engine, KeyCode::Home, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer().1, 0);
+    }
+
+    /// Queries the hinter like painting the buffer does
+    fn update_hint(engine: &mut Reedline) -> String {
+        let buffer = engine.editor.get_buffer().to_string();
+        engine
+            .hinter
+            .handle(&buffer, buffer.len(), engine.history.as_ref(), false);
+        engine.current_hint()
+    }
+
+    #[test]
+    fn cleared_hint_returns_after_an_edit() {
+        let mut engine = Reedline::create();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        let prompt = DefaultPrompt::default();
+        engine.history.append("git commit --amend");
+        type_text(&mut engine, "git c");
+        assert_eq!(update_hint(&mut engine), "ommit --amend");
+
+        engine.clear_hint();
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(update_hint(&mut engine), "");
+        assert!(!output.take().contains("ommit"));
+        assert!(!handled(&mut engine, ReedlineEvent::HistoryHintComplete));
+
+        type_text(&mut engine, "o");
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(update_hint(&mut engine), "mmit --amend");
+
+        // Stays visible when returning to the buffer it was dismissed for
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(update_hint(&mut engine), "ommit --amend");
+    }
+
+    #[test]
+    fn hint_is_accepted_word_by_word() {
+        let mut engine = Reedline::create();
+        engine.history.append("git commit --amend --no-edit");
+        type_text(&mut engine, "git c");
+        assert_eq!(update_hint(&mut engine), "ommit --amend --no-edit");
+
+        assert!(handled(&mut engine, ReedlineEvent::HistoryHintWordComplete));
+        assert_eq!(engine.editor.get_buffer(), "git commit");
+        assert_eq!(update_hint(&mut engine), " --amend --no-edit");
+
+        assert!(handled(&mut engine, ReedlineEvent::HistoryHintWordComplete));
+        assert_eq!(engine.editor.get_buffer(), "git commit --amend");
+        assert_eq!(update_hint(&mut engine), " --no-edit");
+
+        assert!(handled(&mut engine, ReedlineEvent::HistoryHintComplete));
+        assert_eq!(engine.editor.get_buffer(), "git commit --amend --no-edit");
+        assert_eq!(update_hint(&mut engine), "");
+        assert!(!handled(
+            &mut engine,
+            ReedlineEvent::HistoryHintWordComplete
+        ));
+    }
+
+    #[test]
+    fn alt_f_accepts_a_word_of_the_hint() {
+        let mut engine = Reedline::create();
+        engine.history.append("cargo test --workspace");
+        type_text(&mut engine, "cargo");
+        update_hint(&mut engine);
+
+        press(&mut engine, KeyCode::Char('f'), KeyModifiers::ALT);
+        assert_eq!(engine.editor.get_buffer(), "cargo test");
+    }
+
+    #[test]
+    fn hint_is_shown_right_away_without_delay() {
+        let mut engine = Reedline::create();
+        engine.delay_hint();
+
+        assert!(engine.hints_active());
+        assert!(!engine.hint_delay_elapsed());
+    }
+
+    #[test]
+    fn hint_is_held_back_until_the_delay_elapsed() {
+        let delay = Duration::from_millis(20);
+        let mut engine = Reedline::create().with_hint_delay(delay);
+        engine.delay_hint();
+
+        assert!(!engine.hints_active());
+        assert!(engine.poll_timeout(&DefaultPrompt::default()) <= delay);
+        assert!(!engine.hint_delay_elapsed());
+
+        std::thread::sleep(delay);
+        assert!(engine.hint_delay_elapsed());
+        assert!(engine.hints_active());
+        assert_eq!(
+            engine.poll_timeout(&DefaultPrompt::default()),
+            Duration::from_millis(1000)
+        );
+    }
+
+    #[test]
+    fn multiline_indicator_reflects_validation() {
+        let mut engine = Reedline::create();
+        let prompt = DefaultPrompt::default();
+        engine.run_edit_commands(&[EditCommand::InsertString("echo \"hi".into())]);
+
+        assert!(handled(&mut engine, ReedlineEvent::Enter));
+        assert_eq!(engine.continuation_prompt(&prompt)(1), "... ");
+
+        engine.run_edit_commands(&[EditCommand::InsertString("\"".into())]);
+        assert_eq!(engine.continuation_prompt(&prompt)(1), "::: ");
+    }
+
+    #[test]
+    fn accept_and_get_next_starts_the_next_read_with_the_following_entry() {
+        let mut engine = Reedline::create();
+        let (painter, _output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        for line in ["cd repo", "cargo build", "cargo test"] {
+            type_text(&mut engine, line);
+            handled(&mut engine, ReedlineEvent::Enter);
+        }
+        for _ in 0..3 {
+            handled(&mut engine, ReedlineEvent::Up);
+        }
+        assert_eq!(engine.current_buffer(), ("cd repo", 7));
+
+        let prompt = DefaultPrompt::default();
+        let read = |engine: &mut Reedline| {
+            engine.load_next_history_entry();
+            let initial = engine.current_buffer().0.to_string();
+            let status = engine
+                .handle_event(&prompt, ReedlineEvent::AcceptAndGetNext)
+                .unwrap();
+            assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == initial));
+            initial
+        };
+
+        assert_eq!(read(&mut engine), "cd repo");
+        assert_eq!(read(&mut engine), "cargo build");
+        assert_eq!(read(&mut engine), "cargo test");
+        // The replayed entries were added again, so the sequence repeats
+        assert_eq!(read(&mut engine), "cd repo");
+
+        // A line that wasn't recalled from the history has no following entry
+        engine.load_next_history_entry();
+        engine.run_edit_commands(&[EditCommand::Clear]);
+        type_text(&mut engine, "ls");
+        assert_eq!(read(&mut engine), "ls");
+        engine.load_next_history_entry();
+        assert_eq!(engine.current_buffer(), ("", 0));
+    }
+
+    #[test]
+    fn accept_and_get_next_keeps_the_history_position_without_submitting() {
+        let mut engine = Reedline::create().with_validator(Box::new(BackslashValidator));
+        for entry in ["ls", "echo a\\", "pwd"] {
+            engine.history.append(entry);
+        }
+        handled(&mut engine, ReedlineEvent::Up);
+        handled(&mut engine, ReedlineEvent::Up);
+        assert_eq!(engine.current_buffer(), ("echo a\\", 7));
+
+        // The incomplete line gets a line break instead of being submitted
+        assert!(handled(&mut engine, ReedlineEvent::AcceptAndGetNext));
+        assert_eq!(engine.current_buffer(), ("echo a\\\n", 8));
+        assert_eq!(engine.next_history_entry, None);
+        assert_eq!(
+            engine.history.string_at_cursor(),
+            Some("echo a\\".to_string())
+        );
+    }
+
+    #[test]
+    fn history_recording_can_be_turned_off() {
+        let mut engine = Reedline::create();
+        assert!(engine.history_recording());
+        engine.run_edit_commands(&[EditCommand::InsertString("first".into())]);
+        handled(&mut engine, ReedlineEvent::Enter);
+
+        engine.set_history_recording(false);
+        assert!(!engine.history_recording());
+        engine.run_edit_commands(&[EditCommand::InsertString("secret".into())]);
+        handled(&mut engine, ReedlineEvent::Enter);
+
+        let history: Vec<_> = engine.history().iter_chronologic().collect();
+        assert_eq!(history, vec!["first".to_string()]);
+
+        handled(&mut engine, ReedlineEvent::Up);
+        assert_eq!(engine.editor.get_buffer(), "first");
+    }
+
+    #[test]
+    fn clipboard_text_can_be_set_and_read() {
+        let mut engine = Reedline::create();
+        engine.set_clipboard_text("");
+        assert_eq!(engine.clipboard_text(), None);
+
+        engine.set_clipboard_text("world");
+        type_text(&mut engine, "hello ");
+        engine.run_edit_commands(&[EditCommand::PasteCutBufferBefore]);
+        assert_eq!(engine.current_buffer(), ("hello world", 11));
+
+        engine.run_edit_commands(&[EditCommand::CutWordLeft]);
+        assert_eq!(engine.clipboard_text().as_deref(), Some("world"));
+    }
+
+    #[test]
+    fn switching_histories_keeps_them_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let first_file = dir.path().join("first.txt");
+        let second_file = dir.path().join("second.txt");
+        let submit = |engine: &mut Reedline, line: &str| {
+            type_text(engine, line);
+            handled(engine, ReedlineEvent::Enter);
+        };
+
+        let mut engine = Reedline::create().with_history(Box::new(
+            FileBackedHistory::with_file(10, first_file.clone()).unwrap(),
+        ));
+        submit(&mut engine, "cargo build");
+
+        let first = engine
+            .set_history(Box::new(
+                FileBackedHistory::with_file(10, second_file.clone()).unwrap(),
+            ))
+            .unwrap();
+        submit(&mut engine, "make");
+        let history: Vec<_> = engine.history().iter_chronologic().collect();
+        assert_eq!(history, vec!["make".to_string()]);
+
+        // The previous history was flushed when switching away from it
+        let reloaded = FileBackedHistory::with_file(10, first_file).unwrap();
+        let history: Vec<_> = reloaded.iter_chronologic().collect();
+        assert_eq!(history, vec!["cargo build".to_string()]);
+
+        let second = engine.set_history(first).unwrap();
+        submit(&mut engine, "cargo test");
+        let history: Vec<_> = engine.history().iter_chronologic().collect();
+        assert_eq!(
+            history,
+            vec!["cargo build".to_string(), "cargo test".to_string()]
+        );
+        let history: Vec<_> = second.iter_chronologic().collect();
+        assert_eq!(history, vec!["make".to_string()]);
+
+        handled(&mut engine, ReedlineEvent::Up);
+        assert_eq!(engine.editor.get_buffer(), "cargo test");
+    }
+
+    #[test]
+    fn switching_histories_forgets_the_state_of_the_previous_one() {
+        let mut engine = Reedline::create();
+        engine.next_history_entry = Some("cargo build".to_string());
+        engine.last_history_search = Some(("cargo".to_string(), "cargo build".to_string()));
+
+        engine
+            .set_history(Box::new(FileBackedHistory::default()))
+            .unwrap();
+
+        assert_eq!(engine.next_history_entry, None);
+        assert_eq!(engine.last_history_search, None);
+    }
+
+    #[test]
+    fn snippets_are_inserted_at_the_cursor_as_one_edit() {
+        let mut engine = Reedline::create().with_snippet("date", Box::new(|| "2022-03-04".into()));
+        type_text(&mut engine, "echo  done");
+        engine.run_edit_commands(&[EditCommand::MoveToPosition(5)]);
+
+        handled(&mut engine, ReedlineEvent::InsertSnippet("date".into()));
+        assert_eq!(engine.editor.get_buffer(), "echo 2022-03-04 done");
+        assert_eq!(engine.editor.insertion_point(), 15);
+
+        engine.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(engine.editor.get_buffer(), "echo  done");
+
+        assert!(matches!(
+            engine.handle_event(
+                &DefaultPrompt::default(),
+                ReedlineEvent::InsertSnippet("cwd".into())
+            ),
+            Ok(EventStatus::Inapplicable)
+        ));
+    }
+
+    fn engine_with_abbreviations() -> Reedline {
+        let abbreviations = HashMap::from([
+            ("gco".to_string(), "git checkout".to_string()),
+            ("ll".to_string(), "ls -l".to_string()),
+        ]);
+        Reedline::create().with_abbreviations(abbreviations)
+    }
+
+    #[test]
+    fn numeric_argument_multiplies_the_next_character() {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "a");
+
+        assert!(handled(&mut engine, ReedlineEvent::NumericArgument(5)));
+        type_text(&mut engine, "x");
+        assert_eq!(engine.editor.get_buffer(), "axxxxx");
+
+        // Only the next command is multiplied
+        type_text(&mut engine, "y");
+        assert_eq!(engine.editor.get_buffer(), "axxxxxy");
+    }
+
+    #[rstest]
+    #[case(&[('u', KeyModifiers::CONTROL), ('x', KeyModifiers::NONE)], "abcxxxxdef")]
+    #[case(&[('u', KeyModifiers::CONTROL), ('u', KeyModifiers::CONTROL), ('d', KeyModifiers::CONTROL)], "abc")]
+    #[case(&[('3', KeyModifiers::ALT), ('d', KeyModifiers::CONTROL)], "abc")]
+    #[case(&[('2', KeyModifiers::ALT), ('h', KeyModifiers::CONTROL)], "adef")]
+    #[case(&[('u', KeyModifiers::CONTROL), ('2', KeyModifiers::NONE), ('y', KeyModifiers::NONE)], "abcyydef")]
+    fn emacs_numeric_argument_applies_to_the_next_key(
+        #[case] keys: &[(char, KeyModifiers)],
+        #[case] expected: &str,
+    ) {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "abcdef");
+        engine.run_edit_commands(&[EditCommand::MoveToPosition(3)]);
+
+        for (c, modifiers) in keys {
+            press(&mut engine, KeyCode::Char(*c), *modifiers);
+        }
+        assert_eq!(engine.editor.get_buffer(), expected);
+    }
+
+    #[test]
+    fn numeric_argument_is_dropped_by_other_commands() {
+        let mut engine = Reedline::create();
+
+        assert!(handled(&mut engine, ReedlineEvent::NumericArgument(3)));
+        handled(&mut engine, ReedlineEvent::Repaint);
+        handled(&mut engine, ReedlineEvent::Esc);
+        type_text(&mut engine, "x");
+        assert_eq!(engine.editor.get_buffer(), "x");
+    }
+
+    #[test]
+    fn repeat_applies_the_last_delete_again() {
+        let mut engine = Reedline::create();
+        assert!(!handled(&mut engine, ReedlineEvent::Repeat(2)));
+
+        type_text(&mut engine, "hello world");
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "hello worl");
+
+        assert!(handled(&mut engine, ReedlineEvent::Repeat(3)));
+        assert_eq!(engine.editor.get_buffer(), "hello w");
+
+        // A numeric argument multiplies the repetitions
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+        handled(&mut engine, ReedlineEvent::NumericArgument(2));
+        assert!(handled(&mut engine, ReedlineEvent::Repeat(2)));
+        assert_eq!(engine.editor.insertion_point(), 2);
+        assert_eq!(engine.editor.get_buffer(), "hello w");
+    }
+
+    fn engine_with_auto_pairs() -> Reedline {
+        Reedline::create().with_auto_pairs(vec![('(', ')'), ('[', ']'), ('"', '"'), ('\'', '\'')])
+    }
+
+    #[rstest]
+    #[case("echo (", "echo ()", 6)]
+    #[case("echo (a", "echo (a)", 7)]
+    #[case("echo (a)", "echo (a)", 8)]
+    #[case("f([x])", "f([x])", 6)]
+    #[case("echo \"it's", "echo \"it's\"", 10)]
+    #[case("echo \"it's\" ok", "echo \"it's\" ok", 14)]
+    #[case("echo \"(", "echo \"(\"", 7)]
+    #[case("echo \"a\\\"(", "echo \"a\\\"(\"", 10)]
+    #[case("don't", "don't", 5)]
+    fn auto_pairs_are_inserted_and_skipped(
+        #[case] typed: &str,
+        #[case] expected: &str,
+        #[case] cursor: usize,
+    ) {
+        let mut engine = engine_with_auto_pairs();
+        type_text(&mut engine, typed);
+
+        assert_eq!(engine.current_buffer(), (expected, cursor));
+    }
+
+    #[test]
+    fn auto_pairs_are_not_inserted_before_a_word() {
+        let mut engine = engine_with_auto_pairs();
+        type_text(&mut engine, "foo");
+        engine.run_edit_commands(&[EditCommand::MoveToStart]);
+
+        type_text(&mut engine, "(");
+        assert_eq!(engine.editor.get_buffer(), "(foo");
+    }
+
+    #[rstest]
+    #[case(true, "(", ("", 0))]
+    #[case(true, "\"", ("", 0))]
+    #[case(true, "(a", ("()", 1))]
+    #[case(true, "echo [\"", ("echo []", 6))]
+    #[case(false, "(", (")", 0))]
+    #[case(false, "\"", ("\"", 0))]
+    fn backspace_deletes_an_empty_auto_pair(
+        #[case] deletion: bool,
+        #[case] typed: &str,
+        #[case] expected: (&str, usize),
+    ) {
+        let mut engine = engine_with_auto_pairs().with_auto_pair_deletion(deletion);
+        type_text(&mut engine, typed);
+
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), expected);
+    }
+
+    #[test]
+    fn auto_pairs_are_off_by_default() {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "echo (\"");
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+
+        assert_eq!(engine.editor.get_buffer(), "echo (");
+    }
+
+    #[test]
+    fn empty_enter_submits_by_default() {
+        let mut engine = Reedline::create();
+
+        assert!(matches!(
+            engine.handle_event(&DefaultPrompt::default(), ReedlineEvent::Enter),
+            Ok(EventStatus::Exits(Signal::Success(line))) if line.is_empty()
+        ));
+        assert_eq!(engine.history.iter_chronologic().count(), 0);
+    }
+
+    #[test]
+    fn empty_enter_can_be_ignored() {
+        let mut engine = Reedline::create().with_empty_line_behavior(EmptyLineBehavior::Ignore);
+
+        assert!(handled(&mut engine, ReedlineEvent::Enter));
+        assert!(engine.editor.is_empty());
+        assert_eq!(engine.history.iter_chronologic().count(), 0);
+
+        type_text(&mut engine, "ls");
+        assert!(matches!(
+            engine.handle_event(&DefaultPrompt::default(), ReedlineEvent::Enter),
+            Ok(EventStatus::Exits(Signal::Success(line))) if line == "ls"
+        ));
+    }
+
+    #[test]
+    fn empty_enter_can_repaint_the_prompt_below() {
+        let mut engine =
+            Reedline::create().with_empty_line_behavior(EmptyLineBehavior::RepaintPrompt);
+        let (painter, output) = Painter::headless(80, 3);
+        engine.painter = painter;
+        let prompt = DefaultPrompt::default();
+        engine.repaint(&prompt).unwrap();
+        assert_eq!(engine.painter.prompt_start_row(), 0);
+
+        for expected_row in [1, 2, 2] {
+            output.take();
+            assert!(handled(&mut engine, ReedlineEvent::Enter));
+            engine.repaint(&prompt).unwrap();
+            assert_eq!(engine.painter.prompt_start_row(), expected_row);
+            assert!(output.take().contains("〉"));
+        }
+        assert_eq!(engine.history.iter_chronologic().count(), 0);
+    }
+
+    #[rstest]
+    #[case("gco", "git checkout ")]
+    #[case("echo 1 && gco", "echo 1 && git checkout ")]
+    #[case("cat x | ll", "cat x | ls -l ")]
+    #[case("echo gco", "echo gco ")]
+    #[case("gcok", "gcok ")]
+    fn abbreviations_expand_on_space(#[case] typed: &str, #[case] expected: &str) {
+        let mut engine = engine_with_abbreviations();
+        type_text(&mut engine, typed);
+        type_text(&mut engine, " ");
+
+        assert_eq!(engine.editor.get_buffer(), expected);
+        assert_eq!(engine.editor.insertion_point(), expected.len());
+    }
+
+    #[test]
+    fn abbreviations_expand_on_enter() {
+        let mut engine = engine_with_abbreviations();
+        type_text(&mut engine, "ll");
+
+        assert!(matches!(
+            engine.handle_event(&DefaultPrompt::default(), ReedlineEvent::Enter),
+            Ok(EventStatus::Exits(Signal::Success(line))) if line == "ls -l"
+        ));
+    }
+
+    #[test]
+    fn abbreviation_expansion_is_undone_at_once() {
+        let mut engine = engine_with_abbreviations();
+        type_text(&mut engine, "gco main");
+        assert_eq!(engine.editor.get_buffer(), "git checkout main");
+
+        engine.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(engine.editor.get_buffer(), "git checkout ");
+        engine.run_edit_commands(&[EditCommand::Undo]);
+        assert_eq!(engine.editor.get_buffer(), "gco");
+    }
+
+    #[test]
+    fn abbreviations_can_expand_anywhere() {
+        let mut engine =
+            engine_with_abbreviations().with_abbreviation_position(AbbreviationPosition::Anywhere);
+        type_text(&mut engine, "echo gco ");
+        assert_eq!(engine.editor.get_buffer(), "echo git checkout ");
+
+        // Pasted text is not expanded
+        handled(
+            &mut engine,
+            ReedlineEvent::Edit("ll ".chars().map(EditCommand::InsertChar).collect()),
+        );
+        assert_eq!(engine.editor.get_buffer(), "echo git checkout ll ");
+    }
+
+    fn engine_with_history(entries: &[&str]) -> Reedline {
+        let mut engine = Reedline::create();
+        for entry in entries {
+            engine.run_edit_commands(&[EditCommand::InsertString(entry.to_string())]);
+            handled(&mut engine, ReedlineEvent::Enter);
+        }
+        engine
+    }
+
+    #[test]
+    fn history_prefix_search_recalls_entries_with_the_typed_prefix() {
+        let mut engine = engine_with_history(&["git status", "ls -l", "git log", "cargo test"]);
+        type_text(&mut engine, "git");
+
+        press(&mut engine, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "git log");
+        assert_eq!(engine.editor.insertion_point(), 3);
+
+        press(&mut engine, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "git status");
+        assert_eq!(engine.editor.insertion_point(), 3);
+
+        // No older match, the oldest one stays
+        press(&mut engine, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "git status");
+
+        press(&mut engine, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "git log");
+        assert_eq!(engine.editor.insertion_point(), 3);
+
+        // Past the newest match the typed prefix is restored
+        press(&mut engine, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), "git");
+        assert_eq!(engine.editor.insertion_point(), 3);
+    }
+
+    #[test]
+    fn history_prefix_search_keeps_the_text_after_the_cursor_without_a_match() {
+        let mut engine = engine_with_history(&["zzz", "ls -l"]);
+        type_text(&mut engine, "zzfoo");
+        engine.run_edit_commands(&[EditCommand::MoveToLineStart]);
+        engine.run_edit_commands(&[EditCommand::MoveRight, EditCommand::MoveRight]);
+
+        press(&mut engine, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("zzfoo", 2));
+
+        press(&mut engine, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("zzz", 2));
+
+        // Past the newest match the buffer is restored as typed
+        press(&mut engine, KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("zzfoo", 2));
+
+        let mut engine = engine_with_history(&["git status", "ls -l"]);
+        type_text(&mut engine, "zzfoo");
+        engine.run_edit_commands(&[EditCommand::MoveLeft, EditCommand::MoveLeft]);
+        engine.run_edit_commands(&[EditCommand::MoveLeft]);
+        press(&mut engine, KeyCode::PageUp, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("zzfoo", 2));
+    }
+
+    #[test]
+    fn history_prefix_search_uses_the_text_before_the_cursor() {
+        let mut engine = engine_with_history(&["cargo build", "cat file", "cargo test"]);
+        type_text(&mut engine, "cargo");
+        engine.run_edit_commands(&[EditCommand::MoveToLineStart]);
+        engine.run_edit_commands(&[EditCommand::MoveRight, EditCommand::MoveRight]);
+
+        handled(&mut engine, ReedlineEvent::HistoryPrefixSearchUp);
+        assert_eq!(engine.editor.get_buffer(), "cargo test");
+        handled(&mut engine, ReedlineEvent::HistoryPrefixSearchUp);
+        assert_eq!(engine.editor.get_buffer(), "cat file");
+        assert_eq!(engine.editor.insertion_point(), 2);
+
+        // Editing ends the traversal, the next search uses the new prefix
+        type_text(&mut engine, "r");
+        assert_eq!(engine.editor.get_buffer(), "cart file");
+        engine.run_edit_commands(&[EditCommand::MoveToLineStart]);
+        handled(&mut engine, ReedlineEvent::HistoryPrefixSearchUp);
+        assert_eq!(engine.editor.get_buffer(), "cargo test");
+        assert_eq!(engine.editor.insertion_point(), 0);
+    }
+
+    struct BackslashValidator;
+
+    impl Validator for BackslashValidator {
+        fn validate(&self, line: &str) -> ValidationResult {
+            if line.ends_with('\\') {
+                ValidationResult::Incomplete
+            } else {
+                ValidationResult::Complete
+            }
+        }
+    }
+
+    #[test]
+    fn last_validation_result_reports_the_verdict() {
+        let mut engine = Reedline::create().with_validator(Box::new(BackslashValidator));
+        assert_eq!(engine.last_validation_result(), None);
+
+        engine.run_edit_commands(&[EditCommand::InsertString("echo \\".into())]);
+        handled(&mut engine, ReedlineEvent::Enter);
+        assert_eq!(
+            engine.last_validation_result(),
+            Some(ValidationResult::Incomplete)
+        );
+
+        engine.run_edit_commands(&[EditCommand::InsertString("done".into())]);
+        handled(&mut engine, ReedlineEvent::Enter);
+        assert_eq!(
+            engine.last_validation_result(),
+            Some(ValidationResult::Complete)
+        );
+    }
+
+    struct RejectingValidator;
+
+    impl Validator for RejectingValidator {
+        fn validate(&self, _line: &str) -> ValidationResult {
+            ValidationResult::Incomplete
+        }
+    }
+
+    #[test]
+    fn alt_enter_submits_an_incomplete_buffer() {
+        let mut engine = Reedline::create().with_validator(Box::new(RejectingValidator));
+        type_text(&mut engine, "echo");
+
+        // Enter keeps asking for more input
+        assert!(press(&mut engine, KeyCode::Enter, KeyModifiers::NONE));
+        type_text(&mut engine, "more");
+        assert_eq!(engine.editor.get_buffer(), "echo\nmore");
+
+        let event = engine.edit_mode.parse_event(Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::ALT,
+        }));
+        let prompt = DefaultPrompt::default();
+        assert!(matches!(
+            engine.handle_event(&prompt, event).unwrap(),
+            EventStatus::Exits(Signal::Success(buffer)) if buffer == "echo\nmore"
+        ));
+        assert_eq!(
+            engine.last_validation_result(),
+            Some(ValidationResult::Incomplete)
+        );
+
+        let history: Vec<_> = engine.history().iter_chronologic().collect();
+        assert_eq!(history, vec!["echo\nmore".to_string()]);
+        assert!(engine.editor.is_empty());
+    }
+
+    #[test]
+    fn insert_key_toggles_overtype() {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "hello");
+        press(&mut engine, KeyCode::Home, KeyModifiers::NONE);
+
+        assert!(press(&mut engine, KeyCode::Insert, KeyModifiers::NONE));
+        type_text(&mut engine, "jelly");
+        assert_eq!(engine.current_buffer(), ("jelly", 5));
+        // Nothing left to overwrite at the end of the line
+        type_text(&mut engine, "fish");
+        assert_eq!(engine.current_buffer(), ("jellyfish", 9));
+
+        press(&mut engine, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut engine, KeyCode::Insert, KeyModifiers::NONE);
+        type_text(&mut engine, "a ");
+        assert_eq!(engine.current_buffer(), ("a jellyfish", 2));
+    }
+
+    #[rstest]
+    #[case(KeyCode::Tab, "echo\t")]
+    #[case(KeyCode::Enter, "echo\n")]
+    fn ctrl_v_inserts_the_next_key_literally(#[case] code: KeyCode, #[case] expected: &str) {
+        let mut engine = engine_with_menu();
+        type_text(&mut engine, "echo");
+
+        press(&mut engine, KeyCode::Char('v'), KeyModifiers::CONTROL);
+        press(&mut engine, code, KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), expected);
+        assert!(engine.active_menu().is_none());
+
+        // Only a single key is quoted
+        press(&mut engine, KeyCode::Char('v'), KeyModifiers::NONE);
+        assert_eq!(engine.editor.get_buffer(), format!("{}v", expected));
+    }
+
+    #[test]
+    fn cursor_shape_follows_vi_mode() {
+        let mut engine = Reedline::create()
+            .with_edit_mode(Box::new(Vi::default()))
+            .with_cursor_shapes(CursorShape::Block, CursorShape::Line);
+        assert_eq!(engine.cursor_shape(), Some(CursorShape::Line));
+
+        let key = |code| {
+            Event::Key(KeyEvent {
+                code,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        engine.edit_mode.parse_event(key(KeyCode::Esc));
+        assert_eq!(engine.cursor_shape(), Some(CursorShape::Block));
+
+        engine.edit_mode.parse_event(key(KeyCode::Char('i')));
+        assert_eq!(engine.cursor_shape(), Some(CursorShape::Line));
+    }
+
+    #[test]
+    fn cursor_shape_is_left_alone_by_default() {
+        let engine = Reedline::create().with_edit_mode(Box::new(Vi::default()));
+        assert_eq!(engine.cursor_shape(), None);
+
+        let engine = Reedline::create().with_cursor_shapes(CursorShape::Block, CursorShape::Line);
+        assert_eq!(engine.cursor_shape(), None);
+    }
+
+    #[test]
+    fn repainting_from_scratch_uses_the_current_highlighter() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create()
+            .with_highlighter(Box::new(ExampleHighlighter::new(vec!["echo".into()])));
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "echo");
+
+        engine.repaint_from_scratch(&prompt).unwrap();
+        assert!(output
+            .take()
+            .contains(&nu_ansi_term::Color::Green.paint("echo").to_string()));
+
+        engine.highlighter = Box::new(ExampleHighlighter::new(vec!["ls".into()]));
+        engine.repaint_from_scratch(&prompt).unwrap();
+        assert!(output
+            .take()
+            .contains(&nu_ansi_term::Color::Red.paint("echo").to_string()));
+        assert_eq!(engine.current_buffer(), ("echo", 4));
+    }
+
+    #[test]
+    fn external_messages_are_printed_above_the_input() {
+        let prompt = DefaultPrompt::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut engine = Reedline::create().with_external_printer(receiver);
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+
+        type_text(&mut engine, "ec");
+        sender.send("job 1 done".to_string()).unwrap();
+        sender.send("job 2\ndone".to_string()).unwrap();
+        engine.print_external_messages(&prompt).unwrap();
+        type_text(&mut engine, "ho");
+
+        let painted = output.take();
+        assert!(painted.contains("job 1 done\r\njob 2\r\ndone\r\n"));
+        assert_eq!(engine.painter.prompt_start_row(), 3);
+        assert_eq!(engine.current_buffer(), ("echo", 4));
+
+        // Nothing is painted without new messages
+        engine.print_external_messages(&prompt).unwrap();
+        assert_eq!(output.take(), "");
+    }
+
+    #[test]
+    fn redraw_handles_reach_the_polling_line_editor() {
+        let prompt = DefaultPrompt::default();
+        let engine = Reedline::create();
+        assert!(engine.poll_timeout(&prompt) > EXTERNAL_PRINTER_WAIT);
+
+        let redraw_handle = engine.redraw_handle();
+        assert!(engine.poll_timeout(&prompt) <= EXTERNAL_PRINTER_WAIT);
+
+        std::thread::spawn(move || redraw_handle.request_redraw())
+            .join()
+            .unwrap();
+        assert!(engine.redraw_handle.requested.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn delayed_prompt_is_painted_over_the_placeholder() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let prompt = AsyncPrompt::new(MatchCountPrompt, receiver);
+        let mut engine = Reedline::create();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        assert_eq!(engine.poll_timeout(&prompt), PROMPT_UPDATE_WAIT);
+
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            sender.send("~/repo (main)".to_string()).unwrap();
+        });
+
+        // Typing works over the placeholder prompt
+        for c in "ls".chars() {
+            let event = engine.parse_event(Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE,
+            }));
+            engine.handle_event(&prompt, event).unwrap();
+        }
+        assert!(!output.take().contains("~/repo"));
+
+        while prompt.awaits_update() {
+            engine.repaint_updated_prompt(&prompt).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let painted = output.take();
+        assert!(painted.contains("~/repo (main)> "));
+        assert!(painted.contains("ls"));
+        assert_eq!(engine.current_buffer(), ("ls", 2));
+        assert_eq!(engine.poll_timeout(&prompt), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn external_messages_keep_an_active_menu() {
+        let prompt = DefaultPrompt::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut engine = engine_with_menu().with_external_printer(receiver);
+        let (painter, _output) = Painter::headless(80, 24);
+        engine.painter = painter;
+
+        type_text(&mut engine, "he");
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        sender.send("message".to_string()).unwrap();
+        engine.print_external_messages(&prompt).unwrap();
+
+        assert!(engine.active_menu().is_some());
+        assert_eq!(engine.painter.menu_start_row(), Some(2));
+    }
+
+    #[rstest]
+    #[case(MenuPlacement::AboveWhenNearBottom, 9, 8)]
+    #[case(MenuPlacement::Below, 8, 9)]
+    fn menu_near_the_bottom_of_the_screen(
+        #[case] placement: MenuPlacement,
+        #[case] prompt_start_row: u16,
+        #[case] menu_start_row: u16,
+    ) {
+        let prompt = DefaultPrompt::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut engine = engine_with_menu().with_external_printer(receiver);
+        let (painter, output) = Painter::headless(80, 10);
+        engine.painter = painter;
+        engine = engine.with_menu_placement(placement);
+
+        // Moves the prompt down to the last row of the screen
+        for message in 0..9 {
+            sender.send(message.to_string()).unwrap();
+        }
+        engine.print_external_messages(&prompt).unwrap();
+        type_text(&mut engine, "he");
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.repaint(&prompt).unwrap();
+
+        assert!(output.take().contains("hello"));
+        assert_eq!(engine.painter.prompt_start_row(), prompt_start_row);
+        assert_eq!(engine.painter.menu_start_row(), Some(menu_start_row));
+        // Clicks on the prompt row move the cursor instead of selecting an entry
+        assert_eq!(engine.painter.menu_row(menu_start_row), Some(0));
+        assert_eq!(engine.painter.menu_row(prompt_start_row), None);
+    }
+
+    #[test]
+    fn resize_event_repaints_at_the_new_size() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "echo");
+
+        let event = engine.parse_event(Event::Resize(40, 12));
+        assert!(matches!(
+            engine.handle_event(&prompt, event).unwrap(),
+            EventStatus::Handled
+        ));
+        engine.repaint(&prompt).unwrap();
+
+        assert_eq!(
+            (
+                engine.painter.screen_width(),
+                engine.painter.screen_height()
+            ),
+            (40, 12)
+        );
+        assert!(output.take().contains("echo"));
+        assert_eq!(engine.current_buffer(), ("echo", 4));
+    }
+
+    /// Hints the rest of "cd /usr/bin" without styling it and paints the slashes
+    /// of the hint red
+    struct PathHinter;
+
+    impl Hinter for PathHinter {
+        fn handle(&mut self, line: &str, _: usize, _: &dyn History, _: bool) -> String {
+            "cd /usr/bin"
+                .strip_prefix(line)
+                .unwrap_or_default()
+                .to_string()
+        }
+
+        fn complete_hint(&self) -> String {
+            String::new()
+        }
+
+        fn next_hint_token(&self) -> String {
+            String::new()
+        }
+
+        fn style_hint(&self, hint: &str) -> StyledText {
+            let mut styled_hint = StyledText::new();
+            for c in hint.chars() {
+                let style = if c == '/' {
+                    nu_ansi_term::Style::new().fg(nu_ansi_term::Color::Red)
+                } else {
+                    nu_ansi_term::Style::new().dimmed()
+                };
+                styled_hint.push_str(style, c.encode_utf8(&mut [0; 4]));
+            }
+            styled_hint
+        }
+    }
+
+    #[test]
+    fn hint_is_painted_in_the_style_of_the_hinter() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create().with_hinter(Box::new(PathHinter));
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "cd ");
+
+        engine.repaint(&prompt).unwrap();
+
+        let expected = PathHinter.style_hint("/usr/bin").render_simple();
+        assert!(expected.contains(&nu_ansi_term::Color::Red.paint("/").to_string()));
+        assert!(output.take().contains(&expected));
+    }
+
+    #[test]
+    fn hint_is_left_unstyled_without_ansi_coloring() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create()
+            .with_hinter(Box::new(PathHinter))
+            .with_ansi_colors(false);
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "cd ");
+
+        engine.repaint(&prompt).unwrap();
+
+        let painted = output.take();
+        assert!(painted.contains("/usr/bin"));
+        assert!(!painted.contains(&nu_ansi_term::Color::Red.paint("/").to_string()));
+    }
+
+    /// Prompt with a styled working directory and branch
+    struct StyledPrompt;
+
+    impl Prompt for StyledPrompt {
+        fn render_prompt_left(&self) -> Cow<str> {
+            "~/src (main) ".into()
+        }
+
+        fn render_prompt_right(&self) -> Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_styled(&self) -> StyledText {
+            let mut styled_prompt = StyledText::new();
+            styled_prompt.push_str(nu_ansi_term::Style::new().bold(), "~/src");
+            styled_prompt.push_str(nu_ansi_term::Style::new(), " (");
+            styled_prompt.push_str(nu_ansi_term::Color::Red.normal(), "main");
+            styled_prompt.push_str(nu_ansi_term::Style::new(), ") ");
+            styled_prompt
+        }
+
+        fn render_prompt_indicator(&self, _: PromptEditMode) -> Cow<str> {
+            "> ".into()
+        }
+
+        fn render_prompt_multiline_indicator(&self, _: ValidationResult) -> Cow<str> {
+            "::: ".into()
+        }
+
+        fn render_prompt_history_search_indicator(&self, _: PromptHistorySearch) -> Cow<str> {
+            "? ".into()
+        }
+    }
+
+    #[rstest]
+    // "~/src (main) > " takes up 15 columns
+    #[case(15, 0)]
+    #[case(20, 5)]
+    #[case(14, 0)]
+    fn clicks_after_a_styled_prompt_land_on_the_right_column(
+        #[case] column: u16,
+        #[case] expected: usize,
+    ) {
+        let mut engine = Reedline::create();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "echo hello");
+        engine.repaint(&StyledPrompt).unwrap();
+        assert!(output
+            .take()
+            .contains(&nu_ansi_term::Color::Red.paint("main").to_string()));
+
+        engine.handle_mouse_click(&StyledPrompt, column, 0);
+
+        assert_eq!(engine.current_buffer(), ("echo hello", expected));
+    }
+
+    #[test]
+    fn clicks_on_a_menu_limited_in_height_select_the_painted_value() {
+        let prompt = DefaultPrompt::default();
+        let words = (0..10).map(|i| format!("ha{}", i)).collect();
+        let mut engine = Reedline::create().with_menu(
+            Box::new(CompletionMenu::default().with_columns(1)),
+            Some(Box::new(DefaultCompleter::new(words))),
+        );
+        let (painter, _output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        engine = engine.with_menu_max_height(MaxHeight::Rows(3));
+
+        type_text(&mut engine, "h");
+        handled(&mut engine, ReedlineEvent::Menu("completion_menu".into()));
+        engine.repaint(&prompt).unwrap();
+        for _ in 0..5 {
+            handled(&mut engine, ReedlineEvent::MenuDown);
+            engine.repaint(&prompt).unwrap();
+        }
+
+        // "ha3" to "ha5" are painted, the first rows are scrolled out of view
+        let menu_start_row = engine.painter.menu_start_row().unwrap();
+        engine.handle_mouse_click(&prompt, 0, menu_start_row);
+        engine.update_menu_working_details();
+
+        let selection = engine.active_menu().and_then(|menu| menu.get_value());
+        assert_eq!(
+            selection.map(|suggestion| suggestion.value),
+            Some("ha3".into())
+        );
+    }
+
+    /// Prompt showing the number of matches while a menu is open
+    struct MatchCountPrompt;
+
+    impl Prompt for MatchCountPrompt {
+        fn render_prompt_left(&self) -> Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_right(&self) -> Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_indicator(&self, _: PromptEditMode) -> Cow<str> {
+            "> ".into()
+        }
+
+        fn render_prompt_multiline_indicator(&self, _: ValidationResult) -> Cow<str> {
+            "::: ".into()
+        }
+
+        fn render_prompt_history_search_indicator(&self, _: PromptHistorySearch) -> Cow<str> {
+            "? ".into()
+        }
+
+        fn render_prompt_completion_indicator(
+            &self,
+            _: PromptEditMode,
+            completion: PromptCompletion,
+        ) -> Option<Cow<'_, str>> {
+            Some(
+                format!(
+                    "[{} matches for {}] ",
+                    completion.candidates, completion.common_prefix
+                )
+                .into(),
+            )
+        }
+    }
+
+    #[test]
+    fn prompt_shows_the_state_of_the_open_menu() {
+        let mut engine = engine_with_menu();
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "he");
+
+        engine.repaint(&MatchCountPrompt).unwrap();
+        assert!(!output.take().contains("matches"));
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.repaint(&MatchCountPrompt).unwrap();
+        assert!(output.take().contains("[2 matches for hel] "));
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuAbort));
+        engine.repaint(&MatchCountPrompt).unwrap();
+        assert!(!output.take().contains("matches"));
+    }
+
+    fn vi_engine_with(buffer: &str) -> Reedline {
+        let mut engine = Reedline::create().with_edit_mode(Box::new(Vi::default()));
+        engine.run_edit_commands(&[
+            EditCommand::InsertString(buffer.into()),
+            EditCommand::MoveToStart,
+        ]);
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+        engine
+    }
+
+    #[test]
+    fn vi_r_replaces_the_char_under_the_cursor() {
+        let mut engine = vi_engine_with("hello");
+
+        type_text(&mut engine, "rj");
+
+        assert_eq!(engine.current_buffer(), ("jello", 0));
+        assert_eq!(
+            engine.prompt_edit_mode().to_string(),
+            PromptEditMode::Vi(PromptViMode::Normal).to_string()
+        );
+    }
+
+    #[test]
+    fn vi_word_end_motions() {
+        let mut engine = vi_engine_with("foo bar baz");
+
+        type_text(&mut engine, "e");
+        assert_eq!(engine.current_buffer(), ("foo bar baz", 2));
+        type_text(&mut engine, "e");
+        assert_eq!(engine.current_buffer(), ("foo bar baz", 6));
+
+        type_text(&mut engine, "ge");
+        assert_eq!(engine.current_buffer(), ("foo bar baz", 2));
+
+        type_text(&mut engine, "lde");
+        assert_eq!(engine.current_buffer(), ("foo baz", 3));
+    }
+
+    #[rstest]
+    #[case("hello world", "HEY", "HEYlo world", 3)]
+    #[case("hi", "hey!", "hey!", 4)]
+    fn vi_replace_mode_overwrites_text(
+        #[case] buffer: &str,
+        #[case] typed: &str,
+        #[case] expected: &str,
+        #[case] insertion_point: usize,
+    ) {
+        let mut engine = vi_engine_with(buffer);
+
+        press(&mut engine, KeyCode::Char('R'), KeyModifiers::SHIFT);
+        assert!(matches!(
+            engine.prompt_edit_mode(),
+            PromptEditMode::Vi(PromptViMode::Replace)
+        ));
+        type_text(&mut engine, typed);
+
+        assert_eq!(engine.current_buffer(), (expected, insertion_point));
+    }
+
+    #[test]
+    fn vi_replace_mode_backspace_restores_the_original_text() {
+        let mut engine = vi_engine_with("hi");
+        press(&mut engine, KeyCode::Char('R'), KeyModifiers::SHIFT);
+        type_text(&mut engine, "hey");
+        assert_eq!(engine.current_buffer(), ("hey", 3));
+
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("he", 2));
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("hi", 1));
+
+        // Leaving the replace mode keeps the remaining changes
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+        press(&mut engine, KeyCode::Char('R'), KeyModifiers::SHIFT);
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("hi", 0));
+    }
+
+    #[rstest]
+    #[case('o', "  first\n  echo\nsecond", 14)]
+    #[case('O', "  echo\n  first\nsecond", 6)]
+    fn vi_open_line_inserts_on_a_new_line(
+        #[case] key: char,
+        #[case] expected: &str,
+        #[case] insertion_point: usize,
+    ) {
+        let mut engine = vi_engine_with("  first\nsecond");
+
+        let modifiers = if key.is_uppercase() {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        press(&mut engine, KeyCode::Char(key), modifiers);
+        assert!(matches!(
+            engine.prompt_edit_mode(),
+            PromptEditMode::Vi(PromptViMode::Insert)
+        ));
+        type_text(&mut engine, "echo");
+
+        assert_eq!(engine.current_buffer(), (expected, insertion_point));
+    }
+
+    #[test]
+    fn vi_open_line_below_on_an_empty_buffer() {
+        let mut engine = vi_engine_with("");
+
+        press(&mut engine, KeyCode::Char('o'), KeyModifiers::NONE);
+        type_text(&mut engine, "ls");
+
+        assert_eq!(engine.current_buffer(), ("\nls", 3));
+    }
+
+    #[test]
+    fn stray_bracket_is_underlined() {
+        let mut engine =
+            Reedline::create().with_highlighter(Box::new(SimpleMatchHighlighter::default()));
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        type_text(&mut engine, "echo (ls) | sort)");
+
+        engine.repaint(&MatchCountPrompt).unwrap();
+
+        assert_eq!(
+            engine.diagnostics(),
+            &[Diagnostic::new(Span::new(16, 17), "unexpected `)`")]
+        );
+        assert!(output.take().contains(
+            &nu_ansi_term::Style::new()
+                .underline()
+                .paint(")")
+                .to_string()
+        ));
+
+        // Fixing the input clears the diagnostic
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        engine.repaint(&MatchCountPrompt).unwrap();
+        assert_eq!(engine.diagnostics(), &[]);
+        assert!(!output.take().contains("\u{1b}[4m"));
+    }
+
+    #[test]
+    fn buffer_change_listener_sees_every_change_once() {
+        let changes = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let listened = changes.clone();
+        let mut engine =
+            Reedline::create().with_buffer_change_listener(Box::new(move |buffer, cursor| {
+                listened.lock().unwrap().push((buffer.to_string(), cursor));
+            }));
+
+        type_text(&mut engine, "ls");
+        press(&mut engine, KeyCode::Home, KeyModifiers::NONE);
+        // Neither moves the cursor nor changes the buffer
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        press(&mut engine, KeyCode::Delete, KeyModifiers::NONE);
+        // Pasted text is a single change
+        let paste = ReedlineEvent::Edit(vec![
+            EditCommand::InsertChar('c'),
+            EditCommand::InsertChar('d'),
+        ]);
+        engine
+            .handle_event(&DefaultPrompt::default(), paste)
+            .unwrap();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![
+                ("l".to_string(), 1),
+                ("ls".to_string(), 2),
+                ("ls".to_string(), 0),
+                ("s".to_string(), 0),
+                ("cds".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_key_returns_the_next_key_press() {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "ls");
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+
+        let mut events = vec![
+            Event::Resize(100, 30),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::NONE,
+            }),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::NONE,
+            }),
+        ]
+        .into_iter();
+        let key = engine
+            .next_key_event(|| Ok(events.next().expect("a key is read before running out")))
+            .unwrap();
+
+        assert_eq!(
+            key,
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                modifiers: KeyModifiers::NONE,
+            }
+        );
+        // Only the first key is consumed, the resize on the way is applied
+        assert_eq!(events.len(), 1);
+        assert_eq!(engine.painter.screen_width(), 100);
+        // Neither the buffer nor the screen are touched
+        assert_eq!(engine.current_buffer(), ("ls", 2));
+        assert_eq!(output.take(), "");
+    }
+
+    fn press(engine: &mut Reedline, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let event = engine.parse_event(Event::Key(KeyEvent { code, modifiers }));
+        let prompt = DefaultPrompt::default();
+        matches!(
+            engine.handle_event(&prompt, event).unwrap(),
+            EventStatus::Handled
+        )
+    }
+
+    fn type_text(engine: &mut Reedline, text: &str) {
+        for c in text.chars() {
+            press(engine, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    fn search_match(engine: &Reedline) -> Option<String> {
+        assert_eq!(engine.input_mode, InputMode::HistorySearch);
+        engine.history.string_at_cursor()
+    }
+
+    #[test]
+    fn reverse_search_cycles_through_matches() {
+        let mut engine = Reedline::create();
+        for entry in ["git status", "cargo build", "git commit"] {
+            engine.history.append(entry);
+        }
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "git");
+        assert_eq!(search_match(&engine).as_deref(), Some("git commit"));
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(search_match(&engine).as_deref(), Some("git status"));
+
+        press(&mut engine, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(search_match(&engine).as_deref(), Some("git commit"));
+
+        press(&mut engine, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(engine.input_mode, InputMode::Regular);
+        assert_eq!(engine.editor.get_buffer(), "git commit");
+    }
+
+    #[test]
+    fn initial_buffer_can_be_edited_and_undone() {
+        let mut engine = Reedline::create();
+        engine.set_initial_buffer("echo hi", None);
+        assert_eq!(engine.current_buffer(), ("echo hi", 7));
+
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+        type_text(&mut engine, "-");
+        assert_eq!(engine.current_buffer(), ("echo h-i", 7));
+
+        press(&mut engine, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(engine.current_buffer(), ("echo hi", 6));
+        press(&mut engine, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(engine.current_buffer(), ("", 0));
+    }
+
+    #[rstest]
+    #[case(Some(0), 0)]
+    #[case(Some(5), 5)]
+    #[case(Some(6), 5)]
+    #[case(Some(7), 7)]
+    #[case(Some(20), 8)]
+    #[case(None, 8)]
+    fn initial_cursor_lies_on_a_grapheme(#[case] cursor: Option<usize>, #[case] expected: usize) {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "left over");
+
+        // "ö" takes two bytes
+        engine.set_initial_buffer("echo ö!", cursor);
+        assert_eq!(engine.current_buffer(), ("echo ö!", expected));
+    }
+
+    #[test]
+    fn rebound_ctrl_c_never_interrupts() {
+        let mut keybindings = crate::default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('c'),
+            ReedlineEvent::Edit(vec![EditCommand::Clear]),
+        );
+        keybindings.remove_binding(KeyModifiers::CONTROL, KeyCode::Char('d'));
+        let mut engine =
+            Reedline::create().with_edit_mode(Box::new(crate::Emacs::new(keybindings)));
+        let prompt = DefaultPrompt::default();
+
+        type_text(&mut engine, "echo hi");
+        assert!(press(
+            &mut engine,
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL
+        ));
+        assert_eq!(engine.editor.get_buffer(), "");
+
+        for c in ['c', 'd'] {
+            let event = engine.parse_event(Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::CONTROL,
+            }));
+            assert!(!matches!(
+                engine.handle_event(&prompt, event).unwrap(),
+                EventStatus::Exits(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn ctrl_c_leaves_the_reverse_search_first() {
+        let mut engine = Reedline::create();
+        engine.history.append("git status");
+        type_text(&mut engine, "echo");
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "git");
+        assert!(press(
+            &mut engine,
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL
+        ));
+        assert_eq!(engine.input_mode, InputMode::Regular);
+        assert_eq!(engine.editor.get_buffer(), "echo");
+
+        assert!(!press(
+            &mut engine,
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL
+        ));
+        assert_eq!(engine.editor.get_buffer(), "");
+    }
+
+    #[rstest]
+    #[case(false, "", None)]
+    #[case(true, "git", Some("git status"))]
+    fn reverse_search_can_resume_the_last_search(
+        #[case] resume: bool,
+        #[case] query: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut engine = Reedline::create().with_history_search_resume(resume);
+        for entry in ["git status", "cargo build", "git commit"] {
+            engine.history.append(entry);
+        }
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "git");
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(search_match(&engine).as_deref(), Some("git status"));
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+
+        // Run another command in between
+        type_text(&mut engine, "ls");
+        handled(&mut engine, ReedlineEvent::Enter);
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(engine.search_query().as_deref(), Some(query));
+        assert_eq!(search_match(&engine).as_deref(), expected);
+
+        if resume {
+            press(&mut engine, KeyCode::Char('s'), KeyModifiers::CONTROL);
+            assert_eq!(search_match(&engine).as_deref(), Some("git commit"));
+        }
+    }
+
+    #[test]
+    fn history_search_lists_a_page_of_candidates() {
+        let prompt = DefaultPrompt::default();
+        let mut engine = Reedline::create()
+            .with_history_search_candidates(3)
+            .with_ansi_colors(false);
+        for entry in [
+            "git init",
+            "git add",
+            "ls",
+            "git commit",
+            "git log",
+            "git diff",
+        ] {
+            engine.history.append(entry);
+        }
+        let (painter, output) = Painter::headless(80, 24);
+        engine.painter = painter;
+        // The candidates are not part of the hint and are never truncated
+        engine.painter.set_hint_wrap(HintWrap::TruncateWithEllipsis);
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "git");
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        output.take();
+        engine.repaint(&prompt).unwrap();
+        let painted = output.take();
+        assert_eq!(search_match(&engine).as_deref(), Some("git log"));
+        assert!(painted.contains("git log\u{1b}7\r\n  git diff\r\n> git log\r\n  git commit"));
+        assert!(!painted.contains("git add"));
+        // The matches are looked up again only for a new query
+        let cached_query = engine
+            .history_search_matches
+            .as_ref()
+            .map(|(query, _)| query);
+        assert_eq!(cached_query.map(String::as_str), Some("git"));
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(search_match(&engine).as_deref(), Some("git add"));
+        output.take();
+        engine.repaint(&prompt).unwrap();
+        let painted = output.take();
+        // Only two matches are left for the second page
+        assert!(painted.contains("git add\u{1b}7\r\n> git add\r\n  git init\u{1b}8"));
+        assert!(!painted.contains("git log"));
+    }
+
+    #[test]
+    fn resumed_search_starts_at_the_newest_match_if_the_old_one_is_gone() {
+        let mut engine = Reedline::create().with_history_search_resume(true);
+        engine.history.append("git status");
+        engine.last_history_search = Some(("git".to_string(), "git log".to_string()));
+        engine.history.append("git commit");
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(search_match(&engine).as_deref(), Some("git commit"));
+    }
+
+    #[test]
+    fn reverse_search_escape_restores_the_buffer() {
+        let mut engine = Reedline::create();
+        engine.history.append("git status");
+        engine.run_edit_commands(&[EditCommand::InsertString("typed".into())]);
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "git");
+        assert_eq!(search_match(&engine).as_deref(), Some("git status"));
+
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(engine.input_mode, InputMode::Regular);
+        assert_eq!(engine.editor.get_buffer(), "typed");
+        assert_eq!(engine.editor.insertion_point(), 5);
+    }
+
+    #[test]
+    fn reverse_search_arrows_accept_the_match_for_editing() {
+        let mut engine = Reedline::create();
+        engine.history.append("cargo build");
+
+        press(&mut engine, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut engine, "carg");
+        press(&mut engine, KeyCode::Left, KeyModifiers::NONE);
+
+        assert_eq!(engine.input_mode, InputMode::Regular);
+        assert_eq!(engine.editor.get_buffer(), "cargo build");
+        assert_eq!(engine.editor.insertion_point(), 10);
+    }
+
+    #[test]
+    fn menu_preview_leaves_the_buffer_untouched_on_abort() {
+        let mut engine = engine_with_menu().with_menu_preview(true);
+        engine.painter.handle_resize(80, 24);
+        type_text(&mut engine, "hel world");
+        engine.run_edit_commands(&[EditCommand::MoveToPosition(3)]);
+        assert!(engine.menu_preview().is_none());
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.update_menu_working_details();
+        let preview = engine.menu_preview().unwrap();
+        assert_eq!(preview.get_buffer(), "hello world");
+        assert_eq!(preview.insertion_point(), 5);
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuNext));
+        engine.update_menu_working_details();
+        assert_eq!(engine.menu_preview().unwrap().get_buffer(), "help world");
+        assert_eq!(engine.current_buffer(), ("hel world", 3));
+
+        press(&mut engine, KeyCode::Esc, KeyModifiers::NONE);
+        engine.update_menu_working_details();
+        assert!(engine.active_menu().is_none());
+        assert!(engine.menu_preview().is_none());
+        assert_eq!(engine.current_buffer(), ("hel world", 3));
+    }
+
+    #[test]
+    fn menu_preview_is_committed_on_accept() {
+        let mut engine = engine_with_menu().with_menu_preview(true);
+        engine.painter.handle_resize(80, 24);
+        type_text(&mut engine, "hel");
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.update_menu_working_details();
+        assert!(handled(&mut engine, ReedlineEvent::MenuNext));
+        engine.update_menu_working_details();
+        let preview = engine.menu_preview().unwrap();
+
+        press(&mut engine, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(engine.active_menu().is_none());
+        assert_eq!(
+            engine.current_buffer(),
+            (preview.get_buffer(), preview.insertion_point())
+        );
+    }
+
+    struct SnippetCompleter;
+
+    impl Completer for SnippetCompleter {
+        fn complete(&self, _line: &str, pos: usize) -> Vec<Suggestion> {
+            vec![Suggestion::new("for x in y {\n}", Span::new(pos - 3, pos))]
+        }
+    }
+
+    #[test]
+    fn menu_preview_of_a_multiline_suggestion() {
+        let mut engine = Reedline::create()
+            .with_menu(
+                Box::new(CompletionMenu::default()),
+                Some(Box::new(SnippetCompleter)),
+            )
+            .with_menu_preview(true)
+            .with_tab_behavior(TabBehavior::AlwaysMenu);
+        engine.painter.handle_resize(80, 24);
+        type_text(&mut engine, "do for; done");
+        engine.run_edit_commands(&[EditCommand::MoveToPosition(6)]);
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Menu("completion_menu".into())
+        ));
+        engine.update_menu_working_details();
+        let preview = engine.menu_preview().unwrap();
+        assert_eq!(preview.get_buffer(), "do for x in y {\n}; done");
+        assert_eq!(preview.insertion_point(), 17);
+        assert_eq!(engine.current_buffer(), ("do for; done", 6));
+    }
+
+    struct PathCompleter;
+
+    impl Completer for PathCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+            let typed = &line[start..pos];
+
+            [
+                "src/",
+                "src/menu/",
+                "src/menu/mod.rs",
+                "src/lib.rs",
+                "README.md",
+            ]
+            .iter()
+            .filter(|path| path.len() > typed.len() && path.starts_with(typed))
+            // Only offer the next level of the path
+            .filter(|path| !path[typed.len()..path.len() - 1].contains('/'))
+            .map(|path| {
+                Suggestion::new(path.to_string(), Span::new(start, pos))
+                    .with_continue_completion(path.ends_with('/'))
+            })
+            .collect()
+        }
+    }
+
+    fn menu_values(engine: &Reedline) -> Option<Vec<String>> {
+        engine
+            .menus
+            .iter()
+            .find(|menu| menu.is_active())
+            .map(|menu| menu.get_values().iter().map(|s| s.value.clone()).collect())
+    }
+
+    fn engine_with_completion_triggers() -> Reedline {
+        Reedline::create()
+            .with_menu(
+                Box::new(CompletionMenu::default()),
+                Some(Box::new(PathCompleter)),
+            )
+            .with_completion_triggers(&['/'])
+    }
+
+    #[test]
+    fn trigger_character_opens_the_completion_menu() {
+        let mut engine = engine_with_completion_triggers();
+
+        type_text(&mut engine, "cat src");
+        assert_eq!(menu_values(&engine), None);
+
+        type_text(&mut engine, "/");
+        assert_eq!(engine.current_buffer(), ("cat src/", 8));
+        assert_eq!(
+            menu_values(&engine),
+            Some(vec!["src/menu/".to_string(), "src/lib.rs".to_string()])
+        );
+
+        // Typing more keeps the menu open, as it would after Tab
+        type_text(&mut engine, "l");
+        assert!(menu_is_active(&engine));
+    }
+
+    #[test]
+    fn trigger_character_without_suggestions_is_just_typed() {
+        let mut engine = engine_with_completion_triggers();
+
+        type_text(&mut engine, "cat docs/");
+        assert_eq!(engine.current_buffer(), ("cat docs/", 9));
+        assert_eq!(menu_values(&engine), None);
+
+        type_text(&mut engine, "a/b");
+        assert_eq!(engine.current_buffer(), ("cat docs/a/b", 12));
+        assert_eq!(menu_values(&engine), None);
+    }
+
+    #[test]
+    fn pasted_trigger_characters_keep_the_menu_closed() {
+        let mut engine = engine_with_completion_triggers();
+
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::Edit(vec![EditCommand::InsertString("cat src/".into())])
+        ));
+        assert_eq!(menu_values(&engine), None);
+    }
+
+    #[test]
+    fn accepting_a_directory_continues_the_completion() {
+        let mut engine = Reedline::create()
+            .with_menu(
+                Box::new(CompletionMenu::default()),
+                Some(Box::new(PathCompleter)),
+            )
+            .with_quick_completions(true);
+
+        // The only suggestion is accepted right away
+        press_tab(&mut engine, "cat s");
+        assert_eq!(engine.editor.get_buffer(), "cat src/");
+        assert_eq!(
+            menu_values(&engine),
+            Some(vec!["src/menu/".to_string(), "src/lib.rs".to_string()])
+        );
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuAccept));
+        assert_eq!(engine.editor.get_buffer(), "cat src/menu/");
+        assert_eq!(
+            menu_values(&engine),
+            Some(vec!["src/menu/mod.rs".to_string()])
+        );
+
+        assert!(handled(&mut engine, ReedlineEvent::MenuAccept));
+        assert_eq!(engine.editor.get_buffer(), "cat src/menu/mod.rs");
+        assert_eq!(menu_values(&engine), None);
+    }
+
+    struct StuckCompleter;
+
+    impl Completer for StuckCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            vec![Suggestion::new(line.to_string(), Span::new(0, pos)).with_continue_completion(true)]
+        }
+    }
+
+    #[test]
+    fn continued_completion_without_progress_closes_the_menu() {
+        let mut engine = Reedline::create()
+            .with_menu(
+                Box::new(CompletionMenu::default()),
+                Some(Box::new(StuckCompleter)),
+            )
+            .with_quick_completions(true);
+
+        press_tab(&mut engine, "src/");
+        assert_eq!(engine.editor.get_buffer(), "src/");
+        assert_eq!(menu_values(&engine), None);
+    }
+
+    #[test]
+    fn failed_completion_rings_the_bell() {
+        let mut engine = engine_with_menu().with_bell(BellStyle::Audible);
+
+        press_tab(&mut engine, "he");
+        assert!(!engine.bell_pending);
+
+        handled(&mut engine, ReedlineEvent::MenuAbort);
+        press_tab(&mut engine, "xyz");
+        assert!(engine.bell_pending);
+    }
+
+    #[test]
+    fn enter_submits_incomplete_input_when_newlines_are_denied() {
+        let mut engine = Reedline::create();
+        type_text(&mut engine, "echo (");
+        assert!(handled(&mut engine, ReedlineEvent::Enter));
+        assert_eq!(engine.current_buffer(), ("echo (\n", 7));
+
+        let mut engine = Reedline::create().with_edit_command_restriction(Restriction::Deny(vec![
+            EditCommand::InsertNewline,
+            EditCommand::InsertNewlineWithIndent,
+        ]));
+        type_text(&mut engine, "echo (");
+        let status = engine
+            .handle_event(&DefaultPrompt::default(), ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "echo ("));
+        assert_eq!(
+            engine.last_validation_result(),
+            Some(ValidationResult::Incomplete)
+        );
+    }
+
+    #[test]
+    fn without_validator_line_breaks_come_from_the_bindings() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::ALT,
+            KeyCode::Enter,
+            ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+        );
+        let mut engine = Reedline::create()
+            .with_edit_mode(Box::new(Emacs::new(keybindings)))
+            .disable_validator();
+
+        type_text(&mut engine, "for x in (");
+        assert!(press(&mut engine, KeyCode::Enter, KeyModifiers::ALT));
+        type_text(&mut engine, "1 2)");
+        assert_eq!(engine.current_buffer(), ("for x in (\n1 2)", 15));
+
+        let event = engine.parse_event(Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+        }));
+        let status = engine
+            .handle_event(&DefaultPrompt::default(), event)
+            .unwrap();
+        assert!(
+            matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "for x in (\n1 2)")
+        );
+
+        // An unbalanced line is submitted as well
+        type_text(&mut engine, "echo (");
+        let status = engine
+            .handle_event(&DefaultPrompt::default(), ReedlineEvent::Enter)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "echo ("));
+    }
+
+    #[test]
+    fn restricted_commands_and_events_ring_the_bell() {
+        let mut engine = Reedline::create()
+            .with_bell(BellStyle::Audible)
+            .with_edit_command_restriction(Restriction::Allow(vec![
+                EditCommand::InsertChar('_'),
+                EditCommand::Backspace,
+            ]))
+            .with_event_restriction(Restriction::Deny(vec![ReedlineEvent::Up]));
+
+        type_text(&mut engine, "yes");
+        press(&mut engine, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(engine.current_buffer(), ("ye", 2));
+        assert!(!engine.bell_pending);
+
+        assert!(!press(
+            &mut engine,
+            KeyCode::Char('a'),
+            KeyModifiers::CONTROL
+        ));
+        assert_eq!(engine.current_buffer(), ("ye", 2));
+        assert!(engine.bell_pending);
+
+        engine.bell_pending = false;
+        assert!(!press(&mut engine, KeyCode::Up, KeyModifiers::NONE));
+        assert!(engine.bell_pending);
+    }
+
+    #[test]
+    fn allowed_events_are_carried_out_inside_of_event_lists() {
+        let mut engine = Reedline::create().with_event_restriction(Restriction::Allow(vec![
+            ReedlineEvent::Edit(vec![]),
+            ReedlineEvent::Enter,
+        ]));
+
+        assert!(press(&mut engine, KeyCode::Char('a'), KeyModifiers::NONE));
+        // Only the allowed event of the list is carried out
+        assert!(handled(
+            &mut engine,
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::Up,
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('b')]),
+            ])
+        ));
+        assert_eq!(engine.current_buffer(), ("ab", 2));
+
+        assert!(handled(&mut engine, ReedlineEvent::Repaint));
+        assert!(!handled(&mut engine, ReedlineEvent::Left));
+    }
+
+    #[rstest]
+    #[case("", ReedlineEvent::Edit(vec![EditCommand::Backspace]), true)]
+    #[case("", ReedlineEvent::Left, true)]
+    #[case("abc", ReedlineEvent::Right, true)]
+    #[case("abc", ReedlineEvent::Left, false)]
+    #[case("abc", ReedlineEvent::Edit(vec![EditCommand::Backspace]), false)]
+    #[case("abc", ReedlineEvent::Edit(vec![EditCommand::Undo, EditCommand::Redo]), false)]
+    fn motions_at_the_boundary_ring_the_bell(
+        #[case] input: &str,
+        #[case] event: ReedlineEvent,
+        #[case] expected: bool,
+    ) {
+        let mut engine = Reedline::create().with_bell(BellStyle::Visual);
+        engine.run_edit_commands(&[EditCommand::InsertString(input.into())]);
+
+        handled(&mut engine, event);
+        assert_eq!(engine.bell_pending, expected);
+    }
+
+    #[test]
+    fn bell_is_off_by_default() {
+        let mut engine = engine_with_menu();
+
+        press_tab(&mut engine, "xyz");
+        assert!(!engine.bell_pending);
+    }
+
+    #[test]
+    fn shift_arrows_select_text_in_emacs_mode() {
+        let mut engine = Reedline::create();
+        engine.run_edit_commands(&[EditCommand::InsertString("hello world".into())]);
+
+        for _ in 0..5 {
+            assert!(press(&mut engine, KeyCode::Left, KeyModifiers::SHIFT));
+        }
+        assert!(press(&mut engine, KeyCode::Char('X'), KeyModifiers::SHIFT));
+        assert_eq!(engine.editor.get_buffer(), "hello X");
+
+        assert!(press(&mut engine, KeyCode::Home, KeyModifiers::SHIFT));
+        assert!(press(&mut engine, KeyCode::Left, KeyModifiers::NONE));
+        assert!(press(&mut engine, KeyCode::Char('a'), KeyModifiers::NONE));
+        assert_eq!(engine.editor.get_buffer(), "ahello X");
+    }
+}
@@ -31,6 +31,10 @@ pub enum EditCommand {
     /// Move to the end of the current line
     MoveToLineEnd,
 
+    /// Move to the start of the given line of the buffer, counted from 1.
+    /// Lines past the last one go to the last line
+    MoveToLine(usize),
+
     /// Move one character to the left
     MoveLeft,
 
@@ -43,6 +47,34 @@ pub enum EditCommand {
     /// Move one word to the right
     MoveWordRight,
 
+    /// Move to the start of the previous small word, like `b` in vi
+    ///
+    /// Small words are runs of alphanumeric characters and underscores or runs of
+    /// other non-blank characters
+    MoveSmallWordLeft,
+
+    /// Move to the start of the next small word, like `w` in vi
+    MoveSmallWordRight,
+
+    /// Move to the start of the previous whitespace delimited word, like `B` in vi
+    MoveBigWordLeft,
+
+    /// Move to the start of the next whitespace delimited word, like `W` in vi
+    MoveBigWordRight,
+
+    /// Move onto the last character of the next small word, like `e` in vi
+    MoveToWordEnd,
+
+    /// Move onto the last character of the next whitespace delimited word, like `E` in vi
+    MoveToBigWordEnd,
+
+    /// Move onto the last character of the previous small word, like `ge` in vi
+    MoveToPreviousWordEnd,
+
+    /// Move onto the last character of the previous whitespace delimited word,
+    /// like `gE` in vi
+    MoveToPreviousBigWordEnd,
+
     /// Move to position
     MoveToPosition(usize),
 
@@ -52,6 +84,12 @@ pub enum EditCommand {
     /// Insert a string at the current insertion point
     InsertString(String),
 
+    /// Insert a line break (`\r\n` on Windows, `\n` elsewhere)
+    InsertNewline,
+
+    /// Insert a newline followed by the leading whitespace of the current line
+    InsertNewlineWithIndent,
+
     /// Repace characters with string
     ReplaceChars(usize, String),
 
@@ -67,15 +105,53 @@ pub enum EditCommand {
     /// Delete in-place a word from the current insertion point
     DeleteWord,
 
+    /// Delete from the bracket under the cursor up to and including its matching bracket
+    DeleteToMatchingBracket,
+
     /// Clear the current buffer
     Clear,
 
-    /// Clear the current buffer
+    /// Replace the grapheme under the cursor without moving the cursor
+    ReplaceChar(char),
+
+    /// Overwrite the grapheme under the cursor and move right, appending at the end of a line
+    OvertypeChar(char),
+
+    /// Move left and restore the grapheme overwritten by [`EditCommand::OvertypeChar`]
+    OvertypeBackspace,
+
+    /// Clear from the insertion point to the end of the current line
     ClearToLineEnd,
 
+    /// Clear from the insertion point to the end of the buffer, across newlines
+    ClearToBufferEnd,
+
+    /// Clear from the start of the buffer to the insertion point, across newlines
+    ClearToBufferStart,
+
     /// Cut the current line
     CutCurrentLine,
 
+    /// Cut the whole content of the current line, keeping its line break
+    KillWholeLine,
+
+    /// Join the current line with the next one, like `J` in vi
+    JoinLines,
+
+    /// Start an indented line below the current one, like `o` in vi
+    OpenLineBelow,
+
+    /// Start an indented line above the current one, like `O` in vi
+    OpenLineAbove,
+
+    /// Add one indent unit at the start of the current line or of every selected line,
+    /// see [`crate::Reedline::with_indent_unit()`]
+    Indent,
+
+    /// Remove up to one indent unit from the start of the current line or of every
+    /// selected line
+    Dedent,
+
     /// Cut from the start of the buffer to the insertion point
     CutFromStart,
 
@@ -94,6 +170,13 @@ pub enum EditCommand {
     /// Cut the word right of the insertion point
     CutWordRight,
 
+    /// Cut from the insertion point through the end of the next small word, like `de` in vi
+    CutToWordEnd,
+
+    /// Cut from the insertion point through the end of the next whitespace delimited word,
+    /// like `dE` in vi
+    CutToBigWordEnd,
+
     /// Paste the cut buffer in front of the insertion point (Emacs, vi `P`)
     PasteCutBufferBefore,
 
@@ -109,12 +192,65 @@ pub enum EditCommand {
     /// Capitalize the current character
     CapitalizeChar,
 
+    /// Upper case the text from the cursor to the end of the line
+    UppercaseToLineEnd,
+
+    /// Lower case the text from the cursor to the end of the line
+    LowercaseToLineEnd,
+
+    /// Flip the case of the current character and move one character to the right
+    ToggleCaseChar,
+
     /// Swap the current word with the word to the right
     SwapWords,
 
+    /// Transpose the word before the cursor with the word after it (emacs style)
+    ///
+    /// Moves the cursor behind the second word of the pair
+    TransposeWords,
+
     /// Swap the current grapheme/character with the one to the right
     SwapGraphemes,
 
+    /// Anchor a selection at the current insertion point, cursor moves extend the selection
+    SetSelectionAnchor,
+
+    /// Start or extend a selection one character to the left
+    ///
+    /// Unlike a selection started with [`EditCommand::SetSelectionAnchor`], plain
+    /// cursor moves end the selection and typed text replaces it
+    SelectLeft,
+
+    /// Start or extend a selection one character to the right
+    SelectRight,
+
+    /// Start or extend a selection to the start of the current line
+    SelectToLineStart,
+
+    /// Start or extend a selection to the end of the current line
+    SelectToLineEnd,
+
+    /// Remove the selection anchor without changing the buffer
+    ClearSelection,
+
+    /// Delete the selected text
+    DeleteSelection,
+
+    /// Copy the selected text into the cut buffer
+    YankSelection,
+
+    /// Upper case the selected text
+    UppercaseSelection,
+
+    /// Copy the selected text to the clipboard, or the current line if nothing is selected
+    CopySelection,
+
+    /// Copy the current line to the clipboard, leaving the buffer and the selection as they are
+    CopyCurrentLine,
+
+    /// Cut the selected text to the clipboard, or the current line if nothing is selected
+    CutSelection,
+
     /// Undo the previous edit command
     Undo,
 
@@ -156,53 +292,96 @@ impl EditCommand {
             | EditCommand::MoveToEnd
             | EditCommand::MoveToLineStart
             | EditCommand::MoveToLineEnd
+            | EditCommand::MoveToLine(_)
             | EditCommand::MoveToPosition(_)
             | EditCommand::MoveLeft
             | EditCommand::MoveRight
             | EditCommand::MoveWordLeft
             | EditCommand::MoveWordRight
+            | EditCommand::MoveSmallWordLeft
+            | EditCommand::MoveSmallWordRight
+            | EditCommand::MoveBigWordLeft
+            | EditCommand::MoveBigWordRight
+            | EditCommand::MoveToWordEnd
+            | EditCommand::MoveToBigWordEnd
+            | EditCommand::MoveToPreviousWordEnd
+            | EditCommand::MoveToPreviousBigWordEnd
             | EditCommand::MoveRightUntil(_)
             | EditCommand::MoveRightBefore(_)
             | EditCommand::MoveLeftUntil(_)
-            | EditCommand::MoveLeftBefore(_) => UndoBehavior::Full,
+            | EditCommand::MoveLeftBefore(_)
+            | EditCommand::SelectLeft
+            | EditCommand::SelectRight
+            | EditCommand::SelectToLineStart
+            | EditCommand::SelectToLineEnd => UndoBehavior::MoveCursor,
 
             // Coalesceable insert
-            EditCommand::InsertChar(_) => UndoBehavior::Coalesce,
+            EditCommand::InsertChar(_) | EditCommand::OvertypeChar(_) => UndoBehavior::Coalesce,
 
             // Full edits
             EditCommand::Backspace
             | EditCommand::Delete
             | EditCommand::InsertString(_)
+            | EditCommand::InsertNewline
+            | EditCommand::InsertNewlineWithIndent
             | EditCommand::ReplaceChars(_, _)
+            | EditCommand::ReplaceChar(_)
+            | EditCommand::OvertypeBackspace
             | EditCommand::BackspaceWord
             | EditCommand::DeleteWord
+            | EditCommand::DeleteToMatchingBracket
             | EditCommand::Clear
             | EditCommand::ClearToLineEnd
+            | EditCommand::ClearToBufferEnd
+            | EditCommand::ClearToBufferStart
             | EditCommand::CutCurrentLine
+            | EditCommand::KillWholeLine
+            | EditCommand::JoinLines
+            | EditCommand::OpenLineBelow
+            | EditCommand::OpenLineAbove
+            | EditCommand::Indent
+            | EditCommand::Dedent
             | EditCommand::CutFromStart
             | EditCommand::CutFromLineStart
             | EditCommand::CutToLineEnd
             | EditCommand::CutToEnd
             | EditCommand::CutWordLeft
             | EditCommand::CutWordRight
+            | EditCommand::CutToWordEnd
+            | EditCommand::CutToBigWordEnd
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
             | EditCommand::CapitalizeChar
+            | EditCommand::UppercaseToLineEnd
+            | EditCommand::LowercaseToLineEnd
+            | EditCommand::ToggleCaseChar
             | EditCommand::SwapWords
+            | EditCommand::TransposeWords
             | EditCommand::SwapGraphemes
+            | EditCommand::DeleteSelection
+            | EditCommand::UppercaseSelection
+            | EditCommand::CutSelection
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
             | EditCommand::CutLeftBefore(_) => UndoBehavior::Full,
 
-            EditCommand::Undo | EditCommand::Redo => UndoBehavior::Ignore,
+            EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::SetSelectionAnchor
+            | EditCommand::ClearSelection
+            | EditCommand::YankSelection
+            | EditCommand::CopySelection
+            | EditCommand::CopyCurrentLine => UndoBehavior::Ignore,
         }
     }
 }
 
 /// Specifies how the (previously executed) operation should be treated in the Undo stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UndoBehavior {
     /// Operation is not affecting the LineBuffers content and should be ignored
     ///
@@ -214,6 +393,11 @@ pub enum UndoBehavior {
     ///
     /// e.g. insertion of characters by typing
     Coalesce,
+    /// The operation only moves the cursor and is not stored as a separate entry
+    ///
+    /// The cursor position is remembered with the current entry, so undoing the next
+    /// change places the cursor where it was before that change
+    MoveCursor,
 }
 
 /// Reedline supported actions.
@@ -226,8 +410,15 @@ pub enum ReedlineEvent {
     HistoryHintComplete,
 
     /// Complete a single token/word of the history hint
+    ///
+    /// Inserts the hint up to the end of its next word, the rest of the hint stays
+    /// available (`Alt-F`/`Alt-Right` in the default emacs keybindings)
     HistoryHintWordComplete,
 
+    /// Insert the longest prefix shared by all completer suggestions without
+    /// opening a menu
+    CompleteCommonPrefix,
+
     /// Action event
     ActionHandler,
 
@@ -237,6 +428,9 @@ pub enum ReedlineEvent {
     ///
     /// - On empty line breaks execution to exit with [`Signal::CtrlD`]
     /// - Secondary behavior [`EditCommand::Delete`]
+    ///
+    /// Only produced by the `Ctrl-D` binding of the emacs mode, bind the key to another
+    /// event to handle it like any other key
     CtrlD,
 
     /// Handle SIGTERM key input
@@ -244,9 +438,14 @@ pub enum ReedlineEvent {
     /// Expected behavior:
     ///
     /// Abort entry
+    /// Closes the active menu or leaves the reverse search, keeping the buffer.
+    /// Otherwise:
     /// Run [`EditCommand::Clear`]
     /// Clear the current undo
     /// Bubble up [`Signal::CtrlC`]
+    ///
+    /// Only produced by the `Ctrl-C` binding of the edit modes, bind the key to another
+    /// event to handle it like any other key
     CtrlC,
 
     /// Clears the screen and sets prompt to first line
@@ -255,12 +454,31 @@ pub enum ReedlineEvent {
     /// Handle enter event
     Enter,
 
+    /// Submit the buffer even if the [`crate::Validator`] considers it incomplete
+    SubmitForce,
+
+    /// Submit the buffer like [`ReedlineEvent::Enter`] and start the next
+    /// [`crate::Reedline::read_line`] with the history entry following the submitted one,
+    /// like readline's `operate-and-get-next`
+    AcceptAndGetNext,
+
+    /// Insert the next key literally instead of interpreting it as a command
+    QuotedInsert,
+
+    /// Switch between inserting typed characters and overwriting the text under the cursor
+    ToggleOvertype,
+
     /// Esc event
     Esc,
 
     /// Mouse
     Mouse, // Fill in details later
 
+    /// Click with the left mouse button at the given terminal column and row
+    ///
+    /// Places the cursor in the buffer or selects the clicked menu entry
+    MouseClick(u16, u16),
+
     /// trigger termimal resize
     Resize(u16, u16),
 
@@ -288,9 +506,20 @@ pub enum ReedlineEvent {
     /// Navigate to the next historic buffer
     NextHistory,
 
+    /// Navigate to the previous historic buffer starting with the text before the cursor.
+    /// The cursor stays behind that text
+    HistoryPrefixSearchUp,
+
+    /// Navigate to the next historic buffer starting with the text before the cursor.
+    /// The cursor stays behind that text
+    HistoryPrefixSearchDown,
+
     /// Search the history for a string
     SearchHistory,
 
+    /// Search the history for a string, moving to newer matches while searching
+    SearchHistoryForward,
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -325,8 +554,155 @@ pub enum ReedlineEvent {
     /// Move to the previous history page
     MenuPagePrevious,
 
+    /// Replace the buffer with the selected menu entry and close the menu
+    MenuAccept,
+
+    /// Close the active menu without modifying the buffer
+    MenuAbort,
+
     /// Way to bind the execution of a whole command (directly returning from [`crate::Reedline::read_line()`]) to a keybinding
     ExecuteHostCommand(String),
+
+    /// Insert the text computed by the snippet registered under the name with
+    /// [`crate::Reedline::with_snippet()`] at the cursor, e.g. the current date
+    InsertSnippet(String),
+
+    /// Apply the next edit or cursor movement this many times, like the numeric
+    /// argument of Emacs. The count is taken as is, the edit mode accumulates it
+    NumericArgument(usize),
+
+    /// Apply the last edit or cursor movement again this many times
+    Repeat(usize),
+
+    /// Start the numeric argument of the Emacs mode with four, or multiply it by four.
+    /// Digits typed afterwards replace it with the typed count
+    UniversalArgument,
+}
+
+/// The whitespace added by [`EditCommand::Indent`] and removed by [`EditCommand::Dedent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndentUnit {
+    /// A single tab character
+    Tab,
+    /// The given number of spaces
+    Spaces(usize),
+}
+
+impl Default for IndentUnit {
+    fn default() -> Self {
+        IndentUnit::Spaces(4)
+    }
+}
+
+impl IndentUnit {
+    pub(crate) fn text(self) -> String {
+        match self {
+            IndentUnit::Tab => "\t".to_string(),
+            IndentUnit::Spaces(n) => " ".repeat(n),
+        }
+    }
+}
+
+/// Determines what happens when a completion menu is activated (usually bound to `Tab`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TabBehavior {
+    /// Always open the menu with the available suggestions
+    #[default]
+    AlwaysMenu,
+    /// Accept the suggestion directly if it is the only one, otherwise open the menu
+    AcceptSingle,
+    /// Insert the longest prefix shared by all suggestions and open the menu
+    /// if more than one suggestion remains
+    CompleteCommonPrefix,
+}
+
+/// Words that are expanded when they match an abbreviation,
+/// see [`crate::Reedline::with_abbreviations()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AbbreviationPosition {
+    /// Only the command name, the first word of the buffer or the first word after
+    /// `|`, `;`, `&` or `(`
+    #[default]
+    Command,
+    /// Any word
+    Anywhere,
+}
+
+/// Feedback given when an action has nothing to act on, for example moving past
+/// the end of the line, a completion without matches or a failing history search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BellStyle {
+    /// Stay silent
+    #[default]
+    None,
+    /// Ring the terminal bell
+    Audible,
+    /// Briefly flash the screen in reverse video
+    Visual,
+}
+
+/// Limits the [`EditCommand`]s or [`ReedlineEvent`]s that are carried out, e.g. for a
+/// restricted prompt, see [`crate::Reedline::with_edit_command_restriction()`]
+///
+/// Entries are matched by their variant, the values they hold are ignored: denying
+/// `EditCommand::InsertChar('a')` denies typing any character
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Restriction<T> {
+    /// Everything is carried out
+    #[default]
+    Unrestricted,
+    /// Only the listed variants are carried out
+    Allow(Vec<T>),
+    /// Everything but the listed variants is carried out
+    Deny(Vec<T>),
+}
+
+impl<T> Restriction<T> {
+    /// Whether `item` may be carried out
+    pub fn permits(&self, item: &T) -> bool {
+        let same_variant =
+            |listed: &T| std::mem::discriminant(listed) == std::mem::discriminant(item);
+        match self {
+            Restriction::Unrestricted => true,
+            Restriction::Allow(allowed) => allowed.iter().any(same_variant),
+            Restriction::Deny(denied) => !denied.iter().any(same_variant),
+        }
+    }
+}
+
+/// What pressing `Enter` does while the buffer is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmptyLineBehavior {
+    /// Submit the empty string, [`Signal::Success`] with an empty buffer
+    #[default]
+    Submit,
+    /// Do nothing, the prompt stays where it is
+    Ignore,
+    /// Keep the painted prompt and paint a fresh one on the next line without submitting
+    RepaintPrompt,
+}
+
+/// How a hint that doesn't fit in the rest of the row is painted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HintWrap {
+    /// Wrap the hint onto the following rows like the buffer
+    #[default]
+    Wrap,
+    /// Cut the hint at the end of the row and mark the cut with `…`. Hints spanning
+    /// several lines are cut at their first line break
+    TruncateWithEllipsis,
+}
+
+/// Where a menu is painted when the rows below the prompt can't hold it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MenuPlacement {
+    /// Paint the menu above the prompt when it fits there, instead of scrolling
+    /// the prompt up to make room below. The menu overwrites the rows above the
+    /// prompt, e.g. the output of previous commands, until it is closed
+    AboveWhenNearBottom,
+    /// Always paint the menu below the prompt, scrolling the prompt up if needed
+    #[default]
+    Below,
 }
 
 pub(crate) enum EventStatus {
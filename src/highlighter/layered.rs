@@ -0,0 +1,122 @@
+use crate::highlighter::Highlighter;
+use crate::StyledText;
+
+/// Combine several highlighters, e.g. syntax highlighting with search matches on top
+///
+/// The first layer styles the whole line. Every later layer is painted over the
+/// previous ones: its styles win on the byte ranges it covers, except for text it
+/// leaves in the default style, which keeps the style from below.
+///
+/// ```rust
+/// use reedline::{ExampleHighlighter, LayeredHighlighter, SimpleMatchHighlighter};
+///
+/// let highlighter = LayeredHighlighter::new(vec![
+///     Box::new(ExampleHighlighter::new(vec!["git".into()])),
+///     Box::new(SimpleMatchHighlighter::new("status".into())),
+/// ]);
+/// ```
+#[derive(Default)]
+pub struct LayeredHighlighter {
+    layers: Vec<Box<dyn Highlighter>>,
+}
+
+impl LayeredHighlighter {
+    /// Construct a highlighter from `layers`, ordered from the bottom to the top
+    pub fn new(layers: Vec<Box<dyn Highlighter>>) -> Self {
+        Self { layers }
+    }
+
+    /// Add a layer on top of the existing ones
+    #[must_use]
+    pub fn with_layer(mut self, layer: Box<dyn Highlighter>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+}
+
+impl Highlighter for LayeredHighlighter {
+    fn highlight(&self, line: &str, cursor: usize) -> StyledText {
+        let mut layers = self.layers.iter();
+        let mut styled_text = match layers.next() {
+            Some(base) => base.highlight(line, cursor),
+            None => {
+                let mut styled_text = StyledText::new();
+                styled_text.push_str(Default::default(), line);
+                return styled_text;
+            }
+        };
+
+        for layer in layers {
+            styled_text.overlay(&layer.highlight(line, cursor));
+        }
+
+        styled_text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExampleHighlighter, SimpleMatchHighlighter};
+    use nu_ansi_term::{Color, Style};
+    use pretty_assertions::assert_eq;
+
+    fn segments(styled_text: &StyledText) -> Vec<(Style, &str)> {
+        styled_text
+            .iter()
+            .map(|(style, text)| (*style, text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn match_style_wins_on_the_matched_range() {
+        let highlighter = LayeredHighlighter::new(vec![
+            Box::new(ExampleHighlighter::new(vec!["git".into()])),
+            Box::new(
+                SimpleMatchHighlighter::new("tat".into())
+                    .with_match_style(Style::new().fg(Color::Yellow)),
+            ),
+        ]);
+
+        let styled_text = highlighter.highlight("git status", 0);
+        assert_eq!(
+            segments(&styled_text),
+            vec![
+                (Style::new().fg(Color::Green), "git"),
+                (Style::new().bold().fg(Color::White), " s"),
+                (Style::new().fg(Color::Yellow), "tat"),
+                (Style::new().bold().fg(Color::White), "us"),
+            ]
+        );
+        assert_eq!(styled_text.raw_string(), "git status");
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let highlighter = LayeredHighlighter::default()
+            .with_layer(Box::new(ExampleHighlighter::default()))
+            .with_layer(Box::new(SimpleMatchHighlighter::new("ls".into())))
+            .with_layer(Box::new(
+                SimpleMatchHighlighter::new("s -".into())
+                    .with_match_style(Style::new().fg(Color::Red)),
+            ));
+
+        assert_eq!(
+            segments(&highlighter.highlight("ls -l", 0)),
+            vec![
+                (Style::new().fg(Color::Green), "l"),
+                (Style::new().fg(Color::Red), "s -"),
+                (Style::new().fg(Color::White), "l"),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_layers_leave_the_line_unstyled() {
+        let highlighter = LayeredHighlighter::default();
+        assert_eq!(
+            segments(&highlighter.highlight("ls", 0)),
+            vec![(Style::new(), "ls")]
+        );
+    }
+}
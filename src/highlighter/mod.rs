@@ -1,9 +1,11 @@
 mod example;
+mod layered;
 mod simple_match;
 
 use crate::StyledText;
 
 pub use example::ExampleHighlighter;
+pub use layered::LayeredHighlighter;
 pub use simple_match::SimpleMatchHighlighter;
 /// The syntax highlighting trait. Implementers of this trait will take in the current string and then
 /// return a `StyledText` object, which represents the contents of the original line as styled strings
@@ -1,5 +1,6 @@
-use crate::{Hinter, History};
-use nu_ansi_term::{Color, Style};
+use super::DEFAULT_HINT_COLOR;
+use crate::{Hinter, History, StyledText};
+use nu_ansi_term::Style;
 
 /// A hinter that use the completions or the history to show a hint to the user
 ///
@@ -8,6 +9,7 @@ pub struct DefaultHinter {
     style: Style,
     current_hint: String,
     min_chars: usize,
+    prefer_frequent: bool,
 }
 
 impl Hinter for DefaultHinter {
@@ -19,17 +21,21 @@ impl Hinter for DefaultHinter {
         use_ansi_coloring: bool,
     ) -> String {
         self.current_hint = if line.chars().count() >= self.min_chars {
-            history
-                .iter_chronologic()
-                .rev()
-                .find(|entry| entry.starts_with(line))
-                .map_or_else(String::new, |entry| entry[line.len()..].to_string())
+            let entry = if self.prefer_frequent {
+                history.most_frequent(line, 1).into_iter().next()
+            } else {
+                history
+                    .iter_chronologic()
+                    .rev()
+                    .find(|entry| entry.starts_with(line))
+            };
+            entry.map_or_else(String::new, |entry| entry[line.len()..].to_string())
         } else {
             String::new()
         };
 
         if use_ansi_coloring && !self.current_hint.is_empty() {
-            self.style.paint(&self.current_hint).to_string()
+            self.style_hint(&self.current_hint).render_simple()
         } else {
             self.current_hint.clone()
         }
@@ -56,14 +62,21 @@ impl Hinter for DefaultHinter {
             .collect();
         result
     }
+
+    fn style_hint(&self, hint: &str) -> StyledText {
+        let mut styled_hint = StyledText::new();
+        styled_hint.push_str(self.style, hint);
+        styled_hint
+    }
 }
 
 impl Default for DefaultHinter {
     fn default() -> Self {
         DefaultHinter {
-            style: Style::new().fg(Color::LightGray),
+            style: Style::new().fg(DEFAULT_HINT_COLOR),
             current_hint: String::new(),
             min_chars: 1,
+            prefer_frequent: false,
         }
     }
 }
@@ -82,4 +95,33 @@ impl DefaultHinter {
         self.min_chars = min_chars;
         self
     }
+
+    /// A builder that hints the most frequently used matching entry of the history,
+    /// see [`History::most_frequent`], instead of the most recent one
+    #[must_use]
+    pub fn with_prefer_frequent(mut self, prefer_frequent: bool) -> Self {
+        self.prefer_frequent = prefer_frequent;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hint_can_prefer_the_most_frequent_entry() {
+        let mut history = FileBackedHistory::default();
+        for entry in ["git status", "git log", "git status", "git push"] {
+            history.append(entry);
+        }
+
+        let mut hinter = DefaultHinter::default();
+        assert_eq!(hinter.handle("git ", 4, &history, false), "push");
+
+        let mut hinter = DefaultHinter::default().with_prefer_frequent(true);
+        assert_eq!(hinter.handle("git ", 4, &history, false), "status");
+    }
 }
@@ -1,13 +1,19 @@
 mod default;
 pub use default::DefaultHinter;
 
-use crate::History;
+use crate::{History, StyledText};
+use nu_ansi_term::{Color, Style};
+
+pub(crate) const DEFAULT_HINT_COLOR: Color = Color::LightGray;
+
 /// A trait that's responsible for returning the hint for the current line and position
 /// Hints are often shown in-line as part of the buffer, showing the user text they can accept or ignore
 pub trait Hinter: Send {
     /// Handle the hinting duty by using the line, position, and current history
     ///
-    /// Returns the formatted output to show the user
+    /// Returns the formatted output to show the user, styled if `use_ansi_coloring`
+    /// is set. The engine asks for the unstyled hint and paints it with
+    /// [`Hinter::style_hint`]
     fn handle(
         &mut self,
         line: &str,
@@ -22,4 +28,15 @@ pub trait Hinter: Send {
     /// Return the first semantic token of the hint
     /// for incremental completion
     fn next_hint_token(&self) -> String;
+
+    /// Style the `hint` shown after the buffer, e.g. depending on what kind of
+    /// suggestion it is
+    ///
+    /// The engine paints the hint returned by [`Hinter::handle`] with it when ANSI
+    /// coloring is used. The default implementation paints the whole hint light gray
+    fn style_hint(&self, hint: &str) -> StyledText {
+        let mut styled_hint = StyledText::new();
+        styled_hint.push_str(Style::new().fg(DEFAULT_HINT_COLOR), hint);
+        styled_hint
+    }
 }
@@ -1,4 +1,6 @@
 use crate::core_editor::LineBuffer;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 
 /// Browsing modes for a [`History`]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -13,6 +15,30 @@ pub enum HistoryNavigationQuery {
     // Fuzzy Search
 }
 
+/// Case handling of the [`HistoryNavigationQuery::PrefixSearch`] and [`HistoryNavigationQuery::SubstringSearch`]
+/// as well as the words of the [`DefaultCompleter`](crate::DefaultCompleter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseSensitivity {
+    /// Entries have to match the case of the query
+    #[default]
+    Sensitive,
+    /// Case is ignored when comparing entries to the query
+    Insensitive,
+    /// Case is ignored unless the query contains an uppercase letter
+    SmartCase,
+}
+
+impl CaseSensitivity {
+    /// Whether the entries have to match the case of `query`
+    pub fn is_case_sensitive(self, query: &str) -> bool {
+        match self {
+            CaseSensitivity::Sensitive => true,
+            CaseSensitivity::Insensitive => false,
+            CaseSensitivity::SmartCase => query.chars().any(char::is_uppercase),
+        }
+    }
+}
+
 /// Interface of a history datastructure that supports stateful navigation via [`HistoryNavigationQuery`].
 pub trait History: Send {
     /// Append entry to the history, if capacity management is part of the implementation may perform that as well
@@ -21,6 +47,15 @@ pub trait History: Send {
     /// Chronologic interaction over all entries present in the history
     fn iter_chronologic(&self) -> Box<dyn DoubleEndedIterator<Item=String> + '_>;
 
+    /// Chronologic interaction over all entries together with the time they were added
+    ///
+    /// Histories that don't keep track of the time return `None` for every entry
+    fn iter_chronologic_with_timestamps(
+        &self,
+    ) -> Box<dyn DoubleEndedIterator<Item = (String, Option<DateTime<Utc>>)> + '_> {
+        Box::new(self.iter_chronologic().map(|entry| (entry, None)))
+    }
+
     /// This moves the cursor backwards respecting the navigation query that is set
     /// - Results in a no-op if the cursor is at the initial point
     fn back(&mut self);
@@ -40,15 +75,71 @@ pub trait History: Send {
     /// Poll the current [`HistoryNavigationQuery`] mode
     fn get_navigation(&self) -> HistoryNavigationQuery;
 
+    /// Set how the case of the entries is compared in a prefix or substring search
+    ///
+    /// Ignored by histories that only support the default [`CaseSensitivity`]
+    fn set_case_sensitivity(&mut self, _case_sensitivity: CaseSensitivity) {}
+
+    /// Poll the [`CaseSensitivity`] of the prefix and substring searches
+    fn case_sensitivity(&self) -> CaseSensitivity {
+        CaseSensitivity::default()
+    }
+
     /// Query the values in the history entries
     fn query_entries(&self, search: &str) -> Vec<String>;
 
     /// Max number of values that can be queried from the history
     fn max_values(&self) -> usize;
 
+    /// Number of times `command` was recorded in the history
+    fn frequency(&self, command: &str) -> usize {
+        self.iter_chronologic()
+            .filter(|entry| entry == command)
+            .count()
+    }
+
+    /// Up to `n` distinct entries starting with `prefix`, the most frequently used first.
+    /// Entries used equally often are ordered by their last use, the most recent first
+    fn most_frequent(&self, prefix: &str, n: usize) -> Vec<String> {
+        // Number of uses and position of the last use of every matching entry
+        let mut uses: HashMap<String, (usize, usize)> = HashMap::new();
+        for (index, entry) in self.iter_chronologic().enumerate() {
+            if entry.starts_with(prefix) {
+                let (count, last) = uses.entry(entry).or_default();
+                *count += 1;
+                *last = index;
+            }
+        }
+
+        let mut entries: Vec<_> = uses.into_iter().collect();
+        entries.sort_by(|(_, lhs), (_, rhs)| rhs.cmp(lhs));
+        entries
+            .into_iter()
+            .take(n)
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+
     /// Synchronize the state of the history with the backing filesystem or database if available
     fn sync(&mut self) -> std::io::Result<()>;
 
+    /// Remove every entry, from the backing file or database as well
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.clear_matching(&|_| true)
+    }
+
+    /// Remove the entries `predicate` returns `true` for, e.g. to purge sensitive commands,
+    /// from the backing file or database as well
+    ///
+    /// The default implementation removes nothing and returns an
+    /// [`std::io::ErrorKind::Unsupported`] error
+    fn clear_matching(&mut self, _predicate: &dyn Fn(&str) -> bool) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the history can't remove entries",
+        ))
+    }
+
     /// Reset the browsing cursor back outside the history, does not affect the [`HistoryNavigationQuery`]
     fn reset_cursor(&mut self);
 }
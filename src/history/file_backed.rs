@@ -1,4 +1,7 @@
-use super::{base::HistoryNavigationQuery, History};
+use super::{
+    base::{CaseSensitivity, HistoryNavigationQuery},
+    History,
+};
 use crate::core_editor::LineBuffer;
 use std::{
     collections::{vec_deque::Iter, VecDeque},
@@ -18,6 +21,8 @@ pub const NEWLINE_ESCAPE: &str = "<\\n>";
 /// Similar to bash's behavior without HISTTIMEFORMAT.
 /// (See <https://www.gnu.org/software/bash/manual/html_node/Bash-History-Facilities.html>)
 /// If the history is associated to a file all new changes within a given history capacity will be written to disk when History is dropped.
+/// Writing to the file trims its oldest lines to the capacity while holding a lock on it, so sessions sharing the file
+/// don't corrupt it.
 #[derive(Debug)]
 pub struct FileBackedHistory {
     capacity: usize,
@@ -26,6 +31,8 @@ pub struct FileBackedHistory {
     file: Option<PathBuf>,
     len_on_disk: usize, // Keep track what was previously written to disk
     query: HistoryNavigationQuery,
+    case_sensitivity: CaseSensitivity,
+    sync_on_append: bool,
 }
 
 impl Default for FileBackedHistory {
@@ -49,6 +56,8 @@ impl History for FileBackedHistory {
     /// Appends an entry if non-empty and not repetition of the previous entry.
     /// Resets the browsing cursor to the default state in front of the most recent entry.
     ///
+    /// With [`FileBackedHistory::with_sync_on_append()`] the entry is written to the file right away
+    /// and the entries of other sessions are merged in.
     fn append(&mut self, entry: &str) {
         // Don't append if the preceding value is identical or the string empty
         if self
@@ -64,6 +73,11 @@ impl History for FileBackedHistory {
                 self.len_on_disk = self.len_on_disk.saturating_sub(1);
             }
             self.entries.push_back(entry.to_string());
+
+            if self.sync_on_append {
+                // Like on drop, a failing write must not interrupt the editing
+                let _res = self.sync();
+            }
         }
         self.reset_cursor();
     }
@@ -79,11 +93,9 @@ impl History for FileBackedHistory {
                     self.cursor -= 1;
                 }
             }
-            HistoryNavigationQuery::PrefixSearch(prefix) => {
-                self.back_with_criteria(&|entry| entry.starts_with(&prefix));
-            }
-            HistoryNavigationQuery::SubstringSearch(substring) => {
-                self.back_with_criteria(&|entry| entry.contains(&substring));
+            _ => {
+                let criteria = self.search_criteria();
+                self.back_with_criteria(&criteria);
             }
         }
     }
@@ -95,11 +107,9 @@ impl History for FileBackedHistory {
                     self.cursor += 1;
                 }
             }
-            HistoryNavigationQuery::PrefixSearch(prefix) => {
-                self.forward_with_criteria(&|entry| entry.starts_with(&prefix));
-            }
-            HistoryNavigationQuery::SubstringSearch(substring) => {
-                self.forward_with_criteria(&|entry| entry.contains(&substring));
+            _ => {
+                let criteria = self.search_criteria();
+                self.forward_with_criteria(&criteria);
             }
         }
     }
@@ -117,6 +127,14 @@ impl History for FileBackedHistory {
         self.query.clone()
     }
 
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.case_sensitivity = case_sensitivity;
+    }
+
+    fn case_sensitivity(&self) -> CaseSensitivity {
+        self.case_sensitivity
+    }
+
     fn query_entries(&self, search: &str) -> Vec<String> {
         self.iter_chronologic()
             .rev()
@@ -128,8 +146,10 @@ impl History for FileBackedHistory {
         self.entries.len()
     }
 
-    /// Writes unwritten history contents to disk.
+    /// Writes unwritten history contents to disk and merges in the entries other sessions
+    /// sharing the file have written in the meantime.
     ///
+    /// Only the new entries are appended to the file while holding a lock on it.
     /// If file would exceed `capacity` truncates the oldest entries.
     fn sync(&mut self) -> std::io::Result<()> {
         if let Some(fname) = &self.file {
@@ -196,6 +216,62 @@ impl History for FileBackedHistory {
         Ok(())
     }
 
+    /// Removes the matching entries in memory and rewrites the file while holding a
+    /// lock on it, removing the matching entries other sessions have written as well
+    fn clear_matching(&mut self, predicate: &dyn Fn(&str) -> bool) -> std::io::Result<()> {
+        if let Some(fname) = &self.file {
+            let own_entries = self
+                .entries
+                .range(self.len_on_disk..)
+                .filter(|entry| !predicate(entry))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let mut f_lock = fd_lock::RwLock::new(
+                OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .read(true)
+                    .open(fname)?,
+            );
+            let mut writer_guard = f_lock.write()?;
+            let mut entries = {
+                let reader = BufReader::new(writer_guard.deref());
+                reader
+                    .lines()
+                    .map(|o| o.map(|i| decode_entry(&i)))
+                    .filter(|entry| !entry.as_ref().is_ok_and(|entry| predicate(entry)))
+                    .collect::<Result<VecDeque<_>, _>>()?
+            };
+            entries.extend(own_entries);
+            let excess = entries.len().saturating_sub(self.capacity);
+            entries.drain(..excess);
+
+            {
+                let mut writer = BufWriter::new(writer_guard.deref_mut());
+                writer.seek(SeekFrom::Start(0))?;
+                for line in &entries {
+                    writer.write_all(encode_entry(line).as_bytes())?;
+                    writer.write_all("\n".as_bytes())?;
+                }
+                writer.flush()?;
+            }
+            let file = writer_guard.deref_mut();
+            let file_len = file.stream_position()?;
+            file.set_len(file_len)?;
+
+            self.len_on_disk = entries.len();
+            self.entries = entries;
+        } else {
+            self.entries.retain(|entry| !predicate(entry));
+        }
+
+        self.reset_cursor();
+
+        Ok(())
+    }
+
     /// Reset the internal browsing cursor
     fn reset_cursor(&mut self) {
         self.cursor = self.entries.len();
@@ -219,6 +295,8 @@ impl FileBackedHistory {
             file: None,
             len_on_disk: 0,
             query: HistoryNavigationQuery::Normal(LineBuffer::default()),
+            case_sensitivity: CaseSensitivity::Sensitive,
+            sync_on_append: false,
         }
     }
 
@@ -240,6 +318,68 @@ impl FileBackedHistory {
         Ok(hist)
     }
 
+    /// A builder to write every appended entry to the associated file right away, instead of
+    /// on [`History::sync()`] or drop. Entries other sessions have written to the file are
+    /// merged in at the same time, so sessions sharing the file see each others' entries
+    #[must_use]
+    pub fn with_sync_on_append(mut self, sync_on_append: bool) -> Self {
+        self.sync_on_append = sync_on_append;
+        self
+    }
+
+    /// The number of entries remembered and kept in the associated file
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the number of entries remembered, forgetting the oldest entries beyond
+    /// the new `capacity`.
+    ///
+    /// An associated file is synced and trimmed to the new capacity right away
+    ///
+    /// # Panics
+    ///
+    /// If `capacity == usize::MAX`
+    pub fn set_capacity(&mut self, capacity: usize) -> std::io::Result<()> {
+        if capacity == usize::MAX {
+            panic!("History capacity too large to be addressed safely");
+        }
+        self.capacity = capacity;
+
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+        self.len_on_disk = self.len_on_disk.saturating_sub(excess);
+
+        self.sync()
+    }
+
+    /// Criteria of the entries matching the prefix or substring search
+    fn search_criteria(&self) -> impl Fn(&str) -> bool {
+        let (query, is_prefix) = match &self.query {
+            HistoryNavigationQuery::PrefixSearch(prefix) => (prefix.as_str(), true),
+            HistoryNavigationQuery::SubstringSearch(substring) => (substring.as_str(), false),
+            HistoryNavigationQuery::Normal(_) => ("", false),
+        };
+        let ignore_case = !self.case_sensitivity.is_case_sensitive(query);
+        let fold_case = move |text: &str| -> String {
+            if ignore_case {
+                text.to_lowercase()
+            } else {
+                text.to_string()
+            }
+        };
+        let query = fold_case(query);
+
+        move |entry| {
+            let entry = fold_case(entry);
+            if is_prefix {
+                entry.starts_with(&query)
+            } else {
+                entry.contains(&query)
+            }
+        }
+    }
+
     fn back_with_criteria(&mut self, criteria: &dyn Fn(&str) -> bool) {
         if !self.entries.is_empty() {
             let previous_match = self.entries.get(self.cursor);
@@ -284,9 +424,49 @@ impl Drop for FileBackedHistory {
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     use super::*;
 
+    fn history_with_repeated_commands() -> FileBackedHistory {
+        let mut hist = FileBackedHistory::default();
+        for entry in [
+            "git log",
+            "git status",
+            "ls",
+            "git status",
+            "git push",
+            "git log",
+            "ls",
+        ] {
+            hist.append(entry);
+        }
+        hist
+    }
+
+    #[test]
+    fn counts_the_uses_of_a_command() {
+        let hist = history_with_repeated_commands();
+
+        assert_eq!(hist.frequency("git status"), 2);
+        assert_eq!(hist.frequency("git push"), 1);
+        assert_eq!(hist.frequency("git"), 0);
+    }
+
+    #[test]
+    fn most_frequent_entries_come_first() {
+        let hist = history_with_repeated_commands();
+
+        // Ties are broken by the most recent use
+        assert_eq!(
+            hist.most_frequent("git", 3),
+            vec!["git log", "git status", "git push"]
+        );
+        assert_eq!(hist.most_frequent("git", 1), vec!["git log"]);
+        assert_eq!(hist.most_frequent("", 1), vec!["ls"]);
+        assert_eq!(hist.most_frequent("cd", 3), Vec::<String>::new());
+    }
+
     #[test]
     fn accessing_empty_history_returns_nothing() {
         let hist = FileBackedHistory::default();
@@ -471,6 +651,46 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), None);
     }
 
+    #[rstest]
+    #[case(CaseSensitivity::Sensitive, None)]
+    #[case(CaseSensitivity::Insensitive, Some("git status"))]
+    // The uppercase letter in the query makes the smart case search case sensitive
+    #[case(CaseSensitivity::SmartCase, None)]
+    fn search_respects_case_sensitivity(
+        #[case] case_sensitivity: CaseSensitivity,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut hist = FileBackedHistory::default();
+        hist.append("git status");
+        hist.append("cargo build");
+        hist.set_case_sensitivity(case_sensitivity);
+
+        hist.set_navigation(HistoryNavigationQuery::PrefixSearch("Git".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor().as_deref(), expected);
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("Git".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(CaseSensitivity::Sensitive, None)]
+    #[case(CaseSensitivity::Insensitive, Some("Git status"))]
+    #[case(CaseSensitivity::SmartCase, Some("Git status"))]
+    fn lowercase_query_matches_uppercase_entries(
+        #[case] case_sensitivity: CaseSensitivity,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut hist = FileBackedHistory::default();
+        hist.append("Git status");
+        hist.set_case_sensitivity(case_sensitivity);
+
+        hist.set_navigation(HistoryNavigationQuery::SubstringSearch("git".to_string()));
+        hist.back();
+        assert_eq!(hist.string_at_cursor().as_deref(), expected);
+    }
+
     #[test]
     fn writes_to_new_file() {
         use tempfile::tempdir;
@@ -584,6 +804,149 @@ mod tests {
         tmp.close().unwrap();
     }
 
+    #[test]
+    fn file_holds_at_most_capacity_lines() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut hist = FileBackedHistory::with_file(3, histfile.clone()).unwrap();
+        for i in 1..=5 {
+            hist.append(&format!("test {}", i));
+        }
+        hist.sync().unwrap();
+
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert_eq!(contents, "test 3\ntest 4\ntest 5\n");
+
+        hist.append("test 6");
+        hist.sync().unwrap();
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents, "test 4\ntest 5\ntest 6\n");
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn set_capacity_trims_history_and_file() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut hist = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        for i in 1..=5 {
+            hist.append(&format!("test {}", i));
+        }
+        hist.sync().unwrap();
+        // Not yet written to the file
+        hist.append("test 6");
+
+        hist.set_capacity(2).unwrap();
+        assert_eq!(hist.capacity(), 2);
+
+        let actual: Vec<_> = hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["test 5", "test 6"]);
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents, "test 5\ntest 6\n");
+
+        // Growing keeps the entries and allows new ones
+        hist.set_capacity(3).unwrap();
+        hist.append("test 7");
+        hist.sync().unwrap();
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents, "test 5\ntest 6\ntest 7\n");
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn clear_removes_the_entries_from_the_file() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut hist = FileBackedHistory::with_file(5, histfile.clone()).unwrap();
+        hist.append("test 1");
+        hist.sync().unwrap();
+        hist.append("test 2");
+
+        hist.clear().unwrap();
+        assert_eq!(hist.iter_chronologic().count(), 0);
+        assert_eq!(hist.string_at_cursor(), None);
+        assert_eq!(std::fs::read_to_string(&histfile).unwrap(), "");
+
+        // Syncing doesn't bring the entries back
+        hist.append("test 3");
+        drop(hist);
+        let reading_hist = FileBackedHistory::with_file(5, histfile).unwrap();
+        let actual: Vec<_> = reading_hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["test 3"]);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn clear_matching_purges_the_entries_of_every_session() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let mut other = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+        other.append("export TOKEN=other");
+        other.append("ls");
+        other.sync().unwrap();
+
+        let mut hist = FileBackedHistory::with_file(10, histfile.clone()).unwrap();
+        hist.append("login --password=secret");
+        hist.append("cargo build");
+
+        hist.clear_matching(&|entry| entry.contains("TOKEN") || entry.contains("secret"))
+            .unwrap();
+        let actual: Vec<_> = hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["ls", "cargo build"]);
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents, "ls\ncargo build\n");
+
+        drop(hist);
+        let reading_hist = FileBackedHistory::with_file(10, histfile).unwrap();
+        let actual: Vec<_> = reading_hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["ls", "cargo build"]);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn clear_matching_without_file() {
+        let mut hist = FileBackedHistory::new(4);
+        for entry in ["ls", "rm -rf build", "ls -l"] {
+            hist.append(entry);
+        }
+
+        hist.clear_matching(&|entry| entry.starts_with("ls"))
+            .unwrap();
+        let actual: Vec<_> = hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["rm -rf build"]);
+    }
+
+    #[test]
+    fn set_capacity_without_file() {
+        let mut hist = FileBackedHistory::new(4);
+        for i in 1..=4 {
+            hist.append(&format!("test {}", i));
+        }
+        hist.set_capacity(1).unwrap();
+
+        let actual: Vec<_> = hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["test 4"]);
+        hist.back();
+        assert_eq!(hist.string_at_cursor(), Some("test 4".into()));
+    }
+
     #[test]
     fn truncates_too_large_file() {
         use tempfile::tempdir;
@@ -667,6 +1030,53 @@ mod tests {
         tmp.close().unwrap();
     }
 
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn interleaved_sessions_merge_their_entries(#[case] sync_on_append: bool) {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        let session = || {
+            FileBackedHistory::with_file(10, histfile.clone())
+                .unwrap()
+                .with_sync_on_append(sync_on_append)
+        };
+        let mut hist_a = session();
+        let mut hist_b = session();
+
+        for (a, b) in [("A1", "B1"), ("A2", "B2")] {
+            hist_a.append(a);
+            if !sync_on_append {
+                hist_a.sync().unwrap();
+            }
+            hist_b.append(b);
+            if !sync_on_append {
+                hist_b.sync().unwrap();
+            }
+        }
+
+        // `hist_b` wrote last and has seen all entries
+        let actual: Vec<_> = hist_b.iter_chronologic().collect();
+        assert_eq!(actual, vec!["A1", "B1", "A2", "B2"]);
+        let actual: Vec<_> = hist_a.iter_chronologic().collect();
+        assert_eq!(actual, vec!["A1", "B1", "A2"]);
+
+        // Syncing pulls in the entries of the other session
+        hist_a.sync().unwrap();
+        let actual: Vec<_> = hist_a.iter_chronologic().collect();
+        assert_eq!(actual, vec!["A1", "B1", "A2", "B2"]);
+
+        drop(hist_a);
+        drop(hist_b);
+        let contents = std::fs::read_to_string(&histfile).unwrap();
+        assert_eq!(contents, "A1\nB1\nA2\nB2\n");
+
+        tmp.close().unwrap();
+    }
+
     #[test]
     fn concurrent_histories_are_threadsafe() {
         use tempfile::tempdir;
@@ -5,5 +5,5 @@ mod sqlite_backed;
 #[cfg(feature="sqlite")]
 pub use sqlite_backed::SqliteBackedHistory;
 
-pub use base::{History, HistoryNavigationQuery};
+pub use base::{CaseSensitivity, History, HistoryNavigationQuery};
 pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
\ No newline at end of file
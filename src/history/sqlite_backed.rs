@@ -1,8 +1,12 @@
 use rusqlite::{named_params, params, Connection, MappedRows, OptionalExtension, Row};
 use serde::{de::DeserializeOwned, Serialize};
 
-use super::{base::HistoryNavigationQuery, History};
+use super::{
+    base::{CaseSensitivity, HistoryNavigationQuery},
+    History,
+};
 use crate::core_editor::LineBuffer;
+use chrono::{DateTime, TimeZone, Utc};
 use std::{
     path::PathBuf,
     sync::{Arc, Mutex},
@@ -21,6 +25,7 @@ pub struct SqliteBackedHistory<ContextType> {
     last_run_command_id: Option<i64>,
     last_run_command_context: Option<ContextType>,
     cursor: SqliteHistoryCursor,
+    case_sensitivity: CaseSensitivity,
 }
 
 // for arrow-navigation
@@ -44,6 +49,18 @@ impl<H: History> History for Arc<Mutex<H>> {
         Box::new(inner.iter_chronologic().collect::<Vec<_>>().into_iter())
     }
 
+    fn iter_chronologic_with_timestamps(
+        &self,
+    ) -> Box<dyn DoubleEndedIterator<Item = (String, Option<DateTime<Utc>>)> + '_> {
+        let inner = self.lock().expect("lock poisoned");
+        Box::new(
+            inner
+                .iter_chronologic_with_timestamps()
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
+    }
+
     fn back(&mut self) {
         self.lock().expect("lock poisoned").back()
     }
@@ -66,6 +83,16 @@ impl<H: History> History for Arc<Mutex<H>> {
         self.lock().expect("lock poisoned").get_navigation()
     }
 
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.lock()
+            .expect("lock poisoned")
+            .set_case_sensitivity(case_sensitivity)
+    }
+
+    fn case_sensitivity(&self) -> CaseSensitivity {
+        self.lock().expect("lock poisoned").case_sensitivity()
+    }
+
     fn query_entries(&self, search: &str) -> Vec<String> {
         self.lock().expect("lock poisoned").query_entries(search)
     }
@@ -74,10 +101,28 @@ impl<H: History> History for Arc<Mutex<H>> {
         self.lock().expect("lock poisoned").max_values()
     }
 
+    fn frequency(&self, command: &str) -> usize {
+        self.lock().expect("lock poisoned").frequency(command)
+    }
+
+    fn most_frequent(&self, prefix: &str, n: usize) -> Vec<String> {
+        self.lock().expect("lock poisoned").most_frequent(prefix, n)
+    }
+
     fn sync(&mut self) -> std::io::Result<()> {
         self.lock().expect("lock poisoned").sync()
     }
 
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.lock().expect("lock poisoned").clear()
+    }
+
+    fn clear_matching(&mut self, predicate: &dyn Fn(&str) -> bool) -> std::io::Result<()> {
+        self.lock()
+            .expect("lock poisoned")
+            .clear_matching(predicate)
+    }
+
     fn reset_cursor(&mut self) {
         self.lock().expect("lock poisoned").reset_cursor()
     }
@@ -92,12 +137,13 @@ impl<ContextType: HistoryEntryContext> History for SqliteBackedHistory<ContextTy
         let ret: i64 = self
             .db
             .prepare(
-                "insert into history (command, context) values (:command, :context) returning id",
+                "insert into history (command, context, timestamp) values (:command, :context, :timestamp) returning id",
             )
             .unwrap()
             .query_row(
                 named_params! {
                     ":command": entry,
+                    ":timestamp": Utc::now().timestamp_millis(),
                     ":context": serde_json::to_string(&ctx).unwrap()
                 },
                 |row| row.get(0),
@@ -136,6 +182,26 @@ impl<ContextType: HistoryEntryContext> History for SqliteBackedHistory<ContextTy
         return Box::new(fwd.into_iter());
     }
 
+    fn iter_chronologic_with_timestamps(
+        &self,
+    ) -> Box<dyn DoubleEndedIterator<Item = (String, Option<DateTime<Utc>>)> + '_> {
+        let fwd = self
+            .db
+            .prepare("select command, timestamp from history order by id asc")
+            .unwrap()
+            .query_map(params![], |row| {
+                let timestamp: Option<i64> = row.get(1)?;
+                Ok((
+                    row.get(0)?,
+                    timestamp.and_then(|millis| Utc.timestamp_millis_opt(millis).single()),
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        Box::new(fwd.into_iter())
+    }
+
     fn back(&mut self) {
         self.navigate_in_direction(true)
         // self.cursor.id
@@ -158,6 +224,14 @@ impl<ContextType: HistoryEntryContext> History for SqliteBackedHistory<ContextTy
         self.cursor.query.clone()
     }
 
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.case_sensitivity = case_sensitivity;
+    }
+
+    fn case_sensitivity(&self) -> CaseSensitivity {
+        self.case_sensitivity
+    }
+
     fn query_entries(&self, search: &str) -> Vec<String> {
         self.iter_chronologic()
             .rev()
@@ -169,6 +243,30 @@ impl<ContextType: HistoryEntryContext> History for SqliteBackedHistory<ContextTy
         self.last_run_command_id.unwrap_or(0) as usize
     }
 
+    fn frequency(&self, command: &str) -> usize {
+        self.db
+            .prepare("select count(*) from history where command = :command")
+            .unwrap()
+            .query_row(named_params! { ":command": command }, |row| row.get(0))
+            .unwrap()
+    }
+
+    fn most_frequent(&self, prefix: &str, n: usize) -> Vec<String> {
+        self.db
+            .prepare(
+                "select command from history where substr(command, 1, length(:prefix)) = :prefix \
+                 group by command order by count(*) desc, max(id) desc limit :limit",
+            )
+            .unwrap()
+            .query_map(
+                named_params! { ":prefix": prefix, ":limit": n as i64 },
+                |row| row.get(0),
+            )
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap()
+    }
+
     /// Writes unwritten history contents to disk.
     ///
     /// If file would exceed `capacity` truncates the oldest entries.
@@ -176,6 +274,42 @@ impl<ContextType: HistoryEntryContext> History for SqliteBackedHistory<ContextTy
         Ok(())
     }
 
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.db
+            .execute("delete from history", params![])
+            .map_err(map_sqlite_err)?;
+        self.forget_deleted_command();
+        Ok(())
+    }
+
+    /// Deletes the matching entries in a single transaction
+    fn clear_matching(&mut self, predicate: &dyn Fn(&str) -> bool) -> std::io::Result<()> {
+        let transaction = self.db.transaction().map_err(map_sqlite_err)?;
+        let ids = transaction
+            .prepare("select id, command from history")
+            .map_err(map_sqlite_err)?
+            .query_map(params![], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))
+            .map_err(map_sqlite_err)?
+            .filter_map(|row| match row {
+                Ok((id, command)) if predicate(&command) => Some(Ok(id)),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<rusqlite::Result<Vec<i64>>>()
+            .map_err(map_sqlite_err)?;
+        for id in ids {
+            transaction
+                .execute(
+                    "delete from history where id = :id",
+                    named_params! { ":id": id },
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        transaction.commit().map_err(map_sqlite_err)?;
+        self.forget_deleted_command();
+        Ok(())
+    }
+
     /// Reset the internal browsing cursor
     fn reset_cursor(&mut self) {
         // if no command run yet, fetch last id from db
@@ -230,12 +364,28 @@ impl<ContextType: HistoryEntryContext> SqliteBackedHistory<ContextType> {
         create table if not exists history (
             id integer primary key autoincrement,
             command text not null,
-            context text not null
+            context text not null,
+            timestamp integer
         ) strict;
         ",
             params![],
         )
         .map_err(map_sqlite_err)?;
+        // databases created before the timestamp column existed get it added, their entries have no timestamp
+        let has_timestamp: bool = db
+            .query_row(
+                "select count(*) > 0 from pragma_table_info('history') where name = 'timestamp'",
+                params![],
+                |row| row.get(0),
+            )
+            .map_err(map_sqlite_err)?;
+        if !has_timestamp {
+            db.execute(
+                "alter table history add column timestamp integer",
+                params![],
+            )
+            .map_err(map_sqlite_err)?;
+        }
         let mut hist = SqliteBackedHistory {
             db,
             last_run_command_id: None,
@@ -245,6 +395,8 @@ impl<ContextType: HistoryEntryContext> SqliteBackedHistory<ContextType> {
                 command: None,
                 query: HistoryNavigationQuery::Normal(LineBuffer::default()),
             },
+            // `like` ignores the case of ascii letters
+            case_sensitivity: CaseSensitivity::Insensitive,
         };
         hist.reset_cursor();
         Ok(hist)
@@ -277,25 +429,61 @@ impl<ContextType: HistoryEntryContext> SqliteBackedHistory<ContextType> {
         }
     }
 
+    /// Forgets the last run command if it was deleted, so its context isn't updated anymore
+    fn forget_deleted_command(&mut self) {
+        if let Some(id) = self.last_run_command_id {
+            let exists: bool = self
+                .db
+                .query_row(
+                    "select count(*) > 0 from history where id = :id",
+                    named_params! { ":id": id },
+                    |row| row.get(0),
+                )
+                .unwrap_or(false);
+            if !exists {
+                self.last_run_command_id = None;
+                self.last_run_command_context = None;
+            }
+        }
+        self.reset_cursor();
+    }
+
     fn navigate_in_direction(&mut self, backward: bool) {
-        let like_str = match &self.cursor.query {
-            HistoryNavigationQuery::Normal(_) => format!("%"),
-            HistoryNavigationQuery::PrefixSearch(prefix) => format!("{prefix}%"),
-            HistoryNavigationQuery::SubstringSearch(cont) => format!("%{cont}%"),
+        let (condition, search) = match &self.cursor.query {
+            HistoryNavigationQuery::Normal(_) => ("command like :search", format!("%")),
+            HistoryNavigationQuery::PrefixSearch(prefix)
+                if self.case_sensitivity.is_case_sensitive(prefix) =>
+            {
+                (
+                    "substr(command, 1, length(:search)) = :search",
+                    prefix.clone(),
+                )
+            }
+            HistoryNavigationQuery::PrefixSearch(prefix) => {
+                ("command like :search", format!("{prefix}%"))
+            }
+            HistoryNavigationQuery::SubstringSearch(cont)
+                if self.case_sensitivity.is_case_sensitive(cont) =>
+            {
+                ("instr(command, :search) > 0", cont.clone())
+            }
+            HistoryNavigationQuery::SubstringSearch(cont) => {
+                ("command like :search", format!("%{cont}%"))
+            }
         };
         let query = if backward {
-            "select id, command from history where id < :id and command like :like and command != :prev_result order by id desc limit 1"
+            format!("select id, command from history where id < :id and {condition} and command != :prev_result order by id desc limit 1")
         } else {
-            "select id, command from history where id > :id and command like :like and command != :prev_result order by id asc limit 1"
+            format!("select id, command from history where id > :id and {condition} and command != :prev_result order by id asc limit 1")
         };
         let next_id: Option<(i64, String)> = self
             .db
-            .prepare(query)
+            .prepare(&query)
             .unwrap()
             .query_row(
                 named_params! {
                     ":id": self.cursor.id,
-                    ":like": like_str,
+                    ":search": search,
                     ":prev_result": self.cursor.command.clone().unwrap_or(String::new())
                 },
                 |e| Ok((e.get(0)?, e.get(1)?)),
@@ -375,6 +563,43 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), None);
     }
 
+    #[test]
+    fn appended_entries_carry_a_timestamp() {
+        let before = Utc::now() - chrono::Duration::seconds(1);
+        let mut hist = in_memory_for_test();
+        hist.append("command1");
+        hist.append("command2");
+
+        let entries: Vec<_> = hist.iter_chronologic_with_timestamps().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, "command1");
+        assert!(entries
+            .iter()
+            .all(|(_, timestamp)| matches!(timestamp, Some(timestamp) if *timestamp >= before)));
+    }
+
+    #[test]
+    fn entries_of_databases_without_timestamps_have_none() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute(
+            "create table history (id integer primary key autoincrement, command text not null, context text not null) strict",
+            params![],
+        )
+        .unwrap();
+        db.execute(
+            "insert into history (command, context) values ('old', 'null')",
+            params![],
+        )
+        .unwrap();
+
+        let mut hist = SqliteBackedHistory::<()>::from_connection(db).unwrap();
+        hist.append("new");
+
+        let entries: Vec<_> = hist.iter_chronologic_with_timestamps().collect();
+        assert_eq!(entries[0], ("old".to_string(), None));
+        assert!(entries[1].1.is_some());
+    }
+
     /*#[test]
     fn appends_only_unique() {
         let mut hist = in_memory_for_test();
@@ -391,6 +616,45 @@ mod tests {
         assert_eq!(hist.entries.len(), 0);
     }*/
 
+    fn history_with_repeated_commands() -> SqliteBackedHistory<()> {
+        let mut hist = in_memory_for_test();
+        for entry in [
+            "git log",
+            "git status",
+            "ls",
+            "git status",
+            "git push",
+            "git log",
+            "ls",
+        ] {
+            hist.append(entry);
+        }
+        hist
+    }
+
+    #[test]
+    fn counts_the_uses_of_a_command() {
+        let hist = history_with_repeated_commands();
+
+        assert_eq!(hist.frequency("git status"), 2);
+        assert_eq!(hist.frequency("git push"), 1);
+        assert_eq!(hist.frequency("git"), 0);
+    }
+
+    #[test]
+    fn most_frequent_entries_come_first() {
+        let hist = history_with_repeated_commands();
+
+        // Ties are broken by the most recent use
+        assert_eq!(
+            hist.most_frequent("git", 3),
+            vec!["git log", "git status", "git push"]
+        );
+        assert_eq!(hist.most_frequent("git", 1), vec!["git log"]);
+        assert_eq!(hist.most_frequent("", 1), vec!["ls"]);
+        assert_eq!(hist.most_frequent("cd", 3), Vec::<String>::new());
+    }
+
     #[test]
     fn prefix_search_works() {
         let mut hist = in_memory_for_test();
@@ -503,6 +767,28 @@ mod tests {
         assert_eq!(hist.string_at_cursor(), Some("substring".to_string()));
     }
 
+    #[test]
+    fn search_respects_case_sensitivity() {
+        let mut hist = in_memory_for_test();
+        hist.append("git status");
+
+        for (case_sensitivity, expected) in [
+            (CaseSensitivity::Sensitive, None),
+            (CaseSensitivity::Insensitive, Some("git status".to_string())),
+            (CaseSensitivity::SmartCase, None),
+        ] {
+            hist.set_case_sensitivity(case_sensitivity);
+
+            hist.set_navigation(HistoryNavigationQuery::PrefixSearch("Git".to_string()));
+            hist.back();
+            assert_eq!(hist.string_at_cursor(), expected);
+
+            hist.set_navigation(HistoryNavigationQuery::SubstringSearch("Git".to_string()));
+            hist.back();
+            assert_eq!(hist.string_at_cursor(), expected);
+        }
+    }
+
     #[test]
     fn substring_search_with_empty_value_returns_none() {
         let mut hist = in_memory_for_test();
@@ -539,6 +825,62 @@ mod tests {
         tmp.close().unwrap();
     }
 
+    #[test]
+    fn clear_deletes_every_entry() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = with_file_for_test(5, histfile.clone()).unwrap();
+            hist.append("test");
+            hist.append("more test");
+            hist.clear().unwrap();
+
+            assert_eq!(hist.iter_chronologic().count(), 0);
+            hist.back();
+            assert_eq!(hist.string_at_cursor(), None);
+            assert!(hist.update_last_command_context(|ctx| ctx).is_err());
+        }
+
+        let reading_hist = with_file_for_test(5, histfile).unwrap();
+        assert_eq!(reading_hist.iter_chronologic().count(), 0);
+
+        tmp.close().unwrap();
+    }
+
+    #[test]
+    fn clear_matching_deletes_the_matching_entries() {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = with_file_for_test(5, histfile.clone()).unwrap();
+            for entry in [
+                "ls",
+                "login --password=secret",
+                "cargo build",
+                "echo secret",
+            ] {
+                hist.append(entry);
+            }
+            hist.clear_matching(&|entry| entry.contains("secret"))
+                .unwrap();
+
+            hist.back();
+            assert_eq!(hist.string_at_cursor(), Some("cargo build".to_string()));
+        }
+
+        let reading_hist = with_file_for_test(5, histfile).unwrap();
+        let actual: Vec<_> = reading_hist.iter_chronologic().collect();
+        assert_eq!(actual, vec!["ls", "cargo build"]);
+
+        tmp.close().unwrap();
+    }
+
     #[test]
     fn persists_newlines_in_entries() {
         use tempfile::tempdir;
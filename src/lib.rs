@@ -182,23 +182,28 @@ mod core_editor;
 pub use core_editor::LineBuffer;
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, Signal, UndoBehavior};
+pub use enums::{
+    AbbreviationPosition, BellStyle, EditCommand, EmptyLineBehavior, HintWrap, IndentUnit,
+    MenuPlacement, ReedlineEvent, Restriction, Signal, TabBehavior, UndoBehavior,
+};
 
 mod painting;
 pub use painting::{Painter, StyledText};
 
 mod engine;
-pub use engine::Reedline;
+pub use engine::{BufferChangeListener, MenuObserver, RedrawHandle, Reedline, Snippet};
 
 mod history;
-pub use history::{FileBackedHistory, History, HistoryNavigationQuery, HISTORY_SIZE};
+pub use history::{
+    CaseSensitivity, FileBackedHistory, History, HistoryNavigationQuery, HISTORY_SIZE,
+};
 #[cfg(feature="sqlite")]
 pub use history::{SqliteBackedHistory};
 
 mod prompt;
 pub use prompt::{
-    DefaultPrompt, Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus,
-    PromptViMode,
+    AsyncPrompt, CommandStatus, DefaultPrompt, DefaultPromptSegment, Prompt, PromptCompletion,
+    PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
 };
 
 mod edit_mode;
@@ -208,19 +213,26 @@ pub use edit_mode::{
 };
 
 mod highlighter;
-pub use highlighter::{ExampleHighlighter, Highlighter, SimpleMatchHighlighter};
+pub use highlighter::{
+    ExampleHighlighter, Highlighter, LayeredHighlighter, SimpleMatchHighlighter,
+};
 
 mod completion;
-pub use completion::{Completer, DefaultCompleter, Span, Suggestion};
+pub use completion::{
+    unquote, CommandCompletions, Completer, ContextualCompleter, DefaultCompleter, Span, Suggestion,
+};
 
 mod hinter;
 pub use hinter::{DefaultHinter, Hinter};
 
 mod validator;
-pub use validator::{DefaultValidator, ValidationResult, Validator};
+pub use validator::{DefaultValidator, Diagnostic, ValidationResult, Validator};
 
 mod menu;
-pub use menu::{menu_functions, CompletionMenu, HistoryMenu, Menu, MenuEvent, MenuTextStyle};
+pub use menu::{
+    menu_functions, CompletionMenu, HistoryMenu, MaxHeight, Menu, MenuEvent, MenuNotification,
+    MenuTextStyle,
+};
 
 mod utils;
 pub use utils::{
@@ -1,6 +1,13 @@
-use super::{menu_functions::find_common_string, Menu, MenuEvent, MenuTextStyle};
-use crate::{painting::Painter, Completer, History, LineBuffer, Suggestion};
+use super::{
+    menu_functions::{find_common_string, replace_in_buffer, typed_case_value},
+    Menu, MenuEvent, MenuTextStyle,
+};
+use crate::{
+    painting::{line_width, str_width, Painter},
+    Completer, History, LineBuffer, Suggestion,
+};
 use nu_ansi_term::{ansi::RESET, Style};
+use std::borrow::Cow;
 
 /// Default values used as reference for the menu. These values are set during
 /// the initial declaration of the menu and are always kept as reference for the
@@ -49,6 +56,8 @@ pub struct CompletionMenu {
     working_details: ColumnDetails,
     /// Menu cached values
     values: Vec<Suggestion>,
+    /// Message of the completer shown instead of the values when there are none
+    empty_message: Option<String>,
     /// column position of the cursor. Starts from 0
     col_pos: u16,
     /// row position in the menu. Starts from 0
@@ -70,6 +79,7 @@ impl Default for CompletionMenu {
             min_rows: 3,
             working_details: ColumnDetails::default(),
             values: Vec::new(),
+            empty_message: None,
             col_pos: 0,
             row_pos: 0,
             marker: "| ".to_string(),
@@ -101,6 +111,13 @@ impl CompletionMenu {
         self
     }
 
+    /// Menu builder with a new theme, replacing all the text styles
+    #[must_use]
+    pub fn with_theme(mut self, theme: MenuTextStyle) -> Self {
+        self.color = theme;
+        self
+    }
+
     /// Menu builder with new columns value
     #[must_use]
     pub fn with_columns(mut self, columns: u16) -> Self {
@@ -208,6 +225,35 @@ impl CompletionMenu {
         }
     }
 
+    /// Move menu cursor a page of `rows` down, stopping at the last row of the column
+    fn move_page_down(&mut self, rows: u16) {
+        let cols = self.get_cols().max(1);
+        let last_row = (self.values.len() as u16)
+            .saturating_sub(self.col_pos + 1)
+            .checked_div(cols)
+            .unwrap_or_default();
+        self.row_pos = (self.row_pos + rows.max(1)).min(last_row);
+    }
+
+    /// Move menu cursor a page of `rows` up, stopping at the first row
+    fn move_page_up(&mut self, rows: u16) {
+        self.row_pos = self.row_pos.saturating_sub(rows.max(1));
+    }
+
+    /// Rows of values painted below the border, given the rows left under the
+    /// start of the menu and the height the menu is limited to
+    fn painted_rows(&self, painter: &Painter) -> u16 {
+        let available_lines = painter
+            .screen_height()
+            .saturating_sub(
+                painter
+                    .menu_start_row()
+                    .unwrap_or_else(|| painter.screen_height()),
+            )
+            .min(painter.menu_max_rows());
+        available_lines.saturating_sub(self.color.border_rows())
+    }
+
     /// Move menu cursor left
     fn move_left(&mut self) {
         self.col_pos = if let Some(row) = self.col_pos.checked_sub(1) {
@@ -235,11 +281,6 @@ impl CompletionMenu {
         index as usize
     }
 
-    /// Get selected value from the menu
-    fn get_value(&self) -> Option<Suggestion> {
-        self.get_values().get(self.index()).cloned()
-    }
-
     /// Calculates how many rows the Menu will use
     fn get_rows(&self) -> u16 {
         let values = self.get_values().len() as u16;
@@ -262,6 +303,40 @@ impl CompletionMenu {
         self.working_details.col_width
     }
 
+    /// Columns reserved for the scrollbar
+    fn scrollbar_width(&self) -> u16 {
+        u16::from(self.color.scrollbar_thumb_style.is_some())
+    }
+
+    /// Width of the columns and the scrollbar
+    fn menu_width(&self) -> usize {
+        self.get_cols() as usize * self.get_width() + self.scrollbar_width() as usize
+    }
+
+    /// Number of rows that are scrolled out of view when only `available_lines`
+    /// can be painted
+    fn skip_rows(&self, available_lines: u16) -> u16 {
+        if self.row_pos >= available_lines {
+            self.row_pos.saturating_sub(available_lines) + 1
+        } else {
+            0
+        }
+    }
+
+    /// Selects the value painted at the given row and column of the menu
+    fn select_position(&mut self, row: u16, column: u16, available_lines: u16) {
+        let row = self.skip_rows(available_lines) + row;
+        let col = column as usize / self.get_width().max(1);
+
+        if col < self.get_cols() as usize {
+            let index = row as usize * self.get_cols() as usize + col;
+            if index < self.values.len() {
+                self.row_pos = row;
+                self.col_pos = col as u16;
+            }
+        }
+    }
+
     /// Reset menu position
     fn reset_position(&mut self) {
         self.col_pos = 0;
@@ -269,7 +344,7 @@ impl CompletionMenu {
     }
 
     fn no_records_msg(&self, use_ansi_coloring: bool) -> String {
-        let msg = "NO RECORDS FOUND";
+        let msg = self.empty_message.as_deref().unwrap_or("NO RECORDS FOUND");
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
@@ -287,69 +362,64 @@ impl CompletionMenu {
         self.working_details.columns.max(1)
     }
 
-    /// End of line for menu
-    fn end_of_line(&self, column: u16) -> &str {
-        if column == self.get_cols().saturating_sub(1) {
-            "\r\n"
-        } else {
-            ""
-        }
-    }
-
     /// Creates default string that represents one suggestion from the menu
     fn create_string(
         &self,
         suggestion: &Suggestion,
         index: usize,
-        column: u16,
         empty_space: usize,
         use_ansi_coloring: bool,
     ) -> String {
+        // Padding that aligns the descriptions based on the displayed width of the values
+        let description_padding = (self.longest_suggestion + self.default_details.col_padding)
+            .saturating_sub(str_width(&suggestion.value));
+
         if use_ansi_coloring {
+            // The selection highlight takes precedence over the style of the suggestion
+            let text_style = suggestion.style.unwrap_or(self.color.text_style);
+
             if index == self.index() {
                 if let Some(description) = &suggestion.description {
                     format!(
-                        "{}{:max$}{}{}{}",
+                        "{}{}{:padding$}{}{}",
                         self.color.selected_text_style.prefix(),
                         &suggestion.value,
+                        "",
                         description,
                         RESET,
-                        self.end_of_line(column),
-                        max = self.longest_suggestion + self.default_details.col_padding,
+                        padding = description_padding,
                     )
                 } else {
                     format!(
-                        "{}{}{}{:>empty$}{}",
+                        "{}{}{}{:>empty$}",
                         self.color.selected_text_style.prefix(),
                         &suggestion.value,
                         RESET,
                         "",
-                        self.end_of_line(column),
                         empty = empty_space,
                     )
                 }
             } else if let Some(description) = &suggestion.description {
                 format!(
-                    "{}{:max$}{}{}{}{}{}",
-                    self.color.text_style.prefix(),
+                    "{}{}{}{:padding$}{}{}{}",
+                    text_style.prefix(),
                     &suggestion.value,
                     RESET,
+                    "",
                     self.color.description_style.prefix(),
                     description,
                     RESET,
-                    self.end_of_line(column),
-                    max = self.longest_suggestion + self.default_details.col_padding,
+                    padding = description_padding,
                 )
             } else {
                 format!(
-                    "{}{}{}{}{:>empty$}{}{}",
-                    self.color.text_style.prefix(),
+                    "{}{}{}{}{:>empty$}{}",
+                    text_style.prefix(),
                     &suggestion.value,
                     RESET,
                     self.color.description_style.prefix(),
                     "",
                     RESET,
-                    self.end_of_line(column),
                     empty = empty_space,
                 )
             }
@@ -359,24 +429,19 @@ impl CompletionMenu {
 
             let line = if let Some(description) = &suggestion.description {
                 format!(
-                    "{}{:max$}{}{}",
+                    "{}{}{:padding$}{}",
                     marker,
                     &suggestion.value,
+                    "",
                     description,
-                    self.end_of_line(column),
-                    max = self.longest_suggestion
-                        + self
-                            .default_details
-                            .col_padding
-                            .saturating_sub(marker.len()),
+                    padding = description_padding.saturating_sub(marker.len()),
                 )
             } else {
                 format!(
-                    "{}{}{:>empty$}{}",
+                    "{}{}{:>empty$}",
                     marker,
                     &suggestion.value,
                     "",
-                    self.end_of_line(column),
                     empty = empty_space.saturating_sub(marker.len()),
                 )
             };
@@ -390,6 +455,23 @@ impl CompletionMenu {
     }
 }
 
+/// Text inserted for `value` of the suggestion, keeping the typed casing if the
+/// suggestion asks for it
+fn replacement_value<'a>(
+    line_buffer: &LineBuffer,
+    suggestion: &Suggestion,
+    value: &'a str,
+) -> Cow<'a, str> {
+    let typed = line_buffer
+        .get_buffer()
+        .get(suggestion.span.start..suggestion.span.end);
+
+    match typed {
+        Some(typed) if suggestion.match_typed_case => typed_case_value(typed, value),
+        _ => Cow::Borrowed(value),
+    }
+}
+
 impl Menu for CompletionMenu {
     /// Menu name
     fn name(&self) -> &str {
@@ -427,22 +509,13 @@ impl Menu for CompletionMenu {
         }
 
         let values = self.get_values();
-        if let (Some(Suggestion { value, span, .. }), Some(index)) = find_common_string(values) {
-            let index = index.min(value.len());
-            let matching = &value[0..index];
+        if let (Some(suggestion), Some(index)) = find_common_string(values) {
+            let index = index.min(suggestion.value.len());
+            let span = suggestion.span;
+            let matching = replacement_value(line_buffer, suggestion, &suggestion.value[0..index]);
 
             if !matching.is_empty() {
-                line_buffer.replace(span.start..span.end, matching);
-
-                let offset = if matching.len() < (span.end - span.start) {
-                    line_buffer
-                        .insertion_point()
-                        .saturating_sub((span.end - span.start) - matching.len())
-                } else {
-                    line_buffer.insertion_point() + matching.len() - (span.end - span.start)
-                };
-
-                line_buffer.set_insertion_point(offset);
+                replace_in_buffer(line_buffer, span, &matching);
 
                 // The values need to be updated because the spans need to be
                 // recalculated for accurate replacement in the string
@@ -459,8 +532,10 @@ impl Menu for CompletionMenu {
 
     /// Selects what type of event happened with the menu
     fn menu_event(&mut self, event: MenuEvent) {
-        if let MenuEvent::Activate(_) = event {
-            self.active = true;
+        match &event {
+            MenuEvent::Activate(_) => self.active = true,
+            MenuEvent::Deactivate => self.active = false,
+            _ => {}
         }
 
         self.event = Some(event);
@@ -480,6 +555,11 @@ impl Menu for CompletionMenu {
         // position is maintain in the line buffer.
         let trimmed_buffer = line_buffer.get_buffer().replace('\n', " ");
         self.values = completer.complete(trimmed_buffer.as_str(), line_buffer.insertion_point());
+        self.empty_message = if self.values.is_empty() {
+            completer.empty_message(trimmed_buffer.as_str(), line_buffer.insertion_point())
+        } else {
+            None
+        };
         self.reset_position();
     }
 
@@ -493,6 +573,11 @@ impl Menu for CompletionMenu {
         painter: &Painter,
     ) {
         if let Some(event) = self.event.take() {
+            // The scrollbar takes up the last column of the screen
+            let screen_width = painter
+                .screen_width()
+                .saturating_sub(self.scrollbar_width());
+
             // The working value for the menu are updated first before executing any of the
             // menu events
             //
@@ -505,18 +590,19 @@ impl Menu for CompletionMenu {
 
             if exist_description {
                 self.working_details.columns = 1;
-                self.working_details.col_width = painter.screen_width() as usize;
+                self.working_details.col_width = screen_width as usize;
 
                 self.longest_suggestion = self.get_values().iter().fold(0, |prev, suggestion| {
-                    if prev >= suggestion.value.len() {
+                    let width = str_width(&suggestion.value);
+                    if prev >= width {
                         prev
                     } else {
-                        suggestion.value.len()
+                        width
                     }
                 });
             } else {
                 let max_width = self.get_values().iter().fold(0, |acc, suggestion| {
-                    let str_len = suggestion.value.len() + self.default_details.col_padding;
+                    let str_len = str_width(&suggestion.value) + self.default_details.col_padding;
                     if str_len > acc {
                         str_len
                     } else {
@@ -529,7 +615,7 @@ impl Menu for CompletionMenu {
                 let default_width = if let Some(col_width) = self.default_details.col_width {
                     col_width
                 } else {
-                    let col_width = painter.screen_width() / self.default_details.columns;
+                    let col_width = screen_width / self.default_details.columns;
                     col_width as usize
                 };
 
@@ -543,7 +629,7 @@ impl Menu for CompletionMenu {
 
                 // The working columns is adjusted based on possible number of columns
                 // that could be fitted in the screen with the calculated column width
-                let possible_cols = painter.screen_width() / self.working_details.col_width as u16;
+                let possible_cols = screen_width / self.working_details.col_width as u16;
                 if possible_cols > self.default_details.columns {
                     self.working_details.columns = self.default_details.columns.max(1);
                 } else {
@@ -574,8 +660,13 @@ impl Menu for CompletionMenu {
                 MenuEvent::MoveDown => self.move_down(),
                 MenuEvent::MoveLeft => self.move_left(),
                 MenuEvent::MoveRight => self.move_right(),
-                MenuEvent::PreviousPage | MenuEvent::NextPage => {
-                    // The completion menu doest have the concept of pages, yet
+                // A page is made of the rows painted in the last repaint
+                MenuEvent::NextPage => self.move_page_down(self.painted_rows(painter)),
+                MenuEvent::PreviousPage => self.move_page_up(self.painted_rows(painter)),
+                MenuEvent::Click { row, column } => {
+                    if let Some(row) = row.checked_sub(self.color.border_rows()) {
+                        self.select_position(row, column, self.painted_rows(painter));
+                    }
                 }
             }
         }
@@ -583,18 +674,15 @@ impl Menu for CompletionMenu {
 
     /// The buffer gets replaced in the Span location
     fn replace_in_buffer(&self, line_buffer: &mut LineBuffer) {
-        if let Some(Suggestion { value, span, .. }) = self.get_value() {
-            let mut offset = line_buffer.insertion_point();
-            offset += value.len() - (span.end - span.start);
-
-            line_buffer.replace(span.start..span.end, &value);
-            line_buffer.set_insertion_point(offset);
+        if let Some(suggestion) = self.get_value() {
+            let value = replacement_value(line_buffer, &suggestion, &suggestion.value);
+            replace_in_buffer(line_buffer, suggestion.span, &value);
         }
     }
 
     /// Minimum rows that should be displayed by the menu
     fn min_rows(&self) -> u16 {
-        self.get_rows().min(self.min_rows)
+        self.get_rows().min(self.min_rows) + self.color.border_rows()
     }
 
     /// Gets values from filler that will be displayed in the menu
@@ -602,41 +690,350 @@ impl Menu for CompletionMenu {
         &self.values
     }
 
+    /// Index of the selected value in the values
+    fn selected_index(&self) -> Option<usize> {
+        Some(self.index())
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
-        self.get_rows()
+        self.get_rows() + self.color.border_rows()
     }
 
     fn menu_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
+        let border = self.color.border(self.menu_width(), use_ansi_coloring);
+        let available_lines = available_lines
+            .saturating_sub(self.color.border_rows())
+            .max(1);
+
         if self.get_values().is_empty() {
-            self.no_records_msg(use_ansi_coloring)
+            border + &self.no_records_msg(use_ansi_coloring)
         } else {
-            // The skip values represent the number of lines that should be skipped
+            // The skip rows represent the number of lines that should be skipped
             // while printing the menu
-            let skip_values = if self.row_pos >= available_lines {
-                let skip_lines = self.row_pos.saturating_sub(available_lines) + 1;
-                (skip_lines * self.get_cols()) as usize
-            } else {
-                0
-            };
+            let skip_rows = self.skip_rows(available_lines);
+            let painted_rows = self
+                .get_rows()
+                .saturating_sub(skip_rows)
+                .min(available_lines);
+            let cols = self.get_cols() as usize;
 
             // It seems that crossterm prefers to have a complete string ready to be printed
             // rather than looping through the values and printing multiple things
             // This reduces the flickering when printing the menu
-            let available_values = (available_lines * self.get_cols()) as usize;
-            self.get_values()
-                .iter()
-                .skip(skip_values)
-                .take(available_values)
+            let rows = self
+                .get_values()
+                .chunks(cols)
                 .enumerate()
-                .map(|(index, suggestion)| {
-                    // Correcting the enumerate index based on the number of skipped values
-                    let index = index + skip_values;
-                    let column = index as u16 % self.get_cols();
-                    let empty_space = self.get_width().saturating_sub(suggestion.value.len());
+                .skip(skip_rows as usize)
+                .take(painted_rows as usize)
+                .map(|(row, suggestions)| {
+                    let entries = suggestions
+                        .iter()
+                        .enumerate()
+                        .map(|(column, suggestion)| {
+                            let empty_space = self
+                                .get_width()
+                                .saturating_sub(str_width(&suggestion.value));
+
+                            self.create_string(
+                                suggestion,
+                                row * cols + column,
+                                empty_space,
+                                use_ansi_coloring,
+                            )
+                        })
+                        .collect::<String>();
+
+                    let scrollbar = self.color.scrollbar(
+                        row as u16 - skip_rows,
+                        painted_rows,
+                        skip_rows,
+                        self.get_rows(),
+                        use_ansi_coloring,
+                    );
+                    if scrollbar.is_empty() {
+                        entries
+                    } else {
+                        let padding =
+                            (cols * self.get_width()).saturating_sub(line_width(&entries));
+                        format!("{}{:padding$}{}", entries, "", scrollbar, padding = padding)
+                    }
+                })
+                .collect::<Vec<String>>();
 
-                    self.create_string(suggestion, index, column, empty_space, use_ansi_coloring)
+            border + &rows.join("\r\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    fn menu_with_values(count: usize) -> CompletionMenu {
+        CompletionMenu {
+            working_details: ColumnDetails {
+                columns: 2,
+                col_width: 10,
+            },
+            values: (0..count)
+                .map(|i| Suggestion::new(format!("value{}", i), Span::new(0, 0)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pages_move_by_the_given_rows() {
+        let mut menu = menu_with_values(10);
+
+        menu.move_page_down(2);
+        assert_eq!(menu.index(), 4);
+        menu.move_page_down(2);
+        assert_eq!(menu.index(), 8);
+        // The last page stops at the last row instead of wrapping
+        menu.move_page_down(2);
+        assert_eq!(menu.index(), 8);
+        menu.move_page_up(3);
+        assert_eq!(menu.index(), 2);
+        menu.move_page_up(3);
+        assert_eq!(menu.index(), 0);
+    }
+
+    #[test]
+    fn page_down_stops_at_the_last_value_of_the_column() {
+        let mut menu = menu_with_values(9);
+        menu.col_pos = 1;
+
+        menu.move_page_down(10);
+        assert_eq!(menu.index(), 7);
+    }
+
+    #[test]
+    fn click_selects_value_under_position() {
+        let mut menu = menu_with_values(5);
+        menu.select_position(1, 12, 3);
+
+        assert_eq!(menu.index(), 3);
+    }
+
+    #[test]
+    fn click_respects_scrolled_rows() {
+        let mut menu = menu_with_values(10);
+        menu.row_pos = 3;
+        menu.select_position(0, 0, 2);
+
+        // Two rows are scrolled out of view
+        assert_eq!(menu.index(), 4);
+    }
+
+    #[test]
+    fn click_outside_values_is_ignored() {
+        let mut menu = menu_with_values(3);
+        menu.select_position(1, 12, 3);
+
+        assert_eq!(menu.index(), 0);
+    }
+
+    struct FileCompleter;
+
+    impl Completer for FileCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            match &line[..pos] {
+                "cat R" => vec![Suggestion::new("README.md", Span::new(4, 5))],
+                _ => vec![],
+            }
+        }
+
+        fn empty_message(&self, line: &str, pos: usize) -> Option<String> {
+            Some(format!("no files match `{}`", &line[4..pos]))
+        }
+    }
+
+    #[test]
+    fn completer_message_replaces_the_empty_menu() {
+        let mut menu = CompletionMenu::default();
+        let history = crate::FileBackedHistory::default();
+        let mut line_buffer = LineBuffer::from("cat x");
+        assert_eq!(menu.menu_string(3, false), "NO RECORDS FOUND");
+
+        menu.update_values(&mut line_buffer, &history, &FileCompleter);
+        assert_eq!(menu.get_values(), &[]);
+        assert_eq!(menu.menu_string(3, false), "no files match `x`");
+
+        // Values are shown again once there are some
+        let mut line_buffer = LineBuffer::from("cat R");
+        menu.update_values(&mut line_buffer, &history, &FileCompleter);
+        assert_eq!(menu.get_values().len(), 1);
+        assert!(!menu.menu_string(3, false).contains("no files match"));
+    }
+
+    struct CaseCompleter {
+        match_typed_case: bool,
+    }
+
+    impl Completer for CaseCompleter {
+        fn complete(&self, line: &str, pos: usize) -> Vec<Suggestion> {
+            let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+            let typed = line[start..pos].to_lowercase();
+
+            ["readline", "readlink"]
+                .into_iter()
+                .filter(|value| value.starts_with(&typed))
+                .map(|value| {
+                    Suggestion::new(value, Span::new(start, pos))
+                        .with_match_typed_case(self.match_typed_case)
                 })
                 .collect()
         }
     }
+
+    fn accept_first(line: &str, match_typed_case: bool) -> String {
+        let mut menu = CompletionMenu::default();
+        let history = crate::FileBackedHistory::default();
+        let mut line_buffer = LineBuffer::from(line);
+        let completer = CaseCompleter { match_typed_case };
+
+        menu.update_values(&mut line_buffer, &history, &completer);
+        menu.replace_in_buffer(&mut line_buffer);
+        line_buffer.get_buffer().to_string()
+    }
+
+    #[test]
+    fn suggestions_replace_mixed_case_prefixes_verbatim() {
+        assert_eq!(accept_first("man READ", false), "man readline");
+        assert_eq!(accept_first("man ReAdLi", false), "man readline");
+    }
+
+    #[test]
+    fn suggestions_can_keep_the_typed_case() {
+        assert_eq!(accept_first("man READ", true), "man READline");
+        assert_eq!(accept_first("man ReAdLi", true), "man ReAdLine");
+    }
+
+    #[test]
+    fn partial_completion_keeps_the_typed_case() {
+        let history = crate::FileBackedHistory::default();
+        for (match_typed_case, expected) in [(false, "man readlin"), (true, "man REAdlin")] {
+            let mut menu = CompletionMenu::default();
+            let mut line_buffer = LineBuffer::from("man REA");
+            let completer = CaseCompleter { match_typed_case };
+
+            assert!(menu.can_partially_complete(false, &mut line_buffer, &history, &completer));
+            assert_eq!(line_buffer.get_buffer(), expected);
+        }
+    }
+
+    fn themed_menu() -> CompletionMenu {
+        let theme = MenuTextStyle::default()
+            .with_border_style(Color::Blue.normal())
+            .with_scrollbar_thumb_style(Color::White.normal());
+        let mut menu = menu_with_values(5).with_theme(theme);
+        menu.working_details.col_width = 8;
+        menu
+    }
+
+    #[test]
+    fn theme_adds_border_and_scrollbar() {
+        let mut menu = themed_menu();
+
+        // The border takes up one of the three lines
+        assert_eq!(menu.menu_required_lines(80), 4);
+        assert_eq!(
+            menu.menu_string(3, false),
+            "─────────────────\r\n\
+             >VALUE0 value1  █\r\n\
+             value2  value3  │"
+        );
+
+        menu.row_pos = 2;
+        assert_eq!(
+            menu.menu_string(3, false),
+            "─────────────────\r\n\
+             value2  value3  │\r\n\
+             >VALUE4         █"
+        );
+
+        // No scrollbar while all rows are in view
+        assert_eq!(
+            menu.menu_string(4, false),
+            "─────────────────\r\n\
+             value0  value1  \r\n\
+             value2  value3  \r\n\
+             >VALUE4 "
+        );
+    }
+
+    #[test]
+    fn theme_styles_border_and_scrollbar() {
+        let menu = themed_menu();
+        let theme = &menu.color;
+
+        let rendered = menu.menu_string(2, true);
+        let lines: Vec<&str> = rendered.split("\r\n").collect();
+        assert_eq!(lines[0], Color::Blue.paint("─".repeat(17)).to_string());
+        assert_eq!(
+            lines[1],
+            format!(
+                "{}value0{}  {}value1{}{}  {}{}",
+                theme.selected_text_style.prefix(),
+                RESET,
+                theme.text_style.prefix(),
+                RESET,
+                theme.description_style.prefix(),
+                RESET,
+                Color::White.paint("█"),
+            )
+        );
+    }
+
+    #[test]
+    fn clicks_below_the_border_select_values() {
+        let mut menu = themed_menu();
+        let (mut painter, _output) = Painter::headless(80, 24);
+        painter.set_menu_start_row(1);
+
+        let click = |menu: &mut CompletionMenu, row| {
+            menu.menu_event(MenuEvent::Click { row, column: 9 });
+            menu.update_working_details(
+                &mut LineBuffer::new(),
+                &crate::FileBackedHistory::default(),
+                &crate::DefaultCompleter::default(),
+                &painter,
+            );
+        };
+
+        // The first value in the second row of four columns
+        click(&mut menu, 2);
+        assert_eq!(menu.index(), 4);
+
+        // A click on the border keeps the selection
+        click(&mut menu, 0);
+        assert_eq!(menu.index(), 4);
+    }
+
+    #[test]
+    fn suggestions_are_painted_in_their_own_style() {
+        let mut menu = menu_with_values(3);
+        menu.values[0].style = Some(Color::Red.normal());
+        menu.values[1].style = Some(Color::Blue.bold());
+
+        let rendered: Vec<String> = menu
+            .values
+            .iter()
+            .enumerate()
+            .map(|(index, suggestion)| menu.create_string(suggestion, index, 0, true))
+            .collect();
+
+        // The selected row keeps the selection highlight
+        assert!(rendered[0].starts_with(&format!(
+            "{}value0",
+            menu.color.selected_text_style.prefix()
+        )));
+        assert!(rendered[1].starts_with(&format!("{}value1", Color::Blue.bold().prefix())));
+        assert!(rendered[2].starts_with(&format!("{}value2", menu.color.text_style.prefix())));
+    }
 }
@@ -1,11 +1,12 @@
 use super::{
-    menu_functions::{parse_selection_char, string_difference},
+    menu_functions::{parse_selection_char, replace_in_buffer, string_difference},
     Menu, MenuEvent, MenuTextStyle,
 };
 use crate::{
-    painting::{estimate_single_line_wraps, Painter},
+    painting::{estimate_single_line_wraps, line_width, Painter},
     Completer, History, LineBuffer, Span, Suggestion,
 };
+use chrono::{DateTime, Local, TimeZone, Utc};
 use nu_ansi_term::{ansi::RESET, Style};
 use std::iter::Sum;
 
@@ -68,6 +69,24 @@ pub struct HistoryMenu {
     event: Option<MenuEvent>,
     /// String collected after the menu is activated
     input: Option<String>,
+    /// Entries are labeled by how recent they are
+    recency_groups: bool,
+    /// When the cached values were added to the history, if known
+    timestamps: Vec<Option<DateTime<Utc>>>,
+    /// Time the recency of the entries is measured against
+    now: DateTime<Local>,
+}
+
+/// Label of the group an entry added at `timestamp` belongs to.
+/// Timestamps in the future count as today
+fn recency_label<Tz: TimeZone>(timestamp: &DateTime<Tz>, now: &DateTime<Tz>) -> &'static str {
+    let days = (now.date_naive() - timestamp.date_naive()).num_days();
+    match days {
+        i64::MIN..=0 => "today",
+        1 => "yesterday",
+        2..=7 => "last week",
+        _ => "older",
+    }
 }
 
 impl Default for HistoryMenu {
@@ -87,6 +106,9 @@ impl Default for HistoryMenu {
             pages: Vec::new(),
             event: None,
             input: None,
+            recency_groups: false,
+            timestamps: Vec::new(),
+            now: Local::now(),
         }
     }
 }
@@ -106,6 +128,13 @@ impl HistoryMenu {
         self
     }
 
+    /// Menu builder with a new theme, replacing all the text styles
+    #[must_use]
+    pub fn with_theme(mut self, theme: MenuTextStyle) -> Self {
+        self.color = theme;
+        self
+    }
+
     /// Menu builder with page size
     #[must_use]
     pub fn with_page_size(mut self, page_size: usize) -> Self {
@@ -134,6 +163,14 @@ impl HistoryMenu {
         self
     }
 
+    /// Menu builder grouping the entries under labels like "today" or "yesterday",
+    /// if the history keeps track of when they were added
+    #[must_use]
+    pub fn with_recency_groups(mut self, recency_groups: bool) -> Self {
+        self.recency_groups = recency_groups;
+        self
+    }
+
     fn update_row_pos(&mut self, new_pos: Option<usize>) {
         if let (Some(row), Some(page)) = (new_pos, self.pages.get(self.page)) {
             let values_before_page = self.pages.iter().take(self.page).sum::<Page>().size;
@@ -144,7 +181,10 @@ impl HistoryMenu {
         }
     }
 
-    fn create_values_no_query(&mut self, history: &dyn History) -> Vec<String> {
+    fn create_values_no_query(
+        &mut self,
+        history: &dyn History,
+    ) -> Vec<(String, Option<DateTime<Utc>>)> {
         // When there is no line buffer it is better to get a partial list of all
         // the values that can be queried from the history. There is no point to
         // replicate the whole entries list in the history menu
@@ -156,11 +196,61 @@ impl HistoryMenu {
             .unwrap_or(self.page_size);
 
         history
-            .iter_chronologic()
+            .iter_chronologic_with_timestamps()
             .rev()
             .skip(skip)
             .take(take)
-            .collect::<Vec<String>>()
+            .collect()
+    }
+
+    /// The entries containing `query`, the most recent first. The timestamps are only
+    /// used with recency groups
+    fn create_values_with_query(
+        history: &dyn History,
+        query: &str,
+    ) -> Vec<(String, Option<DateTime<Utc>>)> {
+        history
+            .iter_chronologic_with_timestamps()
+            .rev()
+            .filter(|(entry, _)| entry.contains(query))
+            .collect()
+    }
+
+    /// Position of the first value of the current page in the cached values
+    fn values_offset(&self) -> usize {
+        if self.history_size.is_some() {
+            0
+        } else {
+            self.pages.iter().take(self.page).sum::<Page>().size
+        }
+    }
+
+    /// Label shown above the value at `index` of the current page, when it starts a new
+    /// recency group
+    fn group_header(&self, index: usize) -> Option<&'static str> {
+        if !self.recency_groups {
+            return None;
+        }
+
+        let label = |index: usize| {
+            self.timestamps
+                .get(self.values_offset() + index)
+                .copied()
+                .flatten()
+                .map(|timestamp| recency_label(&timestamp.with_timezone(&Local), &self.now))
+        };
+
+        let current = label(index)?;
+        match index.checked_sub(1).and_then(label) {
+            Some(previous) if previous == current => None,
+            _ => Some(current),
+        }
+    }
+
+    /// The number of rows an entry of the current page takes, including its group header
+    fn entry_lines(&self, index: usize, entry: &str, terminal_columns: u16) -> u16 {
+        self.number_of_lines(entry, terminal_columns)
+            + u16::from(self.group_header(index).is_some())
     }
 
     /// The number of rows an entry from the menu can take considering wrapping
@@ -188,11 +278,6 @@ impl HistoryMenu {
         self.row_position as usize
     }
 
-    /// Get selected value from the menu
-    fn get_value(&self) -> Option<Suggestion> {
-        self.get_values().get(self.index()).cloned()
-    }
-
     /// Reset menu position
     fn reset_position(&mut self) {
         self.page = 0;
@@ -203,26 +288,25 @@ impl HistoryMenu {
     fn printable_entries(&self, painter: &Painter) -> usize {
         // The number 2 comes from the prompt line and the banner printed at the bottom
         // of the history menu
-        let available_lines = painter.screen_height().saturating_sub(2);
-        let (printable_entries, _) =
-            self.get_values()
-                .iter()
-                .fold(
-                    (0, Some(0)),
-                    |(lines, total_lines), suggestion| match total_lines {
-                        None => (lines, None),
-                        Some(total_lines) => {
-                            let new_total_lines = total_lines
-                                + self.number_of_lines(&suggestion.value, painter.screen_width());
-
-                            if new_total_lines < available_lines {
-                                (lines + 1, Some(new_total_lines))
-                            } else {
-                                (lines, None)
-                            }
-                        }
-                    },
-                );
+        let available_lines = painter
+            .screen_height()
+            .saturating_sub(2 + self.color.border_rows());
+        let (printable_entries, _) = self.get_values().iter().enumerate().fold(
+            (0, Some(0)),
+            |(lines, total_lines), (index, suggestion)| match total_lines {
+                None => (lines, None),
+                Some(total_lines) => {
+                    let new_total_lines = total_lines
+                        + self.entry_lines(index, &suggestion.value, painter.screen_width());
+
+                    if new_total_lines < available_lines {
+                        (lines + 1, Some(new_total_lines))
+                    } else {
+                        (lines, None)
+                    }
+                }
+            },
+        );
 
         printable_entries
     }
@@ -263,7 +347,7 @@ impl HistoryMenu {
         if use_ansi_coloring {
             format!(
                 "{}{}{}",
-                self.color.selected_text_style.prefix(),
+                self.color.page_indicator_style.prefix(),
                 status_bar,
                 RESET,
             )
@@ -286,6 +370,21 @@ impl HistoryMenu {
         }
     }
 
+    /// Line with the label of the recency group starting at the value at `index`
+    fn create_header(&self, index: usize, use_ansi_coloring: bool) -> String {
+        match self.group_header(index) {
+            Some(label) if use_ansi_coloring => format!(
+                "{}{}{}{}",
+                self.color.description_style.prefix(),
+                label,
+                RESET,
+                Self::end_of_line()
+            ),
+            Some(label) => format!("{}{}", label, Self::end_of_line()),
+            None => String::new(),
+        }
+    }
+
     /// Creates default string that represents one line from a menu
     fn create_string(
         &self,
@@ -391,9 +490,12 @@ impl Menu for HistoryMenu {
             self.create_values_no_query(history)
         } else {
             self.history_size = None;
-            history.query_entries(parsed.remainder)
+            Self::create_values_with_query(history, parsed.remainder)
         };
 
+        let (values, timestamps): (Vec<_>, Vec<_>) = values.into_iter().unzip();
+        self.timestamps = timestamps;
+        self.now = Local::now();
         self.values = values
             .into_iter()
             .map(|value| {
@@ -402,11 +504,7 @@ impl Menu for HistoryMenu {
                     end: start + input.len(),
                 };
 
-                Suggestion {
-                    value,
-                    description: None,
-                    span,
-                }
+                Suggestion::new(value, span)
             })
             .collect();
     }
@@ -425,7 +523,7 @@ impl Menu for HistoryMenu {
                 return &self.values;
             }
 
-            let start = self.pages.iter().take(self.page).sum::<Page>().size;
+            let start = self.values_offset();
 
             let end: usize = if self.page >= self.pages.len() {
                 self.page_size + start
@@ -438,14 +536,15 @@ impl Menu for HistoryMenu {
         }
     }
 
+    /// Index of the selected value in the values
+    fn selected_index(&self) -> Option<usize> {
+        Some(self.index())
+    }
+
     /// The buffer gets cleared with the actual value
     fn replace_in_buffer(&self, line_buffer: &mut LineBuffer) {
         if let Some(Suggestion { value, span, .. }) = self.get_value() {
-            line_buffer.replace(span.start..span.end, &value);
-
-            let mut offset = line_buffer.insertion_point();
-            offset += value.len() - (span.end - span.start);
-            line_buffer.set_insertion_point(offset);
+            replace_in_buffer(line_buffer, span, &value);
         }
     }
 
@@ -534,6 +633,30 @@ impl Menu for HistoryMenu {
                         self.update_values(line_buffer, history, completer);
                     }
                 }
+                MenuEvent::Click { row, .. } => {
+                    let page_size = self.pages.get(self.page).map_or(0, |page| page.size);
+                    // The entries start below the border
+                    let mut lines = self.color.border_rows();
+                    if row >= lines {
+                        for (index, suggestion) in
+                            self.get_values().iter().take(page_size).enumerate()
+                        {
+                            if self.group_header(index).is_some() {
+                                lines += 1;
+                                // Group headers can't be selected
+                                if row < lines {
+                                    break;
+                                }
+                            }
+                            lines +=
+                                self.number_of_lines(&suggestion.value, painter.screen_width());
+                            if row < lines {
+                                self.row_position = index as u16;
+                                break;
+                            }
+                        }
+                    }
+                }
                 MenuEvent::PreviousPage => {
                     match self.page.checked_sub(1) {
                         Some(page_num) => self.page = page_num,
@@ -550,9 +673,14 @@ impl Menu for HistoryMenu {
     /// Calculates the real required lines for the menu considering how many lines
     /// wrap the terminal and if an entry is larger than the remaining lines
     fn menu_required_lines(&self, terminal_columns: u16) -> u16 {
-        self.get_values().iter().fold(0, |acc, suggestion| {
-            acc + self.number_of_lines(&suggestion.value, terminal_columns)
-        }) + 1
+        self.get_values()
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (index, suggestion)| {
+                acc + self.entry_lines(index, &suggestion.value, terminal_columns)
+            })
+            + 1
+            + self.color.border_rows()
     }
 
     /// Creates the menu representation as a string which will be painted by the painter
@@ -582,15 +710,15 @@ impl Menu for HistoryMenu {
 
                         let row_number = format!("{}: ", index + values_before_page);
 
-                        self.create_string(&line, index, &row_number, use_ansi_coloring)
+                        self.create_header(index, use_ansi_coloring)
+                            + &self.create_string(&line, index, &row_number, use_ansi_coloring)
                     })
                     .collect::<String>();
 
-                format!(
-                    "{}{}",
-                    lines_string,
-                    self.banner_message(page, use_ansi_coloring)
-                )
+                let menu = lines_string + &self.banner_message(page, use_ansi_coloring);
+                let width = menu.lines().map(line_width).max().unwrap_or(0);
+
+                self.color.border(width, use_ansi_coloring) + &menu
             }
             None => self.no_page_msg(use_ansi_coloring),
         }
@@ -598,7 +726,7 @@ impl Menu for HistoryMenu {
 
     /// Minimum rows that should be displayed by the menu
     fn min_rows(&self) -> u16 {
-        self.max_lines + 1
+        self.max_lines + 1 + self.color.border_rows()
     }
 }
 
@@ -628,6 +756,111 @@ fn number_of_lines(entry: &str, max_lines: usize, terminal_columns: u16) -> u16
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn utc(day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 5, day, hour, min, 0).unwrap()
+    }
+
+    #[rstest]
+    #[case(utc(10, 12, 0), "today")]
+    #[case(utc(10, 0, 0), "today")]
+    #[case(utc(11, 8, 0), "today")]
+    #[case(utc(9, 23, 59), "yesterday")]
+    #[case(utc(9, 0, 0), "yesterday")]
+    #[case(utc(8, 23, 59), "last week")]
+    #[case(utc(3, 0, 0), "last week")]
+    #[case(utc(2, 23, 59), "older")]
+    fn entries_are_grouped_by_calendar_day(
+        #[case] timestamp: DateTime<Utc>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(recency_label(&timestamp, &utc(10, 12, 0)), expected);
+    }
+
+    /// Menu showing entries added at the given local times, newest first
+    fn grouped_menu(entries: &[(&str, Option<(u32, u32)>)]) -> HistoryMenu {
+        let local = |day, hour| Local.with_ymd_and_hms(2022, 5, day, hour, 0, 0).unwrap();
+
+        let mut menu = HistoryMenu::default().with_recency_groups(true);
+        menu.now = local(10, 12);
+        menu.values = entries
+            .iter()
+            .map(|(value, _)| Suggestion::new(value.to_string(), Span { start: 0, end: 0 }))
+            .collect();
+        menu.timestamps = entries
+            .iter()
+            .map(|(_, time)| time.map(|(day, hour)| local(day, hour).with_timezone(&Utc)))
+            .collect();
+        menu.history_size = Some(entries.len());
+        menu.pages = vec![Page {
+            size: entries.len(),
+            full: false,
+        }];
+        menu
+    }
+
+    #[test]
+    fn timestamped_entries_get_group_headers() {
+        let menu = grouped_menu(&[
+            ("a", Some((10, 9))),
+            ("b", Some((10, 0))),
+            ("c", Some((9, 23))),
+            ("d", Some((5, 8))),
+            ("e", Some((1, 8))),
+            ("f", None),
+        ]);
+
+        let menu_string = menu.menu_string(20, false);
+        let lines: Vec<&str> = menu_string.lines().take(10).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "today",
+                "0: >A",
+                "1: b",
+                "yesterday",
+                "2: c",
+                "last week",
+                "3: d",
+                "older",
+                "4: e",
+                "5: f",
+            ]
+        );
+        // The headers and the page indicator
+        assert_eq!(menu.menu_required_lines(80), 6 + 4 + 1);
+    }
+
+    #[test]
+    fn entries_without_timestamps_are_not_grouped() {
+        let menu = grouped_menu(&[("a", None), ("b", None)]);
+
+        assert!(menu.menu_string(20, false).starts_with("0: >A\r\n1: b\r\n"));
+        assert_eq!(menu.menu_required_lines(80), 3);
+    }
+
+    #[test]
+    fn group_headers_cannot_be_selected() {
+        let mut menu = grouped_menu(&[("a", Some((10, 9))), ("b", Some((9, 9)))]);
+        let (painter, _) = Painter::headless(80, 24);
+        let click = |menu: &mut HistoryMenu, row| {
+            menu.menu_event(MenuEvent::Click { row, column: 0 });
+            menu.update_working_details(
+                &mut LineBuffer::new(),
+                &crate::FileBackedHistory::default(),
+                &crate::DefaultCompleter::default(),
+                &painter,
+            );
+            menu.index()
+        };
+
+        assert_eq!(click(&mut menu, 3), 1);
+        assert_eq!(click(&mut menu, 2), 1);
+        assert_eq!(click(&mut menu, 0), 1);
+        assert_eq!(click(&mut menu, 1), 0);
+    }
 
     #[test]
     fn number_of_lines_test() {
@@ -1,5 +1,7 @@
 //! Collection of common functions that can be used to create menus
-use crate::Suggestion;
+use crate::{LineBuffer, Span, Suggestion};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Index result obtained from parsing a string with an index marker
 /// For example, the next string:
@@ -140,41 +142,123 @@ pub fn parse_selection_char(buffer: &str, marker: char) -> ParseResult {
 }
 
 /// Finds index for the common string in a list of suggestions
+///
+/// The values are compared without regard to ASCII case and the index is the
+/// length of the prefix of the first suggestion shared by all of them. It is
+/// rounded down to a grapheme boundary, so the prefix never splits a multibyte
+/// character or a grapheme cluster. A single suggestion has no index
+///
+/// ## Example usage
+/// ```
+/// use reedline::{menu_functions::find_common_string, Span, Suggestion};
+///
+/// let suggestions: Vec<_> = ["hello", "help"]
+///     .iter()
+///     .map(|value| Suggestion::new(*value, Span::new(0, 2)))
+///     .collect();
+///
+/// assert_eq!(find_common_string(&suggestions), (suggestions.first(), Some(3)));
+/// ```
 pub fn find_common_string(values: &[Suggestion]) -> (Option<&Suggestion>, Option<usize>) {
     let first = values.iter().next();
 
     let index = first.and_then(|first| {
         values.iter().skip(1).fold(None, |index, suggestion| {
-            if suggestion.value.starts_with(&first.value) {
-                Some(first.value.len())
-            } else {
-                first
-                    .value
-                    .char_indices()
-                    .zip(suggestion.value.char_indices())
-                    .find(|((_, mut lhs), (_, mut rhs))| {
-                        lhs.make_ascii_lowercase();
-                        rhs.make_ascii_lowercase();
-
-                        lhs != rhs
-                    })
-                    .map(|((new_index, _), _)| match index {
-                        Some(index) => {
-                            if index <= new_index {
-                                index
-                            } else {
-                                new_index
-                            }
-                        }
-                        None => new_index,
-                    })
+            let new_index = first
+                .value
+                .char_indices()
+                .zip(suggestion.value.chars())
+                .find(|((_, lhs), rhs)| !lhs.eq_ignore_ascii_case(rhs))
+                .map_or(
+                    first.value.len().min(suggestion.value.len()),
+                    |((new_index, _), _)| new_index,
+                );
+
+            match index {
+                Some(index) if index <= new_index => Some(index),
+                _ => Some(new_index),
             }
         })
     });
 
+    let index = first.zip(index).map(|(first, index)| {
+        first
+            .value
+            .grapheme_indices(true)
+            .map(|(boundary, _)| boundary)
+            .chain(std::iter::once(first.value.len()))
+            .take_while(|boundary| *boundary <= index)
+            .last()
+            .unwrap_or(0)
+    });
+
     (first, index)
 }
 
+/// Value with its start replaced by `typed` when `typed` matches it without regard
+/// to case, so the completed text keeps the casing that was typed.
+/// Values that don't start with the typed text are returned as they are
+///
+/// ## Example usage
+/// ```
+/// use reedline::menu_functions::typed_case_value;
+///
+/// assert_eq!(typed_case_value("READ", "readline"), "READline");
+/// assert_eq!(typed_case_value("cat", "readline"), "readline");
+/// ```
+pub fn typed_case_value<'a>(typed: &str, value: &'a str) -> Cow<'a, str> {
+    let mut value_chars = value.char_indices();
+    for typed_char in typed.chars() {
+        match value_chars.next() {
+            Some((_, value_char)) if value_char.to_lowercase().eq(typed_char.to_lowercase()) => {}
+            _ => return Cow::Borrowed(value),
+        }
+    }
+
+    let rest = value_chars.next().map_or(value.len(), |(index, _)| index);
+    Cow::Owned(format!("{}{}", typed, &value[rest..]))
+}
+
+/// Replaces the text covered by `span` with `value`, keeping the text after the span intact
+///
+/// The cursor is placed behind the inserted value. A cursor after the end of the
+/// span keeps its position relative to the text that follows the span, while a
+/// cursor before the span is not moved. Spans outside the buffer are ignored
+///
+/// ## Example usage
+/// ```
+/// use reedline::{menu_functions::replace_in_buffer, LineBuffer, Span};
+///
+/// let mut line_buffer = LineBuffer::from("ls  -la");
+/// line_buffer.set_insertion_point(3);
+///
+/// replace_in_buffer(&mut line_buffer, Span::new(3, 3), "src/");
+/// assert_eq!(line_buffer.get_buffer(), "ls src/ -la");
+/// assert_eq!(line_buffer.insertion_point(), 7);
+/// ```
+pub fn replace_in_buffer(line_buffer: &mut LineBuffer, span: Span, value: &str) {
+    let buffer = line_buffer.get_buffer();
+    if span.start > span.end
+        || span.end > buffer.len()
+        || !buffer.is_char_boundary(span.start)
+        || !buffer.is_char_boundary(span.end)
+    {
+        return;
+    }
+
+    let insertion_point = line_buffer.insertion_point();
+    let offset = if insertion_point > span.end {
+        insertion_point - (span.end - span.start) + value.len()
+    } else if insertion_point < span.start {
+        insertion_point
+    } else {
+        span.start + value.len()
+    };
+
+    line_buffer.replace(span.start..span.end, value);
+    line_buffer.set_insertion_point(offset);
+}
+
 /// Finds different string between two strings
 ///
 /// ## Example usage
@@ -249,6 +333,7 @@ pub fn string_difference<'a>(new_string: &'a str, old_string: &str) -> (usize, &
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn parse_row_test() {
@@ -442,11 +527,7 @@ mod tests {
 
         let input: Vec<_> = ["nushell", "null"]
             .into_iter()
-            .map(|s| Suggestion {
-                value: s.into(),
-                description: None,
-                span: Span::new(0, s.len()),
-            })
+            .map(|s| Suggestion::new(s, Span::new(0, s.len())))
             .collect();
         let res = find_common_string(&input);
 
@@ -459,14 +540,83 @@ mod tests {
 
         let input: Vec<_> = ["ｎｕｓｈｅｌｌ", "ｎｕｌｌ"]
             .into_iter()
-            .map(|s| Suggestion {
-                value: s.into(),
-                description: None,
-                span: Span::new(0, s.len()),
-            })
+            .map(|s| Suggestion::new(s, Span::new(0, s.len())))
             .collect();
         let res = find_common_string(&input);
 
         assert!(matches!(res, (Some(elem), Some(6)) if elem == &input[0]));
     }
+
+    fn suggestions(values: &[&str]) -> Vec<Suggestion> {
+        use crate::Span;
+
+        values
+            .iter()
+            .map(|s| Suggestion::new(s.to_string(), Span::new(0, 0)))
+            .collect()
+    }
+
+    #[rstest]
+    #[case("READ", "readline", "READline")]
+    #[case("ReAd", "readline", "ReAdline")]
+    #[case("readline", "readline", "readline")]
+    #[case("", "readline", "readline")]
+    #[case("ÉC", "école", "ÉCole")]
+    #[case("REDO", "readline", "readline")]
+    #[case("readlines", "readline", "readline")]
+    fn typed_case_value_keeps_the_typed_prefix(
+        #[case] typed: &str,
+        #[case] value: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(typed_case_value(typed, value), expected);
+    }
+
+    #[rstest]
+    #[case(&[], None)]
+    #[case(&["nushell"], None)]
+    #[case(&["nushell", "nu", "null"], Some(2))]
+    #[case(&["nu", "nx", "nushell"], Some(1))]
+    #[case(&["nushell", "bash"], Some(0))]
+    #[case(&["NUshell", "null"], Some(2))]
+    fn find_common_string_of_all_values(#[case] values: &[&str], #[case] expected: Option<usize>) {
+        let input = suggestions(values);
+        assert_eq!(find_common_string(&input).1, expected);
+    }
+
+    #[test]
+    fn find_common_string_keeps_graphemes_intact() {
+        // Both values share the base letter of the combining sequence but
+        // differ in the accent, which must not be split
+        let input = suggestions(&["cafe\u{301}s", "cafe\u{300}s"]);
+        assert_eq!(find_common_string(&input).1, Some(3));
+
+        let input = suggestions(&["ｎｕｓｈｅｌｌ", "ｎｕｌｌ"]);
+        assert_eq!(find_common_string(&input).1, Some(6));
+    }
+
+    #[rstest]
+    #[case("ls  -la", 3, Span::new(3, 3), "src/", "ls src/ -la", 7)]
+    #[case("ls sr -la", 5, Span::new(3, 5), "src/", "ls src/ -la", 7)]
+    #[case("ls sr -la", 4, Span::new(3, 5), "src/", "ls src/ -la", 7)]
+    #[case("ls sr -la", 6, Span::new(3, 5), "src/", "ls src/ -la", 8)]
+    #[case("ls sr -la", 1, Span::new(3, 5), "src/", "ls src/ -la", 1)]
+    #[case("ls source -la", 9, Span::new(3, 9), "src", "ls src -la", 6)]
+    #[case("ls", 2, Span::new(0, 5), "list", "ls", 2)]
+    fn replace_in_buffer_keeps_tail(
+        #[case] input: &str,
+        #[case] insertion_point: usize,
+        #[case] span: Span,
+        #[case] value: &str,
+        #[case] expected: &str,
+        #[case] expected_insertion_point: usize,
+    ) {
+        let mut line_buffer = LineBuffer::from(input);
+        line_buffer.set_insertion_point(insertion_point);
+
+        replace_in_buffer(&mut line_buffer, span, value);
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_insertion_point);
+    }
 }
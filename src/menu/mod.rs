@@ -8,6 +8,10 @@ pub use history_menu::HistoryMenu;
 use nu_ansi_term::{Color, Style};
 
 /// Struct to store the menu style
+///
+/// Set it on a menu with `with_theme`, e.g. [`CompletionMenu::with_theme`]. Start from
+/// [`MenuTextStyle::default`] and change the styles with the builders
+#[derive(Debug, Clone, PartialEq)]
 pub struct MenuTextStyle {
     /// Text style for selected text in a menu
     pub selected_text_style: Style,
@@ -15,6 +19,10 @@ pub struct MenuTextStyle {
     pub text_style: Style,
     /// Text style for the item description
     pub description_style: Style,
+    page_indicator_style: Style,
+    border_style: Option<Style>,
+    scrollbar_thumb_style: Option<Style>,
+    scrollbar_track_style: Style,
 }
 
 impl Default for MenuTextStyle {
@@ -23,10 +31,159 @@ impl Default for MenuTextStyle {
             selected_text_style: Color::Green.bold().reverse(),
             text_style: Color::DarkGray.normal(),
             description_style: Color::Yellow.normal(),
+            page_indicator_style: Color::Green.bold().reverse(),
+            border_style: None,
+            scrollbar_thumb_style: None,
+            scrollbar_track_style: Color::DarkGray.normal(),
         }
     }
 }
 
+impl MenuTextStyle {
+    /// Theme builder with the text style for the page indicator below the entries of
+    /// the history menu
+    #[must_use]
+    pub fn with_page_indicator_style(mut self, page_indicator_style: Style) -> Self {
+        self.page_indicator_style = page_indicator_style;
+        self
+    }
+
+    /// Theme builder with the style of a line drawn above the menu entries. Without it
+    /// there is no line
+    #[must_use]
+    pub fn with_border_style(mut self, border_style: Style) -> Self {
+        self.border_style = Some(border_style);
+        self
+    }
+
+    /// Theme builder with the style of the scrollbar thumb marking the rows in view. The
+    /// completion menu shows a scrollbar at its right edge if this is set and not all
+    /// rows fit on the screen
+    #[must_use]
+    pub fn with_scrollbar_thumb_style(mut self, scrollbar_thumb_style: Style) -> Self {
+        self.scrollbar_thumb_style = Some(scrollbar_thumb_style);
+        self
+    }
+
+    /// Theme builder with the style of the scrollbar next to the rows before and after
+    /// the thumb
+    #[must_use]
+    pub fn with_scrollbar_track_style(mut self, scrollbar_track_style: Style) -> Self {
+        self.scrollbar_track_style = scrollbar_track_style;
+        self
+    }
+
+    /// Rows taken up by the border
+    pub(crate) fn border_rows(&self) -> u16 {
+        u16::from(self.border_style.is_some())
+    }
+
+    /// The border above the menu entries, `width` columns wide, if there is one
+    pub(crate) fn border(&self, width: usize, use_ansi_coloring: bool) -> String {
+        match self.border_style {
+            Some(style) => {
+                let line = "─".repeat(width);
+                if use_ansi_coloring {
+                    format!("{}\r\n", style.paint(line))
+                } else {
+                    format!("{}\r\n", line)
+                }
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Scrollbar cell painted next to `row` of `rows` painted rows, when `skipped_rows`
+    /// of `total_rows` are scrolled out of view above them.
+    /// Empty without a scrollbar or if all rows are in view
+    pub(crate) fn scrollbar(
+        &self,
+        row: u16,
+        rows: u16,
+        skipped_rows: u16,
+        total_rows: u16,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let thumb_style = match self.scrollbar_thumb_style {
+            Some(style) if total_rows > rows => style,
+            _ => return String::new(),
+        };
+
+        let rows = u32::from(rows);
+        let total_rows = u32::from(total_rows);
+        let thumb_size = (rows * rows / total_rows).max(1);
+        // The thumb reaches the bottom once the last row is in view
+        let thumb_start = (u32::from(skipped_rows) * (rows - thumb_size) / (total_rows - rows))
+            .min(rows - thumb_size);
+        let (style, cell) = if (thumb_start..thumb_start + thumb_size).contains(&u32::from(row)) {
+            (thumb_style, "█")
+        } else {
+            (self.scrollbar_track_style, "│")
+        };
+
+        if use_ansi_coloring {
+            style.paint(cell).to_string()
+        } else {
+            cell.to_string()
+        }
+    }
+}
+
+/// Limit of the rows a menu may take up on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxHeight {
+    /// Absolute number of rows
+    Rows(u16),
+    /// Fraction of the terminal height, between 0.0 and 1.0
+    Fraction(f32),
+}
+
+impl MaxHeight {
+    /// Rows available to the menu on a terminal `screen_height` rows high.
+    ///
+    /// At least one row is kept free for the prompt and one row is left for the menu
+    pub fn rows(self, screen_height: u16) -> u16 {
+        let rows = match self {
+            MaxHeight::Rows(rows) => rows,
+            MaxHeight::Fraction(fraction) => (f32::from(screen_height) * fraction) as u16,
+        };
+
+        rows.min(screen_height.saturating_sub(1)).max(1)
+    }
+}
+
+/// Change of the menus reported to the observer set with
+/// [`Reedline::with_menu_observer`](crate::Reedline::with_menu_observer)
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuNotification {
+    /// A menu opened, with the suggestion it highlights first
+    Opened {
+        /// Name of the menu
+        menu: String,
+        /// Highlighted suggestion, if the menu has any values
+        selection: Option<Suggestion>,
+    },
+    /// Another suggestion of the open menu was highlighted
+    SelectionChanged {
+        /// Name of the menu
+        menu: String,
+        /// Highlighted suggestion, if the menu has any values
+        selection: Option<Suggestion>,
+    },
+    /// The menu closed with its suggestion applied to the buffer
+    Accepted {
+        /// Name of the menu
+        menu: String,
+        /// Suggestion applied to the buffer
+        selection: Option<Suggestion>,
+    },
+    /// The menu closed without changing the buffer
+    Aborted {
+        /// Name of the menu
+        menu: String,
+    },
+}
+
 /// Defines all possible events that could happen with a menu.
 #[derive(Clone)]
 pub enum MenuEvent {
@@ -54,6 +211,14 @@ pub enum MenuEvent {
     NextPage,
     /// Move to previous page
     PreviousPage,
+    /// Selecting the element painted at the given position. The row is counted
+    /// from the first painted row of the menu
+    Click {
+        /// Row relative to the start of the menu
+        row: u16,
+        /// Terminal column
+        column: u16,
+    },
 }
 
 /// Trait that defines how a menu will be printed by the painter
@@ -124,6 +289,17 @@ pub trait Menu: Send {
 
     /// Gets cached values from menu that will be displayed
     fn get_values(&self) -> &[Suggestion];
+
+    /// Index of the value currently selected in [`Menu::get_values`]. Menus that don't
+    /// report it have no selected value
+    fn selected_index(&self) -> Option<usize> {
+        None
+    }
+
+    /// Gets the value currently selected in the menu
+    fn get_value(&self) -> Option<Suggestion> {
+        self.get_values().get(self.selected_index()?).cloned()
+    }
 }
 
 pub(crate) enum ReedlineMenu {
@@ -249,4 +425,12 @@ impl Menu for ReedlineMenu {
     fn get_values(&self) -> &[Suggestion] {
         self.as_ref().get_values()
     }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.as_ref().selected_index()
+    }
+
+    fn get_value(&self) -> Option<Suggestion> {
+        self.as_ref().get_value()
+    }
 }
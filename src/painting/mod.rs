@@ -6,4 +6,6 @@ mod utils;
 pub use painter::Painter;
 pub(crate) use prompt_lines::PromptLines;
 pub use styled_text::StyledText;
-pub(crate) use utils::estimate_single_line_wraps;
+pub(crate) use utils::{
+    estimate_required_lines, estimate_single_line_wraps, line_width, offset_at_position, str_width,
+};
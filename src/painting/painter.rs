@@ -1,49 +1,104 @@
 use {
-    super::utils::{coerce_crlf, line_width},
+    super::utils::{coerce_crlf, line_width, row_breaks},
     crate::{
-        menu::{Menu, ReedlineMenu},
+        menu::{MaxHeight, Menu, ReedlineMenu},
         painting::PromptLines,
-        Prompt,
+        BellStyle, HintWrap, MenuPlacement, Prompt,
     },
     crossterm::{
-        cursor::{self, MoveTo, RestorePosition, SavePosition},
+        cursor::{self, CursorShape, MoveTo, RestorePosition, SavePosition, SetCursorShape},
         style::{Print, ResetColor, SetForegroundColor},
         terminal::{self, Clear, ClearType, ScrollUp},
         QueueableCommand, Result,
     },
-    std::io::Write,
+    std::{
+        io::Write,
+        time::{Duration, Instant},
+    },
 };
 
-// Returns a string that skips N number of lines with the next offset of lines
-// An offset of 0 would return only one line after skipping the required lines
-fn skip_buffer_lines(string: &str, skip: usize, offset: Option<usize>) -> &str {
-    let mut matches = string.match_indices('\n');
-    let index = if skip == 0 {
-        0
+// How long the screen stays inverted for a visual bell
+const VISUAL_BELL_DURATION: Duration = Duration::from_millis(100);
+
+/// Cuts the first `skip` rows from `text` printed from `column` on a terminal
+/// `width` columns wide.
+///
+/// `skip` is decreased by the rows that were cut, so the remaining rows can be
+/// cut from the text that follows, and `column` is moved to where `text` ends
+fn skip_rows<'text>(
+    text: &'text str,
+    skip: &mut usize,
+    column: &mut usize,
+    width: usize,
+) -> &'text str {
+    let (breaks, end_column) = row_breaks(text, *column, width);
+    *column = end_column;
+
+    if *skip == 0 {
+        text
+    } else if *skip <= breaks.len() {
+        let start = breaks[*skip - 1].1;
+        *skip = 0;
+        &text[start..]
     } else {
-        matches
-            .clone()
-            .nth(skip - 1)
-            .map(|(index, _)| index + 1)
-            .unwrap_or(string.len())
-    };
-
-    let limit = match offset {
-        Some(offset) => {
-            let offset = skip + offset;
-            matches
-                .nth(offset)
-                .map(|(index, _)| index)
-                .unwrap_or(string.len())
-        }
-        None => string.len(),
-    };
+        *skip -= breaks.len();
+        ""
+    }
+}
 
-    string[index..limit].trim_end_matches('\n')
+/// Keeps the part of `text` that fits in the `rows` available below the
+/// current row of a terminal `width` columns wide.
+///
+/// `rows` and `column` are updated like in [`skip_rows`]. Once the rows are
+/// used up, any following text is cut completely
+fn take_rows<'text>(
+    text: &'text str,
+    rows: &mut usize,
+    column: &mut usize,
+    width: usize,
+) -> &'text str {
+    let (breaks, end_column) = row_breaks(text, *column, width);
+
+    if breaks.len() <= *rows {
+        *rows -= breaks.len();
+        *column = end_column;
+        text
+    } else {
+        let end = breaks[*rows].0;
+        *rows = 0;
+        *column = width;
+        &text[..end]
+    }
+}
+
+/// Placement of content taller than the screen
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ScrollPosition {
+    /// Rows of the content scrolled above the top of the screen
+    pub(crate) offset: u16,
+    /// Screen row where the cursor is painted
+    pub(crate) cursor_row: u16,
+}
+
+impl ScrollPosition {
+    /// Scrolls just enough content out of view to keep the cursor, which is
+    /// `cursor_distance` rows below the first row of the content, visible.
+    /// `reserved_rows` are kept free below the cursor, e.g. for a menu
+    pub(crate) fn new(cursor_distance: u16, reserved_rows: u16, screen_height: u16) -> Self {
+        let visible_rows = screen_height.saturating_sub(reserved_rows).max(1);
+        let offset = cursor_distance
+            .saturating_add(1)
+            .saturating_sub(visible_rows);
+
+        Self {
+            offset,
+            cursor_row: cursor_distance - offset,
+        }
+    }
 }
 
 /// the type used by crossterm operations
-pub type W = std::io::BufWriter<std::io::Stderr>;
+pub type W = std::io::BufWriter<Box<dyn Write + Send>>;
 
 /// Implementation of the output to the terminal
 pub struct Painter {
@@ -52,7 +107,19 @@ pub struct Painter {
     prompt_start_row: u16,
     terminal_size: (u16, u16),
     last_required_lines: u16,
+    // Row of the cursor after the last repaint
+    cursor_row: u16,
+    // Column of the cursor after the last repaint, `None` until the first one
+    cursor_column: Option<u16>,
     large_buffer: bool,
+    menu_start_row: Option<u16>,
+    // Rows of the menu painted above the prompt by the last repaint
+    menu_rows_above: u16,
+    menu_max_height: Option<MaxHeight>,
+    menu_placement: MenuPlacement,
+    hint_wrap: HintWrap,
+    // When the screen inverted by a visual bell is switched back
+    visual_bell_deadline: Option<Instant>,
 }
 
 impl Painter {
@@ -62,10 +129,34 @@ impl Painter {
             prompt_start_row: 0,
             terminal_size: (0, 0),
             last_required_lines: 0,
+            cursor_row: 0,
+            cursor_column: None,
             large_buffer: false,
+            menu_start_row: None,
+            menu_rows_above: 0,
+            menu_max_height: None,
+            menu_placement: MenuPlacement::default(),
+            hint_wrap: HintWrap::default(),
+            visual_bell_deadline: None,
         }
     }
 
+    /// Painter writing to a shared buffer instead of the terminal, for tests
+    /// of the painted output
+    #[cfg(test)]
+    pub(crate) fn headless(width: u16, height: u16) -> (Self, HeadlessOutput) {
+        let output = HeadlessOutput::default();
+        let mut painter = Painter::new(std::io::BufWriter::new(Box::new(output.clone())));
+        painter.terminal_size = (width, height);
+        (painter, output)
+    }
+
+    /// Records a menu as painted from `row` on, for tests of clicks into menus
+    #[cfg(test)]
+    pub(crate) fn set_menu_start_row(&mut self, row: u16) {
+        self.menu_start_row = Some(row);
+    }
+
     /// Height of the current terminal window
     pub fn screen_height(&self) -> u16 {
         self.terminal_size.1
@@ -81,6 +172,56 @@ impl Painter {
         self.screen_height() - self.prompt_start_row
     }
 
+    /// Row of the terminal where the prompt starts
+    pub(crate) fn prompt_start_row(&self) -> u16 {
+        self.prompt_start_row
+    }
+
+    /// Row of the terminal where the menu was last painted, if any
+    pub(crate) fn menu_start_row(&self) -> Option<u16> {
+        self.menu_start_row
+    }
+
+    /// Row within the last painted menu of the screen `row`, `None` if the menu
+    /// doesn't cover it
+    pub(crate) fn menu_row(&self, row: u16) -> Option<u16> {
+        let start = self.menu_start_row?;
+        let end = if self.menu_rows_above > 0 {
+            self.prompt_start_row
+        } else {
+            u16::MAX
+        };
+
+        (start..end).contains(&row).then(|| row - start)
+    }
+
+    /// Screen position of the cursor as `(column, row)` after the last repaint, `None`
+    /// before the first one and once the cursor left the input area
+    pub(crate) fn cursor_position(&self) -> Option<(u16, u16)> {
+        self.cursor_column.map(|column| (column, self.cursor_row))
+    }
+
+    /// Limits the rows a menu takes up, `None` lets it use the whole screen
+    pub(crate) fn set_menu_max_height(&mut self, menu_max_height: Option<MaxHeight>) {
+        self.menu_max_height = menu_max_height;
+    }
+
+    /// Sets where a menu is painted when the rows below the prompt can't hold it
+    pub(crate) fn set_menu_placement(&mut self, menu_placement: MenuPlacement) {
+        self.menu_placement = menu_placement;
+    }
+
+    /// Sets how a hint that doesn't fit in the rest of the row is painted
+    pub(crate) fn set_hint_wrap(&mut self, hint_wrap: HintWrap) {
+        self.hint_wrap = hint_wrap;
+    }
+
+    /// Rows a menu may take up on the current screen
+    pub(crate) fn menu_max_rows(&self) -> u16 {
+        self.menu_max_height
+            .map_or(u16::MAX, |max_height| max_height.rows(self.screen_height()))
+    }
+
     /// Check if the currently painted content exceeds the size of the screen
     /// and thus should not be repainted without reason (disable animation
     /// repaint)
@@ -136,12 +277,41 @@ impl Painter {
         let screen_width = self.screen_width();
         let screen_height = self.screen_height();
 
+        let truncated_lines;
+        let lines = match self.hint_wrap {
+            HintWrap::Wrap => lines,
+            HintWrap::TruncateWithEllipsis => {
+                let mut lines = lines.clone();
+                lines.truncate_hint(screen_width);
+                truncated_lines = lines;
+                &truncated_lines
+            }
+        };
+
         // Lines and distance parameters
         let remaining_lines = self.remaining_lines();
-        let required_lines = lines.required_lines(screen_width, menu);
+        let mut required_lines = lines.required_lines(screen_width, menu, self.menu_max_rows());
+
+        // A menu that doesn't fit below the prompt is flipped above it when the
+        // rows there can hold it, so the prompt stays where it is
+        self.clear_menu_above()?;
+        if let Some(menu) = menu {
+            let menu_rows = menu
+                .menu_required_lines(screen_width)
+                .min(self.menu_max_rows());
+            if self.menu_placement == MenuPlacement::AboveWhenNearBottom
+                && required_lines > remaining_lines
+                && required_lines - menu_rows <= remaining_lines
+                && menu_rows <= self.prompt_start_row
+            {
+                self.menu_rows_above = menu_rows;
+                required_lines -= menu_rows;
+            }
+        }
 
         // Marking the painter state as larger buffer to avoid animations
         self.large_buffer = required_lines >= screen_height;
+        self.menu_start_row = None;
 
         // Moving the start position of the cursor based on the size of the required lines
         if self.large_buffer {
@@ -167,6 +337,17 @@ impl Painter {
         // The last_required_lines is used to move the cursor at the end where stdout
         // can print without overwriting the things written during the painting
         self.last_required_lines = required_lines;
+        self.cursor_row = if self.large_buffer {
+            self.scroll_position(lines, menu).cursor_row
+        } else {
+            self.prompt_start_row + lines.distance_from_prompt(screen_width)
+        };
+        // A full row leaves the cursor on its last column until the next character
+        self.cursor_column = Some(
+            lines
+                .cursor_column(screen_width)
+                .min(screen_width.saturating_sub(1)),
+        );
 
         self.stdout.queue(RestorePosition)?.queue(cursor::Show)?;
 
@@ -194,23 +375,15 @@ impl Painter {
     fn print_menu(
         &mut self,
         menu: &dyn Menu,
-        lines: &PromptLines,
+        starting_row: u16,
         use_ansi_coloring: bool,
     ) -> Result<()> {
-        let screen_width = self.screen_width();
         let screen_height = self.screen_height();
-        let cursor_distance = lines.distance_from_prompt(screen_width);
-
-        // If there is not enough space to print the menu, then the starting
-        // drawing point for the menu will overwrite the last rows in the buffer
-        let starting_row = if cursor_distance >= screen_height.saturating_sub(1) {
-            screen_height.saturating_sub(menu.min_rows())
-        } else {
-            self.prompt_start_row + cursor_distance + 1
-        };
-
-        let remaining_lines = screen_height.saturating_sub(starting_row);
+        let remaining_lines = screen_height
+            .saturating_sub(starting_row)
+            .min(self.menu_max_rows());
         let menu_string = menu.menu_string(remaining_lines, use_ansi_coloring);
+        self.menu_start_row = Some(starting_row);
         self.stdout
             .queue(cursor::MoveTo(0, starting_row))?
             .queue(Clear(ClearType::FromCursorDown))?
@@ -219,6 +392,36 @@ impl Painter {
         Ok(())
     }
 
+    /// Paints the menu on the rows right above the prompt, line by line to leave
+    /// the prompt below untouched
+    fn print_menu_above(&mut self, menu: &dyn Menu, use_ansi_coloring: bool) -> Result<()> {
+        let starting_row = self.prompt_start_row - self.menu_rows_above;
+        let menu_string = menu.menu_string(self.menu_rows_above, use_ansi_coloring);
+        self.menu_start_row = Some(starting_row);
+
+        for (row, line) in (starting_row..self.prompt_start_row).zip(menu_string.lines()) {
+            self.stdout
+                .queue(cursor::MoveTo(0, row))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(Print(line))?;
+        }
+
+        Ok(())
+    }
+
+    /// Clears the rows a menu was painted on above the prompt
+    fn clear_menu_above(&mut self) -> Result<()> {
+        let starting_row = self.prompt_start_row.saturating_sub(self.menu_rows_above);
+        for row in starting_row..self.prompt_start_row {
+            self.stdout
+                .queue(cursor::MoveTo(0, row))?
+                .queue(Clear(ClearType::CurrentLine))?;
+        }
+        self.menu_rows_above = 0;
+
+        Ok(())
+    }
+
     fn print_small_buffer(
         &mut self,
         prompt: &dyn Prompt,
@@ -235,9 +438,10 @@ impl Painter {
         self.stdout
             .queue(Print(&coerce_crlf(&lines.prompt_str_left)))?;
 
-        let prompt_indicator = match menu {
-            Some(menu) => menu.indicator(),
-            None => &lines.prompt_indicator,
+        let prompt_indicator = match (menu, &lines.menu_indicator) {
+            (Some(_), Some(menu_indicator)) => menu_indicator,
+            (Some(menu), None) => menu.indicator(),
+            (None, _) => &lines.prompt_indicator,
         };
         self.stdout.queue(Print(&coerce_crlf(prompt_indicator)))?;
 
@@ -252,15 +456,32 @@ impl Painter {
             .queue(SavePosition)?
             .queue(Print(&lines.after_cursor))?;
 
-        if let Some(menu) = menu {
-            self.print_menu(menu, lines, use_ansi_coloring)?;
+        if let Some(menu) = menu.filter(|_| self.menu_rows_above > 0) {
+            self.print_menu_above(menu, use_ansi_coloring)?;
+        } else if let Some(menu) = menu {
+            let screen_height = self.screen_height();
+            let cursor_distance = lines.distance_from_prompt(self.screen_width());
+
+            // If there is not enough space to print the menu, then the starting
+            // drawing point for the menu will overwrite the last rows in the buffer
+            let starting_row = if cursor_distance >= screen_height.saturating_sub(1) {
+                screen_height.saturating_sub(menu.min_rows().min(self.menu_max_rows()))
+            } else {
+                self.prompt_start_row + cursor_distance + 1
+            };
+            self.print_menu(menu, starting_row, use_ansi_coloring)?;
         } else {
-            self.stdout.queue(Print(&lines.hint))?;
+            self.stdout
+                .queue(Print(&lines.hint))?
+                .queue(Print(&lines.below_input))?;
         }
 
         Ok(())
     }
 
+    /// Paints content that doesn't fit on the screen. The content is scrolled
+    /// so that the cursor stays visible and nothing is printed above the top
+    /// or below the bottom of the screen, which would scroll the terminal
     fn print_large_buffer(
         &mut self,
         prompt: &dyn Prompt,
@@ -270,25 +491,17 @@ impl Painter {
     ) -> Result<()> {
         let screen_width = self.screen_width();
         let screen_height = self.screen_height();
-        let cursor_distance = lines.distance_from_prompt(screen_width);
-        let remaining_lines = screen_height.saturating_sub(cursor_distance);
-
-        // Calculating the total lines before the cursor
-        // The -1 in the total_lines_before is there because the at least one line of the prompt
-        // indicator is printed in the same line as the first line of the buffer
-        let prompt_lines = lines.prompt_lines_with_wrap(screen_width) as usize;
+        let width = screen_width as usize;
+        let scroll = self.scroll_position(lines, menu);
 
-        let prompt_indicator = match menu {
-            Some(menu) => menu.indicator(),
-            None => &lines.prompt_indicator,
+        let prompt_indicator = match (menu, &lines.menu_indicator) {
+            (Some(_), Some(menu_indicator)) => menu_indicator,
+            (Some(menu), None) => menu.indicator(),
+            (None, _) => &lines.prompt_indicator,
         };
 
-        let prompt_indicator_lines = prompt_indicator.lines().count();
-        let before_cursor_lines = lines.before_cursor.lines().count();
-        let total_lines_before = prompt_lines + prompt_indicator_lines + before_cursor_lines - 1;
-
-        // Extra rows represent how many rows are "above" the visible area in the terminal
-        let extra_rows = (total_lines_before).saturating_sub(screen_height as usize);
+        let mut skip = scroll.offset as usize;
+        let mut column = 0;
 
         // print our prompt with color
         if use_ansi_coloring {
@@ -296,90 +509,80 @@ impl Painter {
                 .queue(SetForegroundColor(prompt.get_prompt_color()))?;
         }
 
-        // In case the prompt is made out of multiple lines, the prompt is split by
-        // lines and only the required ones are printed
-        let prompt_skipped = skip_buffer_lines(&lines.prompt_str_left, extra_rows, None);
+        let prompt_skipped = skip_rows(&lines.prompt_str_left, &mut skip, &mut column, width);
         self.stdout.queue(Print(&coerce_crlf(prompt_skipped)))?;
 
-        if extra_rows == 0 {
+        if scroll.offset == 0 {
             self.print_right_prompt(lines)?;
         }
 
-        // Adjusting extra_rows base on the calculated prompt line size
-        let extra_rows = extra_rows.saturating_sub(prompt_lines);
-
-        let indicator_skipped = skip_buffer_lines(prompt_indicator, extra_rows, None);
+        let indicator_skipped = skip_rows(prompt_indicator, &mut skip, &mut column, width);
         self.stdout.queue(Print(&coerce_crlf(indicator_skipped)))?;
 
         if use_ansi_coloring {
             self.stdout.queue(ResetColor)?;
         }
 
-        // The minimum number of lines from the menu are removed from the buffer if there is no more
-        // space to print the menu. This will only happen if the cursor is at the last line and
-        // it is a large buffer
-        let offset = menu.and_then(|menu| {
-            if cursor_distance >= screen_height.saturating_sub(1) {
-                let rows = lines
-                    .before_cursor
-                    .lines()
-                    .count()
-                    .saturating_sub(extra_rows)
-                    .saturating_sub(menu.min_rows() as usize);
-                Some(rows)
-            } else {
-                None
-            }
-        });
-
-        // Selecting the lines before the cursor that will be printed
-        let before_cursor_skipped = skip_buffer_lines(&lines.before_cursor, extra_rows, offset);
+        let before_cursor_skipped = skip_rows(&lines.before_cursor, &mut skip, &mut column, width);
         self.stdout.queue(Print(before_cursor_skipped))?;
         self.stdout.queue(SavePosition)?;
 
         if let Some(menu) = menu {
             // TODO: Also solve the difficult problem of displaying (parts of)
             // the content after the cursor with the completion menu
-            self.print_menu(menu, lines, use_ansi_coloring)?;
+            self.print_menu(menu, scroll.cursor_row + 1, use_ansi_coloring)?;
         } else {
-            // Selecting lines for the hint
-            // The -1 subtraction is done because the remaining lines consider the line where the
-            // cursor is located as a remaining line. That has to be removed to get the correct offset
-            // for the after-cursor and hint lines
-            let offset = remaining_lines.saturating_sub(1) as usize;
-            // Selecting lines after the cursor
-            let after_cursor_skipped = skip_buffer_lines(&lines.after_cursor, 0, Some(offset));
-            self.stdout.queue(Print(after_cursor_skipped))?;
-            // Hint lines
-            let hint_skipped = skip_buffer_lines(&lines.hint, 0, Some(offset));
-            self.stdout.queue(Print(hint_skipped))?;
+            // Only the rows below the cursor are left for the content after
+            // the cursor and the hint
+            let mut rows = screen_height.saturating_sub(scroll.cursor_row + 1) as usize;
+            let after_cursor = take_rows(&lines.after_cursor, &mut rows, &mut column, width);
+            self.stdout.queue(Print(after_cursor))?;
+            let hint = take_rows(&lines.hint, &mut rows, &mut column, width);
+            self.stdout.queue(Print(hint))?;
+            let below_input = take_rows(&lines.below_input, &mut rows, &mut column, width);
+            self.stdout.queue(Print(below_input))?;
         }
 
         Ok(())
     }
 
+    /// Scroll position of content that is painted from the top of the screen
+    pub(crate) fn scroll_position(
+        &self,
+        lines: &PromptLines,
+        menu: Option<&ReedlineMenu>,
+    ) -> ScrollPosition {
+        let cursor_distance = lines.distance_from_prompt(self.screen_width());
+        let reserved_rows = menu.map_or(0, |menu| menu.min_rows().min(self.menu_max_rows()));
+
+        ScrollPosition::new(cursor_distance, reserved_rows, self.screen_height())
+    }
+
     /// Updates prompt origin and offset to handle a screen resize event
+    ///
+    /// The input is wrapped at the new width by the following repaint, which also
+    /// scrolls the screen if the input no longer fits below the prompt origin
     pub(crate) fn handle_resize(&mut self, width: u16, height: u16) {
-        let prev_terminal_size = self.terminal_size;
-        let prev_prompt_row = self.prompt_start_row;
-
+        let prev_height = self.terminal_size.1;
         self.terminal_size = (width, height);
-        // TODO properly adjusting prompt_origin on resizing while lines > 1
-
-        if prev_prompt_row >= (height - 1) {
-            // Terminal is shrinking up
-            // FIXME: use actual prompt size at some point
-            // Note: you can't just subtract the offset from the origin,
-            // as we could be shrinking so fast that the offset we read back from
-            // crossterm is past where it would have been.
-            self.prompt_start_row = height - 2;
-        } else if prev_terminal_size.1 < height {
+
+        if height < prev_height {
+            // Terminal is shrinking up. The terminal keeps the cursor on the screen
+            // by pushing the rows at the top into the scrollback, which moves the
+            // prompt up by the same amount
+            let overflow = self.cursor_row.saturating_sub(height.saturating_sub(1));
+            self.prompt_start_row = self.prompt_start_row.saturating_sub(overflow);
+            self.cursor_row -= overflow;
+        } else if prev_height < height {
             // Terminal is growing down, so move the prompt down the same amount to make space
             // for history that's on the screen
             // Note: if the terminal doesn't have sufficient history, this will leave a trail
             // of previous prompts currently.
-            self.prompt_start_row = prev_prompt_row + (height - prev_terminal_size.1);
+            self.prompt_start_row = self.prompt_start_row.saturating_add(height - prev_height);
         }
+
+        // The prompt has to start on the screen
+        self.prompt_start_row = self.prompt_start_row.min(height.saturating_sub(1));
     }
 
     /// Writes `line` to the terminal with a following carriage return and newline
@@ -389,6 +592,32 @@ impl Painter {
         self.stdout.flush()
     }
 
+    /// Prints `messages` where the prompt starts, each on its own lines, and moves
+    /// the prompt below them. The prompt and buffer have to be repainted afterwards
+    pub(crate) fn print_above_prompt(&mut self, messages: &[String]) -> Result<()> {
+        self.stdout.queue(cursor::Hide)?;
+        self.clear_menu_above()?;
+        self.stdout
+            .queue(MoveTo(0, self.prompt_start_row))?
+            .queue(Clear(ClearType::FromCursorDown))?;
+
+        let mut rows = 0;
+        for message in messages {
+            let (breaks, _) = row_breaks(message, 0, self.screen_width() as usize);
+            rows += breaks.len() + 1;
+            self.stdout
+                .queue(Print(coerce_crlf(message)))?
+                .queue(Print("\r\n"))?;
+        }
+
+        // Printing below the last row scrolls the screen
+        let row = self.prompt_start_row as usize + rows;
+        self.prompt_start_row = row.min(self.screen_height().saturating_sub(1) as usize) as u16;
+        self.menu_start_row = None;
+
+        self.stdout.flush()
+    }
+
     /// Goes to the beginning of the next line
     ///
     /// Also works in raw mode
@@ -412,10 +641,66 @@ impl Painter {
         self.stdout.flush()
     }
 
+    /// Changes the shape of the cursor, `None` restores the default shape of the terminal
+    pub(crate) fn set_cursor_shape(&mut self, shape: Option<CursorShape>) -> Result<()> {
+        match shape {
+            Some(shape) => self.stdout.queue(SetCursorShape(shape))?,
+            // DECSCUSR with the parameter 0 selects the terminal's default cursor
+            None => self.stdout.queue(Print("\x1b[0 q"))?,
+        };
+
+        self.stdout.flush()
+    }
+
+    /// Gives the feedback of a rejected action in the selected style
+    pub(crate) fn ring_bell(&mut self, style: BellStyle) -> Result<()> {
+        match style {
+            BellStyle::None => {}
+            BellStyle::Audible => {
+                self.stdout.queue(Print('\x07'))?;
+            }
+            BellStyle::Visual => {
+                // DECSCNM switches the whole screen to reverse video, `end_visual_bell`
+                // switches it back
+                self.stdout.queue(Print("\x1b[?5h"))?;
+                self.visual_bell_deadline = Some(Instant::now() + VISUAL_BELL_DURATION);
+            }
+        }
+
+        self.stdout.flush()
+    }
+
+    /// When the screen inverted by the last visual bell has to be switched back
+    pub(crate) fn visual_bell_deadline(&self) -> Option<Instant> {
+        self.visual_bell_deadline
+    }
+
+    /// Switches the screen inverted by a visual bell back to normal video
+    pub(crate) fn end_visual_bell(&mut self) -> Result<()> {
+        if self.visual_bell_deadline.take().is_some() {
+            self.stdout.queue(Print("\x1b[?5l"))?;
+        }
+
+        self.stdout.flush()
+    }
+
+    /// Leaves the painted prompt and buffer in place and moves the prompt origin to the
+    /// row below them, the next repaint paints a fresh prompt there
+    pub(crate) fn start_prompt_below(&mut self) -> Result<()> {
+        self.clear_menu_above()?;
+        self.move_cursor_to_end()?;
+        let final_row = self.prompt_start_row + self.last_required_lines;
+        self.prompt_start_row = final_row.min(self.screen_height().saturating_sub(1));
+        self.menu_start_row = None;
+
+        Ok(())
+    }
+
     // The prompt is moved to the end of the buffer after the event was handled
     // If the prompt is in the middle of a multiline buffer, then the output to stdout
     // could overwrite the buffer writing
     pub(crate) fn move_cursor_to_end(&mut self) -> Result<()> {
+        self.cursor_column = None;
         let final_row = self.prompt_start_row + self.last_required_lines;
         let scroll = final_row.saturating_sub(self.screen_height() - 1);
         if scroll != 0 {
@@ -428,59 +713,399 @@ impl Painter {
     }
 }
 
+/// Everything a headless [`Painter`] has written so far
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct HeadlessOutput(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl HeadlessOutput {
+    /// Takes the output written since the last call
+    pub(crate) fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.0.lock().expect("poisoned output"));
+        String::from_utf8(bytes).expect("painted output is valid utf-8")
+    }
+}
+
+#[cfg(test)]
+impl Write for HeadlessOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .expect("poisoned output")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::StyledText;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::borrow::Cow;
+
+    fn skip_lines(text: &str, skip: usize) -> &str {
+        let (mut skip, mut column) = (skip, 0);
+        skip_rows(text, &mut skip, &mut column, 80).trim_end_matches('\n')
+    }
+
+    fn take_lines(text: &str, rows: usize) -> &str {
+        let (mut rows, mut column) = (rows, 0);
+        take_rows(text, &mut rows, &mut column, 80)
+    }
+
+    /// Painter simulating a terminal of the given size
+    fn painter_with_size(width: u16, height: u16) -> Painter {
+        let mut painter = Painter::new(std::io::BufWriter::new(Box::new(std::io::stderr())));
+        painter.terminal_size = (width, height);
+        painter
+    }
+
+    /// Paints `lines` of a buffer at `prompt_start_row`, resizes the screen and
+    /// paints them again, returning the prompt and cursor rows after the resize
+    fn resize_and_repaint(
+        prompt_start_row: u16,
+        lines: &PromptLines,
+        size: (u16, u16),
+        new_size: (u16, u16),
+    ) -> (u16, u16) {
+        let prompt = crate::DefaultPrompt::default();
+        let (mut painter, _output) = Painter::headless(size.0, size.1);
+        painter.prompt_start_row = prompt_start_row;
+        painter.repaint_buffer(&prompt, lines, None, false).unwrap();
+
+        painter.handle_resize(new_size.0, new_size.1);
+        painter.repaint_buffer(&prompt, lines, None, false).unwrap();
+
+        (painter.prompt_start_row, painter.cursor_row)
+    }
+
+    #[rstest]
+    // 102 columns take up two rows at a width of 80 and three at 40
+    #[case((80, 24), (40, 24), 5, (5, 7))]
+    #[case((40, 24), (80, 24), 5, (5, 6))]
+    // Rows above the cursor are pushed into the scrollback
+    #[case((80, 24), (80, 12), 20, (10, 11))]
+    #[case((80, 24), (80, 12), 5, (5, 6))]
+    // The prompt moves down with the rows pulled out of the scrollback
+    #[case((80, 24), (80, 30), 5, (11, 12))]
+    // The input no longer fits below the prompt and scrolls the screen
+    #[case((80, 24), (20, 24), 20, (18, 23))]
+    fn resizing_recomputes_the_layout(
+        #[case] size: (u16, u16),
+        #[case] new_size: (u16, u16),
+        #[case] prompt_start_row: u16,
+        #[case] expected: (u16, u16),
+    ) {
+        let before_cursor = "x".repeat(100);
+        let lines = prompt_lines(&before_cursor, "");
+
+        assert_eq!(
+            resize_and_repaint(prompt_start_row, &lines, size, new_size),
+            expected
+        );
+    }
+
+    #[test]
+    fn resizing_to_a_single_row_does_not_panic() {
+        let lines = prompt_lines("echo", "");
+
+        assert_eq!(resize_and_repaint(10, &lines, (80, 24), (80, 1)), (0, 0));
+    }
+
+    #[rstest]
+    #[case(0, &["one"], 1)]
+    #[case(5, &["one", "two"], 7)]
+    #[case(0, &["a\nb"], 2)]
+    // Wraps at 80 columns
+    #[case(0, &["0123456789012345678901234567890123456789012345678901234567890123456789012345678901"], 2)]
+    // The screen scrolls, the prompt starts on the last row
+    #[case(22, &["one", "two", "three"], 23)]
+    fn messages_above_the_prompt_move_it_down(
+        #[case] prompt_start_row: u16,
+        #[case] messages: &[&str],
+        #[case] expected: u16,
+    ) {
+        let (mut painter, output) = Painter::headless(80, 24);
+        painter.prompt_start_row = prompt_start_row;
+        let messages: Vec<String> = messages.iter().map(|m| m.to_string()).collect();
+
+        painter.print_above_prompt(&messages).unwrap();
+
+        assert_eq!(painter.prompt_start_row(), expected);
+        assert!(output
+            .take()
+            .ends_with(&format!("{}\r\n", coerce_crlf(messages.last().unwrap()))));
+    }
+
+    fn prompt_lines<'a>(before_cursor: &'a str, after_cursor: &'a str) -> PromptLines<'a> {
+        PromptLines {
+            prompt_str_left: Cow::Borrowed(""),
+            prompt_str_right: Cow::Borrowed(""),
+            prompt_indicator: Cow::Borrowed("> "),
+            menu_indicator: None,
+            before_cursor: coerce_crlf(before_cursor),
+            after_cursor: coerce_crlf(after_cursor),
+            hint: Cow::Borrowed(""),
+            below_input: Cow::Borrowed(""),
+        }
+    }
+
+    #[rstest]
+    #[case(HintWrap::Wrap, "commit --amend --no-edit", 2)]
+    #[case(HintWrap::TruncateWithEllipsis, "commit --amen…", 1)]
+    fn long_hint_at_a_narrow_width(
+        #[case] hint_wrap: HintWrap,
+        #[case] painted_hint: &str,
+        #[case] required_lines: u16,
+    ) {
+        let prompt = crate::DefaultPrompt::default();
+        let (mut painter, output) = Painter::headless(20, 10);
+        painter.set_hint_wrap(hint_wrap);
+        // On the last row a hint taking up more rows scrolls the screen
+        painter.prompt_start_row = 9;
+        let mut lines = prompt_lines("git ", "");
+        lines.hint = Cow::Borrowed("commit --amend --no-edit");
+
+        painter
+            .repaint_buffer(&prompt, &lines, None, false)
+            .unwrap();
+
+        let output = output.take();
+        assert!(output.contains(painted_hint));
+        assert_eq!(output.contains("--no-edit"), hint_wrap == HintWrap::Wrap);
+        assert_eq!(painter.last_required_lines, required_lines);
+        assert_eq!(painter.prompt_start_row, 10 - required_lines);
+        // The cursor stays behind the typed text
+        assert_eq!(painter.cursor_position(), Some((6, 10 - required_lines)));
+    }
+
+    #[test]
+    fn visual_bell_is_switched_back_later() {
+        let (mut painter, output) = Painter::headless(20, 10);
+
+        painter.ring_bell(BellStyle::Visual).unwrap();
+        assert_eq!(output.take(), "\x1b[?5h");
+        assert!(painter.visual_bell_deadline().is_some());
+
+        painter.end_visual_bell().unwrap();
+        assert_eq!(output.take(), "\x1b[?5l");
+        assert_eq!(painter.visual_bell_deadline(), None);
+
+        // Without a bell there is nothing to switch back
+        painter.end_visual_bell().unwrap();
+        assert_eq!(output.take(), "");
+    }
 
     #[test]
     fn test_skip_lines() {
         let string = "sentence1\nsentence2\nsentence3\n";
 
-        assert_eq!(skip_buffer_lines(string, 1, None), "sentence2\nsentence3");
-        assert_eq!(skip_buffer_lines(string, 2, None), "sentence3");
-        assert_eq!(skip_buffer_lines(string, 3, None), "");
-        assert_eq!(skip_buffer_lines(string, 4, None), "");
+        assert_eq!(skip_lines(string, 1), "sentence2\nsentence3");
+        assert_eq!(skip_lines(string, 2), "sentence3");
+        assert_eq!(skip_lines(string, 3), "");
+        assert_eq!(skip_lines(string, 4), "");
     }
 
     #[test]
     fn test_skip_lines_no_newline() {
         let string = "sentence1";
 
-        assert_eq!(skip_buffer_lines(string, 0, None), "sentence1");
-        assert_eq!(skip_buffer_lines(string, 1, None), "");
+        assert_eq!(skip_lines(string, 0), "sentence1");
+        assert_eq!(skip_lines(string, 1), "");
     }
 
     #[test]
-    fn test_skip_lines_with_limit() {
+    fn test_take_lines() {
         let string = "sentence1\nsentence2\nsentence3\nsentence4\nsentence5";
 
+        assert_eq!(take_lines(string, 0), "sentence1");
+        assert_eq!(take_lines(string, 1), "sentence1\nsentence2");
+        assert_eq!(take_lines(string, 2), "sentence1\nsentence2\nsentence3");
+        assert_eq!(take_lines(string, 10), string);
+    }
+
+    #[test]
+    fn test_skip_wrapped_rows() {
+        let (mut skip, mut column) = (3, 2);
+        // The first row holds 3 characters after the prompt of width 2
+        let skipped = skip_rows("abcdefghijklm\nnop", &mut skip, &mut column, 5);
+
+        assert_eq!(skipped, "nop");
+        assert_eq!((skip, column), (0, 3));
+    }
+
+    #[test]
+    fn test_skip_rows_spanning_texts() {
+        let (mut skip, mut column) = (2, 0);
+
+        assert_eq!(skip_rows("ab\ncd", &mut skip, &mut column, 5), "");
+        assert_eq!((skip, column), (1, 2));
+        assert_eq!(skip_rows("e\nfg", &mut skip, &mut column, 5), "fg");
+        assert_eq!((skip, column), (0, 2));
+    }
+
+    #[test]
+    fn test_take_rows_cuts_following_texts() {
+        let (mut rows, mut column) = (1, 0);
+
         assert_eq!(
-            skip_buffer_lines(string, 1, Some(1)),
-            "sentence2\nsentence3",
+            take_rows("abcdefghij", &mut rows, &mut column, 4),
+            "abcdefgh"
         );
+        assert_eq!(take_rows("xyz", &mut rows, &mut column, 4), "");
+    }
 
+    #[rstest]
+    #[case(3, 0, 10, 0, 3)]
+    #[case(9, 0, 10, 0, 9)]
+    #[case(10, 0, 10, 1, 9)]
+    #[case(25, 0, 10, 16, 9)]
+    #[case(25, 3, 10, 19, 6)]
+    #[case(5, 12, 10, 5, 0)]
+    #[case(0, 0, 0, 0, 0)]
+    fn test_scroll_position(
+        #[case] cursor_distance: u16,
+        #[case] reserved_rows: u16,
+        #[case] screen_height: u16,
+        #[case] offset: u16,
+        #[case] cursor_row: u16,
+    ) {
         assert_eq!(
-            skip_buffer_lines(string, 1, Some(2)),
-            "sentence2\nsentence3\nsentence4",
+            ScrollPosition::new(cursor_distance, reserved_rows, screen_height),
+            ScrollPosition { offset, cursor_row }
+        );
+    }
+
+    fn menu_with_values(count: usize) -> ReedlineMenu {
+        let words = (0..count).map(|i| format!("item{:02}", i)).collect();
+        let completer = crate::DefaultCompleter::new(words);
+        let mut line_buffer = crate::LineBuffer::new();
+        line_buffer.insert_str("item");
+
+        let mut menu = crate::CompletionMenu::default();
+        menu.update_values(
+            &mut line_buffer,
+            &crate::FileBackedHistory::default(),
+            &completer,
         );
+        ReedlineMenu::EngineCompleter(Box::new(menu))
+    }
 
+    #[rstest]
+    #[case(MaxHeight::Rows(3), 3)]
+    #[case(MaxHeight::Rows(100), 9)]
+    #[case(MaxHeight::Rows(0), 1)]
+    #[case(MaxHeight::Fraction(0.5), 5)]
+    #[case(MaxHeight::Fraction(1.0), 9)]
+    fn menu_max_height_keeps_the_prompt_visible(
+        #[case] max_height: MaxHeight,
+        #[case] expected: u16,
+    ) {
+        assert_eq!(max_height.rows(10), expected);
+    }
+
+    #[test]
+    fn large_menu_is_limited_on_small_terminal() {
+        let mut painter = painter_with_size(80, 10);
+        let menu = menu_with_values(100);
+        let lines = prompt_lines("item", "");
+
+        // Without a limit the menu needs more rows than the terminal has
+        assert!(lines.required_lines(80, Some(&menu), painter.menu_max_rows()) > 10);
+
+        painter.set_menu_max_height(Some(MaxHeight::Fraction(0.5)));
         assert_eq!(
-            skip_buffer_lines(string, 2, Some(1)),
-            "sentence3\nsentence4",
+            lines.required_lines(80, Some(&menu), painter.menu_max_rows()),
+            6
+        );
+        assert_eq!(
+            menu.menu_string(painter.menu_max_rows(), false)
+                .lines()
+                .count(),
+            5
         );
 
+        painter.set_menu_max_height(Some(MaxHeight::Rows(100)));
         assert_eq!(
-            skip_buffer_lines(string, 1, Some(10)),
-            "sentence2\nsentence3\nsentence4\nsentence5",
+            lines.required_lines(80, Some(&menu), painter.menu_max_rows()),
+            10
         );
+    }
 
+    #[test]
+    fn cursor_stays_visible_on_small_terminal() {
+        let painter = painter_with_size(10, 5);
+        let buffer = (0..20).map(|i| format!("line{}\n", i)).collect::<String>();
+        let (before_cursor, after_cursor) = buffer.split_at(buffer.len() / 2);
+        let lines = prompt_lines(before_cursor, after_cursor);
+
+        let scroll = painter.scroll_position(&lines, None);
+
+        // Cursor after the 10th newline
         assert_eq!(
-            skip_buffer_lines(string, 0, Some(1)),
-            "sentence1\nsentence2",
+            scroll,
+            ScrollPosition {
+                offset: 6,
+                cursor_row: 4
+            }
         );
+        assert!(scroll.cursor_row < painter.screen_height());
+    }
+
+    #[test]
+    fn wrapped_lines_are_scrolled_out_of_view() {
+        let painter = painter_with_size(10, 4);
+        // "> " and 38 characters wrap into 4 rows
+        let buffer = "x".repeat(38);
+        let lines = prompt_lines(&buffer, "");
+
+        let scroll = painter.scroll_position(&lines, None);
+
+        assert_eq!(
+            scroll,
+            ScrollPosition {
+                offset: 0,
+                cursor_row: 3
+            }
+        );
+
+        let buffer = "x".repeat(39);
+        let lines = prompt_lines(&buffer, "");
+        let scroll = painter.scroll_position(&lines, None);
+
+        assert_eq!(
+            scroll,
+            ScrollPosition {
+                offset: 1,
+                cursor_row: 3
+            }
+        );
+    }
 
-        assert_eq!(skip_buffer_lines(string, 0, Some(0)), "sentence1",);
-        assert_eq!(skip_buffer_lines(string, 1, Some(0)), "sentence2",);
+    #[rstest]
+    #[case("", 2, 1)]
+    #[case("... ", 2, 1)]
+    #[case("12345> ", 3, 2)]
+    fn continuation_prompt_takes_part_in_wrapping(
+        #[case] continuation: &'static str,
+        #[case] required_lines: u16,
+        #[case] cursor_distance: u16,
+    ) {
+        let mut buffer = StyledText::new();
+        buffer.push((nu_ansi_term::Style::new(), "abc\nxxxx".into()));
+        let (before_cursor, after_cursor) =
+            buffer.render_around_insertion_point(9, continuation, false);
+        let lines = prompt_lines(&before_cursor, &after_cursor);
+
+        // "> abc" and the continuation line wrapping at 10 columns
+        assert_eq!(lines.required_lines(10, None, u16::MAX), required_lines);
+        assert_eq!(lines.distance_from_prompt(10), cursor_distance);
     }
 }
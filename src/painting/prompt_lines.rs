@@ -1,19 +1,56 @@
-use super::utils::{coerce_crlf, estimate_required_lines, line_width};
+use super::utils::{coerce_crlf, line_width, row_breaks, truncate_with_ellipsis};
 use crate::{
     menu::{Menu, ReedlineMenu},
     prompt::PromptEditMode,
     Prompt, PromptHistorySearch,
 };
+use crossterm::{style::SetForegroundColor, Command};
+use nu_ansi_term::Style;
 use std::borrow::Cow;
 
+/// Renders the styled left prompt. Parts in the default style are painted in the
+/// prompt color, which is set again after every styled part
+fn render_prompt_left(prompt: &dyn Prompt, use_ansi_coloring: bool) -> String {
+    let styled_prompt = prompt.render_prompt_styled();
+    if !use_ansi_coloring {
+        return styled_prompt.raw_string();
+    }
+
+    let mut prompt_color = String::new();
+    let _ = SetForegroundColor(prompt.get_prompt_color()).write_ansi(&mut prompt_color);
+
+    let mut rendered = String::new();
+    let mut color_reset = false;
+    for (style, text) in &styled_prompt {
+        if *style == Style::new() {
+            if color_reset {
+                rendered.push_str(&prompt_color);
+                color_reset = false;
+            }
+            rendered.push_str(text);
+        } else {
+            rendered.push_str(&style.paint(text).to_string());
+            color_reset = true;
+        }
+    }
+
+    rendered
+}
+
 /// Aggregate of prompt and input string used by `Painter`
+#[derive(Clone)]
 pub(crate) struct PromptLines<'prompt> {
     pub(crate) prompt_str_left: Cow<'prompt, str>,
     pub(crate) prompt_str_right: Cow<'prompt, str>,
     pub(crate) prompt_indicator: Cow<'prompt, str>,
+    /// Shown instead of the indicator of the open menu
+    pub(crate) menu_indicator: Option<Cow<'prompt, str>>,
     pub(crate) before_cursor: Cow<'prompt, str>,
     pub(crate) after_cursor: Cow<'prompt, str>,
     pub(crate) hint: Cow<'prompt, str>,
+    /// Rows painted below the input in place of a menu, like the matches listed by the
+    /// reverse history search. Unlike the hint they are never truncated
+    pub(crate) below_input: Cow<'prompt, str>,
 }
 
 impl<'prompt> PromptLines<'prompt> {
@@ -27,8 +64,9 @@ impl<'prompt> PromptLines<'prompt> {
         before_cursor: &'prompt str,
         after_cursor: &'prompt str,
         hint: &'prompt str,
+        use_ansi_coloring: bool,
     ) -> Self {
-        let prompt_str_left = prompt.render_prompt_left();
+        let prompt_str_left = Cow::Owned(render_prompt_left(prompt, use_ansi_coloring));
         let prompt_str_right = prompt.render_prompt_right();
 
         let prompt_indicator = match history_indicator {
@@ -44,22 +82,36 @@ impl<'prompt> PromptLines<'prompt> {
             prompt_str_left,
             prompt_str_right,
             prompt_indicator,
+            menu_indicator: None,
             before_cursor,
             after_cursor,
             hint,
+            below_input: Cow::Borrowed(""),
         }
     }
 
+    /// Sets the rows painted below the input, each starting with a line break
+    pub(crate) fn set_below_input(&mut self, below_input: &str) {
+        self.below_input = Cow::Owned(coerce_crlf(below_input).into_owned());
+    }
+
     /// The required lines to paint the buffer are calculated by counting the
     /// number of newlines in all the strings that form the prompt and buffer.
     /// The plus 1 is to indicate that there should be at least one line.
-    pub(crate) fn required_lines(&self, terminal_columns: u16, menu: Option<&ReedlineMenu>) -> u16 {
+    /// The menu takes up at most `max_menu_rows`
+    pub(crate) fn required_lines(
+        &self,
+        terminal_columns: u16,
+        menu: Option<&ReedlineMenu>,
+        max_menu_rows: u16,
+    ) -> u16 {
         let input = if menu.is_none() {
             self.prompt_str_left.to_string()
                 + &self.prompt_indicator
                 + &self.before_cursor
                 + &self.after_cursor
                 + &self.hint
+                + &self.below_input
         } else {
             self.prompt_str_left.to_string()
                 + &self.prompt_indicator
@@ -67,28 +119,49 @@ impl<'prompt> PromptLines<'prompt> {
                 + &self.after_cursor
         };
 
-        let lines = estimate_required_lines(&input, terminal_columns);
+        let (breaks, _) = row_breaks(&input, 0, terminal_columns as usize);
+        let lines = breaks.len() + 1;
 
         if let Some(menu) = menu {
-            lines as u16 + menu.menu_required_lines(terminal_columns)
+            lines as u16
+                + menu
+                    .menu_required_lines(terminal_columns)
+                    .min(max_menu_rows)
         } else {
             lines as u16
         }
     }
 
+    /// Cuts the hint at the end of the row it starts in, see [`HintWrap::TruncateWithEllipsis`]
+    ///
+    /// [`HintWrap::TruncateWithEllipsis`]: crate::HintWrap::TruncateWithEllipsis
+    pub(crate) fn truncate_hint(&mut self, terminal_columns: u16) {
+        let input = self.prompt_str_left.to_string()
+            + &self.prompt_indicator
+            + &self.before_cursor
+            + &self.after_cursor;
+        let (_, column) = row_breaks(&input, 0, terminal_columns as usize);
+        let columns = (terminal_columns as usize).saturating_sub(column);
+
+        if let Cow::Owned(hint) = truncate_with_ellipsis(&self.hint, columns) {
+            self.hint = Cow::Owned(hint);
+        }
+    }
+
     /// Estimated distance of the cursor to the prompt.
-    /// This considers line wrapping
+    /// This considers line wrapping and a cursor placed after a trailing newline
     pub(crate) fn distance_from_prompt(&self, terminal_columns: u16) -> u16 {
         let input = self.prompt_str_left.to_string() + &self.prompt_indicator + &self.before_cursor;
-        let lines = estimate_required_lines(&input, terminal_columns);
-        lines.saturating_sub(1) as u16
+        let (breaks, _) = row_breaks(&input, 0, terminal_columns as usize);
+        breaks.len().min(u16::MAX as usize) as u16
     }
 
-    /// Total lines that the prompt uses considering that it may wrap the screen
-    pub(crate) fn prompt_lines_with_wrap(&self, screen_width: u16) -> u16 {
-        let complete_prompt = self.prompt_str_left.to_string() + &self.prompt_indicator;
-        let lines = estimate_required_lines(&complete_prompt, screen_width);
-        lines.saturating_sub(1) as u16
+    /// Column of the cursor, counted from the start of its row.
+    /// Like [`PromptLines::distance_from_prompt()`] it considers line wrapping
+    pub(crate) fn cursor_column(&self, terminal_columns: u16) -> u16 {
+        let input = self.prompt_str_left.to_string() + &self.prompt_indicator + &self.before_cursor;
+        let (_, column) = row_breaks(&input, 0, terminal_columns as usize);
+        column.min(u16::MAX as usize) as u16
     }
 
     /// Estimated width of the actual input
@@ -117,3 +190,75 @@ impl<'prompt> PromptLines<'prompt> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StyledText, ValidationResult};
+    use nu_ansi_term::Color;
+    use pretty_assertions::assert_eq;
+
+    /// Prompt with a styled part in the middle
+    struct StyledPrompt;
+
+    impl Prompt for StyledPrompt {
+        fn render_prompt_left(&self) -> Cow<str> {
+            "a b c".into()
+        }
+
+        fn render_prompt_right(&self) -> Cow<str> {
+            "".into()
+        }
+
+        fn render_prompt_styled(&self) -> StyledText {
+            let mut styled_prompt = StyledText::new();
+            styled_prompt.push_str(Style::new(), "a ");
+            styled_prompt.push_str(Color::Red.normal(), "b");
+            styled_prompt.push_str(Style::new(), " c");
+            styled_prompt
+        }
+
+        fn render_prompt_indicator(&self, _: PromptEditMode) -> Cow<str> {
+            "> ".into()
+        }
+
+        fn render_prompt_multiline_indicator(&self, _: ValidationResult) -> Cow<str> {
+            "::: ".into()
+        }
+
+        fn render_prompt_history_search_indicator(&self, _: PromptHistorySearch) -> Cow<str> {
+            "? ".into()
+        }
+    }
+
+    #[test]
+    fn unstyled_prompt_parts_get_the_prompt_color_back() {
+        let mut prompt_color = String::new();
+        SetForegroundColor(StyledPrompt.get_prompt_color())
+            .write_ansi(&mut prompt_color)
+            .unwrap();
+
+        assert_eq!(
+            render_prompt_left(&StyledPrompt, true),
+            format!("a {}{} c", Color::Red.paint("b"), prompt_color)
+        );
+        assert_eq!(render_prompt_left(&StyledPrompt, false), "a b c");
+    }
+
+    #[test]
+    fn styling_takes_no_columns() {
+        let lines = PromptLines::new(
+            &StyledPrompt,
+            PromptEditMode::Default,
+            None,
+            "ls",
+            "",
+            "",
+            true,
+        );
+
+        // "a b c> ls"
+        assert_eq!(lines.estimate_first_input_line_width(), 9);
+        assert_eq!(lines.required_lines(9, None, u16::MAX), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use super::utils::strip_ansi;
 use nu_ansi_term::{Color, Style};
+use std::{borrow::Cow, ops::Range};
 
 /// A representation of a buffer with styling, used for doing syntax highlighting
 pub struct StyledText {
@@ -24,6 +25,90 @@ impl StyledText {
         self.buffer.push(styled_string);
     }
 
+    /// Add `text` in `style` to the end, merging it into the last part if that has the same style
+    pub fn push_str(&mut self, style: Style, text: &str) {
+        match self.buffer.last_mut() {
+            Some((last_style, last_text)) if *last_style == style => last_text.push_str(text),
+            _ => self.buffer.push((style, text.to_string())),
+        }
+    }
+
+    /// Append the parts of `other`, merging adjacent parts with the same style
+    pub fn extend(&mut self, other: StyledText) {
+        for (style, text) in other.buffer {
+            self.push_str(style, &text);
+        }
+    }
+
+    /// Length of the unformatted text in bytes
+    pub fn len(&self) -> usize {
+        self.buffer.iter().map(|(_, text)| text.len()).sum()
+    }
+
+    /// Whether there is no text, parts without text don't count
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Change the style of the text in the byte `range` with `restyle`, splitting
+    /// the parts at the edges of the range
+    ///
+    /// Edges inside a character are widened to restyle the whole character, an empty
+    /// range or one ending before its start restyles nothing
+    pub fn restyle_range(&mut self, range: Range<usize>, restyle: impl Fn(Style) -> Style) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut restyled = StyledText::new();
+        let mut start = 0;
+
+        for (style, text) in self.buffer.drain(..) {
+            let end = start + text.len();
+            let mut inner_start = range.start.clamp(start, end) - start;
+            while !text.is_char_boundary(inner_start) {
+                inner_start -= 1;
+            }
+            let mut inner_end = range.end.clamp(start, end) - start;
+            while !text.is_char_boundary(inner_end) {
+                inner_end += 1;
+            }
+
+            for (style, text) in [
+                (style, &text[..inner_start]),
+                (restyle(style), &text[inner_start..inner_end]),
+                (style, &text[inner_end..]),
+            ] {
+                if !text.is_empty() {
+                    restyled.push_str(style, text);
+                }
+            }
+            start = end;
+        }
+
+        *self = restyled;
+    }
+
+    /// Lay the styles of `other` over the same byte ranges of this text, keeping the text
+    ///
+    /// Parts of `other` in the default style are transparent and leave the style below
+    /// unchanged. Parts beyond the end of this text are ignored.
+    pub fn overlay(&mut self, other: &StyledText) {
+        let mut start = 0;
+        for (style, text) in &other.buffer {
+            let end = start + text.len();
+            if *style != Style::default() {
+                self.restyle_range(start..end, |_| *style);
+            }
+            start = end;
+        }
+    }
+
+    /// Iterate over the styled parts of the text
+    pub fn iter(&self) -> std::slice::Iter<'_, (Style, String)> {
+        self.buffer.iter()
+    }
+
     /// Render the styled string. We use the insertion point to render around so that
     /// we can properly write out the styled string to the screen and find the correct
     /// place to put the cursor. This assumes a logic that prints the first part of the
@@ -31,21 +116,53 @@ impl StyledText {
     /// the cursor position
     ///
     /// Also inserts the multiline continuation prompt
+    ///
+    /// The text is rendered in its logical order. Bidirectional text is not
+    /// reordered for display, so right-to-left runs are left to the terminal
     pub fn render_around_insertion_point(
         &self,
         insertion_point: usize,
         multiline_prompt: &str,
         use_ansi_coloring: bool,
+    ) -> (String, String) {
+        self.render_around_insertion_point_with(
+            insertion_point,
+            |_| Cow::Borrowed(multiline_prompt),
+            use_ansi_coloring,
+        )
+    }
+
+    /// Render the styled string around the insertion point like
+    /// [`StyledText::render_around_insertion_point`]
+    ///
+    /// Inserts the continuation prompt returned by `continuation_prompt` for
+    /// every line after an explicit newline, given the index of that line
+    pub fn render_around_insertion_point_with<'prompt>(
+        &self,
+        insertion_point: usize,
+        continuation_prompt: impl Fn(usize) -> Cow<'prompt, str>,
+        use_ansi_coloring: bool,
     ) -> (String, String) {
         let mut current_idx = 0;
+        let mut line_number = 0;
         let mut left_string = String::new();
         let mut right_string = String::new();
         let prompt_style = Style::new().fg(Color::LightBlue);
         for pair in &self.buffer {
             if current_idx >= insertion_point {
-                right_string.push_str(&render_as_string(pair, &prompt_style, multiline_prompt));
+                right_string.push_str(&render_as_string(
+                    pair,
+                    &prompt_style,
+                    &continuation_prompt,
+                    &mut line_number,
+                ));
             } else if pair.1.len() + current_idx <= insertion_point {
-                left_string.push_str(&render_as_string(pair, &prompt_style, multiline_prompt));
+                left_string.push_str(&render_as_string(
+                    pair,
+                    &prompt_style,
+                    &continuation_prompt,
+                    &mut line_number,
+                ));
             } else if pair.1.len() + current_idx > insertion_point {
                 let offset = insertion_point - current_idx;
 
@@ -55,12 +172,14 @@ impl StyledText {
                 left_string.push_str(&render_as_string(
                     &(pair.0, left_side),
                     &prompt_style,
-                    multiline_prompt,
+                    &continuation_prompt,
+                    &mut line_number,
                 ));
                 right_string.push_str(&render_as_string(
                     &(pair.0, right_side),
                     &prompt_style,
-                    multiline_prompt,
+                    &continuation_prompt,
+                    &mut line_number,
                 ));
             }
             current_idx += pair.1.len();
@@ -87,18 +206,219 @@ impl StyledText {
     }
 }
 
-fn render_as_string(
+impl<'a> IntoIterator for &'a StyledText {
+    type Item = &'a (Style, String);
+    type IntoIter = std::slice::Iter<'a, (Style, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for StyledText {
+    type Item = (Style, String);
+    type IntoIter = std::vec::IntoIter<(Style, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.buffer.into_iter()
+    }
+}
+
+/// Renders a styled part of the buffer, `line_number` keeps track of the buffer line
+/// across the parts
+fn render_as_string<'prompt>(
     renderable: &(Style, String),
     prompt_style: &Style,
-    multiline_prompt: &str,
+    continuation_prompt: impl Fn(usize) -> Cow<'prompt, str>,
+    line_number: &mut usize,
 ) -> String {
     let mut rendered = String::new();
-    let formatted_multiline_prompt = format!("\n{}", multiline_prompt);
-    for (line_number, line) in renderable.1.split('\n').enumerate() {
-        if line_number != 0 {
-            rendered.push_str(&prompt_style.paint(&formatted_multiline_prompt).to_string());
+    for (index, line) in renderable.1.split('\n').enumerate() {
+        if index != 0 {
+            *line_number += 1;
+            let formatted_prompt = format!("\n{}", continuation_prompt(*line_number));
+            rendered.push_str(&prompt_style.paint(&formatted_prompt).to_string());
         }
         rendered.push_str(&renderable.0.paint(line).to_string());
     }
     rendered
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn numbered(line_number: usize) -> Cow<'static, str> {
+        Cow::Owned(format!("{}> ", line_number))
+    }
+
+    #[test]
+    fn continuation_prompt_is_numbered_across_styled_parts() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "a\nb".into()));
+        text.push((Style::new().bold(), "c\n".into()));
+        text.push((Style::new(), "d".into()));
+
+        let (before, after) = text.render_around_insertion_point_with(5, numbered, false);
+        assert_eq!(before, "a\n1> bc\n2> ");
+        assert_eq!(after, "d");
+    }
+
+    #[test]
+    fn restyled_range_splits_the_parts() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "echo ".into()));
+        text.push((Style::new().bold(), "(ls))".into()));
+
+        text.restyle_range(3..9, |style| style.underline());
+        assert_eq!(
+            text.buffer,
+            vec![
+                (Style::new(), "ech".into()),
+                (Style::new().underline(), "o ".into()),
+                (Style::new().bold().underline(), "(ls)".into()),
+                (Style::new().bold(), ")".into()),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn restyled_range_is_widened_to_whole_characters() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "aöb".into()));
+
+        text.restyle_range(2..3, |style| style.underline());
+        assert_eq!(
+            text.buffer,
+            vec![
+                (Style::new(), "a".into()),
+                (Style::new().underline(), "ö".into()),
+                (Style::new(), "b".into()),
+            ]
+        );
+
+        let mut text = StyledText::new();
+        text.push((Style::new(), "aöb".into()));
+        text.restyle_range(3..1, |style| style.underline());
+        assert_eq!(text.buffer, vec![(Style::new(), "aöb".into())]);
+    }
+
+    #[test]
+    fn overlay_replaces_styles_except_for_default_parts() {
+        let red = Style::new().fg(Color::Red);
+        let green = Style::new().fg(Color::Green);
+        let mut text = StyledText::new();
+        text.push((red, "git ".into()));
+        text.push((Style::new().bold(), "status".into()));
+        let mut overlay = StyledText::new();
+        overlay.push((Style::new(), "gi".into()));
+        overlay.push((green, "t st".into()));
+        overlay.push((Style::new(), "atus and more".into()));
+
+        text.overlay(&overlay);
+        assert_eq!(
+            text.buffer,
+            vec![
+                (red, "gi".into()),
+                (green, "t st".into()),
+                (Style::new().bold(), "atus".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlay_styles_whole_characters() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "aöb".into()));
+        let mut overlay = StyledText::new();
+        overlay.push((Style::new(), "a".into()));
+        overlay.push((Style::new().bold(), "b".into()));
+
+        text.overlay(&overlay);
+        assert_eq!(
+            text.buffer,
+            vec![
+                (Style::new(), "a".into()),
+                (Style::new().bold(), "ö".into()),
+                (Style::new(), "b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rtl_text_is_split_at_the_logical_insertion_point() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "echo مرحبا world".into()));
+
+        let (before, after) = text.render_around_insertion_point(9, "", false);
+        assert_eq!(before, "echo مر");
+        assert_eq!(after, "حبا world");
+        assert_eq!(before + &after, "echo مرحبا world");
+    }
+
+    #[test]
+    fn push_str_merges_parts_with_the_same_style() {
+        let bold = Style::new().bold();
+        let mut text = StyledText::new();
+        text.push_str(bold, "ec");
+        text.push_str(bold, "ho");
+        text.push_str(Style::new(), " ");
+        text.push_str(bold, "hi");
+
+        assert_eq!(
+            text.buffer,
+            vec![
+                (bold, "echo".to_string()),
+                (Style::new(), " ".to_string()),
+                (bold, "hi".to_string())
+            ]
+        );
+        assert_eq!(text.len(), 7);
+    }
+
+    #[test]
+    fn extend_concatenates_and_merges_at_the_boundary() {
+        let red = Style::new().fg(Color::Red);
+        let mut text = StyledText::new();
+        text.push_str(Style::new(), "ls ");
+        text.push_str(red, "-l");
+        let mut other = StyledText::new();
+        other.push_str(red, "a");
+        other.push_str(Style::new(), " ~");
+
+        text.extend(other);
+
+        let segments: Vec<(Style, &str)> = text
+            .iter()
+            .map(|(style, text)| (*style, text.as_str()))
+            .collect();
+        assert_eq!(
+            segments,
+            vec![(Style::new(), "ls "), (red, "-la"), (Style::new(), " ~")]
+        );
+        assert_eq!(text.raw_string(), "ls -la ~");
+        assert_eq!(text.render_simple(), format!("ls {} ~", red.paint("-la")));
+    }
+
+    #[test]
+    fn empty_parts_do_not_count_towards_the_length() {
+        let mut text = StyledText::new();
+        assert!(text.is_empty());
+
+        text.push((Style::new().bold(), String::new()));
+        assert!(text.is_empty());
+        assert_eq!(text.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn continuation_prompt_is_numbered_around_the_cursor() {
+        let mut text = StyledText::new();
+        text.push((Style::new(), "a\nb\nc".into()));
+
+        let (before, after) = text.render_around_insertion_point_with(3, numbered, false);
+        assert_eq!(before, "a\n1> b");
+        assert_eq!(after, "\n2> c");
+    }
+}
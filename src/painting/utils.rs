@@ -1,5 +1,6 @@
 use std::borrow::Cow;
-use unicode_width::UnicodeWidthStr;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
 /// Ensures input uses CRLF line endings.
 ///
@@ -17,11 +18,13 @@ pub(crate) fn coerce_crlf(input: &str) -> Cow<str> {
                 // optimization for `Cow<str>.is_empty` that would replace the
                 // preallocation
                 owned.push_str(&input[cursor..idx]);
+                // Pushed here, adding to an empty `Cow` would borrow the "\r\n"
+                owned.push_str("\r\n");
                 result = Cow::Owned(owned);
             } else {
                 result += &input[cursor..idx];
+                result += "\r\n";
             }
-            result += "\r\n";
             // Advance beyond the matched LF char (single byte)
             cursor = idx + 1;
         }
@@ -66,9 +69,178 @@ pub(crate) fn estimate_single_line_wraps(line: &str, terminal_columns: u16) -> u
     estimated_line_count.saturating_sub(1)
 }
 
+/// Breaks `text` into the rows it occupies when printed from `column` on a
+/// terminal `terminal_columns` wide.
+///
+/// Every break is given as the byte offsets `(end of the row, start of the next row)`,
+/// which differ for line breaks and are equal for wrapped lines. ANSI escape
+/// sequences take no space. Also returns the column where the printed text ends.
+pub(crate) fn row_breaks(
+    text: &str,
+    mut column: usize,
+    terminal_columns: usize,
+) -> (Vec<(usize, usize)>, usize) {
+    let terminal_columns = terminal_columns.max(1);
+    let mut breaks = Vec::new();
+    let mut graphemes = text.grapheme_indices(true).peekable();
+
+    while let Some((index, grapheme)) = graphemes.next() {
+        match grapheme {
+            "\x1b" => {
+                if let Some((_, "[")) = graphemes.peek() {
+                    graphemes.next();
+                    for (_, grapheme) in graphemes.by_ref() {
+                        if grapheme.len() == 1
+                            && (b'\x40'..=b'\x7e').contains(&grapheme.as_bytes()[0])
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            "\n" | "\r\n" => {
+                breaks.push((index, index + grapheme.len()));
+                column = 0;
+            }
+            "\r" => {}
+            grapheme => {
+                let width = grapheme_width(grapheme);
+                if column + width > terminal_columns {
+                    breaks.push((index, index));
+                    column = 0;
+                }
+                column += width;
+            }
+        }
+    }
+
+    (breaks, column)
+}
+
+/// Cuts `text` to at most `columns` wide, replacing the end of the cut text with `…`.
+/// Text is also cut at its first line break. ANSI escape sequences are kept,
+/// so styles opened in the text are still closed
+pub(crate) fn truncate_with_ellipsis(text: &str, columns: usize) -> Cow<'_, str> {
+    let fits = !text.contains('\n') && line_width(text) <= columns;
+    if fits {
+        return Cow::Borrowed(text);
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    let mut cut = false;
+    let mut graphemes = text.graphemes(true).peekable();
+
+    while let Some(grapheme) = graphemes.next() {
+        match grapheme {
+            "\x1b" => {
+                truncated.push_str(grapheme);
+                if let Some(&"[") = graphemes.peek() {
+                    truncated.push('[');
+                    graphemes.next();
+                    for grapheme in graphemes.by_ref() {
+                        truncated.push_str(grapheme);
+                        if grapheme.len() == 1
+                            && (b'\x40'..=b'\x7e').contains(&grapheme.as_bytes()[0])
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ if cut => {}
+            grapheme => {
+                let grapheme_width = grapheme_width(grapheme);
+                if grapheme == "\n" || grapheme == "\r\n" || width + grapheme_width + 1 > columns {
+                    if columns > width {
+                        truncated.push('…');
+                    }
+                    cut = true;
+                } else {
+                    truncated.push_str(grapheme);
+                    width += grapheme_width;
+                }
+            }
+        }
+    }
+
+    Cow::Owned(truncated)
+}
+
+/// Display width of a single grapheme cluster
+///
+/// Combining characters take no space, while emoji sequences joined by zero
+/// width joiners or using the emoji presentation selector are drawn as a single
+/// wide character
+pub(crate) fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let is_sequence = chars.next().is_some() && chars.next().is_some();
+
+    if is_sequence && grapheme.contains(['\u{200d}', '\u{fe0f}']) {
+        2
+    } else {
+        grapheme.chars().map(|c| c.width().unwrap_or(0)).sum()
+    }
+}
+
+/// Display width of text without ANSI escape sequences
+pub(crate) fn str_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
 /// Compute the line width for ANSI escaped text
 pub(crate) fn line_width(line: &str) -> usize {
-    strip_ansi(line).width()
+    str_width(&strip_ansi(line))
+}
+
+/// Translates a position on the screen into a byte offset of the buffer
+///
+/// `row` is counted from the first row of the input. The input starts at
+/// `first_column` and every line after a newline at the column returned by
+/// `continuation_column` for the index of that line (after its continuation prompt).
+/// Lines wrap at `terminal_columns`.
+/// Positions past the end of a line resolve to the end of that line.
+/// Right-to-left text is painted in its logical order, so its columns are not reversed.
+pub(crate) fn offset_at_position(
+    buffer: &str,
+    first_column: usize,
+    continuation_column: impl Fn(usize) -> usize,
+    row: u16,
+    column: u16,
+    terminal_columns: u16,
+) -> usize {
+    let (row, column) = (row as usize, column as usize);
+    let terminal_columns = (terminal_columns as usize).max(1);
+    let (mut current_row, mut current_column) = (0, first_column);
+    let mut line_number = 0;
+
+    for (offset, grapheme) in buffer.grapheme_indices(true) {
+        if grapheme == "\n" || grapheme == "\r\n" {
+            if current_row == row {
+                return offset;
+            }
+            current_row += 1;
+            line_number += 1;
+            current_column = continuation_column(line_number);
+            continue;
+        }
+
+        let width = grapheme_width(grapheme);
+        if current_column + width > terminal_columns {
+            if current_row == row {
+                return offset;
+            }
+            current_row += 1;
+            current_column = 0;
+        }
+
+        if current_row == row && column < current_column + width {
+            return offset;
+        }
+        current_column += width;
+    }
+
+    buffer.len()
 }
 
 #[cfg(test)]
@@ -77,6 +249,23 @@ mod test {
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
+    #[rstest]
+    #[case("--amend", 10, "--amend")]
+    #[case("--amend", 7, "--amend")]
+    #[case("--amend", 6, "--ame…")]
+    #[case("--amend", 1, "…")]
+    #[case("--amend", 0, "")]
+    #[case("--amend\n--no-edit", 20, "--amend…")]
+    #[case("\x1b[2m--amend\x1b[0m", 4, "\x1b[2m--a…\x1b[0m")]
+    #[case("日本語", 4, "日…")]
+    fn test_truncate_with_ellipsis(
+        #[case] text: &str,
+        #[case] columns: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(truncate_with_ellipsis(text, columns), expected);
+    }
+
     #[rstest]
     #[case("sentence\nsentence", "sentence\r\nsentence")]
     #[case("sentence\r\nsentence", "sentence\r\nsentence")]
@@ -84,6 +273,7 @@ mod test {
     #[case("😇\nsentence", "😇\r\nsentence")]
     #[case("sentence\n😇", "sentence\r\n😇")]
     #[case("\n", "\r\n")]
+    #[case("\nsentence\nsentence", "\r\nsentence\r\nsentence")]
     #[case("", "")]
     fn test_coerce_crlf(#[case] input: &str, #[case] expected: &str) {
         let result = coerce_crlf(input);
@@ -95,4 +285,130 @@ mod test {
             "Unnecessary allocation"
         )
     }
+
+    #[rstest]
+    #[case("hello", 2, 0, 4, 2)]
+    #[case("hello", 2, 0, 0, 0)]
+    #[case("hello", 2, 0, 30, 5)]
+    #[case("hello", 2, 1, 0, 5)]
+    #[case("abcdefghijkl", 2, 0, 9, 7)]
+    #[case("abcdefghijkl", 2, 1, 0, 8)]
+    #[case("abcdefghijkl", 2, 1, 3, 11)]
+    #[case("ab\ncd", 2, 0, 8, 2)]
+    #[case("ab\ncd", 2, 1, 0, 3)]
+    #[case("ab\ncd", 2, 1, 5, 4)]
+    #[case("ab\ncd", 2, 1, 9, 5)]
+    #[case("你好", 0, 0, 1, 0)]
+    #[case("你好", 0, 0, 2, 3)]
+    #[case("你好", 0, 0, 3, 3)]
+    fn test_offset_at_position(
+        #[case] buffer: &str,
+        #[case] first_column: usize,
+        #[case] row: u16,
+        #[case] column: u16,
+        #[case] expected: usize,
+    ) {
+        // Terminal of 10 columns and a multiline indicator of width 4
+        assert_eq!(
+            offset_at_position(buffer, first_column, |_| 4, row, column, 10),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(6, 4)]
+    #[case(7, 5)]
+    #[case(9, 9)]
+    #[case(11, 13)]
+    #[case(12, 15)]
+    fn rtl_text_is_positioned_in_logical_order(#[case] column: u16, #[case] expected: usize) {
+        // Arabic word of five letters embedded in an english command after a prompt of width 2
+        assert_eq!(
+            offset_at_position("echo مرحبا world", 2, |_| 0, 0, column, 80),
+            expected
+        );
+    }
+
+    #[test]
+    fn arabic_letters_take_a_single_column() {
+        assert_eq!(str_width("مرحبا"), 5);
+        // Diacritics combine with the letter before them
+        assert_eq!(str_width("مَرحبا"), 5);
+    }
+
+    #[rstest]
+    #[case(1, 0, 2)]
+    #[case(1, 4, 3)]
+    #[case(2, 4, 5)]
+    #[case(2, 6, 6)]
+    #[case(3, 0, 7)]
+    #[case(3, 9, 8)]
+    fn offset_at_position_follows_the_continuation_prompt_of_each_line(
+        #[case] row: u16,
+        #[case] column: u16,
+        #[case] expected: usize,
+    ) {
+        // Continuation prompts growing by one column on every line
+        assert_eq!(
+            offset_at_position("a\nbc\nd\ne", 0, |line| line + 2, row, column, 10),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("", 0, vec![], 0)]
+    #[case("abc", 0, vec![], 3)]
+    #[case("abcde", 0, vec![], 5)]
+    #[case("abcdef", 0, vec![(5, 5)], 1)]
+    #[case("abc", 3, vec![(2, 2)], 1)]
+    #[case("ab\ncd", 0, vec![(2, 3)], 2)]
+    #[case("ab\r\ncd\r\n", 0, vec![(2, 4), (6, 8)], 0)]
+    #[case("\x1b[31mabcde\x1b[0m", 0, vec![], 5)]
+    #[case("你好你", 0, vec![(6, 6)], 2)]
+    fn test_row_breaks(
+        #[case] text: &str,
+        #[case] column: usize,
+        #[case] expected_breaks: Vec<(usize, usize)>,
+        #[case] expected_column: usize,
+    ) {
+        // Terminal of 5 columns
+        assert_eq!(
+            row_breaks(text, column, 5),
+            (expected_breaks, expected_column)
+        );
+    }
+
+    #[rstest]
+    #[case("你好", 4)]
+    #[case("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}", 2)]
+    #[case("e\u{301}", 1)]
+    #[case("\u{1f1e9}\u{1f1ea}", 2)]
+    #[case("abc", 3)]
+    #[case("\x1b[31m你好\x1b[0m", 4)]
+    fn test_line_width(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(line_width(text), expected);
+    }
+
+    #[rstest]
+    #[case("你好", 2, 3)]
+    #[case("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}x", 2, 18)]
+    #[case("e\u{301}x", 1, 3)]
+    fn test_column_advance(
+        #[case] buffer: &str,
+        #[case] second_column: u16,
+        #[case] second_offset: usize,
+    ) {
+        let first_grapheme = &buffer[..second_offset];
+
+        assert_eq!(grapheme_width(first_grapheme), second_column as usize);
+        assert_eq!(
+            offset_at_position(buffer, 0, |_| 0, 0, second_column, 80),
+            second_offset
+        );
+        assert_eq!(
+            offset_at_position(buffer, 0, |_| 0, 0, second_column - 1, 80),
+            0
+        );
+        assert_eq!(row_breaks(buffer, 0, 80).1, line_width(buffer));
+    }
 }
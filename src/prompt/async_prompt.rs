@@ -0,0 +1,177 @@
+use crate::{
+    CommandStatus, Prompt, PromptCompletion, PromptEditMode, PromptHistorySearch, StyledText,
+    ValidationResult,
+};
+
+use {
+    crossterm::style::Color,
+    nu_ansi_term::Style,
+    std::{
+        borrow::Cow,
+        cell::{Cell, RefCell},
+        sync::mpsc::{Receiver, TryRecvError},
+    },
+};
+
+/// [`Prompt`] whose left prompt is computed in the background, e.g. to show a slow git status
+///
+/// The `placeholder` is painted right away and provides everything except the left
+/// prompt once the text sent over the `receiver` arrived. Until then typing works as
+/// usual, the prompt is painted again when the text arrives.
+///
+/// ```rust
+/// use reedline::{AsyncPrompt, DefaultPrompt};
+/// use std::sync::mpsc;
+///
+/// let (sender, receiver) = mpsc::channel();
+/// std::thread::spawn(move || sender.send("~/reedline (main)".to_string()));
+/// let prompt = AsyncPrompt::new(DefaultPrompt::default(), receiver);
+/// ```
+pub struct AsyncPrompt<P: Prompt> {
+    placeholder: P,
+    receiver: Receiver<String>,
+    left_prompt: RefCell<Option<String>>,
+    waiting: Cell<bool>,
+}
+
+impl<P: Prompt> AsyncPrompt<P> {
+    /// Creates a prompt showing `placeholder` until a left prompt is received
+    pub fn new(placeholder: P, receiver: Receiver<String>) -> Self {
+        AsyncPrompt {
+            placeholder,
+            receiver,
+            left_prompt: RefCell::new(None),
+            waiting: Cell::new(true),
+        }
+    }
+}
+
+impl<P: Prompt> Prompt for AsyncPrompt<P> {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        match &*self.left_prompt.borrow() {
+            Some(left_prompt) => Cow::Owned(left_prompt.clone()),
+            None => self.placeholder.render_prompt_left(),
+        }
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        self.placeholder.render_prompt_right()
+    }
+
+    fn render_prompt_styled(&self) -> StyledText {
+        match &*self.left_prompt.borrow() {
+            Some(left_prompt) => {
+                let mut styled_prompt = StyledText::new();
+                styled_prompt.push_str(Style::new(), left_prompt);
+                styled_prompt
+            }
+            None => self.placeholder.render_prompt_styled(),
+        }
+    }
+
+    fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<'_, str> {
+        self.placeholder.render_prompt_indicator(prompt_mode)
+    }
+
+    fn render_prompt_multiline_indicator(&self, validation: ValidationResult) -> Cow<'_, str> {
+        self.placeholder
+            .render_prompt_multiline_indicator(validation)
+    }
+
+    fn render_prompt_continuation(
+        &self,
+        line_number: usize,
+        validation: ValidationResult,
+    ) -> Cow<'_, str> {
+        self.placeholder
+            .render_prompt_continuation(line_number, validation)
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        self.placeholder
+            .render_prompt_history_search_indicator(history_search)
+    }
+
+    fn render_prompt_completion_indicator(
+        &self,
+        prompt_mode: PromptEditMode,
+        completion: PromptCompletion,
+    ) -> Option<Cow<'_, str>> {
+        self.placeholder
+            .render_prompt_completion_indicator(prompt_mode, completion)
+    }
+
+    fn get_prompt_color(&self) -> Color {
+        self.placeholder.get_prompt_color()
+    }
+
+    fn poll_update(&self) -> bool {
+        if !self.waiting.get() {
+            return false;
+        }
+
+        match self.receiver.try_recv() {
+            Ok(left_prompt) => {
+                self.waiting.set(false);
+                *self.left_prompt.borrow_mut() = Some(left_prompt);
+                true
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.waiting.set(false);
+                false
+            }
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn awaits_update(&self) -> bool {
+        self.waiting.get()
+    }
+
+    fn set_last_command_status(&self, status: Option<CommandStatus>) {
+        self.placeholder.set_last_command_status(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultPrompt;
+    use pretty_assertions::assert_eq;
+    use std::sync::mpsc;
+
+    #[test]
+    fn shows_the_placeholder_until_the_prompt_arrives() {
+        let (sender, receiver) = mpsc::channel();
+        let prompt = AsyncPrompt::new(DefaultPrompt::default(), receiver);
+        let placeholder = DefaultPrompt::default().render_prompt_left().to_string();
+
+        assert!(!prompt.poll_update());
+        assert!(prompt.awaits_update());
+        assert_eq!(prompt.render_prompt_left(), placeholder);
+
+        sender.send("~/repo (main)".to_string()).unwrap();
+        assert!(prompt.poll_update());
+        assert!(!prompt.awaits_update());
+        assert_eq!(prompt.render_prompt_left(), "~/repo (main)");
+        assert_eq!(prompt.render_prompt_styled().raw_string(), "~/repo (main)");
+
+        // Later messages are ignored
+        sender.send("~/other".to_string()).unwrap();
+        assert!(!prompt.poll_update());
+        assert_eq!(prompt.render_prompt_left(), "~/repo (main)");
+    }
+
+    #[test]
+    fn stops_waiting_when_the_sender_is_gone() {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let prompt = AsyncPrompt::new(DefaultPrompt::default(), receiver);
+        drop(sender);
+
+        assert!(!prompt.poll_update());
+        assert!(!prompt.awaits_update());
+    }
+}
@@ -1,9 +1,12 @@
 use {
+    crate::{StyledText, ValidationResult},
     crossterm::style::Color,
+    nu_ansi_term::Style,
     serde::{Deserialize, Serialize},
     std::{
         borrow::Cow,
         fmt::{Display, Formatter},
+        time::Duration,
     },
     strum_macros::EnumIter,
 };
@@ -39,6 +42,33 @@ impl PromptHistorySearch {
     }
 }
 
+/// State of the open menu, e.g. to show the number of matches while completing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptCompletion {
+    /// Number of suggestions offered by the menu
+    pub candidates: usize,
+
+    /// Longest prefix shared by all the suggestions
+    pub common_prefix: String,
+}
+
+/// Outcome of the previous command, passed on by [`crate::Reedline::set_last_command_status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandStatus {
+    /// Exit code of the command, `0` for success
+    pub exit_code: i32,
+
+    /// How long the command ran
+    pub duration: Duration,
+}
+
+impl CommandStatus {
+    /// Whether the command exited with `0`
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
 /// Modes that the prompt can be in
 #[derive(Serialize, Deserialize, Clone, Debug, EnumIter)]
 pub enum PromptEditMode {
@@ -57,12 +87,16 @@ pub enum PromptEditMode {
 
 /// The vi-specific modes that the prompt can be in
 #[derive(Serialize, Deserialize, Clone, Debug, EnumIter)]
+#[non_exhaustive]
 pub enum PromptViMode {
     /// The default mode
     Normal,
 
     /// Insertion mode
     Insert,
+
+    /// Replace mode, typing overwrites the existing text
+    Replace,
 }
 
 impl Default for PromptViMode {
@@ -76,7 +110,7 @@ impl Display for PromptEditMode {
         match self {
             PromptEditMode::Default => write!(f, "Default"),
             PromptEditMode::Emacs => write!(f, "Emacs"),
-            PromptEditMode::Vi(_) => write!(f, "Vi_Normal\nVi_Insert"),
+            PromptEditMode::Vi(_) => write!(f, "Vi_Normal\nVi_Insert\nVi_Replace"),
             PromptEditMode::Custom(s) => write!(f, "Custom_{}", s),
         }
     }
@@ -90,17 +124,70 @@ pub trait Prompt: Send {
     fn render_prompt_left(&self) -> Cow<str>;
     /// Provide content off the left full prompt
     fn render_prompt_right(&self) -> Cow<str>;
+    /// Provide the styled content of the left full prompt, which is painted instead of
+    /// [`Prompt::render_prompt_left`]
+    ///
+    /// Styling the prompt here instead of embedding ANSI escape sequences keeps the
+    /// painter aware of the styling. Parts in the default [`Style`] are painted in the
+    /// [`Prompt::get_prompt_color`]. Defaults to the unstyled left prompt
+    fn render_prompt_styled(&self) -> StyledText {
+        let mut styled_prompt = StyledText::new();
+        styled_prompt.push_str(Style::new(), &self.render_prompt_left());
+        styled_prompt
+    }
     /// Render the prompt indicator (Last part of the prompt that changes based on the editor mode)
     fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<str>;
     /// Indicator to show before explicit new lines
-    fn render_prompt_multiline_indicator(&self) -> Cow<str>;
+    ///
+    /// `validation` is the result of validating the current buffer, allowing the
+    /// indicator to signal an incomplete input before it is submitted
+    fn render_prompt_multiline_indicator(&self, validation: ValidationResult) -> Cow<str>;
+    /// Prompt to show at the start of a continuation line of the buffer
+    ///
+    /// `line_number` is the index of the buffer line, so the first continuation line is `1`.
+    /// Defaults to the multiline indicator on every line
+    fn render_prompt_continuation(
+        &self,
+        line_number: usize,
+        validation: ValidationResult,
+    ) -> Cow<'_, str> {
+        let _ = line_number;
+        self.render_prompt_multiline_indicator(validation)
+    }
     /// Render the prompt indicator for `Ctrl-R` history search
     fn render_prompt_history_search_indicator(
         &self,
         history_search: PromptHistorySearch,
     ) -> Cow<str>;
+    /// Render the prompt indicator while a menu is open, e.g. to show the number of matches
+    ///
+    /// Defaults to `None`, which shows the marker of the menu instead
+    fn render_prompt_completion_indicator(
+        &self,
+        prompt_mode: PromptEditMode,
+        completion: PromptCompletion,
+    ) -> Option<Cow<'_, str>> {
+        let _ = (prompt_mode, completion);
+        None
+    }
     /// Get back the prompt color
     fn get_prompt_color(&self) -> Color {
         DEFAULT_PROMPT_COLOR
     }
+    /// Checked repeatedly while [`crate::Reedline::read_line`] waits for input, returns
+    /// whether the prompt changed and has to be painted again
+    ///
+    /// Lets a prompt paint a placeholder first and update itself once something slow
+    /// was computed in the background, see [`crate::AsyncPrompt`]. Defaults to `false`
+    fn poll_update(&self) -> bool {
+        false
+    }
+    /// Whether an update reported by [`Prompt::poll_update`] is still expected, which
+    /// makes the line editor check for it more frequently. Defaults to `false`
+    fn awaits_update(&self) -> bool {
+        false
+    }
+    /// Receives the status of the previous command when [`crate::Reedline::read_line`]
+    /// starts, `None` if the embedder hasn't reported one. Ignored by default
+    fn set_last_command_status(&self, _status: Option<CommandStatus>) {}
 }
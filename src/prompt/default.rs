@@ -1,8 +1,12 @@
-use crate::{Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode};
+use crate::{
+    CommandStatus, Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus,
+    PromptViMode, StyledText, ValidationResult,
+};
 
 use {
     chrono::Local,
-    std::{borrow::Cow, env},
+    nu_ansi_term::{Color, Style},
+    std::{borrow::Cow, cell::Cell, env, time::Duration},
 };
 
 /// The default prompt indicator
@@ -10,39 +14,102 @@ pub static DEFAULT_PROMPT_INDICATOR: &str = "〉";
 pub static DEFAULT_VI_INSERT_PROMPT_INDICATOR: &str = ": ";
 pub static DEFAULT_VI_NORMAL_PROMPT_INDICATOR: &str = "〉";
 pub static DEFAULT_MULTILINE_INDICATOR: &str = "::: ";
+pub static DEFAULT_INCOMPLETE_MULTILINE_INDICATOR: &str = "... ";
+
+/// The default label of the history search indicator
+pub static DEFAULT_HISTORY_SEARCH_LABEL: &str = "reverse-i-search";
+
+/// A part of the [`DefaultPrompt`] on the left or right side of the entry line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefaultPromptSegment {
+    /// The current working directory
+    WorkingDirectory,
+    /// The current date and time
+    CurrentDateTime,
+    /// A fixed text
+    Basic(String),
+    /// Nothing at all
+    Empty,
+}
+
+impl DefaultPromptSegment {
+    fn render(&self) -> Cow<'_, str> {
+        match self {
+            DefaultPromptSegment::WorkingDirectory => {
+                Cow::Owned(get_working_dir().unwrap_or_else(|_| String::from("no path")))
+            }
+            DefaultPromptSegment::CurrentDateTime => Cow::Owned(get_now()),
+            DefaultPromptSegment::Basic(text) => Cow::Borrowed(text),
+            DefaultPromptSegment::Empty => Cow::Borrowed(""),
+        }
+    }
+}
 
 /// Simple two-line [`Prompt`] displaying the current working directory and the time above the entry line.
+///
+/// The segments and the indicators can be changed with the builder methods:
+///
+/// ```rust
+/// use reedline::{DefaultPrompt, DefaultPromptSegment};
+///
+/// let prompt = DefaultPrompt::new()
+///     .with_left_prompt(DefaultPromptSegment::Basic("~/reedline".into()))
+///     .with_right_prompt(DefaultPromptSegment::Empty)
+///     .with_indicator("$ ")
+///     .with_vi_indicators("[i] ", "[n] ");
+/// ```
 #[derive(Clone)]
-pub struct DefaultPrompt;
+pub struct DefaultPrompt {
+    left_prompt: DefaultPromptSegment,
+    right_prompt: DefaultPromptSegment,
+    indicator: String,
+    vi_insert_indicator: String,
+    vi_normal_indicator: String,
+    multiline_indicator: String,
+    incomplete_multiline_indicator: String,
+    history_search_label: String,
+    show_command_status: bool,
+    command_status: Cell<Option<CommandStatus>>,
+}
 
 impl Prompt for DefaultPrompt {
     fn render_prompt_left(&self) -> Cow<str> {
-        {
-            let left_prompt = get_working_dir().unwrap_or_else(|_| String::from("no path"));
-
-            Cow::Owned(left_prompt)
+        if self.render_command_status().is_empty() {
+            self.left_prompt.render()
+        } else {
+            Cow::Owned(self.render_prompt_styled().raw_string())
         }
     }
 
+    fn render_prompt_styled(&self) -> StyledText {
+        let mut styled_prompt = StyledText::new();
+        styled_prompt.push_str(Style::new(), &self.left_prompt.render());
+        styled_prompt.extend(self.render_command_status());
+        styled_prompt
+    }
+
     fn render_prompt_right(&self) -> Cow<str> {
-        {
-            Cow::Owned(get_now())
-        }
+        self.right_prompt.render()
     }
 
     fn render_prompt_indicator(&self, edit_mode: PromptEditMode) -> Cow<str> {
         match edit_mode {
-            PromptEditMode::Default | PromptEditMode::Emacs => DEFAULT_PROMPT_INDICATOR.into(),
+            PromptEditMode::Default | PromptEditMode::Emacs => self.indicator.as_str().into(),
             PromptEditMode::Vi(vi_mode) => match vi_mode {
-                PromptViMode::Normal => DEFAULT_VI_NORMAL_PROMPT_INDICATOR.into(),
-                PromptViMode::Insert => DEFAULT_VI_INSERT_PROMPT_INDICATOR.into(),
+                PromptViMode::Normal => self.vi_normal_indicator.as_str().into(),
+                PromptViMode::Insert | PromptViMode::Replace => {
+                    self.vi_insert_indicator.as_str().into()
+                }
             },
             PromptEditMode::Custom(str) => format!("({})", str).into(),
         }
     }
 
-    fn render_prompt_multiline_indicator(&self) -> Cow<str> {
-        Cow::Borrowed(DEFAULT_MULTILINE_INDICATOR)
+    fn render_prompt_multiline_indicator(&self, validation: ValidationResult) -> Cow<str> {
+        match validation {
+            ValidationResult::Complete => Cow::Borrowed(&self.multiline_indicator),
+            ValidationResult::Incomplete => Cow::Borrowed(&self.incomplete_multiline_indicator),
+        }
     }
 
     fn render_prompt_history_search_indicator(
@@ -56,10 +123,14 @@ impl Prompt for DefaultPrompt {
         // NOTE: magic strings, given there is logic on how these compose I am not sure if it
         // is worth extracting in to static constant
         Cow::Owned(format!(
-            "({}reverse-search: {}) ",
-            prefix, history_search.term
+            "({}{})'{}': ",
+            prefix, self.history_search_label, history_search.term
         ))
     }
+
+    fn set_last_command_status(&self, status: Option<CommandStatus>) {
+        self.command_status.set(status);
+    }
 }
 
 impl Default for DefaultPrompt {
@@ -69,9 +140,103 @@ impl Default for DefaultPrompt {
 }
 
 impl DefaultPrompt {
-    /// Constructor for the default prompt, which takes the amount of spaces required between the left and right-hand sides of the prompt
+    /// Constructor for the default prompt, showing the working directory on the left and
+    /// the time on the right
     pub fn new() -> DefaultPrompt {
-        DefaultPrompt {}
+        DefaultPrompt {
+            left_prompt: DefaultPromptSegment::WorkingDirectory,
+            right_prompt: DefaultPromptSegment::CurrentDateTime,
+            indicator: DEFAULT_PROMPT_INDICATOR.to_string(),
+            vi_insert_indicator: DEFAULT_VI_INSERT_PROMPT_INDICATOR.to_string(),
+            vi_normal_indicator: DEFAULT_VI_NORMAL_PROMPT_INDICATOR.to_string(),
+            multiline_indicator: DEFAULT_MULTILINE_INDICATOR.to_string(),
+            incomplete_multiline_indicator: DEFAULT_INCOMPLETE_MULTILINE_INDICATOR.to_string(),
+            history_search_label: DEFAULT_HISTORY_SEARCH_LABEL.to_string(),
+            show_command_status: false,
+            command_status: Cell::new(None),
+        }
+    }
+
+    /// Show the duration of the previous command after the left prompt, and its
+    /// exit code in red if it failed. The status is reported with
+    /// [`crate::Reedline::set_last_command_status`]
+    #[must_use]
+    pub fn with_command_status(mut self, show_command_status: bool) -> Self {
+        self.show_command_status = show_command_status;
+        self
+    }
+
+    /// The status of the previous command, e.g. ` ✘ 1 2.3s`
+    fn render_command_status(&self) -> StyledText {
+        let mut styled_status = StyledText::new();
+        if let Some(status) = self
+            .command_status
+            .get()
+            .filter(|_| self.show_command_status)
+        {
+            if !status.is_success() {
+                styled_status.push_str(Style::new(), " ");
+                styled_status.push_str(Color::Red.normal(), &format!("✘ {}", status.exit_code));
+            }
+            styled_status.push_str(
+                Style::new(),
+                &format!(" {}", format_duration(status.duration)),
+            );
+        }
+        styled_status
+    }
+
+    /// Set the segment shown on the left, above or before the entry line
+    #[must_use]
+    pub fn with_left_prompt(mut self, left_prompt: DefaultPromptSegment) -> Self {
+        self.left_prompt = left_prompt;
+        self
+    }
+
+    /// Set the segment shown at the right edge of the terminal
+    #[must_use]
+    pub fn with_right_prompt(mut self, right_prompt: DefaultPromptSegment) -> Self {
+        self.right_prompt = right_prompt;
+        self
+    }
+
+    /// Set the indicator in the emacs and the default edit mode
+    #[must_use]
+    pub fn with_indicator(mut self, indicator: impl Into<String>) -> Self {
+        self.indicator = indicator.into();
+        self
+    }
+
+    /// Set the indicators of the vi insert (also used in replace mode) and normal modes
+    #[must_use]
+    pub fn with_vi_indicators(
+        mut self,
+        insert_indicator: impl Into<String>,
+        normal_indicator: impl Into<String>,
+    ) -> Self {
+        self.vi_insert_indicator = insert_indicator.into();
+        self.vi_normal_indicator = normal_indicator.into();
+        self
+    }
+
+    /// Set the markers at the start of the continuation lines, for complete and for
+    /// incomplete input
+    #[must_use]
+    pub fn with_multiline_indicators(
+        mut self,
+        complete_indicator: impl Into<String>,
+        incomplete_indicator: impl Into<String>,
+    ) -> Self {
+        self.multiline_indicator = complete_indicator.into();
+        self.incomplete_multiline_indicator = incomplete_indicator.into();
+        self
+    }
+
+    /// Set the label of the history search indicator, `reverse-i-search` by default
+    #[must_use]
+    pub fn with_history_search_label(mut self, label: impl Into<String>) -> Self {
+        self.history_search_label = label.into();
+        self
     }
 }
 
@@ -80,7 +245,133 @@ fn get_working_dir() -> Result<String, std::io::Error> {
     Ok(path.display().to_string())
 }
 
+fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
 fn get_now() -> String {
     let now = Local::now();
     format!("{:>}", now.format("%m/%d/%Y %I:%M:%S %p"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn default_look_is_kept() {
+        let prompt = DefaultPrompt::default();
+        assert_eq!(prompt.render_prompt_indicator(PromptEditMode::Emacs), "〉");
+        assert_eq!(
+            prompt.render_prompt_indicator(PromptEditMode::Vi(PromptViMode::Insert)),
+            ": "
+        );
+        assert_eq!(
+            prompt.render_prompt_multiline_indicator(ValidationResult::Incomplete),
+            "... "
+        );
+        let search = PromptHistorySearch::new(PromptHistorySearchStatus::Failing, "ls".into());
+        assert_eq!(
+            prompt.render_prompt_history_search_indicator(search),
+            "(failing reverse-i-search)'ls': "
+        );
+    }
+
+    #[test]
+    fn reverse_search_indicator_shows_query() {
+        let prompt = DefaultPrompt::default();
+        let search = PromptHistorySearch::new(PromptHistorySearchStatus::Passing, "git".into());
+
+        assert_eq!(
+            prompt.render_prompt_history_search_indicator(search),
+            "(reverse-i-search)'git': "
+        );
+    }
+
+    #[rstest]
+    #[case(PromptEditMode::Default, "$ ")]
+    #[case(PromptEditMode::Emacs, "$ ")]
+    #[case(PromptEditMode::Vi(PromptViMode::Insert), "[i] ")]
+    #[case(PromptEditMode::Vi(PromptViMode::Replace), "[i] ")]
+    #[case(PromptEditMode::Vi(PromptViMode::Normal), "[n] ")]
+    #[case(PromptEditMode::Custom("calc".into()), "(calc)")]
+    fn configured_indicators(#[case] edit_mode: PromptEditMode, #[case] expected: &str) {
+        let prompt = DefaultPrompt::new()
+            .with_indicator("$ ")
+            .with_vi_indicators("[i] ", "[n] ");
+
+        assert_eq!(prompt.render_prompt_indicator(edit_mode), expected);
+    }
+
+    #[test]
+    fn failed_command_status_is_red() {
+        let prompt = DefaultPrompt::new()
+            .with_left_prompt(DefaultPromptSegment::Basic("~".into()))
+            .with_command_status(true);
+        assert_eq!(prompt.render_prompt_left(), "~");
+
+        prompt.set_last_command_status(Some(CommandStatus {
+            exit_code: 1,
+            duration: Duration::from_millis(2345),
+        }));
+        assert_eq!(prompt.render_prompt_left(), "~ ✘ 1 2.3s");
+        let styled: Vec<(Style, String)> = prompt.render_prompt_styled().buffer;
+        assert_eq!(
+            styled,
+            vec![
+                (Style::new(), "~ ".to_string()),
+                (Color::Red.normal(), "✘ 1".to_string()),
+                (Style::new(), " 2.3s".to_string()),
+            ]
+        );
+
+        prompt.set_last_command_status(Some(CommandStatus {
+            exit_code: 0,
+            duration: Duration::from_millis(40),
+        }));
+        assert_eq!(prompt.render_prompt_left(), "~ 40ms");
+    }
+
+    #[test]
+    fn command_status_is_hidden_unless_enabled() {
+        let prompt = DefaultPrompt::new().with_left_prompt(DefaultPromptSegment::Basic("~".into()));
+        prompt.set_last_command_status(Some(CommandStatus {
+            exit_code: 1,
+            duration: Duration::from_secs(1),
+        }));
+
+        assert_eq!(prompt.render_prompt_left(), "~");
+        assert_eq!(prompt.render_prompt_styled().raw_string(), "~");
+    }
+
+    #[test]
+    fn configured_segments_and_markers() {
+        let prompt = DefaultPrompt::new()
+            .with_left_prompt(DefaultPromptSegment::Basic("~/src".into()))
+            .with_right_prompt(DefaultPromptSegment::Empty)
+            .with_multiline_indicators("| ", "> ")
+            .with_history_search_label("search");
+
+        assert_eq!(prompt.render_prompt_left(), "~/src");
+        assert_eq!(prompt.render_prompt_right(), "");
+        assert_eq!(
+            prompt.render_prompt_multiline_indicator(ValidationResult::Complete),
+            "| "
+        );
+        assert_eq!(
+            prompt.render_prompt_continuation(2, ValidationResult::Incomplete),
+            "> "
+        );
+        let search = PromptHistorySearch::new(PromptHistorySearchStatus::Passing, "git".into());
+        assert_eq!(
+            prompt.render_prompt_history_search_indicator(search),
+            "(search)'git': "
+        );
+    }
+}
@@ -1,8 +1,12 @@
+mod async_prompt;
 mod base;
 mod default;
 
+pub use async_prompt::AsyncPrompt;
+
 pub use base::{
-    Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus, PromptViMode,
+    CommandStatus, Prompt, PromptCompletion, PromptEditMode, PromptHistorySearch,
+    PromptHistorySearchStatus, PromptViMode,
 };
 
-pub use default::DefaultPrompt;
+pub use default::{DefaultPrompt, DefaultPromptSegment};
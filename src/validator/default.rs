@@ -1,38 +1,126 @@
-use crate::{ValidationResult, Validator};
+use {
+    crate::{Diagnostic, Span, ValidationResult, Validator},
+    std::borrow::Cow,
+};
 
 /// A default validator which checks for mismatched quotes and brackets
-pub struct DefaultValidator;
+///
+/// The input is incomplete while a bracket or quote is left open or the last line
+/// ends with the line continuation character. Brackets inside quotes don't count,
+/// and the line continuation character also escapes the character following it,
+/// except inside single quotes `'` like in a POSIX shell.
+///
+/// By default `()`, `[]` and `{}` are brackets, `"` and `'` are quotes and `\` is
+/// the line continuation character.
+pub struct DefaultValidator {
+    brackets: Cow<'static, [(char, char)]>,
+    quotes: Cow<'static, [char]>,
+    line_continuation: Option<char>,
+}
+
+impl Default for DefaultValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefaultValidator {
+    /// The validator with the default brackets, quotes and line continuation character
+    pub const fn new() -> Self {
+        Self {
+            brackets: Cow::Borrowed(&[('(', ')'), ('[', ']'), ('{', '}')]),
+            quotes: Cow::Borrowed(&['"', '\'']),
+            line_continuation: Some('\\'),
+        }
+    }
+
+    /// Set the pairs of opening and closing brackets
+    #[must_use]
+    pub fn with_brackets(mut self, brackets: Vec<(char, char)>) -> Self {
+        self.brackets = Cow::Owned(brackets);
+        self
+    }
+
+    /// Set the characters that open and close a quote
+    #[must_use]
+    pub fn with_quotes(mut self, quotes: Vec<char>) -> Self {
+        self.quotes = Cow::Owned(quotes);
+        self
+    }
+
+    /// Set the character that continues the input on the next line when it ends a line,
+    /// `None` to not continue lines
+    #[must_use]
+    pub fn with_line_continuation(mut self, line_continuation: Option<char>) -> Self {
+        self.line_continuation = line_continuation;
+        self
+    }
+
+    fn scan(&self, line: &str) -> Scan {
+        let mut scan = Scan::default();
+        let mut chars = line.char_indices();
+
+        while let Some((index, c)) = chars.next() {
+            if Some(c) == self.line_continuation && scan.open_quote != Some('\'') {
+                if chars.next().is_none() && scan.open_quote.is_none() {
+                    scan.continued = true;
+                }
+                continue;
+            }
+
+            if let Some(quote) = scan.open_quote {
+                if c == quote {
+                    scan.open_quote = None;
+                }
+            } else if self.quotes.contains(&c) {
+                scan.open_quote = Some(c);
+            } else if let Some((_, close)) = self.brackets.iter().find(|(open, _)| *open == c) {
+                scan.open_brackets.push(*close);
+            } else if self.brackets.iter().any(|(_, close)| *close == c) {
+                if scan.open_brackets.last() == Some(&c) {
+                    scan.open_brackets.pop();
+                } else {
+                    scan.unexpected.push((index, c));
+                }
+            }
+        }
+
+        scan
+    }
+}
+
+/// What a line leaves open, and the closing brackets that don't close the innermost
+/// open bracket with their position
+#[derive(Default)]
+struct Scan {
+    open_brackets: Vec<char>,
+    open_quote: Option<char>,
+    continued: bool,
+    unexpected: Vec<(usize, char)>,
+}
 
 impl Validator for DefaultValidator {
     fn validate(&self, line: &str) -> ValidationResult {
-        if line.split('"').count() % 2 == 0 || incomplete_brackets(line) {
+        let scan = self.scan(line);
+        if scan.open_quote.is_some() || !scan.open_brackets.is_empty() || scan.continued {
             ValidationResult::Incomplete
         } else {
             ValidationResult::Complete
         }
     }
-}
 
-fn incomplete_brackets(line: &str) -> bool {
-    let mut balance: Vec<char> = Vec::new();
-
-    for c in line.chars() {
-        if c == '{' {
-            balance.push('}');
-        } else if c == '[' {
-            balance.push(']');
-        } else if c == '(' {
-            balance.push(')');
-        } else if ['}', ']', ')'].contains(&c) {
-            if let Some(last) = balance.last() {
-                if last == &c {
-                    balance.pop();
-                }
-            }
-        }
+    fn diagnostics(&self, line: &str) -> Vec<Diagnostic> {
+        self.scan(line)
+            .unexpected
+            .into_iter()
+            .map(|(index, c)| {
+                Diagnostic::new(
+                    Span::new(index, index + c.len_utf8()),
+                    format!("unexpected `{}`", c),
+                )
+            })
+            .collect()
     }
-
-    !balance.is_empty()
 }
 
 #[cfg(test)]
@@ -40,14 +128,101 @@ mod test {
     use super::*;
     use rstest::rstest;
 
+    fn incomplete(input: &str) -> bool {
+        DefaultValidator::default().validate(input) == ValidationResult::Incomplete
+    }
+
     #[rstest]
     #[case("(([[]]))", false)]
     #[case("(([[]]", true)]
     #[case("{[}]", true)]
     #[case("{[]}{()}", false)]
     fn test_incomplete_brackets(#[case] input: &str, #[case] expected: bool) {
-        let result = incomplete_brackets(input);
+        let result = incomplete(input);
 
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case("echo \"hi", true)]
+    #[case("echo 'hi", true)]
+    #[case("echo \"it's\"", false)]
+    #[case("echo 'say \"hi\"'", false)]
+    #[case("echo \"hi\nthere\"", false)]
+    #[case("echo \"a\\\"b", true)]
+    #[case("echo \"a\\\"b\"", false)]
+    #[case("echo 'a\\'", false)]
+    #[case("echo 'a\\' \"b", true)]
+    fn test_incomplete_quotes(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(incomplete(input), expected);
+    }
+
+    #[rstest]
+    #[case("echo \"(\"", false)]
+    #[case("echo '[{' (", true)]
+    #[case("echo (\")\"", true)]
+    #[case("echo (\")\")", false)]
+    fn test_quotes_hide_brackets(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(incomplete(input), expected);
+    }
+
+    #[rstest]
+    #[case("ls \\", true)]
+    #[case("ls \\\n-l", false)]
+    #[case("ls \\\\", false)]
+    #[case("ls \\(", false)]
+    #[case("ls \\ -l", false)]
+    fn test_line_continuation(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(incomplete(input), expected);
+    }
+
+    #[test]
+    fn new_is_the_default() {
+        const VALIDATOR: DefaultValidator = DefaultValidator::new();
+
+        assert_eq!(VALIDATOR.validate("ls ("), ValidationResult::Incomplete);
+        assert_eq!(
+            VALIDATOR.validate("echo 'a\\'"),
+            DefaultValidator::default().validate("echo 'a\\'")
+        );
+    }
+
+    #[test]
+    fn character_sets_are_configurable() {
+        let validator = DefaultValidator::default()
+            .with_brackets(vec![('<', '>')])
+            .with_quotes(vec!['`'])
+            .with_line_continuation(None);
+
+        assert_eq!(validator.validate("(<"), ValidationResult::Incomplete);
+        assert_eq!(validator.validate("(<>"), ValidationResult::Complete);
+        assert_eq!(
+            validator.validate("echo `it's"),
+            ValidationResult::Incomplete
+        );
+        assert_eq!(
+            validator.validate("echo `it's`"),
+            ValidationResult::Complete
+        );
+        assert_eq!(validator.validate("ls \\"), ValidationResult::Complete);
+    }
+
+    #[rstest]
+    #[case("(([[]]))", &[])]
+    #[case("(([[]]", &[])]
+    #[case("echo (ls) | sort)", &[(16, 17, "unexpected `)`")])]
+    #[case("{[}]", &[(2, 3, "unexpected `}`")])]
+    #[case("é)", &[(2, 3, "unexpected `)`")])]
+    #[case("echo \")\" \\)", &[])]
+    fn test_unexpected_bracket_diagnostics(
+        #[case] input: &str,
+        #[case] expected: &[(usize, usize, &str)],
+    ) {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(start, end, message)| Diagnostic::new(Span::new(*start, *end), *message))
+            .collect();
+
+        assert_eq!(DefaultValidator::default().diagnostics(input), expected);
+    }
 }
@@ -1,14 +1,43 @@
 mod default;
 pub use default::DefaultValidator;
 
+use crate::Span;
+
 /// The syntax validation trait. Implementers of this trait will check to see if the current input
 /// is incomplete and spans multiple lines
 pub trait Validator: Send {
     /// The action that will handle the current buffer as a line and return the corresponding validation
     fn validate(&self, line: &str) -> ValidationResult;
+
+    /// Problems found in `line` to point out while it is edited, e.g. a stray closing
+    /// bracket. The spans are underlined in the buffer. Diagnostics don't decide
+    /// whether the line is submitted, that is up to [`Validator::validate`]
+    fn diagnostics(&self, _line: &str) -> Vec<Diagnostic> {
+        Vec::new()
+    }
+}
+
+/// A problem of the input reported by [`Validator::diagnostics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Part of the line the problem is about, in bytes
+    pub span: Span,
+    /// Explanation of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic of `span` explained by `message`
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
 }
 
 /// Whether or not the validation shows the input was complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ValidationResult {
     /// An incomplete input which may need to span multiple lines to be complete
     Incomplete,